@@ -0,0 +1,74 @@
+//! Time taken by the ticks that spawn a wave, with packages drawn from the pool and spawned fresh.
+//! Run with `cargo bench --features test-support`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use play_nice::*;
+
+const WAVES: usize = 50;
+/// Gives up rather than spinning forever if the waves stop coming.
+const MAX_TICKS: u32 = 100_000;
+
+#[derive(Resource, Default)]
+struct WaveCount(usize);
+
+fn count_waves(mut wave_events: EventReader<WaveSpawned>, mut wave_count: ResMut<WaveCount>) {
+    wave_count.0 += wave_events.read().count();
+}
+
+/// Nobody plays, and belts jam and clear quickly so the waves come close together.
+fn wave_config(pool_size: usize) -> Config {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    config.game.package_config.pool_size = pool_size;
+    config.game.package_config.wave_delay_seconds = 0.5;
+    config.game.conveyor_config.jam_grace_seconds = 0.25;
+    config.game.conveyor_config.jam_countdown_seconds = 0.25;
+    config
+}
+
+/// Every tick that spawned a wave, however long it took.
+fn time_wave_ticks(pool_size: usize) -> Vec<Duration> {
+    let mut app = headless_app(wave_config(pool_size));
+    app.init_resource::<WaveCount>()
+        .add_systems(FixedUpdate, count_waves.after(spawn_package_wave));
+    // startup
+    app.update();
+
+    let mut wave_ticks = Vec::with_capacity(WAVES);
+    for _ in 0..MAX_TICKS {
+        if wave_ticks.len() == WAVES {
+            break;
+        }
+        let waves_before = app.world.resource::<WaveCount>().0;
+        let started = Instant::now();
+        app.update();
+        let elapsed = started.elapsed();
+        if app.world.resource::<WaveCount>().0 > waves_before {
+            wave_ticks.push(elapsed);
+        }
+    }
+    assert!(!wave_ticks.is_empty(), "no waves in {} ticks", MAX_TICKS);
+    wave_ticks
+}
+
+fn report(label: &str, mut wave_ticks: Vec<Duration>) {
+    wave_ticks.sort();
+    let mean = wave_ticks.iter().sum::<Duration>() / wave_ticks.len() as u32;
+    println!(
+        "{:>8}: mean {:?}, median {:?}, worst {:?} over {} waves",
+        label,
+        mean,
+        wave_ticks[wave_ticks.len() / 2],
+        wave_ticks[wave_ticks.len() - 1],
+        wave_ticks.len()
+    );
+}
+
+fn main() {
+    let pool_size = PackageConfig::default().pool_size;
+    report("pooled", time_wave_ticks(pool_size));
+    report("unpooled", time_wave_ticks(0));
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use enum_map::EnumMap;
+
+use crate::{AttractMode, FreezeSource, GameConfig, GameState, PlayerIndex, ScoreChanged};
+
+/// Marks players who haven't touched their controls in `timeout_seconds` as away, every
+/// multiplier their deliveries feed holds and waves shrink to what the remaining players can
+/// deliver. Any input brings them straight back.
+pub fn update_afk_players(
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    attract_mode: Res<AttractMode>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    let afk_config = &game_config.afk_config;
+    let GameState {
+        player_controls,
+        player_scores,
+        ..
+    } = game_state.as_mut();
+    let mut any_changed = false;
+    for (player_index, player_control) in player_controls.iter_mut() {
+        // nobody is away while the demo is playing itself
+        let afk = afk_config.enabled
+            && !attract_mode.active
+            && player_control.seconds_since_input >= afk_config.timeout_seconds;
+        if afk == player_control.afk {
+            continue;
+        }
+
+        player_control.afk = afk;
+        any_changed = true;
+        if afk {
+            info!("{:?} is away", player_index);
+        } else {
+            info!("{:?} is back", player_index);
+        }
+    }
+    if !any_changed {
+        return;
+    }
+
+    // a shared team multiplier stays held until everyone who feeds it is back
+    let mut held = EnumMap::<PlayerIndex, bool>::default();
+    for (player_index, player_control) in player_controls.iter() {
+        if player_control.afk {
+            for recipient in game_config.game_mode.multiplier_recipients(player_index) {
+                held[recipient] = true;
+            }
+        }
+    }
+    for (player_index, player_score) in player_scores.iter_mut() {
+        if held[player_index] {
+            player_score
+                .multiplier_freeze
+                .freeze(FreezeSource::Afk, f32::INFINITY);
+        } else {
+            player_score.multiplier_freeze.unfreeze(FreezeSource::Afk);
+        }
+        score_events.send(ScoreChanged {
+            player: player_index,
+        });
+    }
+}
@@ -0,0 +1,921 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use enum_map::{enum_map, EnumMap};
+
+use crate::{
+    player_on_side, player_start_position, AppConfig, BlinkerColours, ConveyorLabelTag,
+    ConveyorSpawner, GameConfig, InteriorWallConfig, LayoutBeltKind, PlayAreaAligment, Player,
+    PlayerIndex, SideAssignment, SpawnContext, WallTag,
+};
+
+pub const WALL_WIDTH: f32 = 10.;
+/// How far past a walkable edge the layout check looks for something solid.
+const LEAK_PROBE_DISTANCE: f32 = 0.5;
+/// Shortest a belt can be and still be worth working, in packages laid end to end.
+const MIN_BELT_LENGTH_IN_PACKAGES: f32 = 4.;
+const DISPLAY_HEIGHT: f32 = 24.;
+const DISPLAY_GAP: f32 = 4.;
+const PLAYER_DISPLAY_Y: f32 = 12.;
+/// Spacing of the grid the path check walks, as a fraction of a player's size. Anything under a
+/// whole player can't step over a wall, however thin.
+const PATH_GRID_STEP: f32 = 0.5;
+
+/// Whether two rectangles share any area, edges that only touch don't count.
+fn rects_overlap(a: Rect, b: Rect) -> bool {
+    let overlap = a.intersect(b);
+    !overlap.is_empty() && overlap.width() * overlap.height() > f32::EPSILON
+}
+
+pub struct ConveyorPlacement {
+    pub position: Vec2,
+    pub length: f32,
+    pub alignment: PlayAreaAligment,
+    pub tag: ConveyorLabelTag,
+}
+
+impl ConveyorPlacement {
+    pub fn bounds(&self, conveyor_width: f32) -> Rect {
+        Rect::from_center_size(self.position, Vec2::new(conveyor_width, self.length))
+    }
+}
+
+/// A wall segment a layout adds inside the arena, the only walls that can be turned.
+pub struct InteriorWall {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    /// Anticlockwise, in radians.
+    pub rotation: f32,
+}
+
+impl InteriorWall {
+    fn new(wall_config: &InteriorWallConfig) -> Self {
+        Self {
+            center: wall_config.position,
+            half_size: wall_config.size / 2.,
+            rotation: wall_config.rotation_degrees.to_radians(),
+        }
+    }
+
+    fn axes(&self) -> [Vec2; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        [Vec2::new(cos, sin), Vec2::new(-sin, cos)]
+    }
+
+    fn corners(&self) -> [Vec2; 4] {
+        let [x_axis, y_axis] = self.axes();
+        let half_x = x_axis * self.half_size.x;
+        let half_y = y_axis * self.half_size.y;
+        [
+            self.center - half_x - half_y,
+            self.center + half_x - half_y,
+            self.center + half_x + half_y,
+            self.center - half_x + half_y,
+        ]
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let offset = point - self.center;
+        let [x_axis, y_axis] = self.axes();
+        offset.dot(x_axis).abs() <= self.half_size.x && offset.dot(y_axis).abs() <= self.half_size.y
+    }
+
+    /// Whether the wall shares any area with `rect`. Two boxes are apart exactly when one of
+    /// their edge directions separates them.
+    pub fn overlaps(&self, rect: Rect) -> bool {
+        let rect_corners = [
+            rect.min,
+            Vec2::new(rect.max.x, rect.min.y),
+            rect.max,
+            Vec2::new(rect.min.x, rect.max.y),
+        ];
+        let wall_corners = self.corners();
+        let [x_axis, y_axis] = self.axes();
+        let project = |corners: &[Vec2; 4], axis: Vec2| {
+            corners.iter().map(|corner| corner.dot(axis)).fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min, max), projected| (min.min(projected), max.max(projected)),
+            )
+        };
+        [Vec2::X, Vec2::Y, x_axis, y_axis].into_iter().all(|axis| {
+            let (rect_min, rect_max) = project(&rect_corners, axis);
+            let (wall_min, wall_max) = project(&wall_corners, axis);
+            rect_max.min(wall_max) - rect_min.max(wall_min) > f32::EPSILON
+        })
+    }
+}
+
+/// The score displays, in the office sprite's own space as they're spawned as its children.
+pub struct DisplayLayout {
+    pub team: Rect,
+    pub wave_preview: Rect,
+    pub match_score: Rect,
+    pub players: EnumMap<PlayerIndex, Rect>,
+}
+
+impl DisplayLayout {
+    fn new(office_size: Vec2, game_config: &GameConfig) -> Self {
+        // the player displays sit half their width in from the edges either side of the team
+        // display, so four widths is as wide as they can be before meeting it
+        let width = (game_config.supervisor_config.office_sprite_size.x as f32 * 0.5)
+            .min(office_size.x / 4.);
+        let bottom = -office_size.y / 2.;
+        let team = Rect::new(-width / 2., bottom, width / 2., bottom + DISPLAY_HEIGHT);
+        // next wave preview and then rounds won stack above the team score
+        let stacked = |below: Rect| {
+            let width = width * 0.6;
+            let bottom = below.max.y + DISPLAY_GAP;
+            Rect::new(-width / 2., bottom, width / 2., bottom + DISPLAY_HEIGHT)
+        };
+        let wave_preview = stacked(team);
+        let match_score = stacked(wave_preview);
+        let player_left = -(office_size.x / 2.) + (width / 2.);
+        let players = enum_map! {
+            PlayerIndex::Player1 => Rect::new(
+                player_left,
+                PLAYER_DISPLAY_Y,
+                player_left + width,
+                PLAYER_DISPLAY_Y + DISPLAY_HEIGHT,
+            ),
+            PlayerIndex::Player2 => Rect::new(
+                -player_left - width,
+                PLAYER_DISPLAY_Y,
+                -player_left,
+                PLAYER_DISPLAY_Y + DISPLAY_HEIGHT,
+            ),
+        };
+        Self {
+            team,
+            wave_preview,
+            match_score,
+            players,
+        }
+    }
+
+    fn all(&self) -> impl Iterator<Item = (&'static str, Rect)> + '_ {
+        [
+            ("team", self.team),
+            ("wave preview", self.wave_preview),
+            ("match score", self.match_score),
+        ]
+        .into_iter()
+        .chain(self.players.iter().map(|(player_index, rect)| {
+            (
+                match player_index {
+                    PlayerIndex::Player1 => "player 1",
+                    PlayerIndex::Player2 => "player 2",
+                },
+                *rect,
+            )
+        }))
+    }
+}
+
+/// Where everything goes for a resolution, worked out once so nothing else does arithmetic on
+/// `base_resolution`. Wall segments are cut from the belt extents so every edge they share is
+/// flush.
+#[derive(Resource)]
+pub struct ArenaLayout {
+    pub play_area: Rect,
+    /// The supervisor's office across the top of the play area.
+    pub office: Rect,
+    /// The gap under the incoming belts joining the two halves.
+    pub walkway: Rect,
+    /// Floor the supervisor can see from the office.
+    pub restricted_zone: Rect,
+    pub displays: DisplayLayout,
+    pub conveyor_width: f32,
+    pub conveyors: Vec<ConveyorPlacement>,
+    pub walls: Vec<Rect>,
+    pub interior_walls: Vec<InteriorWall>,
+    /// Everywhere a player's centre may legally be.
+    pub floor: Vec<Rect>,
+    /// Where a player working each side starts, for the path check.
+    starts: Vec<(PlayAreaAligment, Vec2)>,
+    min_belt_length: f32,
+    player_size: f32,
+    pickup_radius: f32,
+}
+
+impl ArenaLayout {
+    pub fn new(app_config: &AppConfig, game_config: &GameConfig, sides: &SideAssignment) -> Self {
+        let layout_config = game_config.get_layout();
+        let half_resolution = app_config.base_resolution.as_vec2() / 2.;
+        let conveyor_width = game_config.conveyor_config.size.x;
+        let office_height = layout_config
+            .office_height
+            .unwrap_or(game_config.supervisor_config.office_sprite_size.y as f32);
+        // left to the classic defaults the incoming belts stop short of the office and of the
+        // bottom walkway by the same amount
+        let walkway_height = layout_config.walkway_height.unwrap_or(office_height);
+        let walkway_width = layout_config.walkway_width.unwrap_or(conveyor_width * 2.);
+        let incoming_belt_top = half_resolution.y - office_height;
+        let incoming_belt_bottom = -half_resolution.y + walkway_height;
+        let office_wall_y = half_resolution.y - (office_height / 2.);
+        let outgoing_belt_length = (half_resolution.y * 2.) - office_height;
+        let play_area = Rect::from_center_half_size(Vec2::ZERO, half_resolution);
+        let office = Rect::new(
+            -half_resolution.x,
+            half_resolution.y - office_height,
+            half_resolution.x,
+            half_resolution.y,
+        );
+        let walkway = Rect::new(
+            layout_config.walkway_offset_x - walkway_width / 2.,
+            -half_resolution.y,
+            layout_config.walkway_offset_x + walkway_width / 2.,
+            incoming_belt_bottom,
+        );
+        let restricted_zone = Rect::new(
+            -half_resolution.x,
+            office_wall_y - game_config.supervisor_config.restricted_zone_depth,
+            half_resolution.x,
+            office_wall_y,
+        );
+
+        let mut conveyors = Vec::new();
+        match &layout_config.conveyors {
+            Some(layout_conveyors) => {
+                for layout_conveyor in layout_conveyors {
+                    let alignment = if layout_conveyor.position.x < 0. {
+                        PlayAreaAligment::Left
+                    } else {
+                        PlayAreaAligment::Right
+                    };
+                    let tag = match layout_conveyor.kind {
+                        LayoutBeltKind::Incoming => ConveyorLabelTag::Incoming,
+                        LayoutBeltKind::Outgoing => {
+                            let Some(player_index) = player_on_side(sides, alignment) else {
+                                continue;
+                            };
+                            ConveyorLabelTag::Outgoing(player_index)
+                        }
+                    };
+                    conveyors.push(ConveyorPlacement {
+                        position: layout_conveyor.position,
+                        length: layout_conveyor.length,
+                        alignment,
+                        tag,
+                    });
+                }
+            }
+            None => {
+                for (alignment, side) in
+                    [(PlayAreaAligment::Left, -1.), (PlayAreaAligment::Right, 1.)]
+                {
+                    conveyors.push(ConveyorPlacement {
+                        position: Vec2::new(
+                            side * conveyor_width / 2.,
+                            (incoming_belt_top + incoming_belt_bottom) / 2.,
+                        ),
+                        length: incoming_belt_top - incoming_belt_bottom,
+                        alignment,
+                        tag: ConveyorLabelTag::Incoming,
+                    });
+                    let Some(player_index) = player_on_side(sides, alignment) else {
+                        continue;
+                    };
+                    conveyors.push(ConveyorPlacement {
+                        position: Vec2::new(
+                            side * (half_resolution.x - (conveyor_width / 2.)),
+                            -half_resolution.y + (outgoing_belt_length / 2.),
+                        ),
+                        length: outgoing_belt_length,
+                        alignment,
+                        tag: ConveyorLabelTag::Outgoing(player_index),
+                    });
+                }
+            }
+        }
+
+        // the outer walls sit just outside the window so they butt up against the outgoing belts
+        // rather than overlapping them
+        let outer_left = -half_resolution.x - WALL_WIDTH;
+        let outer_right = half_resolution.x + WALL_WIDTH;
+        let mut walls = vec![
+            Rect::new(
+                outer_left,
+                -half_resolution.y,
+                -half_resolution.x,
+                half_resolution.y,
+            ),
+            Rect::new(
+                half_resolution.x,
+                -half_resolution.y,
+                outer_right,
+                half_resolution.y,
+            ),
+            Rect::new(
+                outer_left,
+                -half_resolution.y - WALL_WIDTH,
+                outer_right,
+                -half_resolution.y,
+            ),
+        ];
+        for side in [-1., 1.] {
+            // divider between the walkway and the gap above the incoming belts, flush with the
+            // belt's outer edge
+            let divider = Rect::new(
+                side * conveyor_width,
+                incoming_belt_top,
+                side * (conveyor_width - WALL_WIDTH),
+                half_resolution.y,
+            );
+            // office wall, from the divider out to the side wall
+            let office_wall = Rect::new(
+                side * conveyor_width,
+                office_wall_y,
+                side * outer_right,
+                office_wall_y + WALL_WIDTH,
+            );
+            walls.push(divider);
+            walls.push(office_wall);
+        }
+
+        let mut floor = Vec::new();
+        for side in [-1., 1.] {
+            // between the belts, up to the office wall
+            floor.push(Rect::new(
+                side * (half_resolution.x - conveyor_width),
+                -half_resolution.y,
+                side * conveyor_width,
+                office_wall_y,
+            ));
+            // above the outgoing belt
+            floor.push(Rect::new(
+                side * half_resolution.x,
+                half_resolution.y - office_height,
+                side * (half_resolution.x - conveyor_width),
+                office_wall_y,
+            ));
+        }
+        floor.push(walkway);
+
+        let starts = [PlayAreaAligment::Left, PlayAreaAligment::Right]
+            .into_iter()
+            .map(|side| {
+                (
+                    side,
+                    player_start_position(side, app_config, game_config).truncate(),
+                )
+            })
+            .collect();
+
+        Self {
+            play_area,
+            office,
+            walkway,
+            restricted_zone,
+            displays: DisplayLayout::new(office.size(), game_config),
+            conveyor_width,
+            conveyors,
+            walls,
+            interior_walls: layout_config
+                .interior_walls
+                .iter()
+                .map(InteriorWall::new)
+                .collect(),
+            floor,
+            starts,
+            min_belt_length: game_config.package_config.size * MIN_BELT_LENGTH_IN_PACKAGES,
+            player_size: game_config.player_config.size,
+            pickup_radius: game_config.player_config.pickup_radius(),
+        }
+    }
+
+    /// The tutorial's layout, only `side`'s two belts are kept. The others are walled over where
+    /// they stood, so the floor is the same as a normal game's.
+    pub fn for_tutorial(mut self, side: PlayAreaAligment) -> Self {
+        let (kept, walled_over): (Vec<_>, Vec<_>) = self
+            .conveyors
+            .into_iter()
+            .partition(|conveyor| conveyor.alignment == side);
+        self.walls.extend(
+            walled_over
+                .iter()
+                .map(|conveyor| conveyor.bounds(self.conveyor_width)),
+        );
+        self.conveyors = kept;
+        self
+    }
+
+    /// Spawns the belts and walls, the floor only exists as far as the layout checks go.
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        ctx: &mut SpawnContext,
+        blinker_colours: BlinkerColours,
+    ) {
+        for conveyor in &self.conveyors {
+            ConveyorSpawner::new(conveyor.tag)
+                .at(conveyor.position.extend(0.))
+                .with_length(conveyor.length)
+                .aligned(conveyor.alignment)
+                .with_blinker_colours(blinker_colours)
+                .spawn(commands, ctx);
+        }
+
+        for wall in &self.walls {
+            commands.spawn((
+                RigidBody::Fixed,
+                TransformBundle::from_transform(Transform::from_translation(
+                    wall.center().extend(0.),
+                )),
+                Collider::cuboid(wall.width() / 2., wall.height() / 2.),
+                WallTag,
+            ));
+        }
+        for wall in &self.interior_walls {
+            commands.spawn((
+                RigidBody::Fixed,
+                TransformBundle::from_transform(
+                    Transform::from_translation(wall.center.extend(0.))
+                        .with_rotation(Quat::from_rotation_z(wall.rotation)),
+                ),
+                Collider::cuboid(wall.half_size.x, wall.half_size.y),
+                WallTag,
+            ));
+        }
+    }
+
+    /// Hands each outgoing belt to whoever now works its side.
+    pub fn assign_sides(&mut self, sides: &SideAssignment) {
+        for conveyor in &mut self.conveyors {
+            if let ConveyorLabelTag::Outgoing(owner) = &mut conveyor.tag {
+                if let Some(player_index) = player_on_side(sides, conveyor.alignment) {
+                    *owner = player_index;
+                }
+            }
+        }
+    }
+
+    fn is_solid(&self, point: Vec2) -> bool {
+        self.walls.iter().any(|wall| wall.contains(point))
+            || self.interior_walls.iter().any(|wall| wall.contains(point))
+            || self
+                .conveyors
+                .iter()
+                .any(|conveyor| conveyor.bounds(self.conveyor_width).contains(point))
+    }
+
+    /// The office, walkway and belts have to fit the resolution before anything else about the
+    /// layout means much.
+    fn validate_fit(&self) -> anyhow::Result<()> {
+        let resolution = self.play_area.size();
+        for conveyor in &self.conveyors {
+            if conveyor.length < self.min_belt_length {
+                anyhow::bail!(
+                    "a {}x{} base resolution leaves the {:?} conveyor {} long, it needs at least \
+                     {} after the office and walkway",
+                    resolution.x,
+                    resolution.y,
+                    conveyor.tag,
+                    conveyor.length,
+                    self.min_belt_length
+                );
+            }
+            let bounds = conveyor.bounds(self.conveyor_width);
+            if !self.play_area.contains(bounds.min) || !self.play_area.contains(bounds.max) {
+                anyhow::bail!(
+                    "the {:?} conveyor at {:?} runs outside the {}x{} play area",
+                    conveyor.tag,
+                    conveyor.position,
+                    resolution.x,
+                    resolution.y
+                );
+            }
+            for other in [self.office, self.walkway] {
+                let overlap = other.intersect(bounds);
+                if !overlap.is_empty() && overlap.width() * overlap.height() > f32::EPSILON {
+                    anyhow::bail!(
+                        "the {:?} conveyor at {:?} runs into {:?}",
+                        conveyor.tag,
+                        conveyor.position,
+                        other
+                    );
+                }
+            }
+        }
+
+        for floor in &self.floor {
+            if floor.width() < self.player_size || floor.height() < self.player_size {
+                anyhow::bail!(
+                    "a {}x{} base resolution leaves floor {:?} too small for a player",
+                    resolution.x,
+                    resolution.y,
+                    floor
+                );
+            }
+        }
+
+        let office = Rect::from_center_size(Vec2::ZERO, self.office.size());
+        let displays = self.displays.all().collect::<Vec<_>>();
+        for (i, (name, display)) in displays.iter().enumerate() {
+            if !office.contains(display.min) || !office.contains(display.max) {
+                anyhow::bail!("the {} display {:?} doesn't fit the office", name, display);
+            }
+            for (other_name, other) in &displays[i + 1..] {
+                if !display.intersect(*other).is_empty() {
+                    anyhow::bail!("the {} and {} displays overlap", name, other_name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a player could stand at `position` without touching anything solid.
+    fn body_fits(&self, position: Vec2) -> bool {
+        let body = Rect::from_center_size(position, Vec2::splat(self.player_size));
+        self.is_legal_position(position)
+            && !self.walls.iter().any(|wall| rects_overlap(*wall, body))
+            && !self.interior_walls.iter().any(|wall| wall.overlaps(body))
+            && !self
+                .conveyors
+                .iter()
+                .any(|conveyor| rects_overlap(conveyor.bounds(self.conveyor_width), body))
+    }
+
+    /// Every side with an outgoing belt has to be able to walk from its start to that belt and to
+    /// an incoming one. Flood fills a grid laid from the start, a belt counts as reached once a
+    /// cell is within pickup range of it.
+    fn validate_paths(&self) -> anyhow::Result<()> {
+        let step = self.player_size * PATH_GRID_STEP;
+        for (side, start) in &self.starts {
+            let outgoing = self
+                .conveyors
+                .iter()
+                .filter(|conveyor| {
+                    conveyor.alignment == *side
+                        && matches!(conveyor.tag, ConveyorLabelTag::Outgoing(_))
+                })
+                .collect::<Vec<_>>();
+            if outgoing.is_empty() {
+                continue;
+            }
+            if !self.body_fits(*start) {
+                anyhow::bail!(
+                    "a player starting on the {:?} side at {:?} has no room to stand",
+                    side,
+                    start
+                );
+            }
+
+            let cell_position =
+                |(column, row): (i32, i32)| *start + Vec2::new(column as f32, row as f32) * step;
+            let mut reached = HashSet::from([(0, 0)]);
+            let mut queue = VecDeque::from([(0, 0)]);
+            while let Some((column, row)) = queue.pop_front() {
+                for next in [
+                    (column - 1, row),
+                    (column + 1, row),
+                    (column, row - 1),
+                    (column, row + 1),
+                ] {
+                    if !reached.contains(&next) && self.body_fits(cell_position(next)) {
+                        reached.insert(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            let can_reach = |conveyor: &ConveyorPlacement| {
+                let bounds = conveyor.bounds(self.conveyor_width);
+                reached.iter().any(|cell| {
+                    let reach = Rect::from_center_half_size(
+                        cell_position(*cell),
+                        Vec2::splat(self.pickup_radius),
+                    );
+                    rects_overlap(reach, bounds)
+                })
+            };
+
+            for conveyor in outgoing {
+                if !can_reach(conveyor) {
+                    anyhow::bail!(
+                        "a player starting on the {:?} side at {:?} can't walk to the {:?} \
+                         conveyor at {:?}, check the layout's interior walls and belts",
+                        side,
+                        start,
+                        conveyor.tag,
+                        conveyor.position
+                    );
+                }
+            }
+            if !self
+                .conveyors
+                .iter()
+                .filter(|conveyor| matches!(conveyor.tag, ConveyorLabelTag::Incoming))
+                .any(can_reach)
+            {
+                anyhow::bail!(
+                    "a player starting on the {:?} side at {:?} can't walk to any incoming \
+                     conveyor, check the layout's interior walls and belts",
+                    side,
+                    start
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The layout must fit the resolution, every floor edge must be backed by a wall, a belt or
+    /// more floor, no wall may overlap a belt, and each player has to be able to walk to the
+    /// belts they work.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.validate_fit()?;
+        for wall in &self.walls {
+            for conveyor in &self.conveyors {
+                let overlap = wall.intersect(conveyor.bounds(self.conveyor_width));
+                if !overlap.is_empty() && overlap.width() * overlap.height() > f32::EPSILON {
+                    anyhow::bail!(
+                        "wall {:?} overlaps the {:?} conveyor at {:?}",
+                        wall,
+                        conveyor.tag,
+                        conveyor.position
+                    );
+                }
+            }
+        }
+        for wall in &self.interior_walls {
+            for conveyor in &self.conveyors {
+                if wall.overlaps(conveyor.bounds(self.conveyor_width)) {
+                    anyhow::bail!(
+                        "the interior wall at {:?} overlaps the {:?} conveyor at {:?}",
+                        wall.center,
+                        conveyor.tag,
+                        conveyor.position
+                    );
+                }
+            }
+        }
+
+        for floor in &self.floor {
+            let probes = [
+                (
+                    Vec2::new(floor.min.x, floor.min.y),
+                    Vec2::new(floor.max.x, floor.min.y),
+                    Vec2::NEG_Y,
+                ),
+                (
+                    Vec2::new(floor.min.x, floor.max.y),
+                    Vec2::new(floor.max.x, floor.max.y),
+                    Vec2::Y,
+                ),
+                (
+                    Vec2::new(floor.min.x, floor.min.y),
+                    Vec2::new(floor.min.x, floor.max.y),
+                    Vec2::NEG_X,
+                ),
+                (
+                    Vec2::new(floor.max.x, floor.min.y),
+                    Vec2::new(floor.max.x, floor.max.y),
+                    Vec2::X,
+                ),
+            ];
+            for (start, end, outward) in probes {
+                let steps = start.distance(end).ceil() as usize;
+                for step in 0..=steps {
+                    let point = start.lerp(end, step as f32 / steps.max(1) as f32)
+                        + (outward * LEAK_PROBE_DISTANCE);
+                    if !self.is_solid(point)
+                        && !self.floor.iter().any(|other| other.contains(point))
+                    {
+                        anyhow::bail!("arena has a gap at {:?} next to floor {:?}", point, floor);
+                    }
+                }
+            }
+        }
+        self.validate_paths()
+    }
+
+    pub fn is_legal_position(&self, position: Vec2) -> bool {
+        !self.is_solid(position) && self.floor.iter().any(|floor| floor.contains(position))
+    }
+
+    /// Nearest point a body of `half_size` can stand on without touching anything solid.
+    pub fn nearest_legal_position(&self, position: Vec2, half_size: f32) -> Vec2 {
+        self.floor
+            .iter()
+            .map(|floor| {
+                let inset = Rect::from_center_size(
+                    floor.center(),
+                    (floor.size() - Vec2::splat(half_size * 2.)).max(Vec2::ZERO),
+                );
+                position.clamp(inset.min, inset.max)
+            })
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+            .unwrap_or(position)
+    }
+}
+
+/// Both controllers move in the same step and each only sees where the other was, so players
+/// walking into each other can end up overlapping. They're pushed apart evenly along the
+/// shallower axis, unless that would put one of them somewhere illegal, then the other takes
+/// the whole push.
+pub fn separate_players(
+    arena_layout: Res<ArenaLayout>,
+    game_config: Res<GameConfig>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    if !game_config.players_collide() {
+        return;
+    }
+    let mut players = player_query.iter_mut().collect::<Vec<_>>();
+    let [a, b] = players.as_mut_slice() else {
+        return;
+    };
+    let size = game_config.player_config.size;
+    let delta = b.translation.truncate() - a.translation.truncate();
+    let overlap = Vec2::splat(size) - delta.abs();
+    if overlap.x <= 0. || overlap.y <= 0. {
+        return;
+    }
+    let push = if overlap.x < overlap.y {
+        Vec2::new(overlap.x * if delta.x < 0. { -1. } else { 1. }, 0.)
+    } else {
+        Vec2::new(0., overlap.y * if delta.y < 0. { -1. } else { 1. })
+    };
+    let (a_push, b_push) = if !arena_layout.is_legal_position(a.translation.truncate() - push / 2.)
+    {
+        (Vec2::ZERO, push)
+    } else if !arena_layout.is_legal_position(b.translation.truncate() + push / 2.) {
+        (push, Vec2::ZERO)
+    } else {
+        (push / 2., push / 2.)
+    };
+    a.translation -= a_push.extend(0.);
+    b.translation += b_push.extend(0.);
+}
+
+/// Safety net for the collision resolution, a player that ends up inside a wall or off the floor
+/// is eased back to the nearest place they can stand.
+pub fn contain_players(
+    arena_layout: Res<ArenaLayout>,
+    game_config: Res<GameConfig>,
+    time: Res<Time>,
+    mut player_query: Query<(&Player, &mut Transform)>,
+) {
+    let half_size = game_config.player_config.size / 2.;
+    for (player_info, mut player_transform) in &mut player_query {
+        let position = player_transform.translation.truncate();
+        if arena_layout.is_legal_position(position) {
+            continue;
+        }
+        let target = arena_layout.nearest_legal_position(position, half_size);
+        let t = (game_config.player_config.containment_return_rate * time.delta_seconds()).min(1.);
+        debug!(
+            "{:?} outside the play area at {:?}, returning to {:?}",
+            player_info.player_index, position, target
+        );
+        player_transform.translation = position
+            .lerp(target, t)
+            .extend(player_transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout_at(width: u32, height: u32) -> ArenaLayout {
+        let app_config = AppConfig {
+            base_resolution: UVec2::new(width, height),
+            ..default()
+        };
+        ArenaLayout::new(
+            &app_config,
+            &GameConfig::default(),
+            &SideAssignment::default(),
+        )
+    }
+
+    #[test]
+    fn walls_meet_the_belts_without_gaps_or_overlaps() {
+        for (width, height) in [(1280, 720), (1920, 1080), (1024, 768)] {
+            let layout = layout_at(width, height);
+            if let Err(error) = layout.validate() {
+                panic!("{}x{}: {}", width, height, error);
+            }
+            for wall in &layout.walls {
+                for conveyor in &layout.conveyors {
+                    assert!(
+                        !rects_overlap(*wall, conveyor.bounds(layout.conveyor_width)),
+                        "{}x{}: wall {:?} overlaps the {:?} conveyor",
+                        width,
+                        height,
+                        wall,
+                        conveyor.tag
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn players_in_geometry_are_eased_back_onto_the_floor() {
+        let layout = layout_at(1280, 720);
+        let half_size = GameConfig::default().player_config.size / 2.;
+        let stuck = layout
+            .walls
+            .iter()
+            .map(|wall| wall.center())
+            .chain(layout.conveyors.iter().map(|conveyor| conveyor.position));
+        for position in stuck {
+            let eased = layout.nearest_legal_position(position, half_size);
+            assert!(
+                layout.is_legal_position(eased),
+                "{:?} was eased to {:?}",
+                position,
+                eased
+            );
+        }
+    }
+    #[test]
+    fn layouts_fit_from_800x600_to_ultrawide() {
+        let inside =
+            |outer: Rect, inner: Rect| outer.contains(inner.min) && outer.contains(inner.max);
+        for (width, height) in [
+            (800, 600),
+            (1024, 768),
+            (1280, 720),
+            (1280, 1024),
+            (1600, 900),
+            (1920, 1080),
+            (1920, 1200),
+            (2560, 1080),
+            (2560, 1440),
+            (3440, 1440),
+        ] {
+            let layouts = [
+                ("game", layout_at(width, height)),
+                (
+                    "left tutorial",
+                    layout_at(width, height).for_tutorial(PlayAreaAligment::Left),
+                ),
+                (
+                    "right tutorial",
+                    layout_at(width, height).for_tutorial(PlayAreaAligment::Right),
+                ),
+            ];
+            for (name, layout) in layouts {
+                if let Err(error) = layout.validate() {
+                    panic!("{} layout at {}x{}: {}", name, width, height, error);
+                }
+                let rects = [layout.office, layout.walkway, layout.restricted_zone]
+                    .into_iter()
+                    .chain(
+                        layout
+                            .conveyors
+                            .iter()
+                            .map(|conveyor| conveyor.bounds(layout.conveyor_width)),
+                    )
+                    .chain(layout.floor.iter().copied())
+                    .collect::<Vec<_>>();
+                for rect in rects {
+                    assert!(
+                        rect.width() > 0. && rect.height() > 0.,
+                        "{} layout at {}x{}: {:?} has no area",
+                        name,
+                        width,
+                        height,
+                        rect
+                    );
+                    assert!(
+                        inside(layout.play_area, rect),
+                        "{} layout at {}x{}: {:?} is outside the play area",
+                        name,
+                        width,
+                        height,
+                        rect
+                    );
+                }
+                let office = Rect::from_center_size(Vec2::ZERO, layout.office.size());
+                for (display_name, display) in layout.displays.all() {
+                    assert!(
+                        inside(office, display),
+                        "{} layout at {}x{}: the {} display is off the office",
+                        name,
+                        width,
+                        height,
+                        display_name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resolutions_too_small_for_the_arena_are_rejected() {
+        for (width, height) in [(640, 360), (500, 600)] {
+            assert!(
+                layout_at(width, height).validate().is_err(),
+                "{}x{} was accepted",
+                width,
+                height
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::TextureTarget;
+
+/// Which texture pack slot an entity's image was loaded for, so a failed load can be reported
+/// and swapped for a placeholder.
+#[derive(Component, Clone)]
+pub struct TrackedTexture {
+    pub target: TextureTarget,
+    pub source: String,
+    pub path: String,
+}
+
+impl TrackedTexture {
+    pub fn new(target: TextureTarget, source: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            target,
+            source: source.into(),
+            path: path.into(),
+        }
+    }
+}
+
+/// Every texture the game has spawned with, split by how its load went. Loaded handles are kept
+/// so textures shared by short lived entities, like packages, aren't reloaded every wave.
+#[derive(Resource, Default)]
+pub struct LoadedGameAssets {
+    pub loaded: HashMap<AssetId<Image>, Handle<Image>>,
+    pub failed: HashSet<AssetId<Image>>,
+}
+
+impl TextureTarget {
+    /// Solid colour drawn in place of a texture that failed to load, picked so every target
+    /// stays distinguishable from the floor and from each other.
+    pub fn placeholder_colour(&self) -> Color {
+        match self {
+            TextureTarget::AllPlayers => Color::rgb(0.85, 0.85, 0.9),
+            TextureTarget::Supervisor => Color::rgb(0.6, 0.15, 0.15),
+            TextureTarget::Package => Color::rgb(0.8, 0.6, 0.35),
+            TextureTarget::Conveyor => Color::rgb(0.35, 0.35, 0.4),
+            TextureTarget::Background => Color::rgb(0.15, 0.15, 0.18),
+            TextureTarget::SupervisorOffice => Color::rgb(0.3, 0.25, 0.2),
+            TextureTarget::ScoreDisplay => Color::rgb(0.08, 0.08, 0.08),
+            TextureTarget::InputGlyphs => Color::rgb(0.2, 0.2, 0.25),
+            TextureTarget::Forklift => Color::rgb(0.9, 0.7, 0.1),
+            TextureTarget::Emotes => Color::rgb(0.95, 0.95, 0.95),
+            TextureTarget::BeltArrow => Color::rgb(0.9, 0.9, 0.9),
+        }
+    }
+}
+
+pub fn verify_game_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loaded_assets: ResMut<LoadedGameAssets>,
+    mut tracked_query: Query<(Entity, &TrackedTexture, &mut Handle<Image>, &mut Sprite)>,
+) {
+    for (entity, tracked_texture, mut image_handle, mut sprite) in &mut tracked_query {
+        let image_id = image_handle.id();
+        let failed = if loaded_assets.failed.contains(&image_id) {
+            true
+        } else {
+            match asset_server.get_load_state(image_id) {
+                Some(LoadState::Loaded) => {
+                    loaded_assets.loaded.insert(image_id, image_handle.clone());
+                    commands.entity(entity).remove::<TrackedTexture>();
+                    continue;
+                }
+                Some(LoadState::Failed) => {
+                    loaded_assets.failed.insert(image_id);
+                    warn!(
+                        "missing texture for {:?} from {}: '{}', drawing a placeholder instead",
+                        tracked_texture.target, tracked_texture.source, tracked_texture.path
+                    );
+                    true
+                }
+                _ => false,
+            }
+        };
+        if !failed {
+            continue;
+        }
+
+        // the default image is plain white, so the sprite colour becomes the whole quad
+        *image_handle = Handle::default();
+        sprite.color = tracked_texture.target.placeholder_colour();
+        let label_size = sprite
+            .custom_size
+            .map_or(20., |size| (size.min_element() * 0.3).clamp(6., 20.));
+        commands
+            .entity(entity)
+            .remove::<(TrackedTexture, TextureAtlas)>()
+            .with_children(|builder| {
+                builder.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        format!("{:?}", tracked_texture.target),
+                        TextStyle {
+                            font_size: label_size,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(Vec3::new(0., 0., 0.1)),
+                    ..default()
+                });
+            });
+    }
+}
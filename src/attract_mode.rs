@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{GameConfig, GameState, PlayerBrain, ScoreChanged, SessionStarted};
+
+#[derive(Resource, Default)]
+pub struct AttractMode {
+    pub active: bool,
+    seconds_without_input: f32,
+    /// Input is ignored until this runs out, so results aren't skipped by players still playing.
+    hold_seconds: f32,
+}
+
+impl AttractMode {
+    pub fn new(game_config: &GameConfig) -> Self {
+        Self {
+            active: game_config.attract_mode_config.enabled
+                && game_config.attract_mode_config.start_in_attract_mode,
+            seconds_without_input: 0.,
+            hold_seconds: 0.,
+        }
+    }
+
+    /// Starts the demo straight away rather than waiting for the players to go idle.
+    pub fn start(&mut self, hold_seconds: f32) {
+        self.active = true;
+        self.seconds_without_input = 0.;
+        self.hold_seconds = hold_seconds;
+    }
+}
+
+// runs between reading the controllers and the brains overwriting them, so the control state
+// only holds real input here
+pub fn update_attract_mode(
+    time: Res<Time>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    mut brain_query: Query<&mut PlayerBrain>,
+    mut session_events: EventWriter<SessionStarted>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    let has_real_input = game_state.player_controls.values().any(|player_control| {
+        player_control
+            .state
+            .values()
+            .any(|button_state| button_state.pressed)
+    });
+
+    if attract_mode.active {
+        attract_mode.hold_seconds = (attract_mode.hold_seconds - time.delta_seconds()).max(0.);
+        if has_real_input && attract_mode.hold_seconds <= 0. {
+            info!("Input received, leaving attract mode");
+            attract_mode.active = false;
+            attract_mode.seconds_without_input = 0.;
+            // scores from the demo shouldn't carry over to the people now playing
+            game_state.player_scores = default();
+            for (player_index, _) in game_state.player_scores.iter() {
+                score_events.send(ScoreChanged {
+                    player: player_index,
+                });
+            }
+            session_events.send(SessionStarted);
+        }
+    } else if has_real_input {
+        attract_mode.seconds_without_input = 0.;
+    } else {
+        attract_mode.seconds_without_input += time.delta_seconds();
+        if game_config.attract_mode_config.enabled
+            && attract_mode.seconds_without_input >= game_config.attract_mode_config.idle_seconds
+        {
+            info!("No input received, starting attract mode");
+            attract_mode.active = true;
+        }
+    }
+
+    for mut brain in &mut brain_query {
+        if brain.enabled != attract_mode.active {
+            brain.enabled = attract_mode.active;
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+struct BufferUsage {
+    configured_cap: usize,
+    bytes_per_entry: usize,
+    len: usize,
+    evictions: u64,
+    warned: bool,
+}
+
+/// Every in-memory buffer that grows during play reports here, so a long unattended session can't
+/// quietly grow without limit. When the configured caps together would need more than the memory
+/// budget they are all scaled down by the same fraction.
+#[derive(Resource)]
+pub struct BufferBudgets {
+    budget_bytes: usize,
+    buffers: BTreeMap<&'static str, BufferUsage>,
+}
+
+impl BufferBudgets {
+    pub fn new(budget_mb: f32) -> Self {
+        Self {
+            budget_bytes: (budget_mb.max(0.) * 1024. * 1024.) as usize,
+            buffers: BTreeMap::new(),
+        }
+    }
+
+    fn requested_bytes(&self) -> usize {
+        self.buffers
+            .values()
+            .map(|usage| usage.configured_cap * usage.bytes_per_entry)
+            .sum()
+    }
+
+    /// The cap a buffer should hold itself to, registering it on first use.
+    pub fn cap_for<T>(&mut self, name: &'static str, configured_cap: usize) -> usize {
+        self.buffers.entry(name).or_insert_with(|| BufferUsage {
+            configured_cap,
+            bytes_per_entry: std::mem::size_of::<T>().max(1),
+            len: 0,
+            evictions: 0,
+            warned: false,
+        });
+
+        let requested_bytes = self.requested_bytes();
+        if requested_bytes <= self.budget_bytes {
+            return configured_cap;
+        }
+        let scale = self.budget_bytes as f64 / requested_bytes as f64;
+        ((configured_cap as f64 * scale) as usize).max(1)
+    }
+
+    /// Records the buffer's size after it has applied its cap, `evicted` being how many entries
+    /// its eviction policy dropped to get there.
+    pub fn report(&mut self, name: &'static str, len: usize, evicted: usize) {
+        let Some(usage) = self.buffers.get_mut(name) else {
+            return;
+        };
+        usage.len = len;
+        usage.evictions += evicted as u64;
+        if evicted > 0 && !usage.warned {
+            usage.warned = true;
+            warn!(
+                "{} reached its cap of {} entries, dropping the oldest",
+                name, len
+            );
+        }
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.buffers
+            .values()
+            .map(|usage| usage.len * usage.bytes_per_entry)
+            .sum()
+    }
+
+    /// One line per buffer: name, entries held, configured cap, estimated bytes and evictions.
+    pub fn summary(&self) -> impl Iterator<Item = String> + '_ {
+        self.buffers.iter().map(|(name, usage)| {
+            format!(
+                "{}: {}/{} entries, ~{} bytes, {} evicted",
+                name,
+                usage.len,
+                usage.configured_cap,
+                usage.len * usage.bytes_per_entry,
+                usage.evictions
+            )
+        })
+    }
+}
+
+/// Drops the oldest entries, those at the front, until `buffer` fits `cap`. Returns how many went.
+pub fn evict_oldest<T>(buffer: &mut Vec<T>, cap: usize) -> usize {
+    let excess = buffer.len().saturating_sub(cap);
+    buffer.drain(..excess);
+    excess
+}
+
+pub fn log_buffer_budgets_on_exit(
+    buffer_budgets: Res<BufferBudgets>,
+    mut exit_events: EventReader<bevy::app::AppExit>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    info!(
+        "Observability buffers held ~{} bytes at exit",
+        buffer_budgets.total_bytes()
+    );
+    for line in buffer_budgets.summary() {
+        info!("  {}", line);
+    }
+}
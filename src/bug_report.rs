@@ -0,0 +1,354 @@
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Write as _},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    log::{
+        tracing_subscriber::{
+            layer::{Context, SubscriberExt},
+            Layer,
+        },
+        BoxedSubscriber,
+    },
+    prelude::*,
+    utils::tracing::{
+        field::{Field, Visit},
+        Event, Subscriber,
+    },
+};
+
+use crate::{AppConfig, Config, DataOutputPolicy, GameState, MatchState};
+
+/// Bevy's own default filter, kept so the renderer stays quiet whatever modules are turned up.
+const BASE_LOG_FILTER: &str = "wgpu=error,naga=warn";
+/// How often the game state summary kept for a crash is refreshed.
+const SUMMARY_INTERVAL_SECONDS: f32 = 1.;
+
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LOG_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+static REPORT_CONTEXT: Mutex<Option<BugReportContext>> = Mutex::new(None);
+static STATE_SUMMARY: Mutex<String> = Mutex::new(String::new());
+
+/// What a bug report says about the session besides its log, set once the config is read.
+#[derive(Clone)]
+pub struct BugReportContext {
+    /// Reports are written here, next to the config that was loaded.
+    pub directory: PathBuf,
+    pub config_hash: u64,
+    pub rng_seed: Option<u64>,
+    pub output_policy: DataOutputPolicy,
+}
+
+impl BugReportContext {
+    pub fn new(
+        config: &Config,
+        config_directory: &Path,
+        output_policy: DataOutputPolicy,
+    ) -> anyhow::Result<Self> {
+        // through a toml value so tables come out in sorted order, whatever maps the config uses
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        toml::Value::try_from(config)?.to_string().hash(&mut hasher);
+        Ok(Self {
+            directory: config_directory.to_path_buf(),
+            config_hash: hasher.finish(),
+            rng_seed: config.app.rng_seed,
+            output_policy,
+        })
+    }
+
+    /// Makes this the context the panic hook reports with.
+    pub fn install(&self) {
+        *REPORT_CONTEXT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(self.clone());
+    }
+}
+
+/// The log filter for `LogPlugin`, with each configured module at its own level.
+pub fn log_filter(app_config: &AppConfig) -> String {
+    let mut filter = BASE_LOG_FILTER.to_string();
+    for (module, level) in &app_config.log_levels {
+        filter.push_str(&format!(",{}={}", module, level));
+    }
+    filter
+}
+
+/// Sets how many of the latest log lines are kept for a report, 0 keeps none.
+pub fn set_log_buffer_lines(lines: usize) {
+    LOG_CAPACITY.store(lines, Ordering::Relaxed);
+    let mut log_lines = LOG_LINES.lock().unwrap_or_else(PoisonError::into_inner);
+    while log_lines.len() > lines {
+        log_lines.pop_front();
+    }
+}
+
+fn push_log_line(line: String) {
+    let capacity = LOG_CAPACITY.load(Ordering::Relaxed);
+    if capacity == 0 {
+        return;
+    }
+    let mut log_lines = LOG_LINES.lock().unwrap_or_else(PoisonError::into_inner);
+    if log_lines.len() >= capacity {
+        log_lines.pop_front();
+    }
+    log_lines.push_back(line);
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Copies every log event that gets past the filter into the ring buffer.
+pub struct RingBufferLayer;
+
+impl<S: Subscriber> Layer<S> for RingBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        push_log_line(format!(
+            "{:>5} {}: {}{}",
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        ));
+    }
+}
+
+/// For `LogPlugin::update_subscriber`.
+pub fn add_ring_buffer_layer(subscriber: BoxedSubscriber) -> BoxedSubscriber {
+    Box::new(subscriber.with(RingBufferLayer))
+}
+
+/// Writes a report whenever the game panics, then carries on with the usual panic output. Meant
+/// to be installed before anything else in main, until the config is read reports go to the
+/// working directory.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        match write_bug_report(&format!("panic: {}", panic_info)) {
+            Ok(Some(path)) => eprintln!("wrote bug report to {}", path.display()),
+            Ok(None) => {}
+            Err(error) => eprintln!("couldn't write a bug report: {}", error),
+        }
+        default_hook(panic_info);
+    }));
+}
+
+/// The log buffer, session details and the latest game state in a timestamped file. Returns
+/// `None` when this instance isn't allowed to write files.
+pub fn write_bug_report(reason: &str) -> std::io::Result<Option<PathBuf>> {
+    let context = REPORT_CONTEXT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if context
+        .as_ref()
+        .is_some_and(|context| !context.output_policy.allows_writes())
+    {
+        return Ok(None);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let mut report = format!("reason: {}\ntimestamp: {}\n", reason, timestamp);
+    match &context {
+        Some(context) => {
+            let _ = writeln!(report, "config hash: {:016x}", context.config_hash);
+            let _ = writeln!(report, "rng seed: {:?}", context.rng_seed);
+        }
+        None => report.push_str("config: not read yet\n"),
+    }
+    // a panic while either lock is held would wait forever here, so they're only tried
+    if let Ok(state_summary) = STATE_SUMMARY.try_lock() {
+        let _ = writeln!(report, "game state: {}", state_summary);
+    }
+    report.push_str("\nrecent log:\n");
+    if let Ok(log_lines) = LOG_LINES.try_lock() {
+        for line in log_lines.iter() {
+            report.push_str(line);
+            report.push('\n');
+        }
+    }
+
+    let directory = context.map_or_else(|| PathBuf::from("."), |context| context.directory);
+    let path = directory.join(format!("bug_report_{}.txt", timestamp));
+    std::fs::write(&path, report)?;
+    Ok(Some(path))
+}
+
+/// Keeps a one line summary of the game ready for a panic report, which can't reach the world.
+pub fn update_bug_report_summary(
+    real_time: Res<Time<Real>>,
+    game_state: Res<GameState>,
+    match_state: Res<MatchState>,
+    mut since_summary: Local<f32>,
+) {
+    *since_summary += real_time.delta_seconds();
+    if *since_summary < SUMMARY_INTERVAL_SECONDS {
+        return;
+    }
+    *since_summary = 0.;
+
+    let mut summary = format!("round {}", match_state.round);
+    for (player_index, player_score) in game_state.player_scores.iter() {
+        let _ = write!(
+            summary,
+            ", {:?} {} x{:.1}",
+            player_index,
+            player_score.score.points(),
+            player_score.multiplier
+        );
+    }
+    let _ = write!(
+        summary,
+        ", next wave in {:.1}s",
+        game_state.package_wave_timer.remaining().as_secs_f32()
+    );
+    *STATE_SUMMARY.lock().unwrap_or_else(PoisonError::into_inner) = summary;
+}
+
+pub fn report_bug_on_key(keyboard_input: Res<ButtonInput<KeyCode>>, app_config: Res<AppConfig>) {
+    if !keyboard_input.just_pressed(app_config.bug_report_key) {
+        return;
+    }
+    match write_bug_report("reported from the keyboard") {
+        Ok(Some(path)) => info!("Wrote bug report to {}", path.display()),
+        Ok(None) => warn!("Another instance owns this config, no bug report written"),
+        Err(error) => error!("Couldn't write a bug report: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{log::tracing_subscriber, utils::tracing::subscriber::with_default};
+
+    use super::*;
+
+    fn log_lines() -> Vec<String> {
+        LOG_LINES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn each_configured_module_gets_its_level() {
+        let mut app_config = AppConfig::default();
+        app_config.log_levels.clear();
+        assert_eq!(log_filter(&app_config), BASE_LOG_FILTER);
+
+        app_config
+            .log_levels
+            .insert("play_nice::conveyor".to_string(), "trace".to_string());
+        app_config
+            .log_levels
+            .insert("bevy_render".to_string(), "warn".to_string());
+        assert_eq!(
+            log_filter(&app_config),
+            format!(
+                "{},bevy_render=warn,play_nice::conveyor=trace",
+                BASE_LOG_FILTER
+            )
+        );
+    }
+
+    // the buffer and report context are global, so everything touching them is in one test
+    #[test]
+    fn reports_carry_the_latest_log_lines() {
+        let subscriber = tracing_subscriber::registry().with(RingBufferLayer);
+        with_default(subscriber, || {
+            set_log_buffer_lines(0);
+            info!("before the buffer");
+            assert!(log_lines().is_empty());
+
+            set_log_buffer_lines(3);
+            for line in 0..5 {
+                info!(belt = line, "line {}", line);
+            }
+            let lines = log_lines();
+            assert_eq!(lines.len(), 3);
+            for (line, logged) in (2..5).zip(&lines) {
+                assert!(
+                    logged.starts_with(" INFO play_nice::bug_report"),
+                    "{}",
+                    logged
+                );
+                assert!(
+                    logged.ends_with(&format!(": line {} belt={}", line, line)),
+                    "{}",
+                    logged
+                );
+            }
+
+            // shrinking keeps the newest
+            set_log_buffer_lines(2);
+            assert_eq!(log_lines(), lines[1..]);
+        });
+
+        let directory = std::env::temp_dir()
+            .join(format!("play-nice-test-{}", std::process::id()))
+            .join("bug-report");
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut config = Config::default();
+        config.app.rng_seed = Some(2116);
+        let context = BugReportContext::new(&config, &directory, DataOutputPolicy::Full).unwrap();
+        assert_eq!(
+            context.config_hash,
+            BugReportContext::new(&config, &directory, DataOutputPolicy::Full)
+                .unwrap()
+                .config_hash
+        );
+        context.install();
+
+        let path = write_bug_report("a test").unwrap().unwrap();
+        assert_eq!(path.parent(), Some(directory.as_path()));
+        let report = std::fs::read_to_string(&path).unwrap();
+        assert!(report.starts_with("reason: a test\n"), "{}", report);
+        assert!(report.contains(&format!("config hash: {:016x}\n", context.config_hash)));
+        assert!(report.contains("rng seed: Some(2116)\n"));
+        let recent_log = report.split_once("\nrecent log:\n").unwrap().1;
+        assert_eq!(
+            recent_log.lines().collect::<Vec<_>>(),
+            log_lines().iter().map(String::as_str).collect::<Vec<_>>()
+        );
+
+        // another instance owns the config, it mustn't write anywhere
+        BugReportContext::new(&config, &directory, DataOutputPolicy::Restricted)
+            .unwrap()
+            .install();
+        std::fs::remove_file(&path).unwrap();
+        assert!(write_bug_report("a test").unwrap().is_none());
+        assert!(std::fs::read_dir(&directory).unwrap().next().is_none());
+
+        *REPORT_CONTEXT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+        set_log_buffer_lines(0);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::{prelude::*, render::camera::Viewport, window::PrimaryWindow};
+
+use crate::{AppConfig, Player, PlayerIndex};
+
+/// Where a camera rests before shake is added on top.
+#[derive(Component, Default)]
+pub struct CameraAnchor {
+    pub position: Vec2,
+}
+
+/// A split screen camera that keeps one player in view.
+#[derive(Component)]
+pub struct CameraFollow {
+    pub player: PlayerIndex,
+}
+
+/// Root of a bevy_ui score readout that belongs to one player's half in split screen, the team
+/// score rides along with Player1's half next to the seam.
+#[derive(Component)]
+pub struct ScoreUiRoot {
+    pub player: PlayerIndex,
+}
+
+/// Left half for Player1, right half for Player2.
+pub fn split_viewport(player: PlayerIndex, physical_size: UVec2) -> Viewport {
+    let half_width = physical_size.x / 2;
+    Viewport {
+        physical_position: match player {
+            PlayerIndex::Player1 => UVec2::ZERO,
+            PlayerIndex::Player2 => UVec2::new(half_width, 0),
+        },
+        physical_size: UVec2::new(
+            match player {
+                PlayerIndex::Player1 => half_width,
+                PlayerIndex::Player2 => physical_size.x - half_width,
+            },
+            physical_size.y,
+        )
+        .max(UVec2::ONE),
+        ..default()
+    }
+}
+
+/// Keeps a camera showing `view_size` of the world from looking past the edges of the arena, an
+/// arena smaller than the view stays centred.
+pub fn clamp_camera_centre(target: Vec2, view_size: Vec2, arena_size: Vec2) -> Vec2 {
+    let max_offset = ((arena_size - view_size) / 2.).max(Vec2::ZERO);
+    target.clamp(-max_offset, max_offset)
+}
+
+pub fn spawn_split_cameras(commands: &mut Commands, app_config: &AppConfig) {
+    let view_size = split_view_size(app_config);
+    for (order, player) in [PlayerIndex::Player1, PlayerIndex::Player2]
+        .into_iter()
+        .enumerate()
+    {
+        let mut camera_bundle = Camera2dBundle::default();
+        camera_bundle.projection.scaling_mode = bevy::render::camera::ScalingMode::Fixed {
+            width: view_size.x,
+            height: view_size.y,
+        };
+        camera_bundle.camera.order = order as isize;
+        camera_bundle.camera.viewport = Some(split_viewport(player, app_config.base_resolution));
+        commands.spawn((
+            camera_bundle,
+            crate::MainCamera,
+            CameraAnchor::default(),
+            CameraFollow { player },
+        ));
+    }
+}
+
+fn split_view_size(app_config: &AppConfig) -> Vec2 {
+    let resolution = app_config.base_resolution.as_vec2();
+    Vec2::new(resolution.x / 2., resolution.y)
+}
+
+pub fn follow_players_with_cameras(
+    time: Res<Time>,
+    app_config: Res<AppConfig>,
+    player_query: Query<(&Player, &Transform)>,
+    mut camera_query: Query<(&CameraFollow, &mut CameraAnchor)>,
+) {
+    let view_size = split_view_size(&app_config);
+    let arena_size = app_config.base_resolution.as_vec2();
+    // framerate independent exponential smoothing
+    let blend = 1. - (-app_config.camera_follow_smoothing * time.delta_seconds()).exp();
+    for (camera_follow, mut camera_anchor) in &mut camera_query {
+        let Some((_, player_transform)) = player_query
+            .iter()
+            .find(|(player_info, _)| player_info.player_index == camera_follow.player)
+        else {
+            continue;
+        };
+
+        let target = clamp_camera_centre(
+            player_transform.translation.truncate(),
+            view_size,
+            arena_size,
+        );
+        camera_anchor.position = camera_anchor.position.lerp(target, blend);
+    }
+}
+
+pub fn update_split_viewports(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&CameraFollow, &mut Camera)>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let physical_size = UVec2::new(window.physical_width(), window.physical_height());
+    for (camera_follow, mut camera) in &mut camera_query {
+        let viewport = split_viewport(camera_follow.player, physical_size);
+        let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+pub fn assign_score_ui_to_cameras(
+    mut commands: Commands,
+    ui_query: Query<(Entity, &ScoreUiRoot), Without<TargetCamera>>,
+    camera_query: Query<(Entity, &CameraFollow)>,
+) {
+    for (ui_entity, ui_root) in &ui_query {
+        if let Some((camera_entity, _)) = camera_query
+            .iter()
+            .find(|(_, camera_follow)| camera_follow.player == ui_root.player)
+        {
+            commands
+                .entity(ui_entity)
+                .insert(TargetCamera(camera_entity));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::{
+    held_packages, GameConfig, GameState, InteractionIntents, KeyAction, MultiplierChanged,
+    Package, Player, Score, ScoreChanged, Stunned,
+};
+
+/// How long the readout takes to count a cashed out multiplier into the score.
+pub const CASH_OUT_COUNT_SECONDS: f32 = 0.5;
+
+/// A cash out the readout is still counting across, the score itself was paid straight away.
+pub struct CashOutCount {
+    pub from_score: Score,
+    pub from_multiplier: f32,
+    pub elapsed_seconds: f32,
+}
+
+impl CashOutCount {
+    pub fn progress(&self) -> f32 {
+        (self.elapsed_seconds / CASH_OUT_COUNT_SECONDS).clamp(0., 1.)
+    }
+}
+
+/// Pressing pickup while sprinting with empty hands and nothing in reach banks the multiplier,
+/// paying `cash_out_points` into the score and dropping it back to 1. Runs after `pickup_package`
+/// so a press that finds a package still picks it up.
+pub fn cash_out(
+    player_query: Query<(&Player, Option<&Children>), Without<Stunned>>,
+    package_query: Query<(), With<Package>>,
+    interaction_intents: Res<InteractionIntents>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    mut multiplier_events: EventWriter<MultiplierChanged>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    let score_config = &game_config.score_config;
+    if !score_config.cash_out_enabled() {
+        return;
+    }
+
+    for (player_info, player_children) in &player_query {
+        let player_index = player_info.player_index;
+        let control_state = &game_state.player_controls[player_index].state;
+        if !control_state[KeyAction::Sprint].pressed()
+            || !control_state[KeyAction::PickupOrThrow].just_pressed()
+            || held_packages(player_children, |child| package_query.contains(child))
+                .next()
+                .is_some()
+            || interaction_intents.has_claim_from(player_index)
+        {
+            continue;
+        }
+
+        let player_score = &game_state.player_scores[player_index];
+        if player_score.cash_out_cooldown > 0. || player_score.multiplier <= 1. {
+            continue;
+        }
+
+        let multiplier = player_score.multiplier;
+        let points = score_config.cash_out_points(multiplier);
+        // a shared team multiplier is spent for everyone, the points go to whoever cashed it
+        for recipient in game_config.game_mode.multiplier_recipients(player_index) {
+            let recipient_score = &mut game_state.player_scores[recipient];
+            recipient_score.cash_out_count = Some(CashOutCount {
+                from_score: recipient_score.score,
+                from_multiplier: recipient_score.multiplier,
+                elapsed_seconds: 0.,
+            });
+            if recipient == player_index {
+                recipient_score.score.add_with_multiplier(points, 1.);
+            }
+            multiplier_events.send(MultiplierChanged {
+                player: recipient,
+                old: recipient_score.reported_multiplier,
+                new: 1.,
+            });
+            recipient_score.multiplier = 1.;
+            recipient_score.reported_multiplier = 1.;
+            recipient_score.cash_out_cooldown = score_config.cashout_cooldown_seconds;
+            score_events.send(ScoreChanged { player: recipient });
+        }
+        info!(
+            "{:?} cashed out x{:.1} for {:.0} points",
+            player_index, multiplier, points
+        );
+    }
+}
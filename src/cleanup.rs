@@ -0,0 +1,130 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::{ecs::event::ManualEventReader, prelude::*, utils::get_short_name};
+
+use crate::{park_package, AppConfig, Conveyor, Package, SessionStarted};
+
+/// Label the audit uses for the atlas layouts it counts alongside the entities.
+const ATLAS_LAYOUTS_LABEL: &str = "TextureAtlasLayout assets";
+
+/// Anything put on the floor, the belts or the screen during play that has no business
+/// outliving its round. Spawn helpers add it, `despawn_round_entities` clears it all out.
+#[derive(Component, Default, Clone, Copy)]
+pub struct RoundScoped;
+
+/// Clears every round scoped entity along with its children when a new session starts. Packages
+/// go back to the pool first, so the belts are emptied too.
+pub fn despawn_round_entities(
+    world: &mut World,
+    mut session_reader: Local<ManualEventReader<SessionStarted>>,
+) {
+    let events = world.resource::<Events<SessionStarted>>();
+    if session_reader.read(events).count() == 0 {
+        return;
+    }
+
+    let mut package_query = world.query_filtered::<Entity, (With<RoundScoped>, With<Package>)>();
+    let packages = package_query.iter(world).collect::<Vec<_>>();
+    for package_entity in packages {
+        park_package(world, package_entity);
+    }
+
+    let mut round_query = world.query_filtered::<Entity, With<RoundScoped>>();
+    let round_entities = round_query.iter(world).collect::<Vec<_>>();
+    let mut despawned = 0;
+    for entity in round_entities {
+        // a scoped child may already have gone with its scoped parent
+        if let Some(entity_mut) = world.get_entity_mut(entity) {
+            entity_mut.despawn_recursive();
+            despawned += 1;
+        }
+    }
+
+    // a jam is over along with the packages that caused it
+    let mut conveyor_query = world.query::<&mut Conveyor>();
+    for mut conveyor_info in conveyor_query.iter_mut(world) {
+        conveyor_info.package_count = 0;
+        conveyor_info.blocked_seconds = 0.;
+        conveyor_info.jam_timer = None;
+    }
+    debug!("Cleared {} round scoped entities", despawned);
+}
+
+/// Counts per archetype from the last few rounds, newest last.
+#[derive(Default)]
+pub struct EntityAudit {
+    history: VecDeque<HashMap<String, usize>>,
+}
+
+fn count_archetypes(world: &World) -> HashMap<String, usize> {
+    let components = world.components();
+    let mut counts = HashMap::new();
+    for archetype in world
+        .archetypes()
+        .iter()
+        .filter(|archetype| !archetype.is_empty())
+    {
+        let mut names = archetype
+            .components()
+            .filter_map(|component_id| components.get_name(component_id))
+            .map(get_short_name)
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        *counts.entry(names.join(", ")).or_default() += archetype.len();
+    }
+    counts.insert(
+        ATLAS_LAYOUTS_LABEL.to_string(),
+        world.resource::<Assets<TextureAtlasLayout>>().len(),
+    );
+    counts
+}
+
+/// Takes a count of every archetype once the round's entities are cleared, and warns about any
+/// that has grown every round over the last `entity_audit_rounds`. Only added with the debug
+/// overlays, walking every archetype isn't free.
+pub fn audit_round_entities(
+    world: &mut World,
+    mut session_reader: Local<ManualEventReader<SessionStarted>>,
+    mut audit: Local<EntityAudit>,
+) {
+    let events = world.resource::<Events<SessionStarted>>();
+    if session_reader.read(events).count() == 0 {
+        return;
+    }
+
+    let rounds = world.resource::<AppConfig>().entity_audit_rounds.max(2);
+    let counts = count_archetypes(world);
+    debug!(
+        "{} entities in {} archetypes at the start of the round",
+        world.entities().len(),
+        counts.len()
+    );
+    audit.history.push_back(counts);
+    while audit.history.len() > rounds {
+        audit.history.pop_front();
+    }
+    if audit.history.len() < rounds {
+        return;
+    }
+
+    let Some(latest) = audit.history.back() else {
+        return;
+    };
+    for (signature, count) in latest {
+        let growing =
+            audit
+                .history
+                .iter()
+                .zip(audit.history.iter().skip(1))
+                .all(|(before, after)| {
+                    before.get(signature).copied().unwrap_or(0)
+                        < after.get(signature).copied().unwrap_or(0)
+                });
+        if growing {
+            warn!(
+                "[{}] has grown every round for the last {} rounds, now at {}",
+                signature, rounds, count
+            );
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use enum_map::{enum_map, EnumMap};
+
+use crate::{ColourPalette, GameConfig, PlayerIndex, PlayerPattern};
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlinkerColours {
+    pub active: Color,
+    pub inactive: Color,
+    pub readying: Color,
+    /// Outgoing belts held still while the supervisor watches, see `GameConfig::stealth_outgoing`.
+    pub watched: Color,
+}
+
+struct PaletteTable {
+    players: EnumMap<PlayerIndex, Color>,
+    team: Color,
+    blinker: BlinkerColours,
+}
+
+impl ColourPalette {
+    /// Replacement colours for each role, drawn from the Okabe-Ito set so every pair that has to
+    /// be told apart differs in lightness as well as hue.
+    fn table(&self) -> Option<PaletteTable> {
+        let blue = Color::rgb_u8(0, 114, 178);
+        let orange = Color::rgb_u8(230, 159, 0);
+        let sky_blue = Color::rgb_u8(86, 180, 233);
+        let vermillion = Color::rgb_u8(213, 94, 0);
+        let yellow = Color::rgb_u8(240, 228, 66);
+        let bluish_green = Color::rgb_u8(0, 158, 115);
+        let reddish_purple = Color::rgb_u8(204, 121, 167);
+
+        match self {
+            ColourPalette::Normal => None,
+            ColourPalette::Deuteranopia | ColourPalette::Protanopia => Some(PaletteTable {
+                players: enum_map! {
+                    PlayerIndex::Player1 => orange,
+                    PlayerIndex::Player2 => blue,
+                },
+                team: yellow,
+                blinker: BlinkerColours {
+                    active: sky_blue,
+                    inactive: vermillion,
+                    readying: yellow,
+                    watched: reddish_purple,
+                },
+            }),
+            ColourPalette::Tritanopia => Some(PaletteTable {
+                players: enum_map! {
+                    PlayerIndex::Player1 => vermillion,
+                    PlayerIndex::Player2 => bluish_green,
+                },
+                team: reddish_purple,
+                blinker: BlinkerColours {
+                    active: bluish_green,
+                    inactive: vermillion,
+                    readying: Color::WHITE,
+                    watched: reddish_purple,
+                },
+            }),
+        }
+    }
+
+    pub fn blinker_colours(&self) -> BlinkerColours {
+        self.table().map_or(
+            BlinkerColours {
+                active: Color::GREEN,
+                inactive: Color::RED,
+                readying: Color::ORANGE,
+                watched: Color::PURPLE,
+            },
+            |table| table.blinker,
+        )
+    }
+
+    /// Swaps the configured team and player colours for the palette's, keeping their alpha. Under
+    /// any palette but Normal players without a pattern get one, so telling them apart never
+    /// rests on hue alone.
+    pub fn apply(&self, game_config: &mut GameConfig) {
+        let Some(table) = self.table() else {
+            return;
+        };
+        game_config.team_colour = table.team.with_a(game_config.team_colour.a()).into();
+        for (player_index, per_player) in game_config.player_config.per_player.iter_mut() {
+            per_player.colour = table.players[player_index]
+                .with_a(per_player.colour.a())
+                .into();
+            per_player.pattern.get_or_insert(match player_index {
+                PlayerIndex::Player1 => PlayerPattern::Stripes,
+                PlayerIndex::Player2 => PlayerPattern::Dots,
+            });
+        }
+    }
+}
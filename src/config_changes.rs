@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::prelude::*;
+
+use crate::{evict_oldest, BufferBudgets, GameConfig, Metrics, SessionStarted};
+
+const CONFIG_CHANGE_HISTORY_CAP: usize = 256;
+
+/// Config sections and field names that only change how the game looks or feels, not how it
+/// plays, changing these doesn't mark a round as modified.
+const COSMETIC_SECTIONS: [&str; 6] = [
+    "selected_texture_pack",
+    "texture_packs",
+    "team_colour",
+    "feedback_config",
+    "trajectory_preview_config",
+    "rumble_config",
+];
+const COSMETIC_FIELDS: [&str; 2] = ["colour", "tint"];
+/// Anywhere in the config, e.g. either player's bindings.
+const COSMETIC_TABLES: [&str; 1] = ["key_map"];
+
+pub fn is_cosmetic_field(field_path: &str) -> bool {
+    field_path
+        .split('.')
+        .next()
+        .is_some_and(|section| COSMETIC_SECTIONS.contains(&section))
+        || field_path
+            .rsplit('.')
+            .next()
+            .is_some_and(|field| COSMETIC_FIELDS.contains(&field))
+        || field_path
+            .split('.')
+            .any(|table| COSMETIC_TABLES.contains(&table))
+}
+
+pub struct ConfigChange {
+    pub field_path: String,
+    pub old_value: String,
+    pub new_value: String,
+    /// Seconds since the round started.
+    pub game_time: f32,
+    pub cosmetic: bool,
+}
+
+/// Every change to the game config made while a round was being played, cleared when the next
+/// round starts.
+#[derive(Resource, Default)]
+pub struct ConfigChangeLog {
+    pub changes: Vec<ConfigChange>,
+    baseline: Option<BTreeMap<String, String>>,
+    round_started_at: f32,
+}
+
+impl ConfigChangeLog {
+    /// Whether anything that affects play changed, such rounds aren't comparable with others.
+    pub fn modified_mid_round(&self) -> bool {
+        self.changes.iter().any(|change| !change.cosmetic)
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        match self.changes.len() {
+            0 => None,
+            1 => Some("1 rule changed mid-round".to_string()),
+            count => Some(format!("{} rules changed mid-round", count)),
+        }
+    }
+}
+
+/// Flattens the config to `section.field` paths, list entries are indexed by position.
+fn flatten_config(value: &toml::Value, path: String, fields: &mut BTreeMap<String, String>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                flatten_config(value, join(key), fields);
+            }
+        }
+        toml::Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten_config(value, join(&index.to_string()), fields);
+            }
+        }
+        value => {
+            fields.insert(path, value.to_string());
+        }
+    }
+}
+
+fn snapshot_config(game_config: &GameConfig) -> Option<BTreeMap<String, String>> {
+    match toml::Value::try_from(game_config) {
+        Ok(value) => {
+            let mut fields = BTreeMap::new();
+            flatten_config(&value, String::new(), &mut fields);
+            Some(fields)
+        }
+        Err(error) => {
+            warn!(
+                "Could not snapshot the game config for change tracking: {}",
+                error
+            );
+            None
+        }
+    }
+}
+
+pub fn track_config_changes(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut session_events: EventReader<SessionStarted>,
+    mut change_log: ResMut<ConfigChangeLog>,
+    mut buffer_budgets: ResMut<BufferBudgets>,
+    mut metrics: Option<ResMut<Metrics>>,
+) {
+    if session_events.read().count() > 0 {
+        if let Some(summary) = change_log.summary() {
+            info!("Previous round: {}", summary);
+        }
+        change_log.changes.clear();
+        change_log.baseline = snapshot_config(&game_config);
+        change_log.round_started_at = time.elapsed_seconds();
+        if let Some(metrics) = metrics.as_mut() {
+            metrics.modified_mid_round = false;
+        }
+        return;
+    }
+
+    if !game_config.is_changed() || game_config.is_added() {
+        return;
+    }
+    let Some(current) = snapshot_config(&game_config) else {
+        return;
+    };
+    let Some(baseline) = change_log.baseline.replace(current.clone()) else {
+        return;
+    };
+
+    let game_time = time.elapsed_seconds() - change_log.round_started_at;
+    let missing = "<unset>".to_string();
+    let field_paths: BTreeSet<&String> = baseline.keys().chain(current.keys()).collect();
+    for field_path in field_paths {
+        let old_value = baseline.get(field_path).unwrap_or(&missing);
+        let new_value = current.get(field_path).unwrap_or(&missing);
+        if old_value == new_value {
+            continue;
+        }
+
+        info!(
+            "Config changed mid-round at {:.1}s: {} {} -> {}",
+            game_time, field_path, old_value, new_value
+        );
+        change_log.changes.push(ConfigChange {
+            field_path: field_path.clone(),
+            old_value: old_value.clone(),
+            new_value: new_value.clone(),
+            game_time,
+            cosmetic: is_cosmetic_field(field_path),
+        });
+    }
+
+    let history_cap =
+        buffer_budgets.cap_for::<ConfigChange>("config_changes", CONFIG_CHANGE_HISTORY_CAP);
+    let evicted = evict_oldest(&mut change_log.changes, history_cap);
+    buffer_budgets.report("config_changes", change_log.changes.len(), evicted);
+
+    if let Some(metrics) = metrics.as_mut() {
+        metrics.modified_mid_round |= change_log.modified_mid_round();
+    }
+}
@@ -0,0 +1,315 @@
+use bevy::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A colour as written in the config. Reads a hex string ("#RRGGBB" or "#RRGGBBAA"), a CSS
+/// colour name, or Bevy's structured form so older files keep loading, and writes hex.
+///
+/// Hex and names are sRGB like any colour picker shows them, Bevy converts them to linear
+/// when drawing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfigColour(pub Color);
+
+impl std::ops::Deref for ConfigColour {
+    type Target = Color;
+
+    fn deref(&self) -> &Color {
+        &self.0
+    }
+}
+
+impl From<Color> for ConfigColour {
+    fn from(colour: Color) -> Self {
+        Self(colour)
+    }
+}
+
+impl From<ConfigColour> for Color {
+    fn from(colour: ConfigColour) -> Self {
+        colour.0
+    }
+}
+
+impl ConfigColour {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.starts_with('#') {
+            return Color::hex(text)
+                .map(Self)
+                .map_err(|err| format!("invalid hex colour '{}': {}", text, err));
+        }
+        NAMED_COLOURS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(text))
+            .map(|(_, [red, green, blue])| Self(Color::rgb_u8(*red, *green, *blue)))
+            .ok_or_else(|| {
+                format!(
+                    "unknown colour '{}', expected \"#RRGGBB\", \"#RRGGBBAA\" or a CSS colour name",
+                    text
+                )
+            })
+    }
+
+    /// None for colours outside what hex can hold, like the brighter than white linear values
+    /// the default player colours use for their glow.
+    pub fn to_hex(&self) -> Option<String> {
+        let srgba = self.0.as_rgba_f32();
+        if srgba.iter().any(|channel| !(0. ..=1.).contains(channel)) {
+            return None;
+        }
+        let [red, green, blue, alpha] = srgba.map(|channel| (channel * 255.).round() as u8);
+        Some(if alpha == u8::MAX {
+            format!("#{:02X}{:02X}{:02X}", red, green, blue)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", red, green, blue, alpha)
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigColourRepr {
+    Text(String),
+    Structured(Color),
+}
+
+impl<'de> Deserialize<'de> for ConfigColour {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match ConfigColourRepr::deserialize(deserializer)? {
+            ConfigColourRepr::Text(text) => ConfigColour::parse(&text).map_err(de::Error::custom),
+            ConfigColourRepr::Structured(colour) => Ok(ConfigColour(colour)),
+        }
+    }
+}
+
+impl Serialize for ConfigColour {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_hex() {
+            Some(hex) => serializer.serialize_str(&hex),
+            None => self.0.serialize(serializer),
+        }
+    }
+}
+
+/// The CSS named colours, in sRGB.
+const NAMED_COLOURS: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Colours {
+        colour: ConfigColour,
+    }
+
+    fn read(text: &str) -> Result<Color, toml::de::Error> {
+        toml::from_str::<Colours>(&format!("colour = {}", text)).map(|colours| *colours.colour)
+    }
+
+    fn written(colour: Color) -> String {
+        toml::to_string(&Colours {
+            colour: colour.into(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn hex_and_names_round_trip() {
+        for (text, colour, hex) in [
+            ("\"#FF5533\"", Color::rgb_u8(255, 0x55, 0x33), "#FF5533"),
+            ("\"#ff5533\"", Color::rgb_u8(255, 0x55, 0x33), "#FF5533"),
+            (
+                "\"#FF553380\"",
+                Color::rgba_u8(255, 0x55, 0x33, 0x80),
+                "#FF553380",
+            ),
+            ("\"rebeccapurple\"", Color::rgb_u8(102, 51, 153), "#663399"),
+            ("\" Red \"", Color::rgb_u8(255, 0, 0), "#FF0000"),
+        ] {
+            assert_eq!(read(text).unwrap(), colour, "{}", text);
+            assert_eq!(written(colour), format!("colour = \"{}\"\n", hex));
+            assert_eq!(read(&format!("\"{}\"", hex)).unwrap(), colour);
+        }
+    }
+
+    #[test]
+    fn hex_is_srgb() {
+        let linear = read("\"#808080\"").unwrap().as_linear_rgba_f32();
+        assert!((linear[0] - 0.2158).abs() < 0.001, "{:?}", linear);
+        assert_eq!(linear[3], 1.);
+    }
+
+    #[test]
+    fn structured_colours_still_load() {
+        let colour = Color::rgb(0.2, 0.4, 0.6);
+        let structured = toml::to_string(&toml::Table::from_iter([(
+            "colour".to_string(),
+            toml::Value::try_from(colour).unwrap(),
+        )]))
+        .unwrap();
+        let colours = toml::from_str::<Colours>(&structured).unwrap();
+        assert_eq!(*colours.colour, colour);
+
+        // brighter than white can't be hex, so it's written structured and reads back the same
+        let glow = Color::rgb_linear(1.5, 1.2, 0.8);
+        assert_eq!(ConfigColour(glow).to_hex(), None);
+        let colours = toml::from_str::<Colours>(&written(glow)).unwrap();
+        assert_eq!(*colours.colour, glow);
+    }
+
+    #[test]
+    fn bad_colours_are_rejected() {
+        for text in ["\"#GG0000\"", "\"#12345\"", "\"notacolour\"", "\"\"", "12"] {
+            assert!(read(text).is_err(), "{}", text);
+        }
+    }
+}
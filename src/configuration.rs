@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -8,26 +9,41 @@ use bevy::{
     input::{
         gamepad::{GamepadAxisType, GamepadButtonType},
         keyboard::KeyCode,
+        mouse::MouseButton,
     },
     math::{UVec2, Vec2},
     render::color::Color,
+    sprite::TextureAtlasLayout,
 };
 use enum_map::{enum_map, Enum, EnumMap};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::random::*;
+use crate::{random::*, ConfigColour};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum AxisDirection {
     Positive,
     Negative,
 }
 
-#[derive(Serialize, Deserialize)]
 pub enum KeyBind {
     Key(KeyCode),
     ControllerButton(GamepadButtonType),
     ControllerAxis((GamepadAxisType, AxisDirection)),
+    MouseButton(MouseButton),
+}
+
+/// Read in place of an axis a pad never reports, e.g. triggers exposed as buttons.
+pub struct PadAxisFallback {
+    pub axis: GamepadAxisType,
+    pub direction: AxisDirection,
+    pub button: GamepadButtonType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PadSide {
+    Left,
+    Right,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,7 +52,7 @@ pub struct KeyBindConfig {
     pub secondary: KeyBind,
 }
 
-#[derive(Enum, Serialize, Deserialize, Clone)]
+#[derive(Debug, Enum, Serialize, Deserialize, Clone)]
 pub enum KeyAction {
     MoveUp,
     MoveDown,
@@ -44,6 +60,9 @@ pub enum KeyAction {
     MoveRight,
     Sprint,
     PickupOrThrow,
+    Drop,
+    Interact,
+    Emote,
 }
 
 #[derive(Debug, Enum, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
@@ -52,7 +71,7 @@ pub enum PlayerIndex {
     Player2,
 }
 
-#[derive(Enum, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Enum, Deserialize, Serialize)]
 pub enum TextureTarget {
     AllPlayers,
     Supervisor,
@@ -61,6 +80,10 @@ pub enum TextureTarget {
     Background,
     SupervisorOffice,
     ScoreDisplay,
+    InputGlyphs,
+    Forklift,
+    Emotes,
+    BeltArrow,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -69,6 +92,14 @@ pub enum TextureValue {
     Choose(Vec<SpriteSheetConfig>),
 }
 
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum TextureSelection {
+    #[default]
+    Random,
+    StablePerEntity,
+    Fixed(usize),
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct SpriteSheetConfig {
     pub path: String,
@@ -79,14 +110,43 @@ pub struct SpriteSheetConfig {
 #[derive(Deserialize, Serialize)]
 pub struct TexturePack {
     pub root: String,
-    pub texture_map: EnumMap<TextureTarget, TextureValue>,
+    /// Cell resolution the sprite sizes were authored against, other sheets are scaled to match.
+    #[serde(default = "default_reference_cell_size")]
+    pub reference_cell_size: UVec2,
+    #[serde(deserialize_with = "deserialize_texture_map")]
+    pub texture_map: EnumMap<TextureTarget, Option<TextureValue>>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct PerPlayerConfig {
-    pub colour: Color,
+    pub colour: ConfigColour,
     pub sprite_override: Option<TextureValue>,
     pub key_map: EnumMap<KeyAction, KeyBindConfig>,
+    #[serde(default)]
+    pub pad_share: Option<PadSide>,
+    #[serde(default)]
+    pub pattern: Option<PlayerPattern>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerPattern {
+    Stripes,
+    Dots,
+}
+
+#[derive(Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum BlinkerStyle {
+    Colour,
+    #[default]
+    Ring,
+}
+
+#[derive(Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum FacingBias {
+    #[default]
+    LastPressed,
+    Horizontal,
+    Vertical,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -95,6 +155,48 @@ pub struct PlayerConfig {
     pub move_speed: f32,
     pub sprint_move_modifier: f32,
     pub throw_power: f32,
+    #[serde(default)]
+    pub facing_bias: FacingBias,
+    #[serde(default = "default_allow_direct_place")]
+    pub allow_direct_place: bool,
+    #[serde(default = "default_direct_place_max_charge")]
+    pub direct_place_max_charge: f32,
+    /// A release under `direct_place_max_charge` drops the package rather than lobbing it.
+    #[serde(default = "default_tap_to_drop")]
+    pub tap_to_drop: bool,
+    /// Releases are ignored this long after a pickup, charge still builds meanwhile.
+    #[serde(default = "default_pickup_cooldown_seconds")]
+    pub pickup_cooldown_seconds: f32,
+    /// A release under `direct_place_max_charge` against an incoming belt puts the package back.
+    #[serde(default = "default_allow_returns")]
+    pub allow_returns: bool,
+    #[serde(default = "default_allow_returns")]
+    pub allow_returns_in_versus: bool,
+    #[serde(default = "default_return_multiplier_penalty")]
+    pub return_multiplier_penalty: f32,
+    #[serde(default = "default_pickup_radius_scale")]
+    pub pickup_radius_scale: f32,
+    #[serde(default = "default_max_carry")]
+    pub max_carry: usize,
+    /// Fraction of move speed lost for each package carried beyond the first.
+    #[serde(default = "default_extra_carry_speed_penalty")]
+    pub extra_carry_speed_penalty: f32,
+    /// How fast a pushed package moves relative to the player pushing it.
+    #[serde(default = "default_push_strength")]
+    pub push_strength: f32,
+    /// Higher eases a player stuck outside the play area back in quicker.
+    #[serde(default = "default_containment_return_rate")]
+    pub containment_return_rate: f32,
+    #[serde(default)]
+    pub aim_assist: bool,
+    /// Full width of the cone, not either side.
+    #[serde(default = "default_aim_assist_cone_degrees")]
+    pub aim_assist_cone_degrees: f32,
+    #[serde(default = "default_aim_assist_max_distance")]
+    pub aim_assist_max_distance: f32,
+    /// Left unset the game mode picks.
+    #[serde(default)]
+    pub players_collide: Option<bool>,
     pub per_player: EnumMap<PlayerIndex, PerPlayerConfig>,
 }
 
@@ -103,6 +205,36 @@ pub struct SupervisorConfig {
     pub size: f32,
     pub monitoring_y_pos: f32,
     pub office_sprite_size: UVec2,
+    #[serde(default = "default_restricted_zone_depth")]
+    pub restricted_zone_depth: f32,
+    #[serde(default = "default_restricted_zone_tint_alpha")]
+    pub restricted_zone_tint_alpha: f32,
+    #[serde(default = "default_confiscation_penalty")]
+    pub confiscation_penalty: f32,
+    #[serde(default = "default_confiscation_exemption_seconds")]
+    pub confiscation_exemption_seconds: f32,
+    #[serde(default = "default_supervisor_hit_penalty")]
+    pub hit_penalty: f32,
+    #[serde(default = "default_bump_knockback_speed")]
+    pub bump_knockback_speed: f32,
+    #[serde(default = "default_bump_knockback_seconds")]
+    pub bump_knockback_seconds: f32,
+    #[serde(default = "default_supervisors")]
+    pub supervisors: Vec<SupervisorInstanceConfig>,
+    #[serde(default = "default_detection_grace_seconds")]
+    pub detection_grace_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SupervisorInstanceConfig {
+    #[serde(default)]
+    pub monitoring_x_pos: f32,
+    /// Either side of `monitoring_x_pos`, 0 stands still.
+    #[serde(default)]
+    pub patrol_range: f32,
+    /// Seconds into the watch and distracted cycle.
+    #[serde(default)]
+    pub timer_offset_seconds: f32,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -112,24 +244,609 @@ pub struct ConveyorConfig {
     pub border_size: f32,
     pub blinker_size: f32,
     pub blink_duration_seconds: f32,
+    #[serde(default)]
+    pub blinker_style: BlinkerStyle,
+    #[serde(default = "default_countdown_pulse_speed")]
+    pub countdown_pulse_speed: f32,
+    #[serde(default = "default_outgoing_capacity_tolerance")]
+    pub outgoing_capacity_tolerance: f32,
+    #[serde(default = "default_front_grab_zone_depth")]
+    pub front_grab_zone_depth: f32,
+    #[serde(default = "default_jam_grace_seconds")]
+    pub jam_grace_seconds: f32,
+    #[serde(default = "default_jam_countdown_seconds")]
+    pub jam_countdown_seconds: f32,
+    #[serde(default = "default_jam_blink_duration_seconds")]
+    pub jam_blink_duration_seconds: f32,
+    #[serde(default = "default_outgoing_max_dwell_seconds")]
+    pub outgoing_max_dwell_seconds: f32,
+    #[serde(default = "default_dwell_eject_impulse")]
+    pub dwell_eject_impulse: f32,
+    #[serde(default = "default_show_capacity_indicators")]
+    pub show_capacity_indicators: bool,
+    #[serde(default = "default_capacity_indicator_width")]
+    pub capacity_indicator_width: f32,
+    /// Per incoming belt per wave, 0 turns breakdowns off.
+    #[serde(default)]
+    pub breakdown_probability: f32,
+    #[serde(default = "default_repair_seconds")]
+    pub repair_seconds: f32,
+    #[serde(default = "default_repair_reach")]
+    pub repair_reach: f32,
+    #[serde(default = "default_show_belt_arrows")]
+    pub show_belt_arrows: bool,
+    #[serde(default = "default_belt_arrow_spacing")]
+    pub belt_arrow_spacing: f32,
+    #[serde(default = "default_belt_arrow_size")]
+    pub belt_arrow_size: f32,
+    #[serde(default = "default_belt_arrow_alpha")]
+    pub belt_arrow_alpha: f32,
+    /// Easing in and out of each run, which lasts this much longer to make up for it.
+    #[serde(default = "default_ramp_seconds")]
+    pub ramp_seconds: f32,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct PackageConfig {
     pub size: f32,
     pub base_score_value: f32,
+    /// 0 splits waves at random, 1 always favours the emptiest belts. Unset the game mode picks.
+    #[serde(default)]
+    pub wave_fairness: Option<f32>,
+    #[serde(default)]
+    pub decay_enabled: bool,
+    #[serde(default = "default_decay_seconds")]
+    pub decay_seconds: f32,
+    #[serde(default = "default_min_value_fraction")]
+    pub min_value_fraction: f32,
+    #[serde(default = "default_wave_delay_seconds")]
+    pub wave_delay_seconds: f32,
+    /// 0 spawns every package fresh.
+    #[serde(default = "default_package_pool_size")]
+    pub pool_size: usize,
+    /// Slower than this for `settle_seconds` puts a loose package to sleep.
+    #[serde(default = "default_settle_speed")]
+    pub settle_speed: f32,
+    #[serde(default = "default_settle_seconds")]
+    pub settle_seconds: f32,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct ScoreConfig {
     pub multiplier_increase_per_package: f32,
     pub multiplier_decrease_per_second: f32,
+    #[serde(default = "default_delivery_freeze_seconds")]
+    pub delivery_freeze_seconds: f32,
+    #[serde(default = "default_jam_penalty_per_package")]
+    pub jam_penalty_per_package: f32,
+    #[serde(default)]
+    pub score_floor: ScoreFloor,
+    #[serde(default)]
+    pub penalties_scale_with_multiplier: bool,
+    #[serde(default = "default_streaks")]
+    pub streaks: Vec<StreakTier>,
+    /// Paid per multiplier point above 1, 0 turns cashing out off.
+    #[serde(default)]
+    pub cashout_value: f32,
+    #[serde(default = "default_cashout_cooldown_seconds")]
+    pub cashout_cooldown_seconds: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct StreakTier {
+    pub deliveries: usize,
+    pub window_seconds: f32,
+    pub bonus_points: f32,
+    /// Scales multiplier gain while the boost lasts.
+    pub multiplier_gain_factor: f32,
+    pub boost_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct HazardZoneConfig {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub kind: HazardKind,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum HazardKind {
+    /// Lower grip is slipperier.
+    Slippery {
+        grip: f32,
+    },
+    Sticky {
+        speed_multiplier: f32,
+    },
+    ConveyorFloor {
+        drift: Vec2,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum PowerupKind {
+    SpeedBoost,
+    InstantMaxCharge,
+    MultiplierShield,
+    MagnetHands,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PowerupConfig {
+    /// Each wait is picked from half to one and a half times this.
+    #[serde(default = "default_powerup_spawn_interval_seconds")]
+    pub spawn_interval_seconds: f32,
+    #[serde(default = "default_powerup_max_on_floor")]
+    pub max_on_floor: usize,
+    #[serde(default = "default_powerup_lifetime_seconds")]
+    pub lifetime_seconds: f32,
+    #[serde(default = "default_powerup_modifier_seconds")]
+    pub modifier_seconds: f32,
+    #[serde(default = "default_powerup_size")]
+    pub size: f32,
+    #[serde(default = "default_powerup_kinds")]
+    pub kinds: Vec<PowerupKind>,
+    #[serde(default = "default_speed_boost_multiplier")]
+    pub speed_boost_multiplier: f32,
+    #[serde(default = "default_magnet_radius_multiplier")]
+    pub magnet_radius_multiplier: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum WaveModifier {
+    HeavyFreight,
+    FragileGoods,
+    RushOrder,
+    Audit,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct WeightedWaveModifier {
+    pub modifier: WaveModifier,
+    pub weight: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct WaveModifierConfig {
+    pub enabled: bool,
+    pub modifiers: Vec<WeightedWaveModifier>,
+    /// Weighed against the listed modifiers.
+    pub plain_wave_weight: f32,
+    pub heavy_density_scale: f32,
+    pub fragile_value_scale: f32,
+    /// Speed lost in a single step.
+    pub fragile_break_speed: f32,
+    pub rush_belt_speed_scale: f32,
+    pub rush_countdown_scale: f32,
+    pub audit_monitoring_scale: f32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum GameModeKind {
+    #[default]
+    Team,
+    Versus,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum ScoreFloor {
+    Floor(f64),
+    AllowNegative,
+}
+
+impl Default for ScoreFloor {
+    fn default() -> Self {
+        ScoreFloor::Floor(0.)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct IdleNudgeConfig {
+    pub enabled: bool,
+    pub idle_seconds: f32,
+    pub pulse_speed: f32,
+    pub highlight_strength: f32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ShiftSelection {
+    #[default]
+    Sequential,
+    Weekday,
+    Random,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShiftModifiers {
+    #[serde(default = "default_modifier_scale")]
+    pub wave_countdown_scale: f32,
+    #[serde(default = "default_modifier_scale")]
+    pub supervisor_cycle_scale: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShiftDefinition {
+    pub name: String,
+    #[serde(default = "default_shift_tint")]
+    pub tint: ConfigColour,
+    #[serde(default)]
+    pub modifiers: ShiftModifiers,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct ShiftConfig {
+    #[serde(default)]
+    pub selection: ShiftSelection,
+    #[serde(default)]
+    pub shifts: Vec<ShiftDefinition>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AttractModeConfig {
+    pub enabled: bool,
+    pub start_in_attract_mode: bool,
+    pub idle_seconds: f32,
+    pub min_hesitation_seconds: f32,
+    pub max_hesitation_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TrajectoryPreviewConfig {
+    pub enabled: bool,
+    pub dot_count: usize,
+    pub dot_size: f32,
+    pub fuzzy_distance: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct FeedbackConfig {
+    pub camera_shake_enabled: bool,
+    pub max_shake_offset: f32,
+    pub max_shake_angle: f32,
+    pub shake_frequency: f32,
+    pub trauma_decay_per_second: f32,
+    pub delivered_trauma: f32,
+    pub caught_trauma: f32,
+    #[serde(default = "default_confiscated_trauma")]
+    pub confiscated_trauma: f32,
+    pub flash_alpha: f32,
+    pub flash_duration_seconds: f32,
+    #[serde(default)]
+    pub time_dilation: TimeDilationConfig,
+    #[serde(default)]
+    pub sprint_trail: SprintTrailConfig,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SprintTrailConfig {
+    /// 0 turns the trail off.
+    pub trail_interval: f32,
+    pub trail_lifetime: f32,
+    pub min_speed: f32,
+    pub start_alpha: f32,
+}
+
+/// Scales go down to `MIN_TIME_SCALE` and 1 turns that trigger off. Durations are real seconds.
+#[derive(Deserialize, Serialize)]
+pub struct TimeDilationConfig {
+    /// The team total in team games.
+    pub match_point_score: Option<f32>,
+    pub match_point_scale: f32,
+    pub match_point_seconds: f32,
+    /// Only throws that reach a belt untouched count.
+    pub full_charge_throw_min_charge: f32,
+    pub full_charge_throw_scale: f32,
+    pub full_charge_throw_seconds: f32,
+    /// Of a timed round.
+    pub final_seconds: f32,
+    pub final_seconds_scale: f32,
+    pub heartbeat_beats_per_second: f32,
+    pub heartbeat_alpha: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AfkConfig {
+    pub enabled: bool,
+    pub warning_seconds: f32,
+    pub timeout_seconds: f32,
+    pub flash_speed: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct EmoteConfig {
+    pub enabled: bool,
+    pub display_seconds: f32,
+    pub cooldown_seconds: f32,
+    pub cycle_window_seconds: f32,
+    pub bubble_size: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct InputHintConfig {
+    pub enabled: bool,
+    pub charge_hint_uses: u32,
+    pub fade_seconds: f32,
+    /// In world units, hint text is sized to match.
+    pub glyph_size: f32,
+    /// 0 never shows them.
+    #[serde(default = "default_keyboard_banner_seconds")]
+    pub keyboard_banner_seconds: f32,
+    #[serde(default = "default_shared_keyboard_key")]
+    pub shared_keyboard_key: KeyCode,
+    /// 0 only logs it.
+    #[serde(default = "default_pad_fallback_notice_seconds")]
+    pub pad_fallback_notice_seconds: f32,
+}
+
+/// Without `round_seconds` play is one endless round.
+#[derive(Deserialize, Serialize)]
+pub struct MatchConfig {
+    #[serde(default)]
+    pub round_seconds: Option<f32>,
+    pub rounds_per_match: usize,
+    pub swap_sides: bool,
+    pub results_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct FriendlyFireConfig {
+    pub enabled: bool,
+    pub stun_seconds: f32,
+    /// After leaving a player's hands.
+    pub thrown_window_seconds: f32,
+    /// Fraction of a package's velocity kept after hitting someone.
+    pub velocity_kept: f32,
+    pub escalation_window_seconds: f32,
+    /// Added to the thrower's pickup cooldown per repeat stun.
+    pub escalation_cooldown_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ForkliftConfig {
+    pub enabled: bool,
+    /// Picked between this and `max_interval_seconds`.
+    pub min_interval_seconds: f32,
+    pub max_interval_seconds: f32,
+    pub speed: f32,
+    pub lane_y_positions: Vec<f32>,
+    pub size: Vec2,
+    pub forklift_warning_seconds: f32,
+    pub stun_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+pub struct RumblePulse {
+    pub strong_motor: f32,
+    pub weak_motor: f32,
+    pub duration_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RumbleConfig {
+    pub enabled: bool,
+    /// Throws charged less than this don't rumble at all.
+    pub throw_min_charge: f32,
+    pub throw: RumblePulse,
+    pub delivered: RumblePulse,
+    pub delivered_gap_seconds: f32,
+    pub penalty: RumblePulse,
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataOutputPolicy {
+    Full,
+    Restricted,
+}
+
+impl DataOutputPolicy {
+    pub fn allows_writes(&self) -> bool {
+        matches!(self, DataOutputPolicy::Full)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum UiMode {
+    #[default]
+    WorldSpace,
+    ScreenSpace,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    Shared,
+    SplitVertical,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsQuality {
+    #[default]
+    High,
+    Low,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FrameDumpConfig {
+    pub directory: PathBuf,
+    #[serde(default = "default_frame_dump_every_n_ticks")]
+    pub every_n_ticks: u32,
+    /// Counted in fixed ticks from launch.
+    #[serde(default)]
+    pub start_tick: u64,
+    #[serde(default)]
+    pub end_tick: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum ColourPalette {
+    #[default]
+    Normal,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
 }
 
 #[derive(Resource, Deserialize, Serialize)]
 pub struct AppConfig {
     pub base_resolution: UVec2,
+    /// Also takes a whole reproduction string, only its seed is read.
+    #[serde(default, deserialize_with = "deserialize_rng_seed")]
     pub rng_seed: Option<u64>,
+    #[serde(default)]
+    pub metrics_path: Option<PathBuf>,
+    #[serde(default = "default_metrics_interval_seconds")]
+    pub metrics_interval_seconds: f32,
+    #[serde(default)]
+    pub ui_mode: UiMode,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default = "default_locale_directory")]
+    pub locale_directory: PathBuf,
+    /// Shared by every in-memory history buffer.
+    #[serde(default = "default_observability_memory_budget_mb")]
+    pub observability_memory_budget_mb: f32,
+    #[serde(default)]
+    pub camera_mode: CameraMode,
+    /// Higher is snappier.
+    #[serde(default = "default_camera_follow_smoothing")]
+    pub camera_follow_smoothing: f32,
+    #[serde(default = "default_pause_on_focus_loss")]
+    pub pause_on_focus_loss: bool,
+    #[serde(default = "default_time_effects")]
+    pub time_effects: bool,
+    #[serde(default)]
+    pub graphics_quality: GraphicsQuality,
+    #[serde(default = "default_graphics_quality_toggle_key")]
+    pub graphics_quality_toggle_key: KeyCode,
+    /// 0 turns them off.
+    #[serde(default)]
+    pub low_quality_max_flashes: usize,
+    #[serde(default)]
+    pub debug_physics_render: bool,
+    #[serde(default)]
+    pub debug_overlays: bool,
+    #[serde(default = "default_debug_overlay_toggle_key")]
+    pub debug_overlay_toggle_key: KeyCode,
+    /// Rounds in a row an entity count has to grow before it's warned about.
+    #[serde(default = "default_entity_audit_rounds")]
+    pub entity_audit_rounds: usize,
+    #[serde(default)]
+    pub frame_dump: Option<FrameDumpConfig>,
+    #[serde(default)]
+    pub colour_palette: ColourPalette,
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    #[serde(default)]
+    pub frame_timing: FrameTimingConfig,
+    #[serde(default)]
+    pub results_path: Option<PathBuf>,
+    /// Needs the results-webhook feature.
+    #[serde(default)]
+    pub results_webhook: Option<String>,
+    #[serde(default = "default_results_webhook_timeout_seconds")]
+    pub results_webhook_timeout_seconds: f32,
+    /// The host name when unset.
+    #[serde(default)]
+    pub machine_name: Option<String>,
+    #[serde(default)]
+    pub tutorial: TutorialConfig,
+    /// e.g. `"play_nice::conveyor" = "debug"`, `RUST_LOG` still wins.
+    #[serde(default)]
+    pub log_levels: BTreeMap<String, String>,
+    #[serde(default = "default_log_buffer_lines")]
+    pub log_buffer_lines: usize,
+    #[serde(default = "default_bug_report_key")]
+    pub bug_report_key: KeyCode,
+    /// Needs the clipboard feature.
+    #[serde(default = "default_copy_seed_key")]
+    pub copy_seed_key: KeyCode,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct TutorialConfig {
+    pub start_in_tutorial: bool,
+    pub exit_key: KeyCode,
+    pub charge_fraction: f32,
+    pub step_pause_seconds: f32,
+    pub finished_seconds: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FrameTimingConfig {
+    /// Wall time.
+    pub overrun_threshold_ms: f32,
+    /// In a row before a warning is logged.
+    pub overrun_ticks: u32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct CalibrationConfig {
+    pub default_deadzone: f32,
+    pub sample_seconds: f32,
+    pub toggle_key: KeyCode,
+    #[serde(default)]
+    pub pads: Vec<PadCalibration>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PadCalibration {
+    pub name: String,
+    pub id: usize,
+    pub deadzone: f32,
+    #[serde(default)]
+    pub axis_offsets: Vec<AxisOffset>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct AxisOffset {
+    pub axis: GamepadAxisType,
+    pub offset: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutBeltKind {
+    Incoming,
+    Outgoing,
+}
+
+/// Belts left of the centre line are on the left player's side.
+#[derive(Deserialize, Serialize)]
+pub struct LayoutConveyorConfig {
+    pub position: Vec2,
+    pub length: f32,
+    pub kind: LayoutBeltKind,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct InteriorWallConfig {
+    pub position: Vec2,
+    pub size: Vec2,
+    /// Anticlockwise, in degrees.
+    #[serde(default)]
+    pub rotation_degrees: f32,
+}
+
+/// Anything left unset is laid out the classic way.
+#[derive(Deserialize, Serialize, Default)]
+pub struct ArenaLayoutConfig {
+    #[serde(default)]
+    pub office_height: Option<f32>,
+    /// As tall as the office band and as wide as the incoming belts unless set.
+    #[serde(default)]
+    pub walkway_height: Option<f32>,
+    #[serde(default)]
+    pub walkway_width: Option<f32>,
+    #[serde(default)]
+    pub walkway_offset_x: f32,
+    #[serde(default)]
+    pub interior_walls: Vec<InteriorWallConfig>,
+    #[serde(default)]
+    pub conveyors: Option<Vec<LayoutConveyorConfig>>,
+    /// Paths aren't under the pack's root.
+    #[serde(default)]
+    pub background_override: Option<TextureValue>,
+    #[serde(default)]
+    pub office_override: Option<TextureValue>,
 }
 
 #[derive(Resource, Deserialize, Serialize)]
@@ -138,8 +855,14 @@ pub struct GameConfig {
     pub selected_texture_pack: String,
     #[serde(default = "default_texture_pack")]
     pub texture_packs: HashMap<String, TexturePack>,
+    #[serde(default)]
+    pub texture_selection: TextureSelection,
+    #[serde(default = "default_layout_key")]
+    pub selected_layout: String,
+    #[serde(default = "default_layouts")]
+    pub layouts: HashMap<String, ArenaLayoutConfig>,
     #[serde(default = "default_team_colour")]
-    pub team_colour: Color,
+    pub team_colour: ConfigColour,
     #[serde(default)]
     pub player_config: PlayerConfig,
     #[serde(default)]
@@ -150,7 +873,44 @@ pub struct GameConfig {
     pub package_config: PackageConfig,
     #[serde(default)]
     pub score_config: ScoreConfig,
+    #[serde(default)]
+    pub idle_nudge_config: IdleNudgeConfig,
+    #[serde(default)]
+    pub feedback_config: FeedbackConfig,
+    #[serde(default)]
+    pub trajectory_preview_config: TrajectoryPreviewConfig,
+    #[serde(default)]
+    pub attract_mode_config: AttractModeConfig,
+    #[serde(default)]
+    pub shift_config: ShiftConfig,
+    #[serde(default)]
+    pub rumble_config: RumbleConfig,
+    #[serde(default)]
+    pub friendly_fire_config: FriendlyFireConfig,
+    #[serde(default)]
+    pub forklift_config: ForkliftConfig,
+    #[serde(default)]
+    pub wave_modifier_config: WaveModifierConfig,
+    #[serde(default)]
+    pub afk_config: AfkConfig,
+    #[serde(default)]
+    pub match_config: MatchConfig,
+    #[serde(default)]
+    pub input_hint_config: InputHintConfig,
+    #[serde(default)]
+    pub emote_config: EmoteConfig,
+    #[serde(default = "default_pad_axis_fallbacks")]
+    pub pad_axis_fallbacks: Vec<PadAxisFallback>,
     pub friction: f32,
+    #[serde(default)]
+    pub game_mode: GameModeKind,
+    #[serde(default)]
+    pub hazards: Vec<HazardZoneConfig>,
+    #[serde(default)]
+    pub powerups: Option<PowerupConfig>,
+    /// Outgoing belts only run while the supervisor is distracted.
+    #[serde(default)]
+    pub stealth_outgoing: bool,
 }
 
 #[derive(Default, Deserialize, Serialize)]
@@ -165,8 +925,9 @@ impl Default for TexturePack {
     fn default() -> Self {
         Self {
             root: "sprites".to_string(),
+            reference_cell_size: default_reference_cell_size(),
             texture_map: enum_map! {
-                TextureTarget::AllPlayers => TextureValue::Choose(vec![
+                TextureTarget::AllPlayers => Some(TextureValue::Choose(vec![
                     SpriteSheetConfig {
                         path: "player_skin_tone_a.png".to_string(),
                         grid_dimensions: Some(UVec2::new(4, 1)),
@@ -187,8 +948,8 @@ impl Default for TexturePack {
                         grid_dimensions: Some(UVec2::new(4, 1)),
                         cell_resolution: Some(UVec2::new(128, 128)),
                     },
-                ]),
-                TextureTarget::Supervisor => TextureValue::Choose(vec![
+                ])),
+                TextureTarget::Supervisor => Some(TextureValue::Choose(vec![
                     SpriteSheetConfig {
                         path: "supervisor_skin_tone_a.png".to_string(),
                         grid_dimensions: Some(UVec2::new(2, 1)),
@@ -209,32 +970,48 @@ impl Default for TexturePack {
                         grid_dimensions: Some(UVec2::new(2, 1)),
                         cell_resolution: Some(UVec2::new(128, 128)),
                     },
-                ]),
-                TextureTarget::Package => TextureValue::Only(SpriteSheetConfig {
+                ])),
+                TextureTarget::Package => Some(TextureValue::Only(SpriteSheetConfig {
                     path: "box.png".to_string(),
                     grid_dimensions: None,
                     cell_resolution: None,
-                }),
-                TextureTarget::Conveyor => TextureValue::Only(SpriteSheetConfig {
+                })),
+                TextureTarget::Conveyor => Some(TextureValue::Only(SpriteSheetConfig {
                     path: "conveyor.png".to_string(),
                     grid_dimensions: Some(UVec2::new(5, 1)),
                     cell_resolution: Some(UVec2::new(128, 128)),
-                }),
-                TextureTarget::Background => TextureValue::Only(SpriteSheetConfig {
+                })),
+                TextureTarget::Background => Some(TextureValue::Only(SpriteSheetConfig {
                     path: "background.png".to_string(),
                     grid_dimensions: None,
                     cell_resolution: None,
-                }),
-                TextureTarget::SupervisorOffice => TextureValue::Only(SpriteSheetConfig {
+                })),
+                TextureTarget::SupervisorOffice => Some(TextureValue::Only(SpriteSheetConfig {
                     path: "supervisor_office.png".to_string(),
                     grid_dimensions: None,
                     cell_resolution: None,
-                }),
-                TextureTarget::ScoreDisplay => TextureValue::Only(SpriteSheetConfig {
+                })),
+                TextureTarget::ScoreDisplay => Some(TextureValue::Only(SpriteSheetConfig {
                     path: "display.png".to_string(),
                     grid_dimensions: None,
                     cell_resolution: None,
-                })
+                })),
+                TextureTarget::InputGlyphs => Some(TextureValue::Only(SpriteSheetConfig {
+                    path: "input_glyphs.png".to_string(),
+                    grid_dimensions: Some(UVec2::new(24, 4)),
+                    cell_resolution: Some(UVec2::new(64, 64)),
+                })),
+                TextureTarget::Forklift => Some(TextureValue::Only(SpriteSheetConfig {
+                    path: "forklift.png".to_string(),
+                    grid_dimensions: None,
+                    cell_resolution: None,
+                })),
+                TextureTarget::Emotes => Some(TextureValue::Only(SpriteSheetConfig {
+                    path: "emotes.png".to_string(),
+                    grid_dimensions: Some(UVec2::new(4, 1)),
+                    cell_resolution: Some(UVec2::new(64, 64)),
+                })),
+                TextureTarget::BeltArrow => None,
             },
         }
     }
@@ -247,20 +1024,41 @@ impl Default for PlayerConfig {
             move_speed: 150.,
             sprint_move_modifier: 2.,
             throw_power: 15.,
+            facing_bias: FacingBias::LastPressed,
+            allow_direct_place: default_allow_direct_place(),
+            direct_place_max_charge: default_direct_place_max_charge(),
+            tap_to_drop: default_tap_to_drop(),
+            pickup_cooldown_seconds: default_pickup_cooldown_seconds(),
+            allow_returns: default_allow_returns(),
+            allow_returns_in_versus: default_allow_returns(),
+            return_multiplier_penalty: default_return_multiplier_penalty(),
+            pickup_radius_scale: default_pickup_radius_scale(),
+            max_carry: default_max_carry(),
+            extra_carry_speed_penalty: default_extra_carry_speed_penalty(),
+            push_strength: default_push_strength(),
+            containment_return_rate: default_containment_return_rate(),
+            aim_assist: false,
+            aim_assist_cone_degrees: default_aim_assist_cone_degrees(),
+            aim_assist_max_distance: default_aim_assist_max_distance(),
+            players_collide: None,
             per_player: enum_map! {
                 PlayerIndex::Player1 => PerPlayerConfig {
-                    colour: Color::rgb_linear(1.0, 0.3, 0.3),
+                    colour: ConfigColour(Color::rgb_linear(1.0, 0.3, 0.3)),
                     sprite_override: Some(TextureValue::Only(SpriteSheetConfig {
                         path: "sprites/custom_player.png".to_string(),
                         grid_dimensions: Some(UVec2::new(4, 1)),
                         cell_resolution: Some(UVec2::new(128, 128)),
                     })),
                     key_map: default_key_map_player_one(),
+                    pad_share: None,
+                    pattern: None,
                 },
                 PlayerIndex::Player2 => PerPlayerConfig {
-                    colour: Color::rgb_linear(0.3, 0.3, 1.6),
+                    colour: ConfigColour(Color::rgb_linear(0.3, 0.3, 1.6)),
                     sprite_override: None,
                     key_map: default_key_map_player_two(),
+                    pad_share: None,
+                    pattern: None,
                 },
             },
         }
@@ -273,6 +1071,15 @@ impl Default for SupervisorConfig {
             size: 30.,
             monitoring_y_pos: 285.,
             office_sprite_size: UVec2::new(400, 150),
+            restricted_zone_depth: default_restricted_zone_depth(),
+            restricted_zone_tint_alpha: default_restricted_zone_tint_alpha(),
+            confiscation_penalty: default_confiscation_penalty(),
+            confiscation_exemption_seconds: default_confiscation_exemption_seconds(),
+            hit_penalty: default_supervisor_hit_penalty(),
+            bump_knockback_speed: default_bump_knockback_speed(),
+            bump_knockback_seconds: default_bump_knockback_seconds(),
+            supervisors: default_supervisors(),
+            detection_grace_seconds: default_detection_grace_seconds(),
         }
     }
 }
@@ -285,6 +1092,25 @@ impl Default for ConveyorConfig {
             border_size: 14.,
             blinker_size: 20.,
             blink_duration_seconds: 0.1,
+            blinker_style: BlinkerStyle::default(),
+            countdown_pulse_speed: default_countdown_pulse_speed(),
+            outgoing_capacity_tolerance: default_outgoing_capacity_tolerance(),
+            front_grab_zone_depth: default_front_grab_zone_depth(),
+            jam_grace_seconds: default_jam_grace_seconds(),
+            jam_countdown_seconds: default_jam_countdown_seconds(),
+            jam_blink_duration_seconds: default_jam_blink_duration_seconds(),
+            outgoing_max_dwell_seconds: default_outgoing_max_dwell_seconds(),
+            dwell_eject_impulse: default_dwell_eject_impulse(),
+            show_capacity_indicators: default_show_capacity_indicators(),
+            capacity_indicator_width: default_capacity_indicator_width(),
+            breakdown_probability: 0.,
+            repair_seconds: default_repair_seconds(),
+            repair_reach: default_repair_reach(),
+            show_belt_arrows: default_show_belt_arrows(),
+            belt_arrow_spacing: default_belt_arrow_spacing(),
+            belt_arrow_size: default_belt_arrow_size(),
+            belt_arrow_alpha: default_belt_arrow_alpha(),
+            ramp_seconds: default_ramp_seconds(),
         }
     }
 }
@@ -294,6 +1120,14 @@ impl Default for PackageConfig {
         Self {
             size: 30.,
             base_score_value: 5.,
+            wave_fairness: None,
+            decay_enabled: false,
+            decay_seconds: default_decay_seconds(),
+            min_value_fraction: default_min_value_fraction(),
+            wave_delay_seconds: default_wave_delay_seconds(),
+            pool_size: default_package_pool_size(),
+            settle_speed: default_settle_speed(),
+            settle_seconds: default_settle_seconds(),
         }
     }
 }
@@ -303,77 +1137,664 @@ impl Default for ScoreConfig {
         Self {
             multiplier_increase_per_package: 0.1,
             multiplier_decrease_per_second: 0.1,
+            delivery_freeze_seconds: default_delivery_freeze_seconds(),
+            jam_penalty_per_package: default_jam_penalty_per_package(),
+            score_floor: ScoreFloor::default(),
+            penalties_scale_with_multiplier: false,
+            streaks: default_streaks(),
+            cashout_value: 0.,
+            cashout_cooldown_seconds: default_cashout_cooldown_seconds(),
         }
     }
 }
 
-impl Default for AppConfig {
+impl Default for IdleNudgeConfig {
     fn default() -> Self {
         Self {
-            base_resolution: UVec2::new(1280, 720),
-            rng_seed: Some(1000),
+            enabled: true,
+            idle_seconds: 8.,
+            pulse_speed: 4.,
+            highlight_strength: 0.35,
         }
     }
 }
 
-impl Default for GameConfig {
+impl Default for ShiftModifiers {
     fn default() -> Self {
         Self {
-            selected_texture_pack: "default".to_string(),
-            texture_packs: maplit::hashmap! {
-                "default".to_string() => TexturePack::default(),
-            },
-            team_colour: default_team_colour(),
-            player_config: PlayerConfig::default(),
-            supervisor_config: SupervisorConfig::default(),
-            conveyor_config: ConveyorConfig::default(),
-            package_config: PackageConfig::default(),
-            score_config: ScoreConfig::default(),
-            friction: 100.,
+            wave_countdown_scale: default_modifier_scale(),
+            supervisor_cycle_scale: default_modifier_scale(),
         }
     }
 }
 
-impl TextureValue {
-    pub fn choose_texture(&self, rng: Option<&mut Rand>) -> &SpriteSheetConfig {
-        match self {
-            TextureValue::Only(config) => config,
-            TextureValue::Choose(configs) => {
-                let index = rng.map_or(0, |rng| rng.gen_range(0..configs.len()));
-                &configs[index]
-            }
+impl KeyBind {
+    /// Either direction of an axis counts as the same control.
+    pub fn shares_pad_control(&self, other: &KeyBind) -> bool {
+        match (self, other) {
+            (KeyBind::ControllerButton(a), KeyBind::ControllerButton(b)) => a == b,
+            (KeyBind::ControllerAxis((a, _)), KeyBind::ControllerAxis((b, _))) => a == b,
+            _ => false,
         }
     }
 }
 
-impl TexturePack {
-    pub fn choose_texture_for(
-        &self,
-        target: TextureTarget,
-        rng: Option<&mut Rand>,
-    ) -> &SpriteSheetConfig {
-        self.texture_map[target].choose_texture(rng)
+impl PlayerConfig {
+    pub fn is_pad_shared(&self) -> bool {
+        matches!(
+            (
+                self.per_player[PlayerIndex::Player1].pad_share,
+                self.per_player[PlayerIndex::Player2].pad_share,
+            ),
+            (Some(a), Some(b)) if a != b
+        )
     }
-}
 
-impl GameConfig {
-    pub fn get_texture_pack(&self) -> &TexturePack {
-        self.texture_packs
-            .get(&self.selected_texture_pack)
-            .expect("Selected texture pack not found")
-    }
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let player_one = &self.per_player[PlayerIndex::Player1];
+        let player_two = &self.per_player[PlayerIndex::Player2];
+        match (player_one.pad_share, player_two.pad_share) {
+            (None, None) => return Ok(()),
+            (Some(a), Some(b)) if a == b => {
+                anyhow::bail!("both players want the {:?} side of a shared pad", a)
+            }
+            (Some(_), Some(_)) => {}
+            _ => anyhow::bail!("pad_share must be set for both players or neither"),
+        }
 
-    pub fn get_key_map(&self, player_index: PlayerIndex) -> &EnumMap<KeyAction, KeyBindConfig> {
-        &self.player_config.per_player[player_index].key_map
+        for (action, bind_config) in &player_one.key_map {
+            for bind in [&bind_config.priamry, &bind_config.secondary] {
+                for (other_action, other_bind_config) in &player_two.key_map {
+                    if [&other_bind_config.priamry, &other_bind_config.secondary]
+                        .iter()
+                        .any(|other_bind| bind.shares_pad_control(other_bind))
+                    {
+                        anyhow::bail!(
+                            "Player1 {:?} and Player2 {:?} are bound to the same control on the shared pad",
+                            action,
+                            other_action
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
-fn default_key_map_player_one() -> EnumMap<KeyAction, KeyBindConfig> {
-    default_key_map(PlayerIndex::Player1)
-}
+impl ScoreConfig {
+    pub fn cash_out_enabled(&self) -> bool {
+        self.cashout_value > 0.
+    }
 
-fn default_key_map_player_two() -> EnumMap<KeyAction, KeyBindConfig> {
-    default_key_map(PlayerIndex::Player2)
+    pub fn cash_out_points(&self, multiplier: f32) -> f32 {
+        (multiplier - 1.).max(0.) * self.cashout_value
+    }
+}
+
+impl ShiftConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (index, shift) in self.shifts.iter().enumerate() {
+            if shift.name.trim().is_empty() {
+                anyhow::bail!("shift {} has no name", index);
+            }
+            if self.shifts[..index]
+                .iter()
+                .any(|other| other.name == shift.name)
+            {
+                anyhow::bail!("shift \"{}\" is defined more than once", shift.name);
+            }
+            for (modifier, scale) in [
+                ("wave_countdown_scale", shift.modifiers.wave_countdown_scale),
+                (
+                    "supervisor_cycle_scale",
+                    shift.modifiers.supervisor_cycle_scale,
+                ),
+            ] {
+                if !scale.is_finite() || scale <= 0. {
+                    anyhow::bail!(
+                        "shift \"{}\" has an invalid {} of {}, it must be greater than zero",
+                        shift.name,
+                        modifier,
+                        scale
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for AfkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            warning_seconds: 30.,
+            timeout_seconds: 45.,
+            flash_speed: 10.,
+        }
+    }
+}
+
+impl Default for InputHintConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            charge_hint_uses: 3,
+            fade_seconds: 0.5,
+            glyph_size: 18.,
+            keyboard_banner_seconds: default_keyboard_banner_seconds(),
+            shared_keyboard_key: default_shared_keyboard_key(),
+            pad_fallback_notice_seconds: default_pad_fallback_notice_seconds(),
+        }
+    }
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            round_seconds: None,
+            rounds_per_match: 3,
+            swap_sides: true,
+            results_seconds: 5.,
+        }
+    }
+}
+
+impl Default for FriendlyFireConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stun_seconds: 1.5,
+            thrown_window_seconds: 1.,
+            velocity_kept: 0.2,
+            escalation_window_seconds: 30.,
+            escalation_cooldown_seconds: 0.5,
+        }
+    }
+}
+
+impl Default for WaveModifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            modifiers: [
+                WaveModifier::HeavyFreight,
+                WaveModifier::FragileGoods,
+                WaveModifier::RushOrder,
+                WaveModifier::Audit,
+            ]
+            .map(|modifier| WeightedWaveModifier {
+                modifier,
+                weight: 1.,
+            })
+            .to_vec(),
+            plain_wave_weight: 2.,
+            heavy_density_scale: 3.,
+            fragile_value_scale: 2.,
+            fragile_break_speed: 250.,
+            rush_belt_speed_scale: 1.5,
+            rush_countdown_scale: 0.5,
+            audit_monitoring_scale: 2.,
+        }
+    }
+}
+
+impl Default for EmoteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            display_seconds: 1.5,
+            cooldown_seconds: 4.,
+            cycle_window_seconds: 0.4,
+            bubble_size: 32.,
+        }
+    }
+}
+
+impl Default for ForkliftConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_interval_seconds: 20.,
+            max_interval_seconds: 40.,
+            speed: 220.,
+            lane_y_positions: vec![-60., 0., 60.],
+            size: Vec2::new(96., 48.),
+            forklift_warning_seconds: 2.,
+            stun_seconds: 1.,
+        }
+    }
+}
+
+impl Default for RumbleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            throw_min_charge: 0.75,
+            throw: RumblePulse {
+                strong_motor: 0.8,
+                weak_motor: 0.4,
+                duration_seconds: 0.15,
+            },
+            delivered: RumblePulse {
+                strong_motor: 0.,
+                weak_motor: 0.5,
+                duration_seconds: 0.08,
+            },
+            delivered_gap_seconds: 0.08,
+            penalty: RumblePulse {
+                strong_motor: 0.6,
+                weak_motor: 0.,
+                duration_seconds: 0.8,
+            },
+        }
+    }
+}
+
+impl Default for AttractModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            start_in_attract_mode: false,
+            idle_seconds: 60.,
+            min_hesitation_seconds: 0.2,
+            max_hesitation_seconds: 0.8,
+        }
+    }
+}
+
+impl Default for TrajectoryPreviewConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dot_count: 10,
+            dot_size: 6.,
+            fuzzy_distance: 300.,
+        }
+    }
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            camera_shake_enabled: true,
+            max_shake_offset: 12.,
+            max_shake_angle: 2.,
+            shake_frequency: 40.,
+            trauma_decay_per_second: 1.5,
+            delivered_trauma: 0.2,
+            caught_trauma: 0.7,
+            confiscated_trauma: default_confiscated_trauma(),
+            flash_alpha: 0.25,
+            flash_duration_seconds: 0.2,
+            time_dilation: TimeDilationConfig::default(),
+            sprint_trail: SprintTrailConfig::default(),
+        }
+    }
+}
+
+impl Default for SprintTrailConfig {
+    fn default() -> Self {
+        Self {
+            trail_interval: 0.05,
+            trail_lifetime: 0.25,
+            min_speed: 225.,
+            start_alpha: 0.5,
+        }
+    }
+}
+
+impl Default for TimeDilationConfig {
+    fn default() -> Self {
+        Self {
+            match_point_score: None,
+            match_point_scale: 0.35,
+            match_point_seconds: 1.5,
+            full_charge_throw_min_charge: 1.,
+            full_charge_throw_scale: 0.5,
+            full_charge_throw_seconds: 0.75,
+            final_seconds: 5.,
+            final_seconds_scale: 0.7,
+            heartbeat_beats_per_second: 1.2,
+            heartbeat_alpha: 0.35,
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            base_resolution: UVec2::new(1280, 720),
+            rng_seed: Some(1000),
+            metrics_path: None,
+            metrics_interval_seconds: default_metrics_interval_seconds(),
+            ui_mode: UiMode::WorldSpace,
+            language: default_language(),
+            locale_directory: default_locale_directory(),
+            observability_memory_budget_mb: default_observability_memory_budget_mb(),
+            camera_mode: CameraMode::Shared,
+            camera_follow_smoothing: default_camera_follow_smoothing(),
+            pause_on_focus_loss: default_pause_on_focus_loss(),
+            time_effects: default_time_effects(),
+            graphics_quality: GraphicsQuality::default(),
+            graphics_quality_toggle_key: default_graphics_quality_toggle_key(),
+            low_quality_max_flashes: 0,
+            debug_physics_render: false,
+            debug_overlays: false,
+            debug_overlay_toggle_key: default_debug_overlay_toggle_key(),
+            entity_audit_rounds: default_entity_audit_rounds(),
+            frame_dump: None,
+            colour_palette: ColourPalette::default(),
+            calibration: CalibrationConfig::default(),
+            frame_timing: FrameTimingConfig::default(),
+            results_path: None,
+            results_webhook: None,
+            results_webhook_timeout_seconds: default_results_webhook_timeout_seconds(),
+            machine_name: None,
+            tutorial: TutorialConfig::default(),
+            log_levels: BTreeMap::new(),
+            log_buffer_lines: default_log_buffer_lines(),
+            bug_report_key: default_bug_report_key(),
+            copy_seed_key: default_copy_seed_key(),
+        }
+    }
+}
+
+impl Default for TutorialConfig {
+    fn default() -> Self {
+        Self {
+            start_in_tutorial: false,
+            exit_key: KeyCode::Tab,
+            charge_fraction: 0.8,
+            step_pause_seconds: 1.,
+            finished_seconds: 4.,
+        }
+    }
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            default_deadzone: 0.1,
+            sample_seconds: 2.,
+            toggle_key: KeyCode::F7,
+            pads: Vec::new(),
+        }
+    }
+}
+
+impl Default for FrameTimingConfig {
+    fn default() -> Self {
+        Self {
+            overrun_threshold_ms: 12.,
+            overrun_ticks: 5,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            selected_texture_pack: "default".to_string(),
+            texture_packs: maplit::hashmap! {
+                "default".to_string() => TexturePack::default(),
+            },
+            texture_selection: TextureSelection::default(),
+            selected_layout: default_layout_key(),
+            layouts: default_layouts(),
+            team_colour: default_team_colour(),
+            player_config: PlayerConfig::default(),
+            supervisor_config: SupervisorConfig::default(),
+            conveyor_config: ConveyorConfig::default(),
+            package_config: PackageConfig::default(),
+            score_config: ScoreConfig::default(),
+            idle_nudge_config: IdleNudgeConfig::default(),
+            feedback_config: FeedbackConfig::default(),
+            trajectory_preview_config: TrajectoryPreviewConfig::default(),
+            attract_mode_config: AttractModeConfig::default(),
+            shift_config: ShiftConfig::default(),
+            rumble_config: RumbleConfig::default(),
+            friendly_fire_config: FriendlyFireConfig::default(),
+            forklift_config: ForkliftConfig::default(),
+            wave_modifier_config: WaveModifierConfig::default(),
+            afk_config: AfkConfig::default(),
+            match_config: MatchConfig::default(),
+            input_hint_config: InputHintConfig::default(),
+            emote_config: EmoteConfig::default(),
+            pad_axis_fallbacks: default_pad_axis_fallbacks(),
+            friction: 100.,
+            game_mode: GameModeKind::default(),
+            hazards: Vec::new(),
+            powerups: None,
+            stealth_outgoing: false,
+        }
+    }
+}
+
+impl TextureValue {
+    pub fn choose_texture(&self, rng: Option<&mut Rand>) -> &SpriteSheetConfig {
+        match self {
+            TextureValue::Only(config) => config,
+            TextureValue::Choose(configs) => {
+                let index = rng.map_or(0, |rng| rng.gen_range(0..configs.len()));
+                &configs[index]
+            }
+        }
+    }
+
+    pub fn choose_texture_stable(&self, key: u64) -> &SpriteSheetConfig {
+        match self {
+            TextureValue::Only(config) => config,
+            TextureValue::Choose(configs) => {
+                &configs[(stable_hash(key) % configs.len() as u64) as usize]
+            }
+        }
+    }
+
+    pub fn select_texture(
+        &self,
+        selection: TextureSelection,
+        key: u64,
+        rng: Option<&mut Rand>,
+    ) -> &SpriteSheetConfig {
+        match (selection, self) {
+            (TextureSelection::Random, _) => self.choose_texture(rng),
+            (TextureSelection::StablePerEntity, _) => self.choose_texture_stable(key),
+            (TextureSelection::Fixed(_), TextureValue::Only(config)) => config,
+            (TextureSelection::Fixed(index), TextureValue::Choose(configs)) => {
+                &configs[index.min(configs.len() - 1)]
+            }
+        }
+    }
+
+    pub fn choice_count(&self) -> usize {
+        match self {
+            TextureValue::Only(_) => 1,
+            TextureValue::Choose(configs) => configs.len(),
+        }
+    }
+}
+
+/// splitmix64's finaliser, std's hashers aren't promised to stay the same between releases.
+fn stable_hash(key: u64) -> u64 {
+    let mut hash = key.wrapping_add(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+impl SpriteSheetConfig {
+    pub fn atlas_layout(&self, what: &str) -> (TextureAtlasLayout, usize) {
+        let cell_resolution = self
+            .cell_resolution
+            .unwrap_or_else(|| panic!("{} sprite must have a cell resolution", what));
+        let grid_dimensions = self
+            .grid_dimensions
+            .unwrap_or_else(|| panic!("{} sprite must have grid dimensions", what));
+        (
+            TextureAtlasLayout::from_grid(
+                cell_resolution.as_vec2(),
+                grid_dimensions.x as usize,
+                grid_dimensions.y as usize,
+                None,
+                None,
+            ),
+            (grid_dimensions.x * grid_dimensions.y) as usize,
+        )
+    }
+
+    pub fn expected_image_size(&self) -> Option<UVec2> {
+        Some(self.grid_dimensions? * self.cell_resolution?)
+    }
+}
+
+impl TexturePack {
+    pub fn choose_texture_for(
+        &self,
+        target: TextureTarget,
+        rng: Option<&mut Rand>,
+    ) -> &SpriteSheetConfig {
+        self.try_choose_texture_for(target, rng)
+            .expect("Required texture missing from the texture pack")
+    }
+
+    pub fn select_texture_for(
+        &self,
+        target: TextureTarget,
+        selection: TextureSelection,
+        key: u64,
+        rng: Option<&mut Rand>,
+    ) -> &SpriteSheetConfig {
+        self.texture_map[target]
+            .as_ref()
+            .expect("Required texture missing from the texture pack")
+            .select_texture(selection, key, rng)
+    }
+
+    pub fn try_choose_texture_for(
+        &self,
+        target: TextureTarget,
+        rng: Option<&mut Rand>,
+    ) -> Option<&SpriteSheetConfig> {
+        self.texture_map[target]
+            .as_ref()
+            .map(|texture| texture.choose_texture(rng))
+    }
+
+    /// 2 for a 256 cell sheet in a pack authored at 128.
+    pub fn pixel_scale(&self, sprite: &SpriteSheetConfig) -> f32 {
+        sprite
+            .cell_resolution
+            .map_or(1., |cell| cell.x as f32 / self.reference_cell_size.x as f32)
+    }
+
+    pub fn display_size(&self, sprite: &SpriteSheetConfig, size: f32) -> Vec2 {
+        let cell = sprite
+            .cell_resolution
+            .unwrap_or(self.reference_cell_size)
+            .as_vec2();
+        (cell / self.pixel_scale(sprite)) * (size / self.reference_cell_size.x as f32)
+    }
+}
+
+impl PlayerConfig {
+    pub fn pickup_radius(&self) -> f32 {
+        self.size * self.pickup_radius_scale
+    }
+
+    pub fn carry_speed_multiplier(&self, held: usize) -> f32 {
+        (1. - self.extra_carry_speed_penalty * held.saturating_sub(1) as f32).max(0.)
+    }
+}
+
+impl GameConfig {
+    pub fn players_collide(&self) -> bool {
+        self.player_config
+            .players_collide
+            .unwrap_or_else(|| self.game_mode.default_players_collide())
+    }
+
+    pub fn get_texture_pack(&self) -> &TexturePack {
+        self.texture_packs
+            .get(&self.selected_texture_pack)
+            .expect("Selected texture pack not found")
+    }
+
+    pub fn validate_layout(&self) -> anyhow::Result<()> {
+        if !self.layouts.contains_key(&self.selected_layout) {
+            let mut known = self.layouts.keys().cloned().collect::<Vec<_>>();
+            known.sort();
+            anyhow::bail!(
+                "selected_layout \"{}\" isn't one of the layouts, there's {}",
+                self.selected_layout,
+                known.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// A `Fixed` selection has to be valid for every sheet list it's used on.
+    pub fn validate_texture_selection(&self) -> anyhow::Result<()> {
+        let TextureSelection::Fixed(index) = self.texture_selection else {
+            return Ok(());
+        };
+        let texture_pack = self
+            .texture_packs
+            .get(&self.selected_texture_pack)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "selected_texture_pack \"{}\" isn't one of the texture packs",
+                    self.selected_texture_pack
+                )
+            })?;
+        let mut selectable = [TextureTarget::AllPlayers, TextureTarget::Supervisor]
+            .into_iter()
+            .filter_map(|target| {
+                texture_pack.texture_map[target]
+                    .as_ref()
+                    .map(|texture| (format!("{:?}", target), texture))
+            })
+            .collect::<Vec<_>>();
+        for (player_index, per_player) in self.player_config.per_player.iter() {
+            if let Some(texture) = &per_player.sprite_override {
+                selectable.push((format!("{:?} sprite_override", player_index), texture));
+            }
+        }
+        for (what, texture) in selectable {
+            if matches!(texture, TextureValue::Choose(_)) && index >= texture.choice_count() {
+                anyhow::bail!(
+                    "texture_selection Fixed({}) is out of range, {} only has {} to choose from",
+                    index,
+                    what,
+                    texture.choice_count()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_layout(&self) -> &ArenaLayoutConfig {
+        self.layouts
+            .get(&self.selected_layout)
+            .expect("Selected layout not found")
+    }
+
+    pub fn pixels_per_meter(&self) -> f32 {
+        self.package_config.size * 2.
+    }
+
+    pub fn get_key_map(&self, player_index: PlayerIndex) -> &EnumMap<KeyAction, KeyBindConfig> {
+        &self.player_config.per_player[player_index].key_map
+    }
+}
+
+fn default_key_map_player_one() -> EnumMap<KeyAction, KeyBindConfig> {
+    default_key_map(PlayerIndex::Player1)
+}
+
+fn default_key_map_player_two() -> EnumMap<KeyAction, KeyBindConfig> {
+    default_key_map(PlayerIndex::Player2)
 }
 
 fn default_key_map(player_index: PlayerIndex) -> EnumMap<KeyAction, KeyBindConfig> {
@@ -420,26 +1841,541 @@ fn default_key_map(player_index: PlayerIndex) -> EnumMap<KeyAction, KeyBindConfi
             }),
             secondary: KeyBind::ControllerAxis((GamepadAxisType::RightZ, AxisDirection::Positive)),
         },
+        KeyAction::Drop => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyE,
+                PlayerIndex::Player2 => KeyCode::Enter,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::East),
+        },
+        KeyAction::Interact => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyF,
+                PlayerIndex::Player2 => KeyCode::Backslash,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::North),
+        },
+        KeyAction::Emote => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyQ,
+                PlayerIndex::Player2 => KeyCode::Quote,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::West),
+        },
     }
 }
 
-fn default_texture_pack_key() -> String {
-    "default".to_string()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMapPreset {
+    Standard,
+    SharedKeyboard,
 }
 
-fn default_texture_pack() -> HashMap<String, TexturePack> {
-    maplit::hashmap! {
-        default_texture_pack_key() => TexturePack::default(),
-    }
-}
+pub const KEY_MAP_PRESETS: [KeyMapPreset; 2] =
+    [KeyMapPreset::Standard, KeyMapPreset::SharedKeyboard];
 
-fn default_team_colour() -> Color {
-    Color::rgb_linear(0.6, 0.1, 0.6)
+impl KeyMapPreset {
+    pub fn key_map(&self, player_index: PlayerIndex) -> EnumMap<KeyAction, KeyBindConfig> {
+        match self {
+            KeyMapPreset::Standard => default_key_map(player_index),
+            KeyMapPreset::SharedKeyboard => shared_keyboard_key_map(player_index),
+        }
+    }
 }
 
-pub const CONFIG_FILENAME: &'static str = "play_nice.toml";
-
-/// Searches for `filename` in `directory` and parent directories until found or root is reached.
+fn shared_keyboard_key_map(player_index: PlayerIndex) -> EnumMap<KeyAction, KeyBindConfig> {
+    enum_map! {
+        KeyAction::MoveUp => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyW,
+                PlayerIndex::Player2 => KeyCode::KeyI,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::LeftStickY, AxisDirection::Positive)),
+        },
+        KeyAction::MoveDown => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyS,
+                PlayerIndex::Player2 => KeyCode::KeyK,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::LeftStickY, AxisDirection::Negative)),
+        },
+        KeyAction::MoveLeft => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyA,
+                PlayerIndex::Player2 => KeyCode::KeyJ,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::LeftStickX, AxisDirection::Negative)),
+        },
+        KeyAction::MoveRight => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyD,
+                PlayerIndex::Player2 => KeyCode::KeyL,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::LeftStickX, AxisDirection::Positive)),
+        },
+        KeyAction::Sprint => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyQ,
+                PlayerIndex::Player2 => KeyCode::KeyU,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::LeftZ, AxisDirection::Positive)),
+        },
+        KeyAction::PickupOrThrow => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyE,
+                PlayerIndex::Player2 => KeyCode::KeyO,
+            }),
+            secondary: KeyBind::ControllerAxis((GamepadAxisType::RightZ, AxisDirection::Positive)),
+        },
+        KeyAction::Drop => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyR,
+                PlayerIndex::Player2 => KeyCode::KeyP,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::East),
+        },
+        KeyAction::Interact => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyF,
+                PlayerIndex::Player2 => KeyCode::Semicolon,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::North),
+        },
+        KeyAction::Emote => KeyBindConfig {
+            priamry: KeyBind::Key(match player_index {
+                PlayerIndex::Player1 => KeyCode::KeyC,
+                PlayerIndex::Player2 => KeyCode::Comma,
+            }),
+            secondary: KeyBind::ControllerButton(GamepadButtonType::West),
+        },
+    }
+}
+
+fn default_texture_pack_key() -> String {
+    "default".to_string()
+}
+
+fn default_reference_cell_size() -> UVec2 {
+    UVec2::new(128, 128)
+}
+
+fn default_texture_pack() -> HashMap<String, TexturePack> {
+    maplit::hashmap! {
+        default_texture_pack_key() => TexturePack::default(),
+    }
+}
+
+impl TextureTarget {
+    pub fn is_optional(&self) -> bool {
+        matches!(self, TextureTarget::BeltArrow)
+    }
+}
+
+fn deserialize_texture_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<EnumMap<TextureTarget, Option<TextureValue>>, D::Error> {
+    let mut texture_map = EnumMap::default();
+    for (target, texture) in HashMap::<TextureTarget, TextureValue>::deserialize(deserializer)? {
+        texture_map[target] = Some(texture);
+    }
+    if let Some((target, _)) = texture_map
+        .iter()
+        .find(|(target, texture)| texture.is_none() && !target.is_optional())
+    {
+        return Err(serde::de::Error::custom(format!(
+            "texture_map has no {:?} texture",
+            target
+        )));
+    }
+    Ok(texture_map)
+}
+
+fn deserialize_rng_seed<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RngSeed {
+        Seed(u64),
+        Reproduction(String),
+    }
+
+    match RngSeed::deserialize(deserializer)? {
+        RngSeed::Seed(seed) => Ok(Some(seed)),
+        RngSeed::Reproduction(reproduction) => crate::parse_seed(&reproduction)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn default_layout_key() -> String {
+    "classic".to_string()
+}
+
+fn default_layouts() -> HashMap<String, ArenaLayoutConfig> {
+    maplit::hashmap! {
+        default_layout_key() => ArenaLayoutConfig::default(),
+    }
+}
+
+fn default_metrics_interval_seconds() -> f32 {
+    60.
+}
+
+fn default_results_webhook_timeout_seconds() -> f32 {
+    5.
+}
+
+fn default_observability_memory_budget_mb() -> f32 {
+    4.
+}
+
+fn default_camera_follow_smoothing() -> f32 {
+    5.
+}
+
+fn default_pause_on_focus_loss() -> bool {
+    true
+}
+
+fn default_time_effects() -> bool {
+    true
+}
+
+fn default_graphics_quality_toggle_key() -> KeyCode {
+    KeyCode::F6
+}
+
+fn default_debug_overlay_toggle_key() -> KeyCode {
+    KeyCode::F3
+}
+
+fn default_entity_audit_rounds() -> usize {
+    5
+}
+
+fn default_log_buffer_lines() -> usize {
+    500
+}
+
+fn default_bug_report_key() -> KeyCode {
+    KeyCode::F8
+}
+
+fn default_copy_seed_key() -> KeyCode {
+    KeyCode::F9
+}
+
+fn default_frame_dump_every_n_ticks() -> u32 {
+    1
+}
+
+fn default_language() -> String {
+    crate::DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_locale_directory() -> PathBuf {
+    PathBuf::from("assets/locale")
+}
+
+fn default_powerup_spawn_interval_seconds() -> f32 {
+    20.
+}
+
+fn default_powerup_max_on_floor() -> usize {
+    2
+}
+
+fn default_powerup_lifetime_seconds() -> f32 {
+    10.
+}
+
+fn default_powerup_modifier_seconds() -> f32 {
+    8.
+}
+
+fn default_powerup_size() -> f32 {
+    24.
+}
+
+fn default_powerup_kinds() -> Vec<PowerupKind> {
+    vec![
+        PowerupKind::SpeedBoost,
+        PowerupKind::InstantMaxCharge,
+        PowerupKind::MultiplierShield,
+        PowerupKind::MagnetHands,
+    ]
+}
+
+fn default_speed_boost_multiplier() -> f32 {
+    1.4
+}
+
+fn default_magnet_radius_multiplier() -> f32 {
+    2.
+}
+
+fn default_pad_axis_fallbacks() -> Vec<PadAxisFallback> {
+    vec![
+        PadAxisFallback {
+            axis: GamepadAxisType::LeftZ,
+            direction: AxisDirection::Positive,
+            button: GamepadButtonType::LeftTrigger2,
+        },
+        PadAxisFallback {
+            axis: GamepadAxisType::RightZ,
+            direction: AxisDirection::Positive,
+            button: GamepadButtonType::RightTrigger2,
+        },
+    ]
+}
+
+fn default_allow_direct_place() -> bool {
+    true
+}
+
+fn default_direct_place_max_charge() -> f32 {
+    0.1
+}
+
+fn default_pickup_cooldown_seconds() -> f32 {
+    0.3
+}
+
+fn default_allow_returns() -> bool {
+    true
+}
+
+fn default_return_multiplier_penalty() -> f32 {
+    0.2
+}
+
+fn default_containment_return_rate() -> f32 {
+    10.
+}
+
+fn default_aim_assist_cone_degrees() -> f32 {
+    60.
+}
+
+fn default_aim_assist_max_distance() -> f32 {
+    500.
+}
+
+fn default_tap_to_drop() -> bool {
+    true
+}
+
+fn default_pickup_radius_scale() -> f32 {
+    2.5
+}
+
+fn default_max_carry() -> usize {
+    1
+}
+
+fn default_extra_carry_speed_penalty() -> f32 {
+    0.25
+}
+
+fn default_push_strength() -> f32 {
+    1.
+}
+
+fn default_outgoing_capacity_tolerance() -> f32 {
+    0.1
+}
+
+fn default_streaks() -> Vec<StreakTier> {
+    vec![
+        StreakTier {
+            deliveries: 3,
+            window_seconds: 5.,
+            bonus_points: 20.,
+            multiplier_gain_factor: 1.5,
+            boost_seconds: 5.,
+        },
+        StreakTier {
+            deliveries: 5,
+            window_seconds: 8.,
+            bonus_points: 60.,
+            multiplier_gain_factor: 2.,
+            boost_seconds: 8.,
+        },
+    ]
+}
+
+fn default_delivery_freeze_seconds() -> f32 {
+    2.
+}
+
+fn default_keyboard_banner_seconds() -> f32 {
+    8.
+}
+
+fn default_shared_keyboard_key() -> KeyCode {
+    KeyCode::F2
+}
+
+fn default_pad_fallback_notice_seconds() -> f32 {
+    6.
+}
+
+fn default_cashout_cooldown_seconds() -> f32 {
+    10.
+}
+
+fn default_front_grab_zone_depth() -> f32 {
+    45.
+}
+
+fn default_jam_grace_seconds() -> f32 {
+    10.
+}
+
+fn default_jam_countdown_seconds() -> f32 {
+    5.
+}
+
+fn default_countdown_pulse_speed() -> f32 {
+    1.5
+}
+
+fn default_jam_blink_duration_seconds() -> f32 {
+    0.05
+}
+
+fn default_outgoing_max_dwell_seconds() -> f32 {
+    8.
+}
+
+fn default_dwell_eject_impulse() -> f32 {
+    1500.
+}
+
+fn default_decay_seconds() -> f32 {
+    30.
+}
+
+fn default_min_value_fraction() -> f32 {
+    0.4
+}
+
+fn default_wave_delay_seconds() -> f32 {
+    5.
+}
+
+fn default_package_pool_size() -> usize {
+    64
+}
+
+fn default_settle_speed() -> f32 {
+    4.
+}
+
+fn default_settle_seconds() -> f32 {
+    0.5
+}
+
+fn default_show_capacity_indicators() -> bool {
+    true
+}
+
+fn default_capacity_indicator_width() -> f32 {
+    4.
+}
+
+fn default_repair_seconds() -> f32 {
+    3.
+}
+
+fn default_repair_reach() -> f32 {
+    20.
+}
+
+fn default_show_belt_arrows() -> bool {
+    true
+}
+
+fn default_belt_arrow_spacing() -> f32 {
+    80.
+}
+
+fn default_belt_arrow_size() -> f32 {
+    24.
+}
+
+fn default_belt_arrow_alpha() -> f32 {
+    0.35
+}
+
+fn default_ramp_seconds() -> f32 {
+    0.25
+}
+
+fn default_restricted_zone_depth() -> f32 {
+    120.
+}
+
+fn default_restricted_zone_tint_alpha() -> f32 {
+    0.08
+}
+
+fn default_confiscation_penalty() -> f32 {
+    25.
+}
+
+fn default_confiscation_exemption_seconds() -> f32 {
+    3.
+}
+
+fn default_supervisor_hit_penalty() -> f32 {
+    10.
+}
+
+fn default_bump_knockback_speed() -> f32 {
+    300.
+}
+
+fn default_bump_knockback_seconds() -> f32 {
+    0.2
+}
+
+fn default_supervisors() -> Vec<SupervisorInstanceConfig> {
+    vec![SupervisorInstanceConfig {
+        monitoring_x_pos: 0.,
+        patrol_range: 0.,
+        timer_offset_seconds: 0.,
+    }]
+}
+
+fn default_detection_grace_seconds() -> f32 {
+    1.
+}
+
+fn default_confiscated_trauma() -> f32 {
+    0.9
+}
+
+fn default_jam_penalty_per_package() -> f32 {
+    5.
+}
+
+fn default_modifier_scale() -> f32 {
+    1.
+}
+
+fn default_shift_tint() -> ConfigColour {
+    ConfigColour(Color::WHITE)
+}
+
+fn default_team_colour() -> ConfigColour {
+    ConfigColour(Color::rgb_linear(0.6, 0.1, 0.6))
+}
+
+pub const CONFIG_FILENAME: &str = "play_nice.toml";
+
+/// Searches for `filename` in `directory` and parent directories until found or root is reached.
 pub fn find_config(directory: &Path, filename: &Path) -> anyhow::Result<PathBuf> {
     let candidate = directory.join(filename);
 
@@ -463,26 +2399,475 @@ pub fn find_config(directory: &Path, filename: &Path) -> anyhow::Result<PathBuf>
     }
 }
 
-pub fn read_config(config_path: Option<PathBuf>) -> anyhow::Result<Config> {
+pub fn config_directory() -> PathBuf {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.unwrap_or_else(std::env::temp_dir).join("play-nice")
+}
+
+/// Through a temporary file, so a crash never leaves a truncated config.
+pub fn write_file_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    {
+        let mut temp_file = std::fs::File::create(&temp_path)?;
+        temp_file.write_all(contents.as_bytes())?;
+        temp_file.sync_all()?;
+    }
+    std::fs::rename(&temp_path, path)
+}
+
+pub fn write_default_config(path: &Path) -> anyhow::Result<()> {
+    let default_config_str = toml::to_string_pretty(&Config::default())?;
+    write_file_atomically(path, &default_config_str)?;
+    Ok(())
+}
+
+pub const LOCAL_CONFIG_FILENAME: &str = "play_nice.local.toml";
+/// e.g. `PLAY_NICE__APP__RNG_SEED=42`.
+pub const CONFIG_ENV_PREFIX: &str = "PLAY_NICE__";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigLayer {
+    Defaults,
+    File(PathBuf),
+    LocalOverride(PathBuf),
+    Environment(Vec<String>),
+    CommandLine(Vec<String>),
+}
+
+#[derive(Resource)]
+pub struct ConfigLayers(pub Vec<ConfigLayer>);
+
+impl ConfigLayers {
+    pub fn local_override_path(&self) -> PathBuf {
+        self.0
+            .iter()
+            .find_map(|layer| match layer {
+                ConfigLayer::File(path) => path
+                    .parent()
+                    .map(|directory| directory.join(LOCAL_CONFIG_FILENAME)),
+                _ => None,
+            })
+            .unwrap_or_else(|| config_directory().join(LOCAL_CONFIG_FILENAME))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    pub rng_seed: Option<u64>,
+    pub base_resolution: Option<UVec2>,
+    pub texture_pack: Option<String>,
+    pub tutorial: bool,
+}
+
+impl ConfigOverrides {
+    fn fields(&self) -> Vec<(&'static str, toml::Value)> {
+        let mut fields = Vec::new();
+        if let Some(rng_seed) = self.rng_seed {
+            fields.push(("app.rng_seed", toml::Value::Integer(rng_seed as i64)));
+        }
+        if let Some(resolution) = self.base_resolution {
+            fields.push((
+                "app.base_resolution",
+                toml::Value::Array(vec![
+                    toml::Value::Integer(resolution.x as i64),
+                    toml::Value::Integer(resolution.y as i64),
+                ]),
+            ));
+        }
+        if let Some(texture_pack) = &self.texture_pack {
+            fields.push((
+                "game.selected_texture_pack",
+                toml::Value::String(texture_pack.clone()),
+            ));
+        }
+        if self.tutorial {
+            fields.push(("app.tutorial.start_in_tutorial", toml::Value::Boolean(true)));
+        }
+        fields
+    }
+}
+
+/// Tables are merged key by key, anything else including arrays is replaced whole.
+pub fn merge_config_values(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn set_config_value(root: &mut toml::Value, path: &str, value: toml::Value) -> anyhow::Result<()> {
+    let mut keys = path.split('.').collect::<Vec<_>>();
+    let last = keys
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("empty config path"))?;
+    let mut table = root
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("config root is not a table"))?;
+    for key in keys {
+        table = table
+            .entry(key)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("{} in {} is not a table", key, path))?;
+    }
+    table.insert(last.to_string(), value);
+    Ok(())
+}
+
+pub fn save_config_value(path: &Path, field_path: &str, value: toml::Value) -> anyhow::Result<()> {
+    let mut root = if path.is_file() {
+        read_config_value(path)?
+    } else {
+        toml::Value::Table(toml::Table::new())
+    };
+    set_config_value(&mut root, field_path, value)?;
+    write_file_atomically(path, &toml::to_string_pretty(&root)?)?;
+    Ok(())
+}
+
+/// Anything that doesn't parse as TOML is taken as a plain string.
+fn parse_env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+fn read_config_value(path: &Path) -> anyhow::Result<toml::Value> {
+    let config_file = std::fs::read_to_string(path)?;
+    toml::from_str(&config_file)
+        .map_err(|error| anyhow::anyhow!("failed to parse {}: {}", path.display(), error))
+}
+
+/// Layers applied in order: the config file, `play_nice.local.toml` beside it, environment
+/// variables and then `overrides`.
+pub fn read_config(
+    config_path: Option<PathBuf>,
+    overrides: &ConfigOverrides,
+    output_policy: DataOutputPolicy,
+) -> anyhow::Result<(Config, Vec<ConfigLayer>)> {
+    let user_config_path = config_directory().join(CONFIG_FILENAME);
     let config_path = if let Some(path) = config_path {
-        Ok(path.to_path_buf())
+        Some(path.to_path_buf())
     } else {
         find_config(&std::env::current_dir()?, Path::new(CONFIG_FILENAME))
+            .ok()
+            .or_else(|| user_config_path.is_file().then(|| user_config_path.clone()))
     };
 
-    match config_path {
-        Ok(config_path) => {
-            let config_file = std::fs::read_to_string(config_path)?;
-            let config: Config = toml::from_str(&config_file)?;
-            Ok(config)
+    let mut layers = Vec::new();
+    let (mut value, config_directory) = match config_path {
+        Some(config_path) => {
+            let value = read_config_value(&config_path)?;
+            let config_directory = config_path.parent().map(Path::to_path_buf);
+            layers.push(ConfigLayer::File(config_path));
+            (value, config_directory)
+        }
+        None => {
+            // no config anywhere, create a default config in the user's config directory
+            if output_policy.allows_writes() {
+                write_default_config(&user_config_path)?;
+            }
+            layers.push(ConfigLayer::Defaults);
+            (
+                toml::Value::try_from(Config::default())?,
+                Some(config_directory()),
+            )
         }
-        Err(_) => {
-            // error finding config file, create a default config and write out to file
-            let default_config = Config::default();
-            let default_config_str = toml::to_string_pretty(&default_config)?;
-            let default_config_path = std::env::current_dir()?.join(CONFIG_FILENAME);
-            std::fs::write(default_config_path, default_config_str)?;
-            Ok(default_config)
+    };
+
+    if let Some(local_config_path) = config_directory
+        .map(|directory| directory.join(LOCAL_CONFIG_FILENAME))
+        .filter(|path| path.is_file())
+    {
+        merge_config_values(&mut value, read_config_value(&local_config_path)?);
+        layers.push(ConfigLayer::LocalOverride(local_config_path));
+    }
+
+    let mut env_overrides = std::env::vars()
+        .filter_map(|(name, raw)| {
+            let path = name
+                .strip_prefix(CONFIG_ENV_PREFIX)?
+                .split("__")
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+                .join(".");
+            Some((name, path, raw))
+        })
+        .collect::<Vec<_>>();
+    env_overrides.sort();
+    if !env_overrides.is_empty() {
+        let mut names = Vec::new();
+        for (name, path, raw) in env_overrides {
+            set_config_value(&mut value, &path, parse_env_value(&raw))?;
+            names.push(name);
         }
+        layers.push(ConfigLayer::Environment(names));
+    }
+
+    let cli_fields = overrides.fields();
+    if !cli_fields.is_empty() {
+        let mut paths = Vec::new();
+        for (path, field_value) in cli_fields {
+            set_config_value(&mut value, path, field_value)?;
+            paths.push(path.to_string());
+        }
+        layers.push(ConfigLayer::CommandLine(paths));
+    }
+
+    Ok((value.try_into()?, layers))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn choice_of(count: usize) -> TextureValue {
+        TextureValue::Choose(
+            (0..count)
+                .map(|index| SpriteSheetConfig {
+                    path: format!("sheet_{}.png", index),
+                    grid_dimensions: Some(UVec2::new(4, 1)),
+                    cell_resolution: Some(UVec2::new(128, 128)),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn stable_hash_is_pinned() {
+        // a changed hash would quietly reshuffle everyone's sheets
+        assert_eq!(stable_hash(0), 0xe220a8397b1dcdaf);
+    }
+
+    #[test]
+    fn stable_selection_is_the_same_for_the_same_key() {
+        let texture = choice_of(4);
+        let mut rng = Rand::new(&Some(1));
+        let mut other_rng = Rand::new(&Some(2));
+        let mut seen = HashSet::new();
+        for key in 0..64 {
+            let first = &texture
+                .select_texture(TextureSelection::StablePerEntity, key, Some(&mut rng))
+                .path;
+            let again = &texture
+                .select_texture(TextureSelection::StablePerEntity, key, Some(&mut other_rng))
+                .path;
+            assert_eq!(first, again, "key {} changed sheet", key);
+            assert_eq!(first, &texture.choose_texture_stable(key).path);
+            seen.insert(first.clone());
+        }
+        assert!(seen.len() > 1, "every key landed on the same sheet");
+    }
+
+    #[test]
+    fn out_of_range_fixed_selection_is_a_config_error() {
+        let mut game_config = GameConfig::default();
+        let player_choices = game_config.get_texture_pack().texture_map[TextureTarget::AllPlayers]
+            .as_ref()
+            .map_or(1, TextureValue::choice_count);
+
+        game_config.texture_selection = TextureSelection::Fixed(0);
+        assert!(game_config.validate_texture_selection().is_ok());
+        game_config.texture_selection = TextureSelection::Fixed(player_choices);
+        assert!(game_config.validate_texture_selection().is_err());
+    }
+
+    #[test]
+    fn fixed_selection_past_a_sprite_override_is_a_config_error() {
+        let mut game_config = GameConfig::default();
+        game_config.texture_selection = TextureSelection::Fixed(1);
+        assert!(game_config.validate_texture_selection().is_ok());
+        game_config.player_config.per_player[PlayerIndex::Player1].sprite_override =
+            Some(choice_of(1));
+        assert!(game_config.validate_texture_selection().is_err());
+    }
+
+    fn preset_keys(preset: KeyMapPreset, player_index: PlayerIndex) -> Vec<KeyCode> {
+        preset
+            .key_map(player_index)
+            .values()
+            .flat_map(|bind| [&bind.priamry, &bind.secondary])
+            .filter_map(|bind| match bind {
+                KeyBind::Key(key_code) => Some(*key_code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn key_map_presets_never_share_a_key() {
+        for preset in KEY_MAP_PRESETS {
+            let player_one = preset_keys(preset, PlayerIndex::Player1);
+            let player_two = preset_keys(preset, PlayerIndex::Player2);
+            for key_code in &player_one {
+                assert!(
+                    !player_two.contains(key_code),
+                    "{:?} binds {:?} for both players",
+                    preset,
+                    key_code
+                );
+            }
+            for keys in [player_one, player_two] {
+                let unique = keys.iter().collect::<HashSet<_>>();
+                assert_eq!(unique.len(), keys.len(), "{:?} binds a key twice", preset);
+            }
+        }
+    }
+
+    fn shift_config(shifts: &[(&str, f32, f32)]) -> ShiftConfig {
+        ShiftConfig {
+            selection: ShiftSelection::Sequential,
+            shifts: shifts
+                .iter()
+                .map(
+                    |(name, wave_countdown_scale, supervisor_cycle_scale)| ShiftDefinition {
+                        name: name.to_string(),
+                        tint: default_shift_tint(),
+                        modifiers: ShiftModifiers {
+                            wave_countdown_scale: *wave_countdown_scale,
+                            supervisor_cycle_scale: *supervisor_cycle_scale,
+                        },
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn invalid_shift_definitions_are_rejected() {
+        assert!(shift_config(&[]).validate().is_ok());
+        assert!(
+            shift_config(&[("Monday Morning", 1., 1.), ("Night Shift", 1.2, 0.5)])
+                .validate()
+                .is_ok()
+        );
+
+        for invalid in [
+            shift_config(&[("  ", 1., 1.)]),
+            shift_config(&[("Night Shift", 1., 1.), ("Night Shift", 2., 1.)]),
+            shift_config(&[("Friday Crunch", 0., 1.)]),
+            shift_config(&[("Friday Crunch", 1., -0.5)]),
+            shift_config(&[("Friday Crunch", f32::NAN, 1.)]),
+            shift_config(&[("Friday Crunch", 1., f32::INFINITY)]),
+        ] {
+            assert!(
+                invalid.validate().is_err(),
+                "{:?}",
+                invalid
+                    .shifts
+                    .iter()
+                    .map(|shift| (&shift.name, &shift.modifiers))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+    #[test]
+    fn partial_tables_only_override_what_they_name() {
+        let mut value = toml::Value::try_from(Config::default()).unwrap();
+        let overlay =
+            toml::from_str::<toml::Value>("[game.player_config]\nmove_speed = 321.0").unwrap();
+        merge_config_values(&mut value, overlay);
+        let config = value.try_into::<Config>().unwrap();
+
+        let defaults = Config::default();
+        assert_eq!(config.game.player_config.move_speed, 321.);
+        assert_eq!(
+            config.game.player_config.throw_power,
+            defaults.game.player_config.throw_power
+        );
+        assert_eq!(
+            config.game.player_config.size,
+            defaults.game.player_config.size
+        );
+        assert_eq!(config.app.base_resolution, defaults.app.base_resolution);
+    }
+
+    #[test]
+    fn config_layers_apply_in_order() {
+        let directory = std::env::temp_dir()
+            .join(format!("play-nice-test-{}", std::process::id()))
+            .join("config-layers");
+        std::fs::create_dir_all(&directory).unwrap();
+        let config_path = directory.join(CONFIG_FILENAME);
+        let mut file_config = Config::default();
+        file_config.app.rng_seed = Some(1);
+        file_config.app.metrics_interval_seconds = 10.;
+        std::fs::write(&config_path, toml::to_string(&file_config).unwrap()).unwrap();
+        std::fs::write(
+            directory.join(LOCAL_CONFIG_FILENAME),
+            "[app]\nrng_seed = 2\nmetrics_interval_seconds = 20.0\n\n\
+             [game.player_config]\nmove_speed = 321.0\n",
+        )
+        .unwrap();
+        let env_name = format!("{}APP__RNG_SEED", CONFIG_ENV_PREFIX);
+        std::env::set_var(&env_name, "3");
+
+        let read = |overrides: &ConfigOverrides| {
+            read_config(
+                Some(config_path.clone()),
+                overrides,
+                DataOutputPolicy::Restricted,
+            )
+            .unwrap()
+        };
+        let (config, layers) = read(&ConfigOverrides {
+            base_resolution: Some(UVec2::new(1024, 768)),
+            ..Default::default()
+        });
+        let (cli_seeded, _) = read(&ConfigOverrides {
+            rng_seed: Some(4),
+            ..Default::default()
+        });
+        std::env::remove_var(&env_name);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        assert_eq!(
+            layers,
+            [
+                ConfigLayer::File(config_path.clone()),
+                ConfigLayer::LocalOverride(directory.join(LOCAL_CONFIG_FILENAME)),
+                ConfigLayer::Environment(vec![env_name]),
+                ConfigLayer::CommandLine(vec!["app.base_resolution".to_string()]),
+            ]
+        );
+        // environment over the local override over the file, the command line over all of them
+        assert_eq!(config.app.rng_seed, Some(3));
+        assert_eq!(cli_seeded.app.rng_seed, Some(4));
+        assert_eq!(config.app.metrics_interval_seconds, 20.);
+        assert_eq!(config.app.base_resolution, UVec2::new(1024, 768));
+        assert_eq!(config.game.player_config.move_speed, 321.);
+        assert_eq!(
+            config.game.player_config.throw_power,
+            file_config.game.player_config.throw_power
+        );
     }
 }
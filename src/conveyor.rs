@@ -1,164 +1,946 @@
-use bevy::prelude::*;
-use bevy_rapier2d::{dynamics::RigidBody, geometry::Collider, plugin::RapierContext};
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    sprite::{Anchor, MaterialMesh2dBundle, Mesh2dHandle},
+};
+use bevy_rapier2d::{
+    dynamics::{RigidBody, Velocity},
+    geometry::Collider,
+    pipeline::QueryFilter,
+    plugin::RapierContext,
+};
+use enum_map::EnumMap;
 
 use crate::{
-    deactivate_package_physics, AnimationData, AnimationTimer, EntityLayer, FacingDirection,
-    GameConfig, GameState, Package, PlayAreaAligment, Player, PlayerIndex, RenderLayers,
-    TextureTarget,
+    activate_package_physics, deactivate_package_physics, random::*, release_package,
+    AnimationData, AnimationTimer, AttractMode, BlinkerColours, BlinkerStyle, ColourPalette,
+    ConveyorBroke, ConveyorCleared, ConveyorJammed, ConveyorRepaired, EntityLayer, FacingDirection,
+    FreezeSource, Freshness, GameConfig, GameState, InteractionActor, InteractionIntent,
+    InteractionIntents, InteractionKind, KeyAction, Metrics, Package, PackageDelivered,
+    PlannedWave, PlayAreaAligment, Player, PlayerIndex, RenderLayers, Score, ScoreChanged,
+    SpawnContext, SpriteSheetSource, StreakAchieved, SupervisorStatus, TextureTarget,
+    TrackedTexture, WaveCargo, WaveModifier, WaveModifiers,
 };
 
-#[derive(Component, PartialEq, Eq)]
+#[derive(Component, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ConveyorLabelTag {
     Incoming,
     Outgoing(PlayerIndex),
 }
 
+/// What a belt is doing, its timers only decide when it moves between running and stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConveyorState {
+    Running,
+    Stopped,
+    /// Incoming belts only, nothing is fed in until a player finishes repairing it.
+    Broken {
+        repair_progress: f32,
+    },
+}
+
 #[derive(Component)]
 pub struct Conveyor {
     pub belt_region: Vec2,
     pub direction: f32,
     pub speed: f32,
-    pub active_timer: Timer,
-    pub idle_timer: Timer,
+    pub state: ConveyorState,
+    /// Runs for the belt's length at full speed plus one `ramp_seconds`, which makes up for the
+    /// ground lost easing in and out. Only changed through the run operations, so a belt's
+    /// lifecycle can be followed in the log.
+    active_timer: Timer,
+    /// Never more than the belt's length at full speed, so the two ramps can't overlap.
+    ramp_seconds: f32,
+    /// Counts down to the next run, only changed through the idle operations.
+    idle_timer: Timer,
 
     pub package_count: usize,
+    pub blocked_seconds: f32,
+    pub jam_timer: Option<Timer>,
+    /// Outgoing belts only, how long a package may wait on the belt while it's stopped.
+    pub max_dwell_seconds: Option<f32>,
+}
+
+/// How long a package has waited on a stopped outgoing belt.
+#[derive(Component)]
+pub struct PackageDwell {
+    pub timer: Timer,
+}
+
+/// Slowest a belt's animation runs while it's running, right at the ends of its ramps it's
+/// barely moving and the frame time would otherwise shoot up.
+const MIN_ANIMATION_SPEED_FRACTION: f32 = 0.05;
+
+/// Eases 0 to 1 over `t` in 0 to 1.
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+/// Area under `smoothstep` from 0 to `t`.
+fn smoothstep_area(t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    t * t * t * (1. - t / 2.)
+}
+
+/// A run of a belt `belt_length` long with its ramps, and the ramp actually used, cut short where
+/// it would outlast the run at full speed.
+fn active_timer_for(belt_length: f32, speed: f32, ramp_seconds: f32) -> (Timer, f32) {
+    let full_speed_seconds = belt_length / speed;
+    let ramp_seconds = ramp_seconds.clamp(0., full_speed_seconds);
+    (
+        Timer::from_seconds(full_speed_seconds + ramp_seconds, TimerMode::Once),
+        ramp_seconds,
+    )
+}
+
+/// How long a freshly loaded belt waits before its first run.
+const FIRST_IDLE_SECONDS: f32 = 3.;
+
+/// Seconds per frame of a belt's animation at full speed.
+fn belt_frame_seconds(frame_count: usize) -> f32 {
+    (60. / frame_count as f32) / 60.
+}
+
+impl Conveyor {
+    /// A stopped, empty belt counting down to its first run.
+    pub fn new(
+        belt_region: Vec2,
+        direction: f32,
+        speed: f32,
+        ramp_seconds: f32,
+        max_dwell_seconds: Option<f32>,
+    ) -> Self {
+        let (mut active_timer, ramp_seconds) = active_timer_for(belt_region.y, speed, ramp_seconds);
+        active_timer.pause();
+        Self {
+            belt_region,
+            direction,
+            speed,
+            state: ConveyorState::Stopped,
+            active_timer,
+            ramp_seconds,
+            idle_timer: Timer::from_seconds(FIRST_IDLE_SECONDS, TimerMode::Once),
+            package_count: 0,
+            blocked_seconds: 0.,
+            jam_timer: None,
+            max_dwell_seconds,
+        }
+    }
+
+    pub fn active_timer(&self) -> &Timer {
+        &self.active_timer
+    }
+
+    pub fn idle_timer(&self) -> &Timer {
+        &self.idle_timer
+    }
+
+    /// The run ticks by `active_delta`, so it can go faster than the idle countdown.
+    pub fn tick_timers(&mut self, active_delta: Duration, idle_delta: Duration) {
+        self.active_timer.tick(active_delta);
+        self.idle_timer.tick(idle_delta);
+    }
+
+    /// Starts a run from the beginning of its ramp, ending any idle countdown.
+    pub fn start_run(&mut self) {
+        self.idle_timer.pause();
+        self.active_timer.reset();
+        self.active_timer.unpause();
+        self.state = ConveyorState::Running;
+        self.log_transition("start_run");
+    }
+
+    pub fn end_run(&mut self) {
+        self.active_timer.pause();
+        self.state = ConveyorState::Stopped;
+        self.log_transition("end_run");
+    }
+
+    /// Counts down to the next run from the start, over `duration` when given or the last
+    /// countdown's length otherwise.
+    pub fn begin_idle_countdown(&mut self, duration: Option<Duration>) {
+        if let Some(duration) = duration {
+            self.idle_timer.set_duration(duration);
+        }
+        self.idle_timer.reset();
+        self.idle_timer.unpause();
+        self.log_transition("begin_idle_countdown");
+    }
+
+    /// Stops the countdown to the next run where it is, e.g. while the belt is blocked or broken.
+    pub fn hold_idle_countdown(&mut self) {
+        if self.idle_timer.paused() {
+            return;
+        }
+        self.idle_timer.pause();
+        self.log_transition("hold_idle_countdown");
+    }
+
+    fn log_transition(&self, operation: &str) {
+        if cfg!(debug_assertions) {
+            debug!(
+                "conveyor {}: {:?}, run remaining {:.2}s, idle remaining {:.2}s, idle held {}",
+                operation,
+                self.state,
+                self.active_timer.remaining_secs(),
+                self.idle_timer.remaining_secs(),
+                self.idle_timer.paused()
+            );
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == ConveyorState::Running
+    }
+
+    pub fn is_broken(&self) -> bool {
+        matches!(self.state, ConveyorState::Broken { .. })
+    }
+
+    /// How much of its full speed the belt is moving at, where its running timer has got to.
+    pub fn speed_fraction(&self) -> f32 {
+        if self.ramp_seconds <= 0. {
+            return 1.;
+        }
+        let easing_in = smoothstep(self.active_timer.elapsed_secs() / self.ramp_seconds);
+        let easing_out = smoothstep(self.active_timer.remaining_secs() / self.ramp_seconds);
+        easing_in.min(easing_out)
+    }
+
+    /// How far the belt has moved this run, ramps included. Everything on or drawn on the belt
+    /// goes by this, so it all moves together.
+    pub fn travelled(&self) -> f32 {
+        let elapsed = self.active_timer.elapsed_secs();
+        let ramp = self.ramp_seconds;
+        if ramp <= 0. {
+            return elapsed * self.speed;
+        }
+        let duration = self.active_timer.duration().as_secs_f32();
+        let easing_out_from = duration - ramp;
+        let eased_in = ramp * smoothstep_area(elapsed / ramp);
+        let full_speed = elapsed.min(easing_out_from) - ramp;
+        let eased_out = if elapsed > easing_out_from {
+            ramp * (smoothstep_area(1.) - smoothstep_area((duration - elapsed) / ramp))
+        } else {
+            0.
+        };
+        (eased_in + full_speed.max(0.) + eased_out) * self.speed
+    }
+
+    pub fn capacity(&self, package_size: f32) -> usize {
+        let per_row = (self.belt_region.x / package_size).floor();
+        let rows = (self.belt_region.y / package_size).floor();
+        (per_row * rows) as usize
+    }
+
+    pub fn has_free_capacity(&self, package_size: f32) -> bool {
+        self.package_count < self.capacity(package_size)
+    }
+
+    /// Centre of the end of the belt packages travel towards.
+    pub fn exit_point(&self, conveyor_pos: Vec2) -> Vec2 {
+        conveyor_pos + Vec2::new(0., self.direction * (self.belt_region.y / 2.))
+    }
+
+    /// Region just past the belt's exit, standing in it lets a player grab the front package.
+    pub fn front_grab_zone(&self, conveyor_pos: Vec2, depth: f32) -> Rect {
+        let exit_point = self.exit_point(conveyor_pos);
+        Rect::from_center_size(
+            exit_point + Vec2::new(0., self.direction * (depth / 2.)),
+            Vec2::new(self.belt_region.x, depth),
+        )
+    }
 }
 
+/// Tracks which conveyors belong to whom, a layout may give a player more than one outgoing belt
+/// so lookups should go through here rather than assuming a single belt per player.
+#[derive(Resource, Default)]
+pub struct BeltRegistry {
+    outgoing: EnumMap<PlayerIndex, Vec<Entity>>,
+    incoming: Vec<Entity>,
+}
+
+impl BeltRegistry {
+    pub fn outgoing(&self, player_index: PlayerIndex) -> &[Entity] {
+        &self.outgoing[player_index]
+    }
+
+    pub fn incoming(&self) -> &[Entity] {
+        &self.incoming
+    }
+
+    pub fn owner_of(&self, conveyor_entity: Entity) -> Option<PlayerIndex> {
+        self.outgoing
+            .iter()
+            .find(|(_, belts)| belts.contains(&conveyor_entity))
+            .map(|(player_index, _)| player_index)
+    }
+
+    fn register(&mut self, conveyor_entity: Entity, conveyor_tag: ConveyorLabelTag) {
+        self.forget(conveyor_entity);
+        match conveyor_tag {
+            ConveyorLabelTag::Incoming => self.incoming.push(conveyor_entity),
+            ConveyorLabelTag::Outgoing(player_index) => {
+                self.outgoing[player_index].push(conveyor_entity)
+            }
+        }
+    }
+
+    fn forget(&mut self, conveyor_entity: Entity) {
+        self.incoming.retain(|belt| *belt != conveyor_entity);
+        for (_, belts) in self.outgoing.iter_mut() {
+            belts.retain(|belt| *belt != conveyor_entity);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct FrontGrabZone;
+
 #[derive(Component)]
 pub struct Blinker {
     pub blink_timer: Timer,
     pub active_colour: Color,
     pub inactive_colour: Color,
     pub readying_colour: Color,
+    pub watched_colour: Color,
+}
+
+/// One of the pips ringing a blinker, counting down to the belt starting, lit clockwise from the
+/// top.
+#[derive(Component)]
+pub struct CountdownPip {
+    pub index: usize,
+}
+
+const COUNTDOWN_PIPS: usize = 8;
+
+/// Fills up along the belt's edge as packages are added, in the owner's colour or the team's for
+/// incoming belts.
+#[derive(Component)]
+pub struct CapacityIndicator;
+
+/// Fills across an incoming belt while a player repairs it.
+#[derive(Component)]
+pub struct RepairBar {
+    pub full_width: f32,
+}
+
+const REPAIR_BAR_HEIGHT: f32 = 8.;
+
+/// Shown while a player's assisted throw is locked on to this belt.
+#[derive(Component)]
+pub struct AimLockGlow;
+
+/// One of the arrows along a belt, scrolled along with it while it runs.
+#[derive(Component)]
+pub struct BeltArrow {
+    /// Where it sits along the belt while the belt's at rest.
+    pub base_y: f32,
+    pub spacing: f32,
 }
 
+/// Spawns a belt at the origin on the left of the play area, as long as the configured conveyor
+/// size and with the normal blinker colours unless told otherwise.
+pub struct ConveyorSpawner {
+    tag: ConveyorLabelTag,
+    position: Vec3,
+    length: Option<f32>,
+    alignment: PlayAreaAligment,
+    blinker_colours: BlinkerColours,
+}
+
+impl ConveyorSpawner {
+    pub fn new(tag: ConveyorLabelTag) -> Self {
+        Self {
+            tag,
+            position: Vec3::ZERO,
+            length: None,
+            alignment: PlayAreaAligment::Left,
+            blinker_colours: ColourPalette::Normal.blinker_colours(),
+        }
+    }
+
+    pub fn at(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    pub fn aligned(mut self, alignment: PlayAreaAligment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn with_blinker_colours(mut self, blinker_colours: BlinkerColours) -> Self {
+        self.blinker_colours = blinker_colours;
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands, ctx: &mut SpawnContext) -> Entity {
+        self.spawn_with(
+            commands,
+            &ctx.asset_server,
+            &mut ctx.texture_atlas_layouts,
+            &mut ctx.meshes,
+            &mut ctx.colour_materials,
+            &ctx.game_config,
+        )
+    }
+
+    fn spawn_with(
+        self,
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+        meshes: &mut Assets<Mesh>,
+        colour_materials: &mut Assets<ColorMaterial>,
+        game_config: &GameConfig,
+    ) -> Entity {
+        let conveyor_pos = self.position;
+        let conveyor_belt_length = self.length.unwrap_or(game_config.conveyor_config.size.y);
+        let area_alignment = self.alignment;
+        let conveyor_tag = self.tag;
+        let blinker_colours = self.blinker_colours;
+        let blinker_pos_modifier = area_alignment.get_blink_position_modifier(&conveyor_tag);
+        let blinker = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: blinker_colours.inactive,
+                        custom_size: Some(Vec2::new(
+                            game_config.conveyor_config.blinker_size,
+                            game_config.conveyor_config.blinker_size,
+                        )),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(
+                            blinker_pos_modifier
+                                * ((game_config.conveyor_config.size.x / 2.)
+                                    - (game_config.conveyor_config.border_size / 2.)),
+                            -((conveyor_belt_length / 2.)
+                                - (game_config.conveyor_config.blinker_size / 2.)),
+                            0.,
+                        ),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Blinker {
+                    blink_timer: Timer::from_seconds(
+                        game_config.conveyor_config.blink_duration_seconds,
+                        TimerMode::Repeating,
+                    ),
+                    active_colour: blinker_colours.active,
+                    inactive_colour: blinker_colours.inactive,
+                    readying_colour: blinker_colours.readying,
+                    watched_colour: blinker_colours.watched,
+                },
+                RenderLayers::Single(EntityLayer::Accent),
+            ))
+            .with_children(|builder| {
+                let blinker_size = game_config.conveyor_config.blinker_size;
+                for index in 0..COUNTDOWN_PIPS {
+                    let angle = index as f32 / COUNTDOWN_PIPS as f32 * std::f32::consts::TAU;
+                    builder.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: blinker_colours.inactive,
+                                custom_size: Some(Vec2::splat(blinker_size * 0.25)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(
+                                (Vec2::new(angle.sin(), angle.cos()) * blinker_size * 0.8)
+                                    .extend(0.),
+                            ),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                        RenderLayers::Single(EntityLayer::Accent),
+                        CountdownPip { index },
+                    ));
+                }
+            })
+            .id();
+
+        let texture_pack = game_config.get_texture_pack();
+        let conveyor_sprite = &texture_pack.choose_texture_for(TextureTarget::Conveyor, None);
+        let (atlas_layout, frame_count) = conveyor_sprite.atlas_layout("Conveyor");
+        // the border is measured in reference pixels so it lands on the same part of the belt art
+        // whatever resolution the pack's sheet is
+        let conveyor_border_local_size = (game_config.conveyor_config.border_size
+            / texture_pack.reference_cell_size.x as f32)
+            * game_config.conveyor_config.size.x;
+        let sprite_path = format!("{}/{}", texture_pack.root, conveyor_sprite.path);
+        let texture_handle: Handle<Image> = asset_server.load(&sprite_path);
+        let animation_indices = AnimationData {
+            start_frame: 0,
+            frame_count,
+            pause: true,
+            facing_direction: FacingDirection::Down,
+        };
+        let belt_direction = -1.;
+        let conveyor_entity = commands
+            .spawn((
+                RigidBody::Fixed,
+                SpriteSheetBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(
+                            game_config.conveyor_config.size.x,
+                            conveyor_belt_length,
+                        )),
+                        ..default()
+                    },
+                    atlas: TextureAtlas {
+                        layout: texture_atlas_layouts.add(atlas_layout),
+                        index: animation_indices.start_frame,
+                    },
+                    texture: texture_handle,
+                    transform: Transform {
+                        translation: conveyor_pos,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Conveyor::new(
+                    Vec2::new(
+                        game_config.conveyor_config.size.x - (conveyor_border_local_size * 2.),
+                        conveyor_belt_length,
+                    ),
+                    belt_direction,
+                    game_config.conveyor_config.speed,
+                    game_config.conveyor_config.ramp_seconds,
+                    matches!(conveyor_tag, ConveyorLabelTag::Outgoing(_))
+                        .then_some(game_config.conveyor_config.outgoing_max_dwell_seconds),
+                ),
+                Collider::cuboid(
+                    game_config.conveyor_config.size.x / 2.,
+                    conveyor_belt_length / 2.,
+                ),
+                RenderLayers::Single(EntityLayer::Furniture),
+                animation_indices,
+                AnimationTimer(Timer::from_seconds(
+                    belt_frame_seconds(frame_count),
+                    TimerMode::Repeating,
+                )),
+                conveyor_tag,
+                TrackedTexture::new(
+                    TextureTarget::Conveyor,
+                    format!("texture pack '{}'", game_config.selected_texture_pack),
+                    &sprite_path,
+                ),
+                SpriteSheetSource {
+                    source: format!("texture pack '{}'", game_config.selected_texture_pack),
+                    path: sprite_path,
+                    expected_size: conveyor_sprite.expected_image_size().unwrap_or_default(),
+                },
+            ))
+            .add_child(blinker)
+            .id();
+
+        let indicator_colour = match conveyor_tag {
+            ConveyorLabelTag::Incoming => game_config.team_colour,
+            ConveyorLabelTag::Outgoing(player_index) => {
+                game_config.player_config.per_player[player_index].colour
+            }
+        };
+        let capacity_indicator = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: *indicator_colour,
+                        custom_size: Some(Vec2::new(
+                            game_config.conveyor_config.capacity_indicator_width,
+                            0.,
+                        )),
+                        anchor: Anchor::BottomCenter,
+                        ..default()
+                    },
+                    // opposite edge to the blinker, growing up from the bottom of the belt
+                    transform: Transform::from_translation(Vec3::new(
+                        -blinker_pos_modifier
+                            * ((game_config.conveyor_config.size.x / 2.)
+                                - (game_config.conveyor_config.border_size / 2.)),
+                        -(conveyor_belt_length / 2.),
+                        0.,
+                    )),
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::Accent),
+                CapacityIndicator,
+            ))
+            .id();
+        commands
+            .entity(conveyor_entity)
+            .add_child(capacity_indicator);
+
+        // as many arrows as fit at about the configured spacing, spread evenly so a belt of any
+        // length starts and ends on a whole gap
+        let arrow_count = (conveyor_belt_length / game_config.conveyor_config.belt_arrow_spacing)
+            .round()
+            .max(1.);
+        let arrow_spacing = conveyor_belt_length / arrow_count;
+        let arrow_size = game_config.conveyor_config.belt_arrow_size;
+        let arrow_colour = indicator_colour.with_a(game_config.conveyor_config.belt_arrow_alpha);
+        // the arrow art points up, turned to face the way packages go
+        let arrow_rotation = Quat::from_rotation_z(if belt_direction < 0. {
+            std::f32::consts::PI
+        } else {
+            0.
+        });
+        let arrow_sprite = texture_pack.try_choose_texture_for(TextureTarget::BeltArrow, None);
+        let arrow_texture = arrow_sprite.map(|sprite| {
+            let path = format!("{}/{}", texture_pack.root, sprite.path);
+            let handle: Handle<Image> = asset_server.load(&path);
+            (path, handle)
+        });
+        // without art from the pack every arrow on the belt shares one plain triangle
+        let arrow_mesh = arrow_texture.is_none().then(|| {
+            (
+                Mesh2dHandle(meshes.add(Triangle2d::new(
+                    Vec2::new(0., arrow_size / 2.),
+                    Vec2::new(-arrow_size / 2., -arrow_size / 2.),
+                    Vec2::new(arrow_size / 2., -arrow_size / 2.),
+                ))),
+                colour_materials.add(arrow_colour),
+            )
+        });
+        for index in 0..arrow_count as usize {
+            let base_y = -(conveyor_belt_length / 2.) + arrow_spacing * (index as f32 + 0.5);
+            let belt_arrow = BeltArrow {
+                base_y,
+                spacing: arrow_spacing,
+            };
+            let arrow_entity = match (&arrow_texture, &arrow_mesh) {
+                (Some((path, texture)), _) => commands
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: arrow_colour,
+                                custom_size: Some(Vec2::splat(arrow_size)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(0., base_y, 0.))
+                                .with_rotation(arrow_rotation),
+                            texture: texture.clone(),
+                            ..default()
+                        },
+                        RenderLayers::Single(EntityLayer::BeltMarking),
+                        TrackedTexture::new(
+                            TextureTarget::BeltArrow,
+                            format!("texture pack '{}'", game_config.selected_texture_pack),
+                            path,
+                        ),
+                        belt_arrow,
+                    ))
+                    .id(),
+                // meshes aren't layered, so the arrow carries the layer's z itself
+                (None, Some((mesh, material))) => commands
+                    .spawn((
+                        MaterialMesh2dBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: Transform::from_translation(Vec3::new(
+                                0.,
+                                base_y,
+                                EntityLayer::BeltMarking.z(),
+                            ))
+                            .with_rotation(arrow_rotation),
+                            ..default()
+                        },
+                        belt_arrow,
+                    ))
+                    .id(),
+                (None, None) => continue,
+            };
+            commands.entity(conveyor_entity).add_child(arrow_entity);
+        }
+
+        if conveyor_tag == ConveyorLabelTag::Incoming {
+            let repair_bar_width = game_config.conveyor_config.size.x * 0.8;
+            let repair_bar = commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: *game_config.team_colour,
+                            custom_size: Some(Vec2::new(0., REPAIR_BAR_HEIGHT)),
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            -repair_bar_width / 2.,
+                            0.,
+                            0.,
+                        )),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    RenderLayers::Single(EntityLayer::Accent),
+                    RepairBar {
+                        full_width: repair_bar_width,
+                    },
+                ))
+                .id();
+            commands.entity(conveyor_entity).add_child(repair_bar);
+        }
+
+        if let ConveyorLabelTag::Outgoing(player_index) = conveyor_tag {
+            let aim_lock_glow = commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: game_config.player_config.per_player[player_index]
+                                .colour
+                                .with_a(0.35),
+                            custom_size: Some(Vec2::new(
+                                game_config.conveyor_config.size.x,
+                                conveyor_belt_length,
+                            )),
+                            ..default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    RenderLayers::Single(EntityLayer::Accent),
+                    AimLockGlow,
+                ))
+                .id();
+            commands.entity(conveyor_entity).add_child(aim_lock_glow);
+        }
+
+        if conveyor_tag == ConveyorLabelTag::Incoming {
+            // packages leave from the bottom of the belt, mark out where a player can grab them from
+            let zone_depth = game_config.conveyor_config.front_grab_zone_depth;
+            let front_grab_zone = commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: game_config.team_colour.with_a(0.2),
+                            custom_size: Some(Vec2::new(
+                                game_config.conveyor_config.size.x,
+                                zone_depth,
+                            )),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec3::new(
+                            0.,
+                            -((conveyor_belt_length / 2.) + (zone_depth / 2.)),
+                            0.,
+                        )),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    RenderLayers::Single(EntityLayer::Accent),
+                    FrontGrabZone,
+                ))
+                .id();
+            commands.entity(conveyor_entity).add_child(front_grab_zone);
+        }
+        conveyor_entity
+    }
+}
+
+#[deprecated(note = "use ConveyorSpawner")]
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_conveyor(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     texture_atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    colour_materials: &mut ResMut<Assets<ColorMaterial>>,
     game_config: &Res<GameConfig>,
     conveyor_pos: Vec3,
     conveyor_belt_length: f32,
     area_alignment: PlayAreaAligment,
     conveyor_tag: ConveyorLabelTag,
+    blinker_colours: BlinkerColours,
 ) {
-    let blinker_pos_modifier = area_alignment.get_blink_position_modifier(&conveyor_tag);
-    let blinker = commands
-        .spawn((
-            SpriteBundle {
-                sprite: Sprite {
-                    color: Color::RED,
-                    custom_size: Some(Vec2::new(
-                        game_config.conveyor_config.blinker_size,
-                        game_config.conveyor_config.blinker_size,
-                    )),
-                    ..default()
-                },
-                transform: Transform {
-                    translation: Vec3::new(
-                        blinker_pos_modifier
-                            * ((game_config.conveyor_config.size.x / 2.)
-                                - (game_config.conveyor_config.border_size / 2.)),
-                        -((conveyor_belt_length / 2.)
-                            - (game_config.conveyor_config.blinker_size / 2.)),
-                        0.,
-                    ),
-                    ..default()
-                },
-                ..default()
-            },
-            Blinker {
-                blink_timer: Timer::from_seconds(
-                    game_config.conveyor_config.blink_duration_seconds,
-                    TimerMode::Repeating,
-                ),
-                active_colour: Color::GREEN,
-                inactive_colour: Color::RED,
-                readying_colour: Color::ORANGE,
-            },
-            RenderLayers::Single(EntityLayer::Accent),
-        ))
-        .id();
-
-    let texture_pack = game_config.get_texture_pack();
-    let conveyor_sprite = &texture_pack.choose_texture_for(TextureTarget::Conveyor, None);
-    let sprite_size = conveyor_sprite
-        .cell_resolution
-        .expect("Conveyor sprite must have a cell resolution")
-        .as_vec2();
-    let grid_dimensions = conveyor_sprite
-        .grid_dimensions
-        .expect("Conveyor sprite must have grid dimensions");
-    let frame_count = grid_dimensions.x * grid_dimensions.y;
-    let conveyor_border_local_size = (game_config.conveyor_config.border_size / sprite_size.x)
-        * game_config.conveyor_config.size.x;
-    let texture_handle: Handle<Image> =
-        asset_server.load(&format!("{}/{}", texture_pack.root, conveyor_sprite.path));
-    let atlas_layout = TextureAtlasLayout::from_grid(
-        Vec2::new(sprite_size.x, sprite_size.y),
-        grid_dimensions.x as usize,
-        grid_dimensions.y as usize,
-        None,
-        None,
-    );
-    let animation_indices = AnimationData {
-        start_frame: 0,
-        frame_count: frame_count as usize,
-        pause: true,
-        facing_direction: FacingDirection::Down,
+    ConveyorSpawner::new(conveyor_tag)
+        .at(conveyor_pos)
+        .with_length(conveyor_belt_length)
+        .aligned(area_alignment)
+        .with_blinker_colours(blinker_colours)
+        .spawn_with(
+            commands,
+            asset_server,
+            texture_atlas_layouts,
+            meshes,
+            colour_materials,
+            game_config,
+        );
+}
+
+/// Scrolls each belt's arrows along with it while it runs, by however far its running timer says
+/// it has gone, and leaves them where they are while it's stopped.
+pub fn update_belt_arrows(
+    game_config: Res<GameConfig>,
+    conveyor_query: Query<(&Conveyor, &Children)>,
+    mut arrow_query: Query<(&BeltArrow, &mut Transform, &mut Visibility)>,
+) {
+    let visibility = if game_config.conveyor_config.show_belt_arrows {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
     };
-    let mut active_timer = Timer::from_seconds(
-        conveyor_belt_length / game_config.conveyor_config.speed,
-        TimerMode::Once,
-    );
-    active_timer.pause();
-    let idle_timer = Timer::from_seconds(3., TimerMode::Once);
-    commands
-        .spawn((
-            RigidBody::Fixed,
-            SpriteSheetBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(
-                        game_config.conveyor_config.size.x,
-                        conveyor_belt_length,
-                    )),
-                    ..default()
-                },
-                atlas: TextureAtlas {
-                    layout: texture_atlas_layouts.add(atlas_layout),
-                    index: animation_indices.start_frame,
-                },
-                texture: texture_handle,
-                transform: Transform {
-                    translation: conveyor_pos,
-                    ..default()
-                },
-                ..default()
-            },
-            Conveyor {
-                belt_region: Vec2::new(
-                    game_config.conveyor_config.size.x - (conveyor_border_local_size * 2.),
-                    conveyor_belt_length,
-                ),
-                direction: -1.,
-                speed: game_config.conveyor_config.speed,
-                active_timer: active_timer,
-                idle_timer: idle_timer,
-                package_count: 0,
-            },
-            Collider::cuboid(
-                game_config.conveyor_config.size.x / 2.,
-                conveyor_belt_length / 2.,
-            ),
-            RenderLayers::Single(EntityLayer::Furniture),
-            animation_indices,
-            AnimationTimer(Timer::from_seconds(
-                (60. / frame_count as f32) / 60.,
-                TimerMode::Repeating,
-            )),
-            conveyor_tag,
-        ))
-        .add_child(blinker);
+    for (conveyor_info, conveyor_children) in &conveyor_query {
+        let belt_length = conveyor_info.belt_region.y;
+        let travelled = conveyor_info.travelled();
+        let mut arrows = arrow_query.iter_many_mut(conveyor_children);
+        while let Some((belt_arrow, mut arrow_transform, mut arrow_visibility)) =
+            arrows.fetch_next()
+        {
+            if *arrow_visibility != visibility {
+                *arrow_visibility = visibility;
+            }
+            // an arrow scrolled off the end comes back on at the start
+            let offset = conveyor_info.direction * (travelled % belt_arrow.spacing);
+            let y = (belt_arrow.base_y + offset + belt_length / 2.).rem_euclid(belt_length)
+                - belt_length / 2.;
+            if arrow_transform.translation.y != y {
+                arrow_transform.translation.y = y;
+            }
+        }
+    }
+}
+
+/// Slows each belt's animation through its ramps to match how fast the belt is moving, so the
+/// surface and the packages on it stay in step.
+pub fn pace_belt_animations(
+    mut conveyor_query: Query<(&Conveyor, &AnimationData, &mut AnimationTimer)>,
+) {
+    for (conveyor_info, anim_data, mut animation_timer) in &mut conveyor_query {
+        let speed_fraction = conveyor_info
+            .speed_fraction()
+            .max(MIN_ANIMATION_SPEED_FRACTION);
+        let frame_duration =
+            Duration::from_secs_f32(belt_frame_seconds(anim_data.frame_count) / speed_fraction);
+        if animation_timer.duration() != frame_duration {
+            animation_timer.set_duration(frame_duration);
+        }
+    }
+}
+
+pub fn update_belt_registry(
+    mut belt_registry: ResMut<BeltRegistry>,
+    changed_conveyor_query: Query<(Entity, &ConveyorLabelTag), Changed<ConveyorLabelTag>>,
+    mut removed_conveyors: RemovedComponents<ConveyorLabelTag>,
+    conveyor_query: Query<&Conveyor>,
+    game_config: Res<GameConfig>,
+) {
+    let mut registry_changed = false;
+    for conveyor_entity in removed_conveyors.read() {
+        belt_registry.forget(conveyor_entity);
+        registry_changed = true;
+    }
+    for (conveyor_entity, conveyor_tag) in &changed_conveyor_query {
+        belt_registry.register(conveyor_entity, *conveyor_tag);
+        registry_changed = true;
+    }
+
+    if !registry_changed {
+        return;
+    }
+
+    // players can own any number of belts, but the total space they have to deliver onto should
+    // be roughly the same
+    let outgoing_capacities = belt_registry.outgoing.values().map(|belts| {
+        conveyor_query
+            .iter_many(belts)
+            .map(|conveyor_info| conveyor_info.capacity(game_config.package_config.size))
+            .sum::<usize>() as f32
+    });
+    let (smallest, largest) = outgoing_capacities.fold((f32::MAX, 0f32), |(min, max), capacity| {
+        (min.min(capacity), max.max(capacity))
+    });
+    if largest > 0.
+        && (largest - smallest) / largest > game_config.conveyor_config.outgoing_capacity_tolerance
+    {
+        warn!(
+            "Outgoing belt capacity is unbalanced between players ({} vs {} packages)",
+            smallest, largest
+        );
+    }
+}
+
+/// Capacity is worked out from the belt each time so a belt changing length is picked up too.
+pub fn update_capacity_indicators(
+    game_config: Res<GameConfig>,
+    conveyor_query: Query<(&Conveyor, &Children)>,
+    mut indicator_query: Query<(&mut Sprite, &mut Visibility), With<CapacityIndicator>>,
+) {
+    let show_indicators = game_config.conveyor_config.show_capacity_indicators;
+    for (conveyor_info, conveyor_children) in &conveyor_query {
+        let capacity = conveyor_info.capacity(game_config.package_config.size);
+        let fill = if capacity > 0 {
+            (conveyor_info.package_count as f32 / capacity as f32).min(1.)
+        } else {
+            0.
+        };
+        let size = Vec2::new(
+            game_config.conveyor_config.capacity_indicator_width,
+            fill * conveyor_info.belt_region.y,
+        );
+        let visibility = if show_indicators {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        for child in conveyor_children.iter() {
+            let Ok((mut indicator_sprite, mut indicator_visibility)) =
+                indicator_query.get_mut(*child)
+            else {
+                continue;
+            };
+            if indicator_sprite.custom_size != Some(size) {
+                indicator_sprite.custom_size = Some(size);
+            }
+            if *indicator_visibility != visibility {
+                *indicator_visibility = visibility;
+            }
+        }
+    }
+}
+
+pub fn update_front_grab_zones(
+    game_config: Res<GameConfig>,
+    player_query: Query<(&Transform, Option<&Children>), With<Player>>,
+    package_query: Query<(), With<Package>>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform, &Children)>,
+    mut zone_query: Query<&mut Visibility, With<FrontGrabZone>>,
+) {
+    let zone_depth = game_config.conveyor_config.front_grab_zone_depth;
+    for (conveyor_info, conveyor_transform, conveyor_children) in &conveyor_query {
+        let front_grab_zone =
+            conveyor_info.front_grab_zone(conveyor_transform.translation().truncate(), zone_depth);
+        // only worth pointing out to someone with free hands while there is something to grab
+        let show_zone = conveyor_info.package_count > 0
+            && player_query
+                .iter()
+                .any(|(player_transform, player_children)| {
+                    front_grab_zone.contains(player_transform.translation.truncate())
+                        && !player_children.is_some_and(|children| {
+                            children.iter().any(|child| package_query.contains(*child))
+                        })
+                });
+
+        for child in conveyor_children.iter() {
+            if let Ok(mut zone_visibility) = zone_query.get_mut(*child) {
+                *zone_visibility = if show_zone {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
 }
 
 pub fn calculate_attach_point_on_conveyor(
@@ -179,184 +961,973 @@ pub fn calculate_attach_point_on_conveyor(
         )
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn check_for_delivered_packages(
     mut commands: Commands,
     mut conveyor_query: Query<(Entity, &mut Conveyor, &ConveyorLabelTag)>,
-    package_query: Query<(Entity, &Transform, &Parent), (With<Package>, Without<Player>)>,
+    package_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Parent,
+            Option<&Freshness>,
+            Option<&WaveCargo>,
+        ),
+        (With<Package>, Without<Player>),
+    >,
     mut game_state: ResMut<GameState>,
     game_config: Res<GameConfig>,
+    mut delivered_events: EventWriter<PackageDelivered>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut streak_events: EventWriter<StreakAchieved>,
+    mut metrics: Option<ResMut<Metrics>>,
+    time: Res<Time>,
 ) {
-    for (conveyor_entity, mut conveyor_info, label) in
-        &mut conveyor_query.iter_mut().filter(|(_, _, t)| match *t {
-            ConveyorLabelTag::Outgoing(_) => true,
-            _ => false,
-        })
+    for (conveyor_entity, mut conveyor_info, label) in &mut conveyor_query
+        .iter_mut()
+        .filter(|(_, _, t)| matches!(*t, ConveyorLabelTag::Outgoing(_)))
     {
-        for (package_entity, package_transform, _) in package_query
+        for (package_entity, package_transform, _, freshness, cargo) in package_query
             .iter()
-            .filter(|(_, _, p)| p.get() == conveyor_entity)
+            .filter(|(_, _, p, _, _)| p.get() == conveyor_entity)
         {
             if package_transform.translation.y.abs() > (conveyor_info.belt_region.y / 2.) {
                 conveyor_info.package_count -= 1;
                 commands
                     .entity(conveyor_entity)
                     .remove_children(&[package_entity]);
-                commands.entity(package_entity).despawn();
-                match label {
-                    ConveyorLabelTag::Outgoing(player_index) => {
-                        game_state.player_scores[*player_index].score +=
-                            game_config.package_config.base_score_value
-                                * game_state.player_scores[*player_index].multiplier;
-                        game_state.player_scores[*player_index].multiplier +=
-                            game_config.score_config.multiplier_increase_per_package;
-                        game_state.player_scores[*player_index]
-                            .multiplier_decrement_freeze_timer
-                            .reset();
+                release_package(&mut commands, package_entity);
+                if let ConveyorLabelTag::Outgoing(player_index) = label {
+                    let player_score = &mut game_state.player_scores[*player_index];
+                    let value_fraction = freshness.map_or(1., |freshness| {
+                        freshness.value_fraction(&game_config.package_config)
+                    });
+                    let value_scale = WaveModifier::value_scale(
+                        cargo.map(|cargo| cargo.modifier),
+                        &game_config.wave_modifier_config,
+                    );
+                    let value = player_score.score.add_with_multiplier(
+                        game_config.package_config.base_score_value * value_fraction * value_scale,
+                        player_score.multiplier,
+                    );
+                    delivered_events.send(PackageDelivered {
+                        player: *player_index,
+                        package: package_entity,
+                        value,
+                    });
+                    if let Some(metrics) = metrics.as_mut() {
+                        metrics.per_player[*player_index].delivered += 1;
+                    }
+                    player_score.deliveries += 1;
+                    if let Some(tier_index) = player_score
+                        .streak
+                        .record_delivery(time.elapsed_seconds(), &game_config.score_config.streaks)
+                    {
+                        let tier = &game_config.score_config.streaks[tier_index];
+                        player_score
+                            .score
+                            .add_with_multiplier(tier.bonus_points, 1.);
+                        streak_events.send(StreakAchieved {
+                            player: *player_index,
+                            tier: tier_index,
+                        });
+                    }
+                    let multiplier_increase =
+                        game_config.score_config.multiplier_increase_per_package
+                            * player_score.streak.multiplier_gain_factor();
+                    for recipient in game_config.game_mode.multiplier_recipients(*player_index) {
+                        game_state.player_scores[recipient].multiplier += multiplier_increase;
+                        game_state.freeze_multiplier(
+                            recipient,
+                            FreezeSource::Delivery,
+                            game_config.score_config.delivery_freeze_seconds,
+                        );
+                        score_events.send(ScoreChanged { player: recipient });
                     }
-                    _ => {}
                 }
             }
         }
     }
 }
 
-pub fn collect_packages_on_outgoing_conveyors(
+/// Puts a package at the back of a conveyor's queue.
+pub fn attach_package_to_conveyor(
+    commands: &mut Commands,
+    conveyor_entity: Entity,
+    conveyor_info: &mut Conveyor,
+    package_entity: Entity,
+    package_transform: &mut Transform,
+    game_config: &GameConfig,
+) {
+    package_transform.translation = calculate_attach_point_on_conveyor(
+        conveyor_info,
+        Vec2::ZERO,
+        game_config.package_config.size,
+    )
+    .extend(0.);
+    deactivate_package_physics(commands, package_entity);
+    commands.entity(conveyor_entity).add_child(package_entity);
+    conveyor_info.package_count += 1;
+    if let Some(max_dwell_seconds) = conveyor_info.max_dwell_seconds {
+        commands.entity(package_entity).insert(PackageDwell {
+            timer: Timer::from_seconds(max_dwell_seconds, TimerMode::Once),
+        });
+    }
+}
+
+/// Outgoing belts aren't storage, a package left on a stopped belt for too long is pushed back
+/// into the owner's area and their delivery freeze is lost.
+#[allow(clippy::type_complexity)]
+pub fn eject_dwelling_packages(
     mut commands: Commands,
-    mut package_query: Query<(Entity, &mut Transform), (With<Package>, Without<Player>)>,
-    mut conveyor_query: Query<(Entity, &mut Conveyor, &ConveyorLabelTag)>,
+    time: Res<Time>,
+    mut package_query: Query<
+        (
+            Entity,
+            &mut PackageDwell,
+            &mut Transform,
+            &GlobalTransform,
+            &Parent,
+        ),
+        (With<Package>, Without<Player>),
+    >,
+    mut conveyor_query: Query<(&mut Conveyor, &GlobalTransform, &ConveyorLabelTag)>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    for (
+        package_entity,
+        mut dwell,
+        mut package_transform,
+        package_global_transform,
+        package_parent,
+    ) in &mut package_query
+    {
+        let Ok((mut conveyor_info, conveyor_transform, conveyor_tag)) =
+            conveyor_query.get_mut(package_parent.get())
+        else {
+            continue;
+        };
+        // a moving belt is doing its job, only time spent stopped counts
+        if conveyor_info.is_running() {
+            continue;
+        }
+        dwell.timer.tick(time.delta());
+        if !dwell.timer.finished() {
+            continue;
+        }
+
+        // set down beside the belt, facing the middle of the office, so it isn't straight back
+        // in contact with it
+        let conveyor_pos = conveyor_transform.translation().truncate();
+        let towards_centre = -conveyor_pos.x.signum();
+        package_transform.translation = Vec3::new(
+            conveyor_pos.x
+                + towards_centre
+                    * (game_config.conveyor_config.size.x / 2. + game_config.package_config.size),
+            package_global_transform.translation().y,
+            0.,
+        );
+        commands
+            .entity(package_entity)
+            .remove::<PackageDwell>()
+            .remove_parent();
+        activate_package_physics(
+            &mut commands,
+            package_entity,
+            &game_config,
+            Vec2::new(
+                towards_centre * game_config.conveyor_config.dwell_eject_impulse,
+                0.,
+            ),
+        );
+        conveyor_info.package_count -= 1;
+
+        if let ConveyorLabelTag::Outgoing(player_index) = conveyor_tag {
+            info!("{:?} left a package on their belt too long", player_index);
+            game_state.player_scores[*player_index]
+                .multiplier_freeze
+                .unfreeze(FreezeSource::Delivery);
+            score_events.send(ScoreChanged {
+                player: *player_index,
+            });
+        }
+    }
+}
+
+/// Each kind of belt has exactly one way of moving loose packages so the two never fight. Outgoing
+/// belts capture anything that touches them and carry it as a child (see
+/// `collect_packages_on_outgoing_conveyors`). Incoming belts never capture, instead a running belt
+/// drags loose packages touching it along its length so they ride off the exit end like the rest.
+#[allow(clippy::type_complexity)]
+pub fn convey_loose_packages(
+    rapier_context: Res<RapierContext>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform, &ConveyorLabelTag)>,
+    mut package_query: Query<&mut Velocity, (With<Package>, With<RigidBody>, Without<Parent>)>,
     game_config: Res<GameConfig>,
+    wave_modifiers: Res<WaveModifiers>,
+) {
+    // touching the belt counts, not just lying exactly inside its edges
+    let contact_margin = game_config.package_config.size / 4.;
+    let belt_speed_scale = wave_modifiers.belt_speed_scale(&game_config.wave_modifier_config);
+    for (conveyor_info, conveyor_transform, _) in conveyor_query
+        .iter()
+        .filter(|(_, _, tag)| matches!(tag, ConveyorLabelTag::Incoming))
+    {
+        if !conveyor_info.is_running() {
+            continue;
+        }
+
+        let belt_area = Collider::cuboid(
+            game_config.conveyor_config.size.x / 2. + contact_margin,
+            conveyor_info.belt_region.y / 2.,
+        );
+        rapier_context.intersections_with_shape(
+            conveyor_transform.translation().truncate(),
+            0.,
+            &belt_area,
+            QueryFilter::only_dynamic(),
+            |colliding_entity| {
+                if let Ok(mut package_velocity) = package_query.get_mut(colliding_entity) {
+                    package_velocity.linvel.y = conveyor_info.direction
+                        * conveyor_info.speed
+                        * conveyor_info.speed_fraction()
+                        * belt_speed_scale;
+                }
+                true
+            },
+        );
+    }
+}
+
+/// Claims loose packages touching an outgoing conveyor, the claims are settled by
+/// `resolve_interactions`.
+pub fn collect_packages_on_outgoing_conveyors(
+    package_query: Query<Entity, (With<Package>, Without<Player>)>,
+    conveyor_query: Query<(Entity, &ConveyorLabelTag), With<Conveyor>>,
     rapier_context: Res<RapierContext>,
+    mut interaction_intents: ResMut<InteractionIntents>,
 ) {
     for contact_pair in rapier_context
         .contact_pairs()
         .filter(|pair| pair.has_any_active_contacts())
     {
-        if let Some((package_entity, mut package_transform)) = package_query
-            .iter_mut()
-            .find(|(p, _)| p == &contact_pair.collider1() || p == &contact_pair.collider2())
-        {
-            if let Some((conveyor_entity, mut conveyor_info, _)) =
-                conveyor_query.iter_mut().find(|(c, _, label)| match label {
-                    ConveyorLabelTag::Outgoing(_) => {
-                        c == &contact_pair.collider1() || c == &contact_pair.collider2()
-                    }
-                    _ => false,
-                })
-            {
-                package_transform.translation = calculate_attach_point_on_conveyor(
-                    &conveyor_info,
-                    Vec2::ZERO,
-                    game_config.package_config.size,
-                )
-                .extend(0.);
-                deactivate_package_physics(&mut commands, package_entity);
-                commands.entity(conveyor_entity).add_child(package_entity);
-                conveyor_info.package_count += 1;
-            }
+        let colliders = [contact_pair.collider1(), contact_pair.collider2()];
+        let Some(package_entity) = colliders
+            .into_iter()
+            .find(|collider| package_query.contains(*collider))
+        else {
+            continue;
+        };
+        if let Some((conveyor_entity, _)) = conveyor_query.iter().find(|(c, label)| {
+            matches!(label, ConveyorLabelTag::Outgoing(_)) && colliders.contains(c)
+        }) {
+            interaction_intents.push(InteractionIntent {
+                actor: InteractionActor::Conveyor(conveyor_entity),
+                package: package_entity,
+                kind: InteractionKind::Capture,
+                distance: 0.,
+                onto: Some(conveyor_entity),
+            });
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_conveyors(
+    mut commands: Commands,
     time: Res<Time>,
     mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
     mut conveyor_query: Query<(Entity, &mut Conveyor, &mut AnimationData, &ConveyorLabelTag)>,
-    mut blinker_query: Query<(Option<&Parent>, &mut Blinker, &mut Sprite)>,
     mut package_query: Query<(Entity, &mut Transform, &Parent), (With<Package>, Without<Player>)>,
+    mut jammed_events: EventWriter<ConveyorJammed>,
+    mut cleared_events: EventWriter<ConveyorCleared>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut broke_events: EventWriter<ConveyorBroke>,
+    mut planned_wave: ResMut<PlannedWave>,
+    mut rng: ResMut<Rand>,
+    attract_mode: Res<AttractMode>,
+    supervisor_status: Res<SupervisorStatus>,
+    metrics: Option<ResMut<Metrics>>,
+    wave_modifiers: Res<WaveModifiers>,
 ) {
+    let conveyor_config = &game_config.conveyor_config;
+    let wave_modifier_config = &game_config.wave_modifier_config;
+    // a faster belt runs its length in less time, so its running timer goes faster too
+    let belt_speed_scale = wave_modifiers.belt_speed_scale(wave_modifier_config);
+
+    // first pass, belt state comes purely from the conveyor's own timers and package count
     let mut incoming_conveyors_empty = true;
+    let mut incoming_conveyors_blocked = false;
+    let mut jam_seconds_remaining: Option<f32> = None;
     for (conveyor_entity, mut conveyor_info, mut anim_data, conveyor_type) in &mut conveyor_query {
-        let is_incoming = match conveyor_type {
-            ConveyorLabelTag::Incoming => true,
-            _ => false,
-        };
+        let is_incoming = matches!(conveyor_type, ConveyorLabelTag::Incoming);
+        // a watched belt's timers don't tick, so it carries on exactly where it stopped
+        let watched = supervisor_status.holds_belt(&game_config, conveyor_type);
 
-        conveyor_info.active_timer.tick(time.delta());
-        conveyor_info.idle_timer.tick(time.delta());
+        let travelled_before = conveyor_info.travelled();
+        if !watched {
+            conveyor_info.tick_timers(time.delta().mul_f32(belt_speed_scale), time.delta());
+        }
+        // taken from the timer rather than the frame time, so packages follow the ramps exactly
+        let belt_moved = (conveyor_info.travelled() - travelled_before).max(0.);
 
-        if conveyor_info.active_timer.just_finished() {
-            conveyor_info.active_timer.pause();
+        if conveyor_info.active_timer().just_finished() {
+            conveyor_info.end_run();
+            // incoming belts wait for the next wave instead
             if !is_incoming {
-                conveyor_info.idle_timer.reset();
-                conveyor_info.idle_timer.unpause();
+                conveyor_info.begin_idle_countdown(None);
             }
         }
-        if conveyor_info.idle_timer.just_finished() {
-            conveyor_info.idle_timer.pause();
-            conveyor_info.active_timer.reset();
-            conveyor_info.active_timer.unpause();
+        if conveyor_info.idle_timer().just_finished() {
+            conveyor_info.start_run();
         }
 
-        let conveyor_active = !conveyor_info.active_timer.finished();
-        let conveyor_just_activated = conveyor_info.idle_timer.just_finished();
-        for (parent, mut blinker, mut blinker_sprite) in &mut blinker_query {
-            if parent.map_or(true, |p| p.get() != conveyor_entity) {
-                continue;
+        let conveyor_active = conveyor_info.is_running() && !watched;
+        let mut conveyor_blocked = false;
+        if is_incoming && conveyor_info.package_count > 0 {
+            incoming_conveyors_empty = false;
+            if !conveyor_active {
+                // player needs to remove packages before the next wave can come
+                conveyor_info.hold_idle_countdown();
+                incoming_conveyors_blocked = true;
+                conveyor_blocked = true;
             }
+        }
 
-            if conveyor_active {
-                if conveyor_just_activated {
-                    // conveyor just activated, reset blinker
-                    blinker_sprite.color = blinker.active_colour;
-                    blinker.blink_timer.reset();
+        // a belt left blocked for too long jams, and is force cleared if it stays that way
+        if conveyor_blocked {
+            conveyor_info.blocked_seconds += time.delta_seconds();
+            if conveyor_info.jam_timer.is_none()
+                && conveyor_info.blocked_seconds >= conveyor_config.jam_grace_seconds
+            {
+                conveyor_info.jam_timer = Some(Timer::from_seconds(
+                    conveyor_config.jam_countdown_seconds,
+                    TimerMode::Once,
+                ));
+                jammed_events.send(ConveyorJammed {
+                    conveyor: conveyor_entity,
+                });
+            }
+        } else {
+            conveyor_info.blocked_seconds = 0.;
+            if conveyor_info.jam_timer.take().is_some() {
+                // players cleared the belt themselves before the jam expired
+                cleared_events.send(ConveyorCleared {
+                    conveyor: conveyor_entity,
+                    packages_lost: 0,
+                });
+            }
+        }
+
+        if let Some(jam_timer) = conveyor_info.jam_timer.as_mut() {
+            jam_timer.tick(time.delta());
+            if jam_timer.finished() {
+                let jammed_packages = package_query
+                    .iter()
+                    .filter(|(_, _, p)| p.get() == conveyor_entity)
+                    .map(|(package_entity, _, _)| package_entity)
+                    .collect::<Vec<_>>();
+                for package_entity in &jammed_packages {
+                    release_package(&mut commands, *package_entity);
                 }
-                // conveyor is active, blink the blinker
-                anim_data.pause = false;
-                blinker.blink_timer.tick(time.delta());
-                if blinker.blink_timer.just_finished() {
-                    blinker_sprite.color = if blinker_sprite.color != blinker.active_colour {
-                        blinker.active_colour
+
+                // the jam costs the team, split evenly so neither player takes it all
+                let score_config = &game_config.score_config;
+                let penalty_points = score_config.jam_penalty_per_package
+                    * jammed_packages.len() as f32
+                    / game_state.player_scores.len() as f32;
+                for (player_index, player_score) in game_state.player_scores.iter_mut() {
+                    let penalty = if score_config.penalties_scale_with_multiplier {
+                        penalty_points * player_score.multiplier
                     } else {
-                        Color::BLACK
+                        penalty_points
                     };
+                    player_score.score.apply_penalty(
+                        Score::from_points(penalty as f64),
+                        score_config.score_floor,
+                    );
+                    score_events.send(ScoreChanged {
+                        player: player_index,
+                    });
                 }
 
-                if is_incoming {
-                    incoming_conveyors_empty = false;
-                }
+                conveyor_info.package_count = 0;
+                conveyor_info.blocked_seconds = 0.;
+                conveyor_info.jam_timer = None;
+                cleared_events.send(ConveyorCleared {
+                    conveyor: conveyor_entity,
+                    packages_lost: jammed_packages.len(),
+                });
             } else {
-                if is_incoming && conveyor_info.package_count > 0 {
-                    // player needs to remove packages before the next wave can come
-                    conveyor_info.idle_timer.pause();
-                    incoming_conveyors_empty = false;
-                }
-                // conveyor is inactive, make sure blinker is inactive
-                anim_data.pause = true;
-                if !conveyor_info.idle_timer.paused()
-                    && conveyor_info.idle_timer.fraction_remaining() <= 0.25
-                {
-                    // 25% of the idle time remaining, let player know we're almost active
-                    blinker_sprite.color = blinker.readying_colour;
-                } else {
-                    blinker_sprite.color = blinker.inactive_colour;
-                }
+                jam_seconds_remaining = Some(
+                    jam_seconds_remaining
+                        .unwrap_or_default()
+                        .max(jam_timer.remaining_secs()),
+                );
             }
         }
 
+        anim_data.pause = !conveyor_active;
         if conveyor_active {
             for (_package_entity, mut package_transform, _) in package_query
                 .iter_mut()
                 .filter(|(_, _, p)| p.get() == conveyor_entity)
             {
-                package_transform.translation.y +=
-                    conveyor_info.direction * conveyor_info.speed * time.delta_seconds();
+                package_transform.translation.y += conveyor_info.direction * belt_moved;
             }
         }
     }
 
-    if incoming_conveyors_empty && game_state.package_wave_timer.paused() {
-        game_state.package_wave_timer.unpause();
-        for (_, mut conveyor_info, _, _) in
-            conveyor_query.iter_mut().filter(|(_, _, _, t)| match **t {
-                ConveyorLabelTag::Incoming => true,
-                _ => false,
-            })
+    // multipliers hold still during a jam rather than decaying, losing points is punishment enough
+    for (_, player_score) in game_state.player_scores.iter_mut() {
+        if let Some(jam_seconds_remaining) = jam_seconds_remaining {
+            player_score
+                .multiplier_freeze
+                .freeze(FreezeSource::Jam, jam_seconds_remaining);
+        } else {
+            player_score.multiplier_freeze.unfreeze(FreezeSource::Jam);
+        }
+    }
+
+    if let Some(mut metrics) = metrics {
+        if incoming_conveyors_blocked {
+            metrics.incoming_blocked_seconds += time.delta_seconds();
+        }
+    }
+
+    if incoming_conveyors_empty && game_state.package_wave_timer.is_held() {
+        game_state.package_wave_timer.resume();
+        // every incoming belt is empty, so this is the one moment a breakdown can't strand
+        // packages part way along a belt
+        let breakdown_probability = conveyor_config.breakdown_probability;
+        for (conveyor_entity, mut conveyor_info, _, _) in conveyor_query
+            .iter_mut()
+            .filter(|(_, _, _, t)| matches!(**t, ConveyorLabelTag::Incoming))
         {
-            conveyor_info.idle_timer.reset();
-            conveyor_info
-                .idle_timer
-                .set_duration(game_state.package_wave_timer.duration());
-            conveyor_info.idle_timer.unpause();
+            if conveyor_info.is_broken() {
+                continue;
+            }
+            if breakdown_probability > 0.
+                && !attract_mode.active
+                && !conveyor_info.is_running()
+                && rng.gen_range(0.0..1.0f32) < breakdown_probability
+            {
+                info!("conveyor {:?} broke down", conveyor_entity);
+                conveyor_info.state = ConveyorState::Broken {
+                    repair_progress: 0.,
+                };
+                conveyor_info.hold_idle_countdown();
+                // the next wave was planned with this belt in it
+                planned_wave.counts = None;
+                broke_events.send(ConveyorBroke {
+                    conveyor: conveyor_entity,
+                });
+                continue;
+            }
+            conveyor_info.begin_idle_countdown(Some(
+                game_state
+                    .package_wave_timer
+                    .duration()
+                    .mul_f32(wave_modifiers.countdown_scale(wave_modifier_config)),
+            ));
+        }
+    }
+}
+
+/// Blinkers just reflect the state of the belt they belong to, nothing here touches the belt's
+/// timers. The sprites are only written when their colour actually changes.
+pub fn update_blinker_visuals(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    supervisor_status: Res<SupervisorStatus>,
+    conveyor_query: Query<(&Conveyor, &ConveyorLabelTag)>,
+    mut blinker_query: Query<(Option<&Parent>, &mut Blinker, &mut Sprite, &Children)>,
+    mut pip_query: Query<(&CountdownPip, &mut Sprite, &mut Visibility), Without<Blinker>>,
+) {
+    let conveyor_config = &game_config.conveyor_config;
+    for (parent, mut blinker, mut blinker_sprite, blinker_children) in &mut blinker_query {
+        let Some((conveyor_info, conveyor_tag)) =
+            parent.and_then(|parent| conveyor_query.get(parent.get()).ok())
+        else {
+            continue;
+        };
+
+        let watched = supervisor_status.holds_belt(&game_config, conveyor_tag);
+        let active = conveyor_info.is_running() && !watched;
+        let jam_flash = conveyor_info.jam_timer.as_ref().map(|jam_timer| {
+            ((jam_timer.elapsed_secs() / conveyor_config.jam_blink_duration_seconds) as u32)
+                .is_multiple_of(2)
+        });
+        // 25% of the idle time remaining, let player know we're almost active
+        let readying = !conveyor_info.idle_timer().paused()
+            && conveyor_info.idle_timer().fraction_remaining() <= 0.25;
+        // an incoming belt with packages left on it won't start again until they're cleared
+        let blocked = matches!(conveyor_tag, ConveyorLabelTag::Incoming)
+            && conveyor_info.package_count > 0
+            && !active;
+
+        let blinker_colour = if let Some(flash_on) = jam_flash {
+            if flash_on {
+                blinker.readying_colour
+            } else {
+                Color::BLACK
+            }
+        } else if watched {
+            // held steady rather than blinking, so it can't be mistaken for a belt about to start
+            blinker.watched_colour
+        } else if active {
+            if conveyor_info.idle_timer().just_finished() {
+                // conveyor just activated, reset blinker
+                blinker.blink_timer.reset();
+                blinker.active_colour
+            } else {
+                // conveyor is active, blink the blinker
+                blinker.blink_timer.tick(time.delta());
+                if !blinker.blink_timer.just_finished() {
+                    blinker_sprite.color
+                } else if blinker_sprite.color != blinker.active_colour {
+                    blinker.active_colour
+                } else {
+                    Color::BLACK
+                }
+            }
+        } else if conveyor_info.is_broken() {
+            // a slow flash that never goes fully dark, unlike anything a working belt shows
+            blinker.blink_timer.tick(time.delta() / 2);
+            if !blinker.blink_timer.just_finished() {
+                blinker_sprite.color
+            } else if blinker_sprite.color != blinker.readying_colour {
+                blinker.readying_colour
+            } else {
+                blinker.inactive_colour
+            }
+        } else if readying {
+            // readying blinks twice as fast as running so the two never rely on colour alone
+            blinker.blink_timer.tick(time.delta() * 2);
+            if !blinker.blink_timer.just_finished() {
+                blinker_sprite.color
+            } else if blinker_sprite.color != blinker.readying_colour {
+                blinker.readying_colour
+            } else {
+                Color::BLACK
+            }
+        } else {
+            // conveyor is inactive, make sure blinker is inactive
+            blinker.inactive_colour
+        };
+
+        if blinker_sprite.color != blinker_colour {
+            blinker_sprite.color = blinker_colour;
+        }
+
+        // the ring fills as the belt's idle time runs out, and pulses while the belt is blocked
+        // since it won't fill until someone clears it
+        let ring = match conveyor_config.blinker_style {
+            BlinkerStyle::Colour => None,
+            BlinkerStyle::Ring if blocked => {
+                let pulse = (time.elapsed_seconds()
+                    * conveyor_config.countdown_pulse_speed
+                    * std::f32::consts::TAU)
+                    .sin()
+                    * 0.5
+                    + 0.5;
+                Some((
+                    COUNTDOWN_PIPS,
+                    blinker.readying_colour.with_a(0.25 + pulse * 0.75),
+                ))
+            }
+            BlinkerStyle::Ring if !active && !conveyor_info.idle_timer().paused() => {
+                let lit = (conveyor_info.idle_timer().fraction() * COUNTDOWN_PIPS as f32) as usize;
+                Some((lit, blinker.readying_colour))
+            }
+            BlinkerStyle::Ring => None,
+        };
+        for child in blinker_children.iter() {
+            let Ok((pip, mut pip_sprite, mut pip_visibility)) = pip_query.get_mut(*child) else {
+                continue;
+            };
+            let (visibility, colour) = match ring {
+                Some((lit, lit_colour)) if pip.index < lit => (Visibility::Inherited, lit_colour),
+                Some(_) => (Visibility::Inherited, blinker.inactive_colour),
+                None => (Visibility::Hidden, pip_sprite.color),
+            };
+            if *pip_visibility != visibility {
+                *pip_visibility = visibility;
+            }
+            if pip_sprite.color != colour {
+                pip_sprite.color = colour;
+            }
+        }
+    }
+}
+
+/// A player standing beside a broken belt repairs it by holding interact, letting go or walking
+/// off loses the progress made so far.
+#[allow(clippy::too_many_arguments)]
+pub fn repair_broken_conveyors(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    game_config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    mut planned_wave: ResMut<PlannedWave>,
+    player_query: Query<&Player>,
+    mut conveyor_query: Query<(Entity, &mut Conveyor, &GlobalTransform, &Children)>,
+    mut bar_query: Query<(&RepairBar, &mut Sprite, &mut Visibility)>,
+    mut repaired_events: EventWriter<ConveyorRepaired>,
+    wave_modifiers: Res<WaveModifiers>,
+) {
+    let conveyor_config = &game_config.conveyor_config;
+    let countdown_scale = wave_modifiers.countdown_scale(&game_config.wave_modifier_config);
+    for (conveyor_entity, mut conveyor_info, conveyor_transform, conveyor_children) in
+        &mut conveyor_query
+    {
+        let ConveyorState::Broken { repair_progress } = conveyor_info.state else {
+            for child in conveyor_children.iter() {
+                if let Ok((_, _, mut bar_visibility)) = bar_query.get_mut(*child) {
+                    if *bar_visibility != Visibility::Hidden {
+                        *bar_visibility = Visibility::Hidden;
+                    }
+                }
+            }
+            continue;
+        };
+
+        let reach_area = Collider::cuboid(
+            conveyor_config.size.x / 2. + conveyor_config.repair_reach,
+            conveyor_info.belt_region.y / 2. + conveyor_config.repair_reach,
+        );
+        let mut repairer = None;
+        rapier_context.intersections_with_shape(
+            conveyor_transform.translation().truncate(),
+            0.,
+            &reach_area,
+            QueryFilter::default(),
+            |colliding_entity| {
+                let Ok(player_info) = player_query.get(colliding_entity) else {
+                    return true;
+                };
+                if game_state.player_controls[player_info.player_index].state[KeyAction::Interact]
+                    .pressed()
+                {
+                    repairer = Some(player_info.player_index);
+                    return false;
+                }
+                true
+            },
+        );
+
+        let repair_progress = match repairer {
+            Some(_) => repair_progress + time.delta_seconds(),
+            None => 0.,
+        };
+        let repaired = repair_progress >= conveyor_config.repair_seconds;
+        if let Some(player_index) = repairer.filter(|_| repaired) {
+            info!("{:?} repaired conveyor {:?}", player_index, conveyor_entity);
+            conveyor_info.state = ConveyorState::Stopped;
+            // ready to run with the next wave, unless the belts are still being cleared in
+            // which case that wave isn't counting down yet
+            if !game_state.package_wave_timer.is_held() {
+                conveyor_info.begin_idle_countdown(Some(
+                    game_state
+                        .package_wave_timer
+                        .remaining()
+                        .mul_f32(countdown_scale),
+                ));
+            }
+            planned_wave.counts = None;
+            repaired_events.send(ConveyorRepaired {
+                conveyor: conveyor_entity,
+                player: player_index,
+            });
+        } else {
+            conveyor_info.state = ConveyorState::Broken { repair_progress };
+        }
+
+        let fill = if repaired {
+            0.
+        } else {
+            (repair_progress / conveyor_config.repair_seconds).clamp(0., 1.)
+        };
+        for child in conveyor_children.iter() {
+            let Ok((repair_bar, mut bar_sprite, mut bar_visibility)) = bar_query.get_mut(*child)
+            else {
+                continue;
+            };
+            let size = Vec2::new(repair_bar.full_width * fill, REPAIR_BAR_HEIGHT);
+            if bar_sprite.custom_size != Some(size) {
+                bar_sprite.custom_size = Some(size);
+            }
+            let visibility = if fill > 0. {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            if *bar_visibility != visibility {
+                *bar_visibility = visibility;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WaveHoldReason, WaveTimer};
+
+    const BELT_LENGTH: f32 = 600.;
+    const SPEED: f32 = 200.;
+
+    fn conveyor_with_ramp(ramp_seconds: f32) -> Conveyor {
+        Conveyor::new(Vec2::new(50., BELT_LENGTH), -1., SPEED, ramp_seconds, None)
+    }
+
+    /// Ticks a whole run in small steps, checking the belt never goes backwards on the way.
+    fn travelled_over_a_run(conveyor: &mut Conveyor) -> f32 {
+        let mut last_travelled = 0.;
+        conveyor.start_run();
+        while !conveyor.active_timer().finished() {
+            conveyor.tick_timers(Duration::from_secs_f32(1. / 64.), Duration::ZERO);
+            let travelled = conveyor.travelled();
+            assert!(travelled >= last_travelled - 0.001);
+            last_travelled = travelled;
+        }
+        last_travelled
+    }
+
+    #[test]
+    fn ramps_dont_change_how_far_a_run_travels() {
+        for ramp_seconds in [0., 0.25, 10.] {
+            let mut conveyor = conveyor_with_ramp(ramp_seconds);
+            let travelled = travelled_over_a_run(&mut conveyor);
+            assert!(
+                (travelled - BELT_LENGTH).abs() < 0.01,
+                "ramp {}: travelled {}",
+                ramp_seconds,
+                travelled
+            );
+        }
+    }
+
+    #[test]
+    fn ramp_is_clamped_to_the_full_speed_run() {
+        let conveyor = conveyor_with_ramp(10.);
+        assert_eq!(conveyor.ramp_seconds, BELT_LENGTH / SPEED);
+        assert_eq!(
+            conveyor.active_timer().duration().as_secs_f32(),
+            2. * BELT_LENGTH / SPEED
+        );
+    }
+    #[test]
+    fn registry_follows_belts_as_they_come_go_and_change_hands() {
+        let mut app = App::new();
+        app.insert_resource(GameConfig::default())
+            .init_resource::<BeltRegistry>()
+            .add_systems(Update, update_belt_registry);
+        let mut spawn_belt =
+            |conveyor_tag| app.world.spawn((conveyor_with_ramp(0.), conveyor_tag)).id();
+        let incoming = [
+            spawn_belt(ConveyorLabelTag::Incoming),
+            spawn_belt(ConveyorLabelTag::Incoming),
+        ];
+        let player_one = [
+            spawn_belt(ConveyorLabelTag::Outgoing(PlayerIndex::Player1)),
+            spawn_belt(ConveyorLabelTag::Outgoing(PlayerIndex::Player1)),
+            spawn_belt(ConveyorLabelTag::Outgoing(PlayerIndex::Player1)),
+        ];
+        let player_two = spawn_belt(ConveyorLabelTag::Outgoing(PlayerIndex::Player2));
+        app.update();
+
+        let belt_registry = app.world.resource::<BeltRegistry>();
+        assert_eq!(belt_registry.incoming(), incoming);
+        assert_eq!(belt_registry.outgoing(PlayerIndex::Player1), player_one);
+        assert_eq!(belt_registry.outgoing(PlayerIndex::Player2), [player_two]);
+        for belt in player_one {
+            assert_eq!(belt_registry.owner_of(belt), Some(PlayerIndex::Player1));
+        }
+        assert_eq!(
+            belt_registry.owner_of(player_two),
+            Some(PlayerIndex::Player2)
+        );
+        assert_eq!(belt_registry.owner_of(incoming[0]), None);
+
+        // one of player one's belts handed over, another taken away and a belt turned to incoming
+        app.world
+            .entity_mut(player_one[1])
+            .insert(ConveyorLabelTag::Outgoing(PlayerIndex::Player2));
+        app.world.despawn(player_one[2]);
+        app.world
+            .entity_mut(player_two)
+            .insert(ConveyorLabelTag::Incoming);
+        app.update();
+
+        let belt_registry = app.world.resource::<BeltRegistry>();
+        assert_eq!(
+            belt_registry.incoming(),
+            [incoming[0], incoming[1], player_two]
+        );
+        assert_eq!(
+            belt_registry.outgoing(PlayerIndex::Player1),
+            [player_one[0]]
+        );
+        assert_eq!(
+            belt_registry.outgoing(PlayerIndex::Player2),
+            [player_one[1]]
+        );
+        assert_eq!(
+            belt_registry.owner_of(player_one[1]),
+            Some(PlayerIndex::Player2)
+        );
+        assert_eq!(belt_registry.owner_of(player_one[2]), None);
+        assert_eq!(belt_registry.owner_of(player_two), None);
+
+        // nothing changed, nothing moves
+        app.update();
+        assert_eq!(
+            app.world
+                .resource::<BeltRegistry>()
+                .outgoing(PlayerIndex::Player1),
+            [player_one[0]]
+        );
+    }
+    #[test]
+    fn runs_and_idle_countdowns_follow_their_operations() {
+        let second = Duration::from_secs(1);
+        let mut conveyor = conveyor_with_ramp(0.);
+        // a new belt counts down to its first run
+        assert!(!conveyor.is_running());
+        assert!(conveyor.active_timer().paused());
+        assert!(!conveyor.idle_timer().paused());
+
+        conveyor.start_run();
+        assert!(conveyor.is_running());
+        assert!(conveyor.idle_timer().paused());
+        conveyor.tick_timers(second, second);
+        assert_eq!(conveyor.active_timer().elapsed(), second);
+        assert_eq!(conveyor.idle_timer().elapsed(), Duration::ZERO);
+
+        conveyor.end_run();
+        assert_eq!(conveyor.state, ConveyorState::Stopped);
+        conveyor.tick_timers(second, second);
+        assert_eq!(conveyor.active_timer().elapsed(), second);
+
+        conveyor.begin_idle_countdown(Some(Duration::from_secs(2)));
+        conveyor.tick_timers(second, second);
+        assert_eq!(conveyor.idle_timer().remaining(), second);
+        // held where it is, then started over at the same length
+        conveyor.hold_idle_countdown();
+        conveyor.tick_timers(second, second);
+        assert_eq!(conveyor.idle_timer().remaining(), second);
+        conveyor.begin_idle_countdown(None);
+        assert_eq!(conveyor.idle_timer().remaining(), Duration::from_secs(2));
+
+        // the next run starts from the beginning of its ramp
+        conveyor.tick_timers(second, Duration::from_secs(2));
+        assert!(conveyor.idle_timer().just_finished());
+        conveyor.start_run();
+        assert_eq!(conveyor.active_timer().elapsed(), Duration::ZERO);
+        assert!(conveyor.is_running());
+    }
+
+    fn advance(app: &mut App, seconds: f32) {
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn belt(app: &App, conveyor_entity: Entity) -> &Conveyor {
+        app.world.get::<Conveyor>(conveyor_entity).unwrap()
+    }
+
+    // no blinkers anywhere, the belts and the wave timer have to get by on their own
+    #[test]
+    fn wave_timer_resumes_the_frame_the_last_incoming_belt_clears() {
+        let mut game_config = GameConfig::default();
+        game_config.conveyor_config.breakdown_probability = 0.;
+        let mut game_state = GameState::from_config(&game_config);
+        game_state.package_wave_timer = WaveTimer::new(2.);
+        game_state
+            .package_wave_timer
+            .hold(WaveHoldReason::IncomingBeltsOccupied);
+
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<ConveyorJammed>()
+            .add_event::<ConveyorCleared>()
+            .add_event::<ScoreChanged>()
+            .add_event::<ConveyorBroke>()
+            .insert_resource(AttractMode::new(&game_config))
+            .insert_resource(Rand::new(&Some(2054)))
+            .insert_resource(game_state)
+            .insert_resource(game_config)
+            .init_resource::<PlannedWave>()
+            .init_resource::<SupervisorStatus>()
+            .init_resource::<WaveModifiers>()
+            .add_systems(Update, update_conveyors);
+        let mut spawn_incoming = |package_count| {
+            let mut conveyor = conveyor_with_ramp(0.);
+            conveyor.hold_idle_countdown();
+            conveyor.package_count = package_count;
+            app.world
+                .spawn((
+                    conveyor,
+                    AnimationData {
+                        start_frame: 0,
+                        frame_count: 1,
+                        pause: true,
+                        facing_direction: FacingDirection::Up,
+                    },
+                    ConveyorLabelTag::Incoming,
+                ))
+                .id()
+        };
+        let belts = [spawn_incoming(1), spawn_incoming(0)];
+
+        advance(&mut app, 0.1);
+        let wave_timer = &app.world.resource::<GameState>().package_wave_timer;
+        assert_eq!(
+            wave_timer.hold_reason(),
+            Some(WaveHoldReason::IncomingBeltsOccupied)
+        );
+        assert_eq!(belt(&app, belts[0]).state, ConveyorState::Stopped);
+        assert!(belt(&app, belts[0]).idle_timer().paused());
+        assert!(belt(&app, belts[0]).blocked_seconds > 0.);
+
+        // the last package taken off, the countdown and the belts' idle timers start together
+        app.world
+            .get_mut::<Conveyor>(belts[0])
+            .unwrap()
+            .package_count = 0;
+        advance(&mut app, 0.1);
+        assert!(!app
+            .world
+            .resource::<GameState>()
+            .package_wave_timer
+            .is_held());
+        for conveyor_entity in belts {
+            let conveyor_info = belt(&app, conveyor_entity);
+            assert_eq!(conveyor_info.state, ConveyorState::Stopped);
+            assert!(!conveyor_info.idle_timer().paused());
+            assert_eq!(
+                conveyor_info.idle_timer().duration(),
+                Duration::from_secs(2)
+            );
+            assert_eq!(conveyor_info.blocked_seconds, 0.);
+        }
+
+        // they start when the wave is due, run their length and then wait for the next one
+        advance(&mut app, 2.);
+        for conveyor_entity in belts {
+            assert_eq!(belt(&app, conveyor_entity).state, ConveyorState::Running);
+        }
+        advance(&mut app, BELT_LENGTH / SPEED);
+        for conveyor_entity in belts {
+            let conveyor_info = belt(&app, conveyor_entity);
+            assert_eq!(conveyor_info.state, ConveyorState::Stopped);
+            assert!(conveyor_info.idle_timer().paused());
         }
     }
 }
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::{
+    EmoteConfig, EntityLayer, GameConfig, GameState, KeyAction, Player, RenderLayers, RoundScoped,
+    TextureTarget, TrackedTexture,
+};
+
+/// The icons a bubble can show, in the order tapping cycles through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmoteIcon {
+    Laugh,
+    Exclaim,
+    Heart,
+    Question,
+}
+
+/// Every icon in cycling order, which is also the order of the cells in the `Emotes` sheet.
+pub const EMOTE_ICONS: [EmoteIcon; 4] = [
+    EmoteIcon::Laugh,
+    EmoteIcon::Exclaim,
+    EmoteIcon::Heart,
+    EmoteIcon::Question,
+];
+
+/// A player's bubble, if one is up, and what's stopping them putting up another.
+#[derive(Component)]
+pub struct EmoteState {
+    /// Index into `EMOTE_ICONS` of the icon being shown.
+    pub selected: usize,
+    /// Runs from the last tap, another tap before it finishes changes the icon.
+    pub selection_timer: Timer,
+    pub cooldown_timer: Timer,
+    pub display_timer: Timer,
+    pub bubble: Option<Entity>,
+}
+
+impl EmoteState {
+    pub fn new(emote_config: &EmoteConfig) -> Self {
+        let finished_timer = |seconds: f32| {
+            let mut timer = Timer::from_seconds(seconds.max(0.), TimerMode::Once);
+            timer.tick(timer.duration());
+            timer
+        };
+        Self {
+            selected: 0,
+            selection_timer: finished_timer(emote_config.cycle_window_seconds),
+            cooldown_timer: finished_timer(emote_config.cooldown_seconds),
+            display_timer: finished_timer(emote_config.display_seconds),
+            bubble: None,
+        }
+    }
+}
+
+/// Marks the speech bubble over a player's head.
+#[derive(Component)]
+pub struct EmoteBubble;
+
+/// Puts a bubble up over a player who taps emote, tapping again quickly moves it on to the next
+/// icon. A new bubble waits on the cooldown, and putting one up gives up any throw being charged.
+#[allow(clippy::too_many_arguments)]
+pub fn update_emotes(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    game_config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    mut player_query: Query<(Entity, &mut Player, &mut EmoteState)>,
+    mut bubble_query: Query<&mut TextureAtlas, With<EmoteBubble>>,
+) {
+    let emote_config = &game_config.emote_config;
+    for (player_entity, mut player_info, mut emote_state) in &mut player_query {
+        emote_state.selection_timer.tick(time.delta());
+        emote_state.cooldown_timer.tick(time.delta());
+        emote_state.display_timer.tick(time.delta());
+
+        if let Some(bubble_entity) = emote_state.bubble {
+            // the round reset may have cleared it already
+            if emote_state.display_timer.finished() || bubble_query.get(bubble_entity).is_err() {
+                if let Some(bubble) = commands.get_entity(bubble_entity) {
+                    bubble.despawn_recursive();
+                }
+                emote_state.bubble = None;
+            }
+        }
+
+        if !emote_config.enabled
+            || !game_state.player_controls[player_info.player_index].state[KeyAction::Emote]
+                .just_pressed()
+        {
+            continue;
+        }
+
+        match emote_state.bubble {
+            Some(bubble_entity) if !emote_state.selection_timer.finished() => {
+                emote_state.selected = (emote_state.selected + 1) % EMOTE_ICONS.len();
+                if let Ok(mut atlas) = bubble_query.get_mut(bubble_entity) {
+                    atlas.index = emote_state.selected;
+                }
+            }
+            _ if emote_state.cooldown_timer.finished() => {
+                if let Some(bubble) = emote_state
+                    .bubble
+                    .take()
+                    .and_then(|bubble_entity| commands.get_entity(bubble_entity))
+                {
+                    bubble.despawn_recursive();
+                }
+                emote_state.selected = 0;
+                emote_state.cooldown_timer.reset();
+                let bubble_entity = spawn_emote_bubble(
+                    &mut commands,
+                    &asset_server,
+                    &mut texture_atlas_layouts,
+                    &game_config,
+                );
+                commands.entity(player_entity).add_child(bubble_entity);
+                emote_state.bubble = Some(bubble_entity);
+                debug!("{:?} emotes {:?}", player_info.player_index, EMOTE_ICONS[0]);
+            }
+            _ => continue,
+        }
+        emote_state.selection_timer.reset();
+        emote_state.display_timer.reset();
+        // the price of taunting mid throw
+        player_info.throw_timer.reset();
+    }
+}
+
+fn spawn_emote_bubble(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    game_config: &GameConfig,
+) -> Entity {
+    let texture_pack = game_config.get_texture_pack();
+    let emote_sprite = texture_pack.choose_texture_for(TextureTarget::Emotes, None);
+    let (atlas_layout, _) = emote_sprite.atlas_layout("Emotes");
+    let sprite_path = format!("{}/{}", texture_pack.root, emote_sprite.path);
+    let bubble_size = game_config.emote_config.bubble_size;
+    commands
+        .spawn((
+            SpriteSheetBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(bubble_size)),
+                    ..default()
+                },
+                atlas: TextureAtlas {
+                    layout: texture_atlas_layouts.add(atlas_layout),
+                    index: 0,
+                },
+                texture: asset_server.load(&sprite_path),
+                transform: Transform::from_translation(Vec3::new(
+                    0.,
+                    (game_config.player_config.size + bubble_size) / 2. + 4.,
+                    0.,
+                )),
+                ..default()
+            },
+            EmoteBubble,
+            TrackedTexture::new(
+                TextureTarget::Emotes,
+                format!("texture pack '{}'", game_config.selected_texture_pack),
+                sprite_path,
+            ),
+            RenderLayers::Single(EntityLayer::Emote),
+            RoundScoped,
+        ))
+        .id()
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use enum_map::EnumMap;
+
+use crate::{
+    AxisDirection, InteractionKind, KeyAction, PlayerIndex, PowerupKind, RoundResult, Score,
+};
+
+#[derive(Event)]
+pub struct PackageDelivered {
+    pub player: PlayerIndex,
+    pub package: Entity,
+    pub value: Score,
+}
+
+/// A player won their claim on a package and is now holding it.
+#[derive(Event)]
+pub struct PackagePickedUp {
+    pub player: PlayerIndex,
+    pub package: Entity,
+}
+
+/// A package left a player's hands with some force behind it, placing one doesn't count.
+#[derive(Event)]
+pub struct PackageThrown {
+    pub player: PlayerIndex,
+    pub package: Entity,
+    pub charge: f32,
+}
+
+/// A player put a package they didn't want back on an incoming belt.
+#[derive(Event)]
+pub struct PackageReturned {
+    pub player: PlayerIndex,
+    pub package: Entity,
+    pub conveyor: Entity,
+}
+
+/// A wave landed on the incoming belts, with how many packages each belt got.
+#[derive(Event)]
+pub struct WaveSpawned {
+    pub per_conveyor_counts: Vec<(Entity, usize)>,
+}
+
+/// A player's multiplier moved by at least the tenth the readout shows, whatever moved it.
+#[derive(Event)]
+pub struct MultiplierChanged {
+    pub player: PlayerIndex,
+    pub old: f32,
+    pub new: f32,
+}
+
+/// A player delivered fast enough to reach one of the score config's streak tiers, `tier` indexes
+/// into that list.
+#[derive(Event)]
+pub struct StreakAchieved {
+    pub player: PlayerIndex,
+    pub tier: usize,
+}
+
+/// A player's claim on a package lost out to another claim in the same tick.
+#[derive(Event)]
+pub struct InteractionFailed {
+    pub player: PlayerIndex,
+    pub package: Entity,
+    pub kind: InteractionKind,
+}
+
+#[derive(Event)]
+pub struct PlayerCaught {
+    pub player: PlayerIndex,
+}
+
+/// The supervisor took the package a player was holding in the restricted zone.
+#[derive(Event)]
+pub struct PackageConfiscated {
+    pub player: PlayerIndex,
+    pub supervisor: Entity,
+}
+
+/// A fresh session begins, at launch and whenever play restarts with new players.
+#[derive(Event)]
+pub struct SessionStarted;
+
+/// An incoming belt has been blocked past its grace period and will be force cleared.
+#[derive(Event)]
+pub struct ConveyorJammed {
+    pub conveyor: Entity,
+}
+
+/// A jammed belt is running again, either cleared by the players or forced clear by the jam.
+#[derive(Event)]
+pub struct ConveyorCleared {
+    pub conveyor: Entity,
+    pub packages_lost: usize,
+}
+
+/// A loose package has decayed as far as it can and is now worth its minimum value.
+#[derive(Event)]
+pub struct PackageWentStale {
+    pub package: Entity,
+}
+
+/// A round ended and the players changed ends for the next one.
+#[derive(Event)]
+pub struct SidesSwapped;
+
+/// An incoming belt broke down and won't be fed until it's repaired.
+#[derive(Event)]
+pub struct ConveyorBroke {
+    pub conveyor: Entity,
+}
+
+/// A player finished repairing a broken belt.
+#[derive(Event)]
+pub struct ConveyorRepaired {
+    pub conveyor: Entity,
+    pub player: PlayerIndex,
+}
+
+/// Something shown in a player's score readout changed: their score, the multiplier as
+/// displayed, or which freezes are holding it.
+#[derive(Event)]
+pub struct ScoreChanged {
+    pub player: PlayerIndex,
+}
+
+/// A thrown package hit the supervisor, the thrower has been penalised.
+#[derive(Event)]
+pub struct SupervisorHit {
+    pub player: PlayerIndex,
+    pub supervisor: Entity,
+}
+
+#[derive(Event)]
+pub struct SupervisorPhaseChanged {
+    pub supervisor: Entity,
+    pub monitoring: bool,
+}
+
+#[derive(Event)]
+pub struct PowerupSpawned {
+    pub powerup: Entity,
+    pub kind: PowerupKind,
+}
+
+/// A player walked over a powerup and now has its modifier.
+#[derive(Event)]
+pub struct PowerupCollected {
+    pub player: PlayerIndex,
+    pub kind: PowerupKind,
+}
+
+/// Either a powerup sat on the floor uncollected until it timed out, or a player's modifier ran
+/// out, `player` says which.
+#[derive(Event)]
+pub struct PowerupExpired {
+    pub player: Option<PlayerIndex>,
+    pub kind: PowerupKind,
+}
+
+/// A timed round ran out, sent before the scores are cleared for the next one.
+#[derive(Event)]
+pub struct RoundEnded {
+    /// Counted from 1.
+    pub round: usize,
+    pub result: RoundResult,
+    pub scores: EnumMap<PlayerIndex, i64>,
+    pub duration_seconds: f32,
+}
+
+/// A player's pad never reported an axis one of their actions is bound to, so its fallback
+/// button is being read instead. Sent once per pad and axis.
+#[derive(Event)]
+pub struct PadFallbackApplied {
+    pub player: PlayerIndex,
+    pub action: KeyAction,
+    pub axis: GamepadAxisType,
+    pub direction: AxisDirection,
+    pub button: GamepadButtonType,
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+};
+
+use crate::{
+    evict_oldest, AppConfig, BufferBudgets, CameraAnchor, ConveyorCleared, EntityLayer, GameConfig,
+    GameState, PackageConfiscated, PackageDelivered, PackageThrown, PlayerCaught, PlayerIndex,
+    PowerupCollected, QualitySettings, RenderLayers, RoundScoped, RumblePulse,
+    TutorialStepCompleted,
+};
+
+#[derive(Component)]
+pub struct MainCamera;
+
+#[derive(Resource, Default)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+#[derive(Component)]
+pub struct ScreenFlash {
+    pub timer: Timer,
+    pub colour: Color,
+}
+
+impl CameraShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0., 1.);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_feedback(
+    mut commands: Commands,
+    mut camera_shake: ResMut<CameraShake>,
+    mut delivered_events: EventReader<PackageDelivered>,
+    mut caught_events: EventReader<PlayerCaught>,
+    mut confiscated_events: EventReader<PackageConfiscated>,
+    mut powerup_events: EventReader<PowerupCollected>,
+    mut tutorial_events: EventReader<TutorialStepCompleted>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    quality_settings: Res<QualitySettings>,
+    flash_query: Query<(), With<ScreenFlash>>,
+) {
+    let feedback_config = &game_config.feedback_config;
+    let mut flash_count = flash_query.iter().count();
+    let mut spawn_flash = |colour: Color| {
+        if flash_count >= quality_settings.max_screen_flashes() {
+            return;
+        }
+        flash_count += 1;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: colour.with_a(feedback_config.flash_alpha),
+                    custom_size: Some(app_config.base_resolution.as_vec2()),
+                    ..default()
+                },
+                ..default()
+            },
+            ScreenFlash {
+                timer: Timer::from_seconds(feedback_config.flash_duration_seconds, TimerMode::Once),
+                colour,
+            },
+            RenderLayers::Single(EntityLayer::ScreenFlash),
+            RoundScoped,
+        ));
+    };
+
+    for event in delivered_events.read() {
+        camera_shake.add_trauma(feedback_config.delivered_trauma);
+        spawn_flash(*game_config.player_config.per_player[event.player].colour);
+    }
+
+    for _ in caught_events.read() {
+        camera_shake.add_trauma(feedback_config.caught_trauma);
+        spawn_flash(Color::RED);
+    }
+
+    for _ in confiscated_events.read() {
+        camera_shake.add_trauma(feedback_config.confiscated_trauma);
+        spawn_flash(Color::RED);
+    }
+
+    for event in powerup_events.read() {
+        spawn_flash(event.kind.colour());
+    }
+
+    for _ in tutorial_events.read() {
+        spawn_flash(*game_config.team_colour);
+    }
+}
+
+pub fn apply_camera_shake(
+    time: Res<Time>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut camera_query: Query<(&mut Transform, &CameraAnchor), With<MainCamera>>,
+    game_config: Res<GameConfig>,
+    quality_settings: Res<QualitySettings>,
+) {
+    let feedback_config = &game_config.feedback_config;
+    camera_shake.trauma = (camera_shake.trauma
+        - feedback_config.trauma_decay_per_second * time.delta_seconds())
+    .max(0.);
+
+    // still runs in low quality, it's what puts the camera back on its anchor
+    let shake = if feedback_config.camera_shake_enabled && quality_settings.full_effects() {
+        camera_shake.trauma * camera_shake.trauma
+    } else {
+        0.
+    };
+
+    // cheap deterministic noise, each channel samples the same curve at a different offset so
+    // they don't move in lockstep
+    let t = time.elapsed_seconds() * feedback_config.shake_frequency;
+    let noise = |offset: f32| ((t + offset).sin() * 0.6) + ((t * 2.3 + offset * 1.7).sin() * 0.4);
+
+    for (mut camera_transform, camera_anchor) in &mut camera_query {
+        camera_transform.translation.x =
+            camera_anchor.position.x + feedback_config.max_shake_offset * shake * noise(0.);
+        camera_transform.translation.y =
+            camera_anchor.position.y + feedback_config.max_shake_offset * shake * noise(10.);
+        camera_transform.rotation = Quat::from_rotation_z(
+            feedback_config.max_shake_angle.to_radians() * shake * noise(20.),
+        );
+    }
+}
+
+pub fn fade_screen_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flash_query: Query<(Entity, &mut ScreenFlash, &mut Sprite)>,
+    game_config: Res<GameConfig>,
+) {
+    for (flash_entity, mut flash, mut flash_sprite) in &mut flash_query {
+        flash.timer.tick(time.delta());
+        if flash.timer.finished() {
+            commands.entity(flash_entity).despawn_recursive();
+            continue;
+        }
+
+        flash_sprite.color = flash
+            .colour
+            .with_a(game_config.feedback_config.flash_alpha * flash.timer.fraction_remaining());
+    }
+}
+
+const PENDING_RUMBLE_CAP: usize = 64;
+
+/// A pulse waiting to be sent, used for the gap in multi pulse patterns.
+pub struct PendingRumble {
+    player: PlayerIndex,
+    delay_seconds: f32,
+    pulse: RumblePulse,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn rumble_feedback(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    mut thrown_events: EventReader<PackageThrown>,
+    mut delivered_events: EventReader<PackageDelivered>,
+    mut caught_events: EventReader<PlayerCaught>,
+    mut confiscated_events: EventReader<PackageConfiscated>,
+    mut cleared_events: EventReader<ConveyorCleared>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+    mut pending_rumbles: Local<Vec<PendingRumble>>,
+    mut buffer_budgets: ResMut<BufferBudgets>,
+) {
+    let rumble_config = &game_config.rumble_config;
+    if !rumble_config.enabled {
+        // still drain the readers so a later enable doesn't replay old events
+        thrown_events.clear();
+        delivered_events.clear();
+        caught_events.clear();
+        confiscated_events.clear();
+        cleared_events.clear();
+        pending_rumbles.clear();
+        return;
+    }
+
+    let mut queue = |player: PlayerIndex, delay_seconds: f32, pulse: RumblePulse| {
+        pending_rumbles.push(PendingRumble {
+            player,
+            delay_seconds,
+            pulse,
+        });
+    };
+
+    for event in thrown_events.read() {
+        if event.charge >= rumble_config.throw_min_charge {
+            queue(event.player, 0., rumble_config.throw);
+        }
+    }
+
+    for event in delivered_events.read() {
+        queue(event.player, 0., rumble_config.delivered);
+        queue(
+            event.player,
+            rumble_config.delivered.duration_seconds + rumble_config.delivered_gap_seconds,
+            rumble_config.delivered,
+        );
+    }
+
+    for event in caught_events.read() {
+        queue(event.player, 0., rumble_config.penalty);
+    }
+
+    for event in confiscated_events.read() {
+        queue(event.player, 0., rumble_config.penalty);
+    }
+
+    // a jam clearing itself costs the whole team
+    for event in cleared_events.read() {
+        if event.packages_lost > 0 {
+            for (player, _) in game_state.player_controls.iter() {
+                queue(player, 0., rumble_config.penalty);
+            }
+        }
+    }
+
+    let cap = buffer_budgets.cap_for::<PendingRumble>("pending_rumbles", PENDING_RUMBLE_CAP);
+    let evicted = evict_oldest(&mut pending_rumbles, cap);
+
+    let is_pad_shared = game_config.player_config.is_pad_shared();
+    pending_rumbles.retain_mut(|pending| {
+        pending.delay_seconds -= time.delta_seconds();
+        if pending.delay_seconds > 0. {
+            return true;
+        }
+
+        // keyboard players have nothing to rumble, and a shared pad can't tell whose side it
+        // was for
+        if let Some(gamepad) = game_state.player_controls[pending.player].pad {
+            if !is_pad_shared {
+                rumble_requests.send(GamepadRumbleRequest::Add {
+                    duration: Duration::from_secs_f32(pending.pulse.duration_seconds.max(0.)),
+                    intensity: GamepadRumbleIntensity {
+                        strong_motor: pending.pulse.strong_motor.clamp(0., 1.),
+                        weak_motor: pending.pulse.weak_motor.clamp(0., 1.),
+                    },
+                    gamepad,
+                });
+            }
+        }
+        false
+    });
+    buffer_budgets.report("pending_rumbles", pending_rumbles.len(), evicted);
+}
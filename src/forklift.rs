@@ -0,0 +1,303 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    activate_package_physics, random::*, stun_player, AppConfig, ArenaLayout, EntityLayer,
+    ForkliftConfig, GameConfig, Package, Player, RenderLayers, RoundScoped, Stunned, TextureTarget,
+    TrackedTexture,
+};
+
+const STRIPE_COLOUR: Color = Color::rgba(1., 0.8, 0.1, 0.25);
+const BLINKER_COLOUR: Color = Color::rgb(1., 0.6, 0.);
+const BLINKER_SIZE: f32 = 16.;
+/// Blinker flashes per second while the lane is marked.
+const BLINK_RATE: f32 = 3.;
+/// Extra reach around the forklift's body when looking for players it has run into.
+const HIT_MARGIN: f32 = 2.;
+
+/// The lane a forklift is about to cross or is crossing, drawn as a stripe along the floor. It
+/// lives until the forklift has driven out the far side.
+#[derive(Component)]
+pub struct ForkliftLane {
+    pub lane_y: f32,
+    /// 1 when driving in from the left, -1 from the right.
+    pub direction: f32,
+    pub warning_timer: Timer,
+    pub forklift: Option<Entity>,
+}
+
+#[derive(Component)]
+pub struct ForkliftBlinker;
+
+#[derive(Component)]
+pub struct Forklift {
+    pub direction: f32,
+    pub lane: Entity,
+    /// Players already stunned this crossing, so staying in the way doesn't keep them down.
+    pub hit_players: Vec<Entity>,
+}
+
+/// When the next crossing is due, only counting down while the lanes are clear.
+#[derive(Resource, Default)]
+pub struct ForkliftSchedule {
+    next_crossing: Option<Timer>,
+}
+
+fn random_crossing_wait(forklift_config: &ForkliftConfig, rng: &mut Rand) -> Timer {
+    let min_seconds = forklift_config
+        .min_interval_seconds
+        .min(forklift_config.max_interval_seconds);
+    let max_seconds = forklift_config
+        .min_interval_seconds
+        .max(forklift_config.max_interval_seconds);
+    Timer::from_seconds(rng.gen_range(min_seconds..=max_seconds), TimerMode::Once)
+}
+
+/// How far from the middle the forklift's centre is once it's wholly off either side.
+fn offstage_x(app_config: &AppConfig, forklift_config: &ForkliftConfig) -> f32 {
+    app_config.base_resolution.x as f32 / 2. + forklift_config.size.x / 2.
+}
+
+/// Marks out a lane once the wait is up, the forklift itself follows from `update_forklift`
+/// after the warning.
+pub fn spawn_forklift(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<Rand>,
+    mut schedule: ResMut<ForkliftSchedule>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    lane_query: Query<(), With<ForkliftLane>>,
+) {
+    let forklift_config = &game_config.forklift_config;
+    if !lane_query.is_empty()
+        || forklift_config.speed <= 0.
+        || forklift_config.lane_y_positions.is_empty()
+    {
+        return;
+    }
+    let next_crossing = schedule
+        .next_crossing
+        .get_or_insert_with(|| random_crossing_wait(forklift_config, &mut rng));
+    if !next_crossing.tick(time.delta()).finished() {
+        return;
+    }
+    *next_crossing = random_crossing_wait(forklift_config, &mut rng);
+
+    let lane_y =
+        forklift_config.lane_y_positions[rng.gen_range(0..forklift_config.lane_y_positions.len())];
+    let direction = if rng.gen_bool(0.5) { 1. } else { -1. };
+    let lane_width = app_config.base_resolution.x as f32;
+    let entry_x = -direction * (lane_width / 2. - BLINKER_SIZE);
+
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: STRIPE_COLOUR,
+                    custom_size: Some(Vec2::new(lane_width, forklift_config.size.y)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., lane_y, 0.)),
+                ..default()
+            },
+            ForkliftLane {
+                lane_y,
+                direction,
+                warning_timer: Timer::from_seconds(
+                    forklift_config.forklift_warning_seconds,
+                    TimerMode::Once,
+                ),
+                forklift: None,
+            },
+            RenderLayers::Single(EntityLayer::FloorDecal),
+            RoundScoped,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: BLINKER_COLOUR,
+                        custom_size: Some(Vec2::splat(BLINKER_SIZE)),
+                        ..default()
+                    },
+                    // the stripe is only lifted onto its layer when drawn, so the blinker sits
+                    // above the whole decal layer
+                    transform: Transform::from_translation(Vec3::new(entry_x, 0., 1.)),
+                    ..default()
+                },
+                ForkliftBlinker,
+            ));
+        });
+    debug!("forklift lane marked at y {} heading {}", lane_y, direction);
+}
+
+/// Flashes the blinker through the warning, then drives the forklift across. Anyone it runs into
+/// drops what they're holding, is stunned and is shoved clear of the lane. Lane and forklift are
+/// despawned together once it's off the far side.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_forklift(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    rapier_context: Res<RapierContext>,
+    arena_layout: Res<ArenaLayout>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    mut lane_query: Query<(Entity, &mut ForkliftLane, &Children)>,
+    mut blinker_query: Query<&mut Visibility, With<ForkliftBlinker>>,
+    mut forklift_query: Query<(Entity, &mut Transform, &mut Forklift)>,
+    mut player_query: Query<
+        (Entity, &mut Transform, Option<&Children>, Has<Stunned>),
+        (With<Player>, Without<Forklift>),
+    >,
+    mut package_query: Query<
+        (&mut Transform, &mut RenderLayers),
+        (With<Package>, Without<Player>, Without<Forklift>),
+    >,
+) {
+    let forklift_config = &game_config.forklift_config;
+    let offstage_x = offstage_x(&app_config, forklift_config);
+
+    for (lane_entity, mut lane, lane_children) in &mut lane_query {
+        lane.warning_timer.tick(time.delta());
+        let warning = !lane.warning_timer.finished();
+        let blinker_lit = warning && (lane.warning_timer.elapsed_secs() * BLINK_RATE).fract() < 0.5;
+        for child in lane_children {
+            if let Ok(mut visibility) = blinker_query.get_mut(*child) {
+                let wanted = if blinker_lit {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+                if *visibility != wanted {
+                    *visibility = wanted;
+                }
+            }
+        }
+        if warning || lane.forklift.is_some() {
+            continue;
+        }
+
+        let texture_pack = game_config.get_texture_pack();
+        let forklift_sprite = texture_pack.choose_texture_for(TextureTarget::Forklift, None);
+        let sprite_path = format!("{}/{}", texture_pack.root, forklift_sprite.path);
+        let forklift_entity = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(forklift_config.size),
+                        flip_x: lane.direction < 0.,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        -lane.direction * offstage_x,
+                        lane.lane_y,
+                        0.,
+                    )),
+                    texture: asset_server.load(&sprite_path),
+                    ..default()
+                },
+                Forklift {
+                    direction: lane.direction,
+                    lane: lane_entity,
+                    hit_players: Vec::new(),
+                },
+                TrackedTexture::new(
+                    TextureTarget::Forklift,
+                    format!("texture pack '{}'", game_config.selected_texture_pack),
+                    sprite_path,
+                ),
+                RenderLayers::Single(EntityLayer::Object),
+                RigidBody::KinematicPositionBased,
+                Collider::cuboid(forklift_config.size.x / 2., forklift_config.size.y / 2.),
+                RoundScoped,
+            ))
+            .id();
+        lane.forklift = Some(forklift_entity);
+        info!("forklift crossing at y {}", lane.lane_y);
+    }
+
+    let player_half_size = game_config.player_config.size / 2.;
+    let hit_area = Collider::cuboid(
+        forklift_config.size.x / 2. + HIT_MARGIN,
+        forklift_config.size.y / 2. + HIT_MARGIN,
+    );
+    for (forklift_entity, mut forklift_transform, mut forklift) in &mut forklift_query {
+        forklift_transform.translation.x +=
+            forklift.direction * forklift_config.speed * time.delta_seconds();
+        let forklift_pos = forklift_transform.translation.truncate();
+        if forklift_pos.x * forklift.direction > offstage_x {
+            commands.entity(forklift.lane).despawn_recursive();
+            commands.entity(forklift_entity).despawn_recursive();
+            debug!("forklift left the arena");
+            continue;
+        }
+
+        let mut hits = Vec::new();
+        rapier_context.intersections_with_shape(
+            forklift_pos,
+            0.,
+            &hit_area,
+            QueryFilter::default()
+                .exclude_collider(forklift_entity)
+                .exclude_sensors(),
+            |colliding_entity| {
+                if player_query.contains(colliding_entity)
+                    && !forklift.hit_players.contains(&colliding_entity)
+                {
+                    hits.push(colliding_entity);
+                }
+                true
+            },
+        );
+
+        for player_entity in hits {
+            forklift.hit_players.push(player_entity);
+            let Ok((_, mut player_transform, player_children, is_stunned)) =
+                player_query.get_mut(player_entity)
+            else {
+                continue;
+            };
+            let player_pos = player_transform.translation;
+
+            // whatever they were holding falls at their feet
+            for child in player_children.into_iter().flatten() {
+                if let Ok((mut held_transform, mut held_layers)) = package_query.get_mut(*child) {
+                    if let RenderLayers::Multi(layers) = held_layers.as_mut() {
+                        layers.remove(&EntityLayer::HeldObject);
+                    }
+                    commands.entity(*child).remove_parent();
+                    held_transform.translation = player_pos;
+                    activate_package_physics(&mut commands, *child, &game_config, Vec2::ZERO);
+                }
+            }
+
+            // knocked to whichever side of the lane they were already on
+            let side = if player_pos.y < forklift_pos.y {
+                -1.
+            } else {
+                1.
+            };
+            let clear_of_lane = Vec2::new(
+                player_pos.x,
+                forklift_pos.y
+                    + side * (forklift_config.size.y / 2. + player_half_size + HIT_MARGIN),
+            );
+            let clear_of_lane =
+                arena_layout.nearest_legal_position(clear_of_lane, player_half_size);
+            player_transform.translation.x = clear_of_lane.x;
+            player_transform.translation.y = clear_of_lane.y;
+
+            if !is_stunned {
+                stun_player(
+                    &mut commands,
+                    player_entity,
+                    forklift_config.stun_seconds,
+                    &game_config,
+                );
+            }
+            info!("{:?} was run into by the forklift", player_entity);
+        }
+    }
+}
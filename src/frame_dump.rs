@@ -0,0 +1,130 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*, render::view::screenshot::ScreenshotManager, time::TimeUpdateStrategy,
+    window::PrimaryWindow,
+};
+
+use crate::FrameDumpConfig;
+
+/// Writes the rendered frame to numbered PNGs every few fixed ticks. While dumping, time is
+/// stepped one fixed tick per rendered frame and held still until each capture has been read back,
+/// so a slow readback never skips a tick.
+#[derive(Resource)]
+pub struct FrameDump {
+    config: FrameDumpConfig,
+    tick: u64,
+    last_captured_tick: Option<u64>,
+    frame_index: u32,
+    pending_captures: Arc<AtomicUsize>,
+    finished: bool,
+}
+
+impl FrameDump {
+    pub fn new(config: FrameDumpConfig) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+        Ok(Self {
+            config,
+            tick: 0,
+            last_captured_tick: None,
+            frame_index: 0,
+            pending_captures: Arc::new(AtomicUsize::new(0)),
+            finished: false,
+        })
+    }
+
+    fn is_past_end(&self) -> bool {
+        self.config
+            .end_tick
+            .is_some_and(|end_tick| self.tick > end_tick)
+    }
+
+    fn wants_capture(&self) -> bool {
+        self.tick >= self.config.start_tick
+            && !self.is_past_end()
+            && (self.tick - self.config.start_tick)
+                .is_multiple_of(self.config.every_n_ticks.max(1) as u64)
+            && self.last_captured_tick != Some(self.tick)
+    }
+
+    fn frame_path(&self) -> PathBuf {
+        self.config
+            .directory
+            .join(format!("frame_{:06}.png", self.frame_index))
+    }
+}
+
+pub fn count_frame_dump_ticks(mut frame_dump: ResMut<FrameDump>) {
+    frame_dump.tick += 1;
+}
+
+pub fn capture_frame_dump(
+    mut frame_dump: ResMut<FrameDump>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+) {
+    if !frame_dump.wants_capture() {
+        return;
+    }
+    let Ok(window_entity) = window_query.get_single() else {
+        return;
+    };
+
+    let path = frame_dump.frame_path();
+    let pending_captures = frame_dump.pending_captures.clone();
+    pending_captures.fetch_add(1, Ordering::SeqCst);
+    let requested = screenshot_manager.take_screenshot(window_entity, move |image| {
+        match image.try_into_dynamic() {
+            Ok(image) => {
+                if let Err(error) = image.to_rgba8().save(&path) {
+                    error!("failed to write frame {}: {}", path.display(), error);
+                }
+            }
+            Err(error) => error!("failed to read back frame {}: {:?}", path.display(), error),
+        }
+        pending_captures.fetch_sub(1, Ordering::SeqCst);
+    });
+
+    if requested.is_err() {
+        // the previous capture hasn't been picked up yet, time is held so try again next frame
+        frame_dump.pending_captures.fetch_sub(1, Ordering::SeqCst);
+        return;
+    }
+    frame_dump.last_captured_tick = Some(frame_dump.tick);
+    frame_dump.frame_index += 1;
+}
+
+pub fn pace_frame_dump(
+    mut frame_dump: ResMut<FrameDump>,
+    fixed_time: Res<Time<Fixed>>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+    if frame_dump.finished {
+        return;
+    }
+    let pending_captures = frame_dump.pending_captures.load(Ordering::SeqCst);
+    if frame_dump.is_past_end() && pending_captures == 0 {
+        info!(
+            "frame dump finished, {} frames written to {}",
+            frame_dump.frame_index,
+            frame_dump.config.directory.display()
+        );
+        frame_dump.finished = true;
+        *time_update_strategy = TimeUpdateStrategy::Automatic;
+        return;
+    }
+
+    let step = if pending_captures > 0 || frame_dump.wants_capture() {
+        Duration::ZERO
+    } else {
+        fixed_time.timestep()
+    };
+    *time_update_strategy = TimeUpdateStrategy::ManualDuration(step);
+}
@@ -0,0 +1,107 @@
+use std::time::Instant;
+
+use bevy::{
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+    },
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{AppConfig, Conveyor, Package, Player};
+
+pub const FIXED_UPDATE_TIME: DiagnosticPath = DiagnosticPath::const_new("fixed_update");
+pub const PICKUP_PACKAGE_TIME: DiagnosticPath = DiagnosticPath::const_new("pickup_package");
+pub const UPDATE_CONVEYORS_TIME: DiagnosticPath = DiagnosticPath::const_new("update_conveyors");
+pub const SPAWN_PACKAGE_WAVE_TIME: DiagnosticPath = DiagnosticPath::const_new("spawn_package_wave");
+pub const PUSH_PACKAGES_TIME: DiagnosticPath = DiagnosticPath::const_new("push_packages");
+
+/// Every wall time diagnostic the game records, in milliseconds.
+pub const TIMED_SYSTEMS: [DiagnosticPath; 5] = [
+    FIXED_UPDATE_TIME,
+    PICKUP_PACKAGE_TIME,
+    UPDATE_CONVEYORS_TIME,
+    SPAWN_PACKAGE_WAVE_TIME,
+    PUSH_PACKAGES_TIME,
+];
+
+/// When each timed span started, and how many FixedUpdate ticks in a row have overrun.
+#[derive(Resource, Default)]
+pub struct SystemTimings {
+    started: HashMap<DiagnosticPath, Instant>,
+    overrun_ticks: u32,
+}
+
+/// Frame time plus the wall time of FixedUpdate as a whole, the heavy systems themselves are
+/// timed by chaining `start_timing` and `finish_timing` around them where they're scheduled.
+pub struct FrameTimingPlugin;
+
+impl Plugin for FrameTimingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .init_resource::<SystemTimings>()
+            .add_systems(FixedFirst, start_timing(FIXED_UPDATE_TIME))
+            .add_systems(FixedLast, finish_fixed_update_timing);
+        for path in TIMED_SYSTEMS {
+            app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+        }
+    }
+}
+
+/// Worst measurement still in the diagnostic's history, which is what the overlay sorts by.
+pub fn worst_recent_time(diagnostic: &Diagnostic) -> f64 {
+    diagnostic.values().copied().fold(0., f64::max)
+}
+
+/// Chained directly before the timed system so nothing else in the schedule runs in between.
+pub fn start_timing(path: DiagnosticPath) -> impl FnMut(ResMut<SystemTimings>) {
+    move |mut timings| {
+        timings.started.insert(path.clone(), Instant::now());
+    }
+}
+
+/// Chained directly after the timed system, records the milliseconds since `start_timing`.
+pub fn finish_timing(path: DiagnosticPath) -> impl FnMut(ResMut<SystemTimings>, Diagnostics) {
+    move |mut timings, mut diagnostics| {
+        if let Some(started) = timings.started.remove(&path) {
+            diagnostics.add_measurement(&path, || started.elapsed().as_secs_f64() * 1000.);
+        }
+    }
+}
+
+pub fn finish_fixed_update_timing(
+    app_config: Res<AppConfig>,
+    mut timings: ResMut<SystemTimings>,
+    mut diagnostics: Diagnostics,
+    entity_query: Query<(), ()>,
+    package_query: Query<(), With<Package>>,
+    conveyor_query: Query<(), With<Conveyor>>,
+    player_query: Query<(), With<Player>>,
+) {
+    let Some(started) = timings.started.remove(&FIXED_UPDATE_TIME) else {
+        return;
+    };
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.;
+    diagnostics.add_measurement(&FIXED_UPDATE_TIME, || elapsed_ms);
+
+    let frame_timing = &app_config.frame_timing;
+    if elapsed_ms <= frame_timing.overrun_threshold_ms as f64 {
+        timings.overrun_ticks = 0;
+        return;
+    }
+    timings.overrun_ticks += 1;
+    // warn once per run of slow ticks rather than every tick of it
+    if timings.overrun_ticks == frame_timing.overrun_ticks.max(1) {
+        warn!(
+            elapsed_ms,
+            threshold_ms = frame_timing.overrun_threshold_ms,
+            ticks = timings.overrun_ticks,
+            entities = entity_query.iter().count(),
+            packages = package_query.iter().count(),
+            conveyors = conveyor_query.iter().count(),
+            players = player_query.iter().count(),
+            "FixedUpdate has overrun its budget for {} ticks in a row",
+            timings.overrun_ticks
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+use bevy_rapier2d::{dynamics::Velocity, plugin::RapierContext};
+
+use crate::{
+    activate_package_physics, evict_oldest, BufferBudgets, EntityLayer, GameConfig, Metrics,
+    Package, Player, PlayerIndex, RenderLayers,
+};
+
+const STUN_HISTORY_CAP: usize = 32;
+
+/// Marks a package as still in flight from a throw, only these can stun.
+#[derive(Component)]
+pub struct ThrownBy {
+    pub player: PlayerIndex,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Stunned {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct StunIndicator;
+
+/// When this player's throws last stunned someone, used to escalate repeat offenders.
+#[derive(Component, Default)]
+pub struct FriendlyFireRecord {
+    stun_times: Vec<f32>,
+}
+
+impl FriendlyFireRecord {
+    fn recent_stuns(&mut self, now: f32, window_seconds: f32) -> usize {
+        self.stun_times
+            .retain(|stun_time| now - *stun_time <= window_seconds);
+        self.stun_times.len()
+    }
+}
+
+/// Leaves the player seeing stars for `stun_seconds`, `update_stuns` brings them round again.
+pub fn stun_player(
+    commands: &mut Commands,
+    player_entity: Entity,
+    stun_seconds: f32,
+    game_config: &GameConfig,
+) {
+    commands
+        .entity(player_entity)
+        .insert(Stunned {
+            timer: Timer::from_seconds(stun_seconds, TimerMode::Once),
+        })
+        .with_children(|builder| {
+            builder.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "* * *",
+                        TextStyle {
+                            font_size: 18.0,
+                            color: Color::YELLOW,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(Vec3::new(
+                        0.,
+                        game_config.player_config.size * 0.75,
+                        1.,
+                    )),
+                    ..default()
+                },
+                StunIndicator,
+            ));
+        });
+}
+
+pub fn expire_throw_windows(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut thrown_query: Query<(Entity, &mut ThrownBy)>,
+) {
+    for (package_entity, mut thrown_by) in &mut thrown_query {
+        thrown_by.timer.tick(time.delta());
+        if thrown_by.timer.finished() {
+            commands.entity(package_entity).remove::<ThrownBy>();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn detect_friendly_fire_hits(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    game_config: Res<GameConfig>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Player,
+            &Transform,
+            &mut FriendlyFireRecord,
+            Option<&Children>,
+            Has<Stunned>,
+        ),
+        Without<Package>,
+    >,
+    mut package_query: Query<
+        (
+            &mut Transform,
+            &mut RenderLayers,
+            Option<&ThrownBy>,
+            Option<&mut Velocity>,
+        ),
+        (With<Package>, Without<Player>),
+    >,
+    mut metrics: Option<ResMut<Metrics>>,
+) {
+    let friendly_fire_config = &game_config.friendly_fire_config;
+    if !friendly_fire_config.enabled {
+        return;
+    }
+
+    let mut hits = Vec::new();
+    for contact_pair in rapier_context
+        .contact_pairs()
+        .filter(|pair| pair.has_any_active_contacts())
+    {
+        for (package_entity, player_entity) in [
+            (contact_pair.collider1(), contact_pair.collider2()),
+            (contact_pair.collider2(), contact_pair.collider1()),
+        ] {
+            let Ok((_, _, Some(thrown_by), _)) = package_query.get(package_entity) else {
+                continue;
+            };
+            let Ok((_, player_info, _, _, _, is_stunned)) = player_query.get(player_entity) else {
+                continue;
+            };
+            // your own throws can't stun you, and someone already seeing stars stays as they are
+            if thrown_by.player != player_info.player_index
+                && !is_stunned
+                && !hits
+                    .iter()
+                    .any(|(_, hit_player, _)| *hit_player == player_entity)
+            {
+                hits.push((package_entity, player_entity, thrown_by.player));
+            }
+        }
+    }
+
+    for (package_entity, player_entity, thrower) in hits {
+        // the package stops dead rather than carrying on across the arena
+        if let Ok((_, _, _, Some(mut package_velocity))) = package_query.get_mut(package_entity) {
+            package_velocity.linvel *= friendly_fire_config.velocity_kept;
+        }
+        commands.entity(package_entity).remove::<ThrownBy>();
+
+        let Ok((_, player_info, player_transform, _, player_children, _)) =
+            player_query.get(player_entity)
+        else {
+            continue;
+        };
+        let victim = player_info.player_index;
+        let victim_pos = player_transform.translation;
+
+        // whatever they were holding falls at their feet
+        for child in player_children.into_iter().flatten() {
+            if let Ok((mut held_transform, mut held_layers, _, _)) = package_query.get_mut(*child) {
+                if let RenderLayers::Multi(layers) = held_layers.as_mut() {
+                    layers.remove(&EntityLayer::HeldObject);
+                }
+                commands.entity(*child).remove_parent();
+                held_transform.translation = victim_pos;
+                activate_package_physics(&mut commands, *child, &game_config, Vec2::ZERO);
+            }
+        }
+
+        stun_player(
+            &mut commands,
+            player_entity,
+            friendly_fire_config.stun_seconds,
+            &game_config,
+        );
+        info!("{:?} was stunned by a package from {:?}", victim, thrower);
+
+        if let Some((_, _, _, mut thrower_record, _, _)) = player_query
+            .iter_mut()
+            .find(|(_, player_info, _, _, _, _)| player_info.player_index == thrower)
+        {
+            thrower_record.stun_times.push(time.elapsed_seconds());
+        }
+
+        if let Some(metrics) = metrics.as_mut() {
+            metrics.per_player[victim].stunned += 1;
+            metrics.per_player[thrower].stuns_caused += 1;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_stuns(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut player_query: Query<(
+        Entity,
+        &mut Player,
+        &mut FriendlyFireRecord,
+        Option<&mut Stunned>,
+        Option<&Children>,
+    )>,
+    mut indicator_query: Query<&mut Transform, With<StunIndicator>>,
+    mut buffer_budgets: ResMut<BufferBudgets>,
+) {
+    let history_cap = buffer_budgets.cap_for::<f32>("stun_history", STUN_HISTORY_CAP);
+    let mut history_len = 0;
+    let mut history_evicted = 0;
+    let friendly_fire_config = &game_config.friendly_fire_config;
+    for (player_entity, mut player_info, mut record, stunned, player_children) in &mut player_query
+    {
+        if let Some(mut stunned) = stunned {
+            stunned.timer.tick(time.delta());
+            let recovered = stunned.timer.finished();
+            if recovered {
+                commands.entity(player_entity).remove::<Stunned>();
+            }
+            for child in player_children.into_iter().flatten() {
+                if let Ok(mut indicator_transform) = indicator_query.get_mut(*child) {
+                    if recovered {
+                        commands.entity(*child).despawn_recursive();
+                    } else {
+                        indicator_transform.rotation =
+                            Quat::from_rotation_z(time.elapsed_seconds() * 6.);
+                    }
+                }
+            }
+        }
+
+        history_evicted += evict_oldest(&mut record.stun_times, history_cap);
+        history_len += record.stun_times.len();
+
+        // every stun beyond the first inside the window makes the thrower wait longer before
+        // their next throw
+        let repeat_stuns = record
+            .recent_stuns(
+                time.elapsed_seconds(),
+                friendly_fire_config.escalation_window_seconds,
+            )
+            .saturating_sub(1);
+        let cooldown_seconds = game_config.player_config.pickup_cooldown_seconds
+            + repeat_stuns as f32 * friendly_fire_config.escalation_cooldown_seconds;
+        if player_info.pickup_cooldown_timer.duration().as_secs_f32() != cooldown_seconds {
+            player_info
+                .pickup_cooldown_timer
+                .set_duration(std::time::Duration::from_secs_f32(cooldown_seconds));
+        }
+    }
+    buffer_budgets.report("stun_history", history_len, history_evicted);
+}
@@ -1,22 +1,261 @@
 use bevy::prelude::*;
-use enum_map::EnumMap;
+use enum_map::{Enum, EnumMap};
+use serde::Serialize;
+use std::collections::VecDeque;
 
-use crate::{GameConfig, PlayerControls, PlayerIndex};
+use crate::{
+    CashOutCount, GameConfig, GameModeKind, MultiplierChanged, PlayerControls, PlayerIndex,
+    ScoreChanged, ScoreFloor, SessionStarted, StreakTier, StringKey, StringTable, WaveTimer,
+};
+
+const MAX_FREEZE_ICONS: usize = 3;
+const SCORE_RECONCILE_INTERVAL_SECONDS: f32 = 1.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreezeSource {
+    Delivery,
+    Jam,
+    Afk,
+    Shield,
+}
+
+impl FreezeSource {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            FreezeSource::Delivery => "+",
+            FreezeSource::Jam => "!",
+            FreezeSource::Afk => "z",
+            FreezeSource::Shield => "#",
+        }
+    }
+}
+
+/// Every effect currently holding a player's multiplier in place, the multiplier only decays
+/// once all of them have run out.
+#[derive(Default)]
+pub struct MultiplierFreeze {
+    entries: Vec<(FreezeSource, f32)>,
+    sources_changed: bool,
+}
+
+impl MultiplierFreeze {
+    /// Freezes for at least `duration_seconds`, a source that is already active is extended
+    /// rather than cut short.
+    pub fn freeze(&mut self, source: FreezeSource, duration_seconds: f32) {
+        if let Some((_, remaining)) = self
+            .entries
+            .iter_mut()
+            .find(|(active_source, _)| *active_source == source)
+        {
+            *remaining = remaining.max(duration_seconds);
+        } else {
+            self.entries.push((source, duration_seconds));
+            self.sources_changed = true;
+        }
+    }
+
+    pub fn unfreeze(&mut self, source: FreezeSource) {
+        let active_count = self.entries.len();
+        self.entries
+            .retain(|(active_source, _)| *active_source != source);
+        self.sources_changed |= self.entries.len() != active_count;
+    }
+
+    pub fn tick(&mut self, delta_seconds: f32) {
+        for (_, remaining) in &mut self.entries {
+            *remaining -= delta_seconds;
+        }
+        let active_count = self.entries.len();
+        self.entries.retain(|(_, remaining)| *remaining > 0.);
+        self.sources_changed |= self.entries.len() != active_count;
+    }
+
+    /// Whether a source started or ended since this was last asked.
+    pub fn take_sources_changed(&mut self) -> bool {
+        std::mem::take(&mut self.sources_changed)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    pub fn active_sources(&self) -> impl Iterator<Item = FreezeSource> + '_ {
+        self.entries.iter().map(|(source, _)| *source)
+    }
+}
+
+/// A player's recent deliveries, checked against the streak tiers each time another lands.
+///
+/// Tiers escalate within a run of quick deliveries, each one is awarded once as the run reaches
+/// it and only the highest is awarded when one delivery reaches several. Reaching the top tier
+/// ends the run so the next streak starts from scratch.
+#[derive(Default)]
+pub struct DeliveryStreak {
+    recent_deliveries: VecDeque<f32>,
+    awarded_tier: Option<usize>,
+    gain_factor: f32,
+    boost_remaining: f32,
+}
+
+impl DeliveryStreak {
+    /// Records a delivery made at `time_seconds` and returns the index of the tier it completes,
+    /// if any. A delivery exactly `window_seconds` after the first one still counts.
+    pub fn record_delivery(&mut self, time_seconds: f32, tiers: &[StreakTier]) -> Option<usize> {
+        let longest_window = tiers
+            .iter()
+            .map(|tier| tier.window_seconds)
+            .fold(0., f32::max);
+        let most_deliveries = tiers.iter().map(|tier| tier.deliveries).max().unwrap_or(0);
+        self.recent_deliveries.push_back(time_seconds);
+        while self.recent_deliveries.len() > most_deliveries
+            || self
+                .recent_deliveries
+                .front()
+                .is_some_and(|delivered_at| time_seconds - delivered_at > longest_window)
+        {
+            self.recent_deliveries.pop_front();
+        }
+
+        let rank = |tier_index: &usize| {
+            let tier = &tiers[*tier_index];
+            (tier.deliveries, tier.bonus_points.to_bits())
+        };
+        let reached = (0..tiers.len())
+            .filter(|tier_index| {
+                let tier = &tiers[*tier_index];
+                tier.deliveries > 0
+                    && self
+                        .recent_deliveries
+                        .iter()
+                        .filter(|delivered_at| time_seconds - *delivered_at <= tier.window_seconds)
+                        .count()
+                        >= tier.deliveries
+            })
+            .max_by_key(rank);
+        let Some(reached) = reached else {
+            // the run has gone cold, the next quick deliveries can earn every tier again
+            self.awarded_tier = None;
+            return None;
+        };
+        if self
+            .awarded_tier
+            .is_some_and(|awarded| rank(&awarded) >= rank(&reached))
+        {
+            return None;
+        }
+
+        let tier = &tiers[reached];
+        self.gain_factor = tier.multiplier_gain_factor;
+        self.boost_remaining = tier.boost_seconds;
+        if (0..tiers.len()).max_by_key(rank) == Some(reached) {
+            self.recent_deliveries.clear();
+            self.awarded_tier = None;
+        } else {
+            self.awarded_tier = Some(reached);
+        }
+        Some(reached)
+    }
+
+    /// How much faster deliveries currently raise the multiplier.
+    pub fn multiplier_gain_factor(&self) -> f32 {
+        if self.boost_remaining > 0. {
+            self.gain_factor
+        } else {
+            1.
+        }
+    }
+
+    pub fn tick(&mut self, delta_seconds: f32) {
+        self.boost_remaining = (self.boost_remaining - delta_seconds).max(0.);
+    }
+}
+
+/// Score kept in whole hundredths of a point so totals add up exactly, however many deliveries
+/// went into them. Only goes below zero when the score floor allows it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Score(i64);
+
+impl Score {
+    const CENTI_POINTS_PER_POINT: i64 = 100;
+
+    pub const ZERO: Score = Score(0);
+
+    pub fn from_points(points: f64) -> Self {
+        Self((points * Self::CENTI_POINTS_PER_POINT as f64).round() as i64)
+    }
+
+    /// Whole points, what the players see, rounded down so any score below zero shows as
+    /// negative.
+    pub fn points(&self) -> i64 {
+        self.0.div_euclid(Self::CENTI_POINTS_PER_POINT)
+    }
+
+    /// Adds `base_points` scaled by `multiplier`, rounded to the nearest centi-point, and returns
+    /// how much was added.
+    pub fn add_with_multiplier(&mut self, base_points: f32, multiplier: f32) -> Score {
+        let added = Score::from_points((base_points * multiplier) as f64);
+        self.0 = self.0.saturating_add(added.0);
+        added
+    }
+
+    /// Takes `penalty` off without going below `floor`, a score already under the floor isn't
+    /// raised to it. Returns how much was actually taken.
+    pub fn apply_penalty(&mut self, penalty: Score, floor: ScoreFloor) -> Score {
+        let before = self.0;
+        let penalised = self.0.saturating_sub(penalty.0.max(0));
+        self.0 = match floor {
+            ScoreFloor::Floor(floor_points) => {
+                penalised.max(Score::from_points(floor_points).0.min(before))
+            }
+            ScoreFloor::AllowNegative => penalised,
+        };
+        Score(before - self.0)
+    }
+
+    /// Part of the way from `self` to `to`, for readouts that count up rather than jump.
+    pub fn lerp(self, to: Score, t: f32) -> Score {
+        Score(self.0 + ((to.0 - self.0) as f64 * t as f64).round() as i64)
+    }
+
+    /// The team total is the sum of what each player sees, so it can never disagree with them.
+    pub fn team_total<'a>(scores: impl IntoIterator<Item = &'a Score>) -> i64 {
+        scores.into_iter().map(Score::points).sum()
+    }
+}
+
+impl std::ops::AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        self.0 = self.0.saturating_add(other.0);
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.points())
+    }
+}
 
 pub struct PlayerScoreData {
-    pub score: f32,
+    pub score: Score,
     pub multiplier: f32,
-    pub multiplier_decrement_freeze_timer: Timer,
+    pub multiplier_freeze: MultiplierFreeze,
+    pub deliveries: u32,
+    /// The multiplier as of the last `MultiplierChanged`.
+    pub reported_multiplier: f32,
+    pub streak: DeliveryStreak,
+    /// Seconds until the multiplier can be cashed out again.
+    pub cash_out_cooldown: f32,
+    pub cash_out_count: Option<CashOutCount>,
 }
 
 #[derive(Resource)]
 pub struct GameState {
     pub player_scores: EnumMap<PlayerIndex, PlayerScoreData>,
-    pub package_wave_timer: Timer,
+    pub package_wave_timer: WaveTimer,
     pub player_controls: EnumMap<PlayerIndex, PlayerControls>,
 }
 
-#[derive(Component)]
+#[derive(Component, Debug)]
 pub enum PlayerScoreTag {
     All,
     Player(PlayerIndex),
@@ -25,56 +264,613 @@ pub enum PlayerScoreTag {
 impl Default for PlayerScoreData {
     fn default() -> Self {
         Self {
-            score: 0.,
+            score: Score::ZERO,
             multiplier: 1.,
-            multiplier_decrement_freeze_timer: Timer::from_seconds(2., TimerMode::Once),
+            multiplier_freeze: MultiplierFreeze::default(),
+            deliveries: 0,
+            reported_multiplier: 1.,
+            streak: DeliveryStreak::default(),
+            cash_out_cooldown: 0.,
+            cash_out_count: None,
+        }
+    }
+}
+
+impl PlayerScoreData {
+    /// The multiplier as the readout shows it, in tenths and hidden at 1.
+    fn displayed_multiplier(&self) -> Option<i32> {
+        (self.multiplier > 1.).then(|| (self.multiplier * 10.).round() as i32)
+    }
+
+    /// The score on the readout, which trails the real one while a cash out counts up.
+    fn shown_score(&self) -> Score {
+        self.cash_out_count.as_ref().map_or(self.score, |count| {
+            count.from_score.lerp(self.score, count.progress())
+        })
+    }
+
+    /// The multiplier on the readout, shrinking back down while a cash out counts up.
+    fn shown_multiplier(&self) -> f32 {
+        self.cash_out_count
+            .as_ref()
+            .map_or(self.multiplier, |count| {
+                count.from_multiplier + (self.multiplier - count.from_multiplier) * count.progress()
+            })
+    }
+}
+
+/// How a round came out under the mode it was played in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RoundResult {
+    Team { total: i64 },
+    Winner(PlayerIndex),
+    Draw,
+}
+
+impl GameModeKind {
+    /// Players whose multiplier a delivery by `player_index` feeds. Team mode moves everyone's
+    /// together, which keeps them as one shared team multiplier.
+    pub fn multiplier_recipients(&self, player_index: PlayerIndex) -> Vec<PlayerIndex> {
+        match self {
+            GameModeKind::Team => (0..PlayerIndex::LENGTH)
+                .map(PlayerIndex::from_usize)
+                .collect(),
+            GameModeKind::Versus => vec![player_index],
+        }
+    }
+
+    /// Players who may place straight onto `player_index`'s outgoing belts.
+    pub fn can_place_on_belt_of(&self, placer: PlayerIndex, belt_owner: PlayerIndex) -> bool {
+        match self {
+            GameModeKind::Team => true,
+            GameModeKind::Versus => placer == belt_owner,
+        }
+    }
+
+    /// How evenly waves are shared out when the config doesn't say, Team keeps the pressure
+    /// level while Versus lets one side get swamped.
+    pub fn default_wave_fairness(&self) -> f32 {
+        match self {
+            GameModeKind::Team => 1.,
+            GameModeKind::Versus => 0.25,
+        }
+    }
+
+    /// Whether players block each other when the config doesn't say, getting in the way is part
+    /// of Versus but only a nuisance to a team.
+    pub fn default_players_collide(&self) -> bool {
+        match self {
+            GameModeKind::Team => false,
+            GameModeKind::Versus => true,
+        }
+    }
+
+    /// Whether an idle player's partner is nudged towards them, pointing a rival at free
+    /// packages would only hand them away.
+    pub fn nudges_idle_partners(&self) -> bool {
+        match self {
+            GameModeKind::Team => true,
+            GameModeKind::Versus => false,
+        }
+    }
+
+    /// Label for the readout shared between the players.
+    pub fn overall_label(&self) -> StringKey {
+        match self {
+            GameModeKind::Team => StringKey::TeamScoreLabel,
+            GameModeKind::Versus => StringKey::LeadLabel,
+        }
+    }
+}
+
+impl GameState {
+    /// Fresh scores and controls for every player, with the wave countdown at its configured
+    /// length.
+    pub fn from_config(game_config: &GameConfig) -> Self {
+        Self {
+            player_scores: EnumMap::from_fn(|_| PlayerScoreData::default()),
+            package_wave_timer: WaveTimer::new(game_config.package_config.wave_delay_seconds),
+            player_controls: EnumMap::from_fn(|_| PlayerControls::default()),
+        }
+    }
+
+    pub fn round_result(&self, game_mode: GameModeKind) -> RoundResult {
+        match game_mode {
+            GameModeKind::Team => RoundResult::Team {
+                total: Score::team_total(self.player_scores.values().map(|data| &data.score)),
+            },
+            GameModeKind::Versus => {
+                // on the points the readout shows, so a round never looks tied but isn't
+                let mut ranked = self
+                    .player_scores
+                    .iter()
+                    .map(|(player_index, data)| {
+                        ((data.score.points(), data.deliveries), player_index)
+                    })
+                    .collect::<Vec<_>>();
+                ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+                match ranked.as_slice() {
+                    [(best, winner), (runner_up, _), ..] if best != runner_up => {
+                        RoundResult::Winner(*winner)
+                    }
+                    [(_, winner)] => RoundResult::Winner(*winner),
+                    _ => RoundResult::Draw,
+                }
+            }
         }
     }
+
+    pub fn freeze_multiplier(
+        &mut self,
+        player_index: PlayerIndex,
+        source: FreezeSource,
+        duration_seconds: f32,
+    ) {
+        self.player_scores[player_index]
+            .multiplier_freeze
+            .freeze(source, duration_seconds);
+    }
 }
 
 pub fn update_score_multipiers(
     mut game_state: ResMut<GameState>,
     time: Res<Time>,
     game_config: Res<GameConfig>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut multiplier_events: EventWriter<MultiplierChanged>,
 ) {
-    for (_, player_data) in &mut game_state.player_scores {
-        player_data
-            .multiplier_decrement_freeze_timer
-            .tick(time.delta());
-        if player_data.multiplier_decrement_freeze_timer.finished() {
+    for (player_index, player_data) in &mut game_state.player_scores {
+        let shown_multiplier = player_data.displayed_multiplier();
+        player_data.multiplier_freeze.tick(time.delta_seconds());
+        player_data.streak.tick(time.delta_seconds());
+        player_data.cash_out_cooldown =
+            (player_data.cash_out_cooldown - time.delta_seconds()).max(0.);
+        let counting_cash_out = player_data.cash_out_count.is_some();
+        if let Some(count) = player_data.cash_out_count.as_mut() {
+            count.elapsed_seconds += time.delta_seconds();
+            if count.progress() >= 1. {
+                player_data.cash_out_count = None;
+            }
+        }
+        if !player_data.multiplier_freeze.is_frozen() {
             player_data.multiplier = (player_data.multiplier
                 - game_config.score_config.multiplier_decrease_per_second * time.delta_seconds())
             .clamp(1., f32::INFINITY);
         }
+
+        // the readout only shows tenths, decay between them isn't worth a notification
+        if player_data.multiplier_freeze.take_sources_changed()
+            || player_data.displayed_multiplier() != shown_multiplier
+            || counting_cash_out
+        {
+            score_events.send(ScoreChanged {
+                player: player_index,
+            });
+        }
+
+        // deliveries and penalties move the multiplier elsewhere, they're all picked up here
+        if (player_data.multiplier * 10.).round() != (player_data.reported_multiplier * 10.).round()
+        {
+            multiplier_events.send(MultiplierChanged {
+                player: player_index,
+                old: player_data.reported_multiplier,
+                new: player_data.multiplier,
+            });
+            player_data.reported_multiplier = player_data.multiplier;
+        }
+    }
+}
+
+/// Streaks belong to the round they were made in, a new session starts everyone's from scratch.
+pub fn reset_delivery_streaks(
+    mut session_events: EventReader<SessionStarted>,
+    mut game_state: ResMut<GameState>,
+) {
+    if session_events.read().count() == 0 {
+        return;
+    }
+    for player_data in game_state.player_scores.values_mut() {
+        player_data.streak = DeliveryStreak::default();
+    }
+}
+
+fn format_player_score(player_score: &PlayerScoreData, show_multiplier: bool) -> String {
+    let multiplier = player_score.shown_multiplier();
+    if show_multiplier && multiplier > 1. {
+        let freeze_icons = player_score
+            .multiplier_freeze
+            .active_sources()
+            .take(MAX_FREEZE_ICONS)
+            .map(|source| source.icon())
+            .collect::<String>();
+        format!(
+            "{} [x{:.1}]{}",
+            player_score.shown_score(),
+            multiplier,
+            freeze_icons
+        )
+    } else {
+        player_score.shown_score().to_string()
     }
 }
 
+/// The team total with the shared multiplier in team mode, or who's ahead and by how much in
+/// versus.
+fn format_overall_score(
+    game_state: &GameState,
+    game_mode: GameModeKind,
+    string_table: &StringTable,
+) -> String {
+    let scores = &game_state.player_scores;
+    match game_mode {
+        GameModeKind::Team => {
+            let shown_scores = scores
+                .values()
+                .map(PlayerScoreData::shown_score)
+                .collect::<Vec<_>>();
+            let total = Score::team_total(&shown_scores);
+            let multiplier = scores
+                .values()
+                .map(PlayerScoreData::shown_multiplier)
+                .fold(1., f32::max);
+            if multiplier > 1. {
+                format!("{} [x{:.1}]", total, multiplier)
+            } else {
+                total.to_string()
+            }
+        }
+        GameModeKind::Versus => {
+            let mut ranked = scores
+                .iter()
+                .map(|(player_index, data)| (data.score.points(), player_index))
+                .collect::<Vec<_>>();
+            ranked.sort_by(|(a, _), (b, _)| b.cmp(a));
+            match ranked.as_slice() {
+                [(best, leader), (runner_up, _), ..] if best != runner_up => {
+                    format!("P{} +{}", leader.into_usize() + 1, best - runner_up)
+                }
+                _ => string_table.tr(StringKey::TiedValue).to_string(),
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_scores(
-    game_state: ResMut<GameState>,
-    mut score_query: Query<(&mut Text, &PlayerScoreTag)>,
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    string_table: Res<StringTable>,
+    mut score_events: EventReader<ScoreChanged>,
+    mut score_query: Query<(&mut Text, Ref<PlayerScoreTag>)>,
+    mut warned_missing_section: Local<bool>,
+    mut seconds_since_reconcile: Local<f32>,
 ) {
+    let mut changed_players = EnumMap::<PlayerIndex, bool>::default();
+    for event in score_events.read() {
+        changed_players[event.player] = true;
+    }
+
+    // anything missed, e.g. a reset that didn't announce itself, is caught up here
+    *seconds_since_reconcile += time.delta_seconds();
+    let reconcile = *seconds_since_reconcile >= SCORE_RECONCILE_INTERVAL_SECONDS;
+    if reconcile {
+        *seconds_since_reconcile = 0.;
+    }
+
     for (mut score, tag) in score_query.iter_mut() {
-        match tag {
+        let needs_update = reconcile
+            || tag.is_added()
+            || match *tag {
+                PlayerScoreTag::All => changed_players.values().any(|changed| *changed),
+                PlayerScoreTag::Player(player_index) => changed_players[player_index],
+            };
+        if !needs_update {
+            continue;
+        }
+
+        let value = match *tag {
             PlayerScoreTag::All => {
-                score.sections[1].value = (game_state
-                    .player_scores
-                    .iter()
-                    .fold(0., |acc, (_, p)| acc + p.score)
-                    .floor() as u64)
-                    .to_string();
+                format_overall_score(&game_state, game_config.game_mode, &string_table)
+            }
+            // the shared multiplier is shown once on the team readout
+            PlayerScoreTag::Player(player_index) => format_player_score(
+                &game_state.player_scores[player_index],
+                game_config.game_mode == GameModeKind::Versus,
+            ),
+        };
+
+        // the value lives in the second section, after the label
+        if score.sections.get(1).is_none() {
+            if !*warned_missing_section {
+                warn!(
+                    "Score text for {:?} has no value section, skipping it",
+                    *tag
+                );
+                *warned_missing_section = true;
+            }
+            continue;
+        }
+        // only touch the text when it differs, so an unchanged readout isn't laid out again
+        if score.sections[1].value != value {
+            score.sections[1].value = value;
+        }
+
+        // an away player's readout is greyed out until they're back
+        if let PlayerScoreTag::Player(player_index) = *tag {
+            let colour = if game_state.player_controls[player_index].afk {
+                Color::GRAY
+            } else {
+                *game_config.player_config.per_player[player_index].colour
+            };
+            for section in score.sections.iter_mut() {
+                if section.style.color != colour {
+                    section.style.color = colour;
+                }
             }
-            PlayerScoreTag::Player(player_index) => {
-                score.sections[1].value = if game_state.player_scores[*player_index].multiplier > 1.
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::*;
+
+    #[test]
+    fn team_total_adds_up_after_many_deliveries() {
+        let mut rng = Rand::new(&Some(2058));
+        let mut scores = EnumMap::<PlayerIndex, Score>::default();
+        let mut added_up = EnumMap::<PlayerIndex, i64>::default();
+        for _ in 0..10_000 {
+            let player_index = PlayerIndex::from_usize(rng.gen_range(0..PlayerIndex::LENGTH));
+            let base_points = rng.gen_range(0.5..20.);
+            let multiplier = rng.gen_range(1. ..8.);
+            let added = scores[player_index].add_with_multiplier(base_points, multiplier);
+            assert!(added > Score::ZERO);
+            added_up[player_index] += added.0;
+        }
+
+        for (player_index, score) in scores.iter() {
+            // nothing lost to rounding between deliveries
+            assert_eq!(score.0, added_up[player_index], "{:?}", player_index);
+        }
+        let shown_total = scores
+            .values()
+            .map(|score| score.to_string().parse::<i64>().unwrap())
+            .sum::<i64>();
+        assert_eq!(Score::team_total(scores.values()), shown_total);
+    }
+
+    #[test]
+    fn penalty_stops_at_the_floor() {
+        let mut score = Score::from_points(10.);
+        let taken = score.apply_penalty(Score::from_points(25.), ScoreFloor::Floor(0.));
+        assert_eq!(score, Score::ZERO);
+        assert_eq!(taken, Score::from_points(10.));
+
+        let mut score = Score::from_points(10.);
+        score.apply_penalty(Score::from_points(25.), ScoreFloor::AllowNegative);
+        assert_eq!(score.points(), -15);
+    }
+
+    #[test]
+    fn negative_scores_never_wrap() {
+        let mut score = Score::from_points(3.);
+        score.apply_penalty(Score::from_points(1_000_000.), ScoreFloor::AllowNegative);
+        assert_eq!(score.to_string(), "-999997");
+        assert_ne!(score.to_string(), u64::MAX.to_string());
+
+        // a fraction below zero still reads as negative rather than rounding up to 0
+        let mut score = Score::ZERO;
+        score.apply_penalty(Score::from_points(0.25), ScoreFloor::AllowNegative);
+        assert_eq!(score.points(), -1);
+
+        // penalties past the range saturate instead of wrapping round to a huge score
+        let mut score = Score::from_points(-10.);
+        score.apply_penalty(Score::from_points(f64::MAX), ScoreFloor::AllowNegative);
+        assert!(score.points() < 0);
+        let mut score = Score::from_points(10.);
+        score.apply_penalty(Score::from_points(f64::MAX), ScoreFloor::Floor(0.));
+        assert_eq!(score, Score::ZERO);
+    }
+
+    const FREEZE_STEP: f32 = 0.125;
+
+    /// Plays `freezes` as (start, source, seconds) on one player's multiplier, checking each step
+    /// that it's frozen exactly while some freeze covers that moment. Returns when it thawed.
+    fn play_freezes(freezes: &[(f32, FreezeSource, f32)]) -> f32 {
+        let mut freeze = MultiplierFreeze::default();
+        let last_expiry = freezes
+            .iter()
+            .map(|(start, _, seconds)| start + seconds)
+            .fold(0., f32::max);
+        let mut time_seconds = 0.;
+        while time_seconds <= last_expiry {
+            for (_, source, seconds) in freezes
+                .iter()
+                .filter(|(start, _, _)| *start == time_seconds)
+            {
+                freeze.freeze(*source, *seconds);
+            }
+            let covered = freezes.iter().any(|(start, _, seconds)| {
+                *start <= time_seconds && time_seconds < start + seconds
+            });
+            assert_eq!(
+                freeze.is_frozen(),
+                covered,
+                "{:?} at {}s",
+                freezes,
+                time_seconds
+            );
+            if !freeze.is_frozen() && time_seconds >= last_expiry {
+                return time_seconds;
+            }
+            freeze.tick(FREEZE_STEP);
+            time_seconds += FREEZE_STEP;
+        }
+        time_seconds
+    }
+
+    #[test]
+    fn overlapping_sources_freeze_for_their_union() {
+        use FreezeSource::*;
+        assert_eq!(play_freezes(&[(0., Delivery, 2.), (1., Jam, 3.)]), 4.);
+        assert_eq!(play_freezes(&[(0., Jam, 4.), (1., Delivery, 1.)]), 4.);
+        assert_eq!(play_freezes(&[(0., Afk, 1.), (0., Shield, 1.5)]), 1.5);
+    }
+
+    #[test]
+    fn same_source_reapplied_extends_but_never_shortens() {
+        use FreezeSource::*;
+        assert_eq!(play_freezes(&[(0., Delivery, 2.), (1., Delivery, 2.)]), 3.);
+        assert_eq!(play_freezes(&[(0., Delivery, 2.), (1., Delivery, 0.5)]), 2.);
+
+        let mut freeze = MultiplierFreeze::default();
+        freeze.freeze(Delivery, 2.);
+        assert!(freeze.take_sources_changed());
+        freeze.freeze(Delivery, 3.);
+        assert!(!freeze.take_sources_changed());
+        assert_eq!(freeze.active_sources().collect::<Vec<_>>(), [Delivery]);
+    }
+
+    #[test]
+    fn decay_resumes_at_the_last_expiry() {
+        use FreezeSource::*;
+        // (start, source, seconds) for every pairing of sources and ways of overlapping
+        let sources = [Delivery, Jam, Afk, Shield];
+        let overlaps = [
+            // disjoint, with a gap between
+            ((0., 1.), (2., 1.), 3.),
+            // touching end to start
+            ((0., 1.), (1., 1.), 2.),
+            // second inside the first
+            ((0., 3.), (1., 1.), 3.),
+            // staggered
+            ((0., 2.), (1., 2.), 3.),
+            // starting together
+            ((0., 1.5), (0., 2.5), 2.5),
+        ];
+        for first_source in sources {
+            for second_source in sources {
+                for ((first_start, first_seconds), (second_start, second_seconds), thaw) in overlaps
                 {
-                    format!(
-                        "{} [x{:.1}]",
-                        game_state.player_scores[*player_index].score as u64,
-                        game_state.player_scores[*player_index].multiplier
-                    )
-                } else {
-                    (game_state.player_scores[*player_index].score as u64).to_string()
-                };
+                    let freezes = [
+                        (first_start, first_source, first_seconds),
+                        (second_start, second_source, second_seconds),
+                    ];
+                    assert_eq!(play_freezes(&freezes), thaw, "{:?}", freezes);
+                }
             }
         }
     }
+
+    #[test]
+    fn versus_tie_on_points_goes_to_deliveries() {
+        let mut game_state = GameState::from_config(&GameConfig::default());
+        let scores = &mut game_state.player_scores;
+        scores[PlayerIndex::Player1].score = Score::from_points(42.75);
+        scores[PlayerIndex::Player1].deliveries = 4;
+        scores[PlayerIndex::Player2].score = Score::from_points(42.1);
+        scores[PlayerIndex::Player2].deliveries = 5;
+        assert_eq!(
+            game_state.round_result(GameModeKind::Versus),
+            RoundResult::Winner(PlayerIndex::Player2)
+        );
+
+        game_state.player_scores[PlayerIndex::Player2].deliveries = 4;
+        assert_eq!(
+            game_state.round_result(GameModeKind::Versus),
+            RoundResult::Draw
+        );
+
+        game_state.player_scores[PlayerIndex::Player2].score = Score::from_points(43.);
+        assert_eq!(
+            game_state.round_result(GameModeKind::Versus),
+            RoundResult::Winner(PlayerIndex::Player2)
+        );
+    }
+
+    fn streak_tiers() -> Vec<StreakTier> {
+        vec![
+            StreakTier {
+                deliveries: 3,
+                window_seconds: 5.,
+                bonus_points: 10.,
+                multiplier_gain_factor: 1.5,
+                boost_seconds: 3.,
+            },
+            StreakTier {
+                deliveries: 5,
+                window_seconds: 8.,
+                bonus_points: 25.,
+                multiplier_gain_factor: 2.,
+                boost_seconds: 3.,
+            },
+        ]
+    }
+
+    fn record_all(streak: &mut DeliveryStreak, times: &[f32]) -> Vec<Option<usize>> {
+        let tiers = streak_tiers();
+        times
+            .iter()
+            .map(|time_seconds| streak.record_delivery(*time_seconds, &tiers))
+            .collect()
+    }
+
+    #[test]
+    fn streak_needs_its_deliveries_inside_the_window() {
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 1., 2.]),
+            [None, None, Some(0)]
+        );
+
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(record_all(&mut streak, &[0., 3., 6.]), [None, None, None]);
+    }
+
+    #[test]
+    fn streak_delivery_on_the_window_boundary_counts() {
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 2.5, 5.]),
+            [None, None, Some(0)]
+        );
+
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 2.5, 5.25]),
+            [None, None, None]
+        );
+    }
+
+    #[test]
+    fn streak_tiers_are_awarded_once_per_run() {
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 1., 2., 3., 4.]),
+            [None, None, Some(0), None, Some(1)]
+        );
+        assert_eq!(streak.multiplier_gain_factor(), 2.);
+        streak.tick(3.);
+        assert_eq!(streak.multiplier_gain_factor(), 1.);
+    }
+
+    #[test]
+    fn back_to_back_streaks_start_from_scratch() {
+        // the top tier ends the run, the deliveries that made it don't count towards the next
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 1., 2., 3., 4., 5., 6., 7.]),
+            [None, None, Some(0), None, Some(1), None, None, Some(0)]
+        );
+
+        // a run that goes cold can earn its tiers again
+        let mut streak = DeliveryStreak::default();
+        assert_eq!(
+            record_all(&mut streak, &[0., 1., 2., 20., 21., 22.]),
+            [None, None, Some(0), None, None, Some(0)]
+        );
+    }
 }
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    cash_out, check_for_delivered_packages, check_supervisor_can_see_players,
+    collect_packages_on_outgoing_conveyors, contain_players, convey_loose_packages,
+    decay_package_freshness, despawn_round_entities, detect_friendly_fire_hits,
+    drift_loose_packages, drive_player_brains, eject_dwelling_packages, expire_throw_windows,
+    finish_timing, gamepad_connected, handle_supervisor_contacts, move_player,
+    no_instance_conflict, not_in_tutorial, pickup_package, plan_package_wave, player_charge_throw,
+    player_start_position, push_packages, record_player_travel, repair_broken_conveyors,
+    resolve_interactions, separate_players, settle_packages, setup_package_pool,
+    spawn_hazard_zones, spawn_package_wave, start_first_session, start_timing, swap_player_sides,
+    throw_package, update_afk_players, update_attract_mode, update_belt_registry,
+    update_blinker_visuals, update_controller_mappings, update_conveyors, update_emotes,
+    update_knockbacks, update_match, update_stuns, update_supervisor, update_zone_occupancy,
+    AppConfig, ArenaLayout, BeltRegistry, ConveyorBroke, ConveyorCleared, ConveyorJammed,
+    ConveyorRepaired, CursorAim, EntityLayer, FrameTimingPlugin, GameConfig, InteractionFailed,
+    InteractionIntents, MatchState, MultiplierChanged, PackageConfiscated, PackageDelivered,
+    PackagePickedUp, PackagePool, PackageReturned, PackageSpawner, PackageThrown, PackageWentStale,
+    PadFallbackApplied, PlannedWave, PlayerCaught, PlayerIndex, PlayerSpawner, PowerupCollected,
+    PowerupExpired, PowerupSpawned, RenderLayers, RoundEnded, RoundStats, ScoreChanged,
+    SessionStarted, ShiftTint, SidesSwapped, SpawnContext, StreakAchieved, SupervisorHit,
+    SupervisorPhaseChanged, SupervisorStatus, TextureTarget, TrackedTexture, TutorialStepCompleted,
+    WaveModifiers, WaveSpawned, PICKUP_PACKAGE_TIME, PUSH_PACKAGES_TIME, SPAWN_PACKAGE_WAVE_TIME,
+    UPDATE_CONVEYORS_TIME,
+};
+
+/// The game itself: the arena and players spawned at startup, the events the game sends and every
+/// system played on the fixed tick. Shared by the game and anything driving a match headless.
+///
+/// Drawing, input sampling and the optional features are left to the app, as is physics, and so
+/// are the resources built from the config. `AppConfig`, `GameConfig`, `GameState`,
+/// `ArenaLayout`, `MatchState`, `AttractMode`, `Rand` and `BufferBudgets` have to be inserted
+/// before the app first updates, and input has to reach each player's `input_accumulators`
+/// before `update_controller_mappings` consumes it.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(FrameTimingPlugin)
+            .init_resource::<CursorAim>()
+            .init_resource::<RoundStats>()
+            .init_resource::<InteractionIntents>()
+            .init_resource::<BeltRegistry>()
+            .init_resource::<PlannedWave>()
+            .init_resource::<SupervisorStatus>()
+            .init_resource::<WaveModifiers>()
+            .init_resource::<PackagePool>()
+            .add_event::<PackageDelivered>()
+            .add_event::<PackagePickedUp>()
+            .add_event::<PackageThrown>()
+            .add_event::<PackageReturned>()
+            .add_event::<WaveSpawned>()
+            .add_event::<MultiplierChanged>()
+            .add_event::<InteractionFailed>()
+            .add_event::<PlayerCaught>()
+            .add_event::<PackageConfiscated>()
+            .add_event::<SessionStarted>()
+            .add_event::<SupervisorHit>()
+            .add_event::<StreakAchieved>()
+            .add_event::<ScoreChanged>()
+            .add_event::<SupervisorPhaseChanged>()
+            .add_event::<ConveyorJammed>()
+            .add_event::<ConveyorCleared>()
+            .add_event::<ConveyorBroke>()
+            .add_event::<ConveyorRepaired>()
+            .add_event::<SidesSwapped>()
+            .add_event::<PackageWentStale>()
+            .add_event::<PowerupSpawned>()
+            .add_event::<PowerupCollected>()
+            .add_event::<PowerupExpired>()
+            .add_event::<RoundEnded>()
+            .add_event::<TutorialStepCompleted>()
+            .add_event::<PadFallbackApplied>()
+            .add_systems(
+                Startup,
+                (
+                    setup_world,
+                    setup_package_pool.before(setup_players),
+                    setup_players,
+                    start_first_session,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_belt_registry,
+                    gamepad_connected,
+                    update_controller_mappings,
+                    (
+                        update_attract_mode,
+                        update_match,
+                        swap_player_sides,
+                        despawn_round_entities,
+                    )
+                        .chain()
+                        .run_if(not_in_tutorial),
+                    update_afk_players,
+                    drive_player_brains,
+                    (
+                        plan_package_wave,
+                        start_timing(SPAWN_PACKAGE_WAVE_TIME),
+                        spawn_package_wave,
+                        finish_timing(SPAWN_PACKAGE_WAVE_TIME),
+                    )
+                        .chain()
+                        .run_if(not_in_tutorial),
+                    (
+                        update_zone_occupancy,
+                        move_player,
+                        separate_players,
+                        contain_players,
+                    )
+                        .chain(),
+                    (
+                        (
+                            start_timing(PUSH_PACKAGES_TIME),
+                            push_packages,
+                            finish_timing(PUSH_PACKAGES_TIME),
+                        )
+                            .chain(),
+                        record_player_travel,
+                        decay_package_freshness,
+                        settle_packages,
+                    ),
+                    (
+                        start_timing(UPDATE_CONVEYORS_TIME),
+                        update_conveyors,
+                        finish_timing(UPDATE_CONVEYORS_TIME),
+                        update_blinker_visuals,
+                        repair_broken_conveyors,
+                    )
+                        .chain(),
+                    (convey_loose_packages, drift_loose_packages).chain(),
+                    (player_charge_throw, update_emotes).chain(),
+                    throw_package,
+                    expire_throw_windows,
+                    detect_friendly_fire_hits,
+                    (update_stuns, update_knockbacks),
+                    check_for_delivered_packages,
+                    eject_dwelling_packages,
+                    (
+                        update_supervisor,
+                        handle_supervisor_contacts,
+                        check_supervisor_can_see_players,
+                    )
+                        .chain(),
+                    // reads presses on the same tick they're consumed, so none is missed or seen
+                    // twice
+                    (
+                        (
+                            (
+                                start_timing(PICKUP_PACKAGE_TIME),
+                                pickup_package,
+                                finish_timing(PICKUP_PACKAGE_TIME),
+                            )
+                                .chain(),
+                            collect_packages_on_outgoing_conveyors,
+                        ),
+                        cash_out,
+                        resolve_interactions,
+                    )
+                        .chain(),
+                )
+                    .chain()
+                    .run_if(no_instance_conflict),
+            );
+    }
+}
+
+/// Both players, and a few packages on the floor between the first player and the belts to get
+/// started with.
+pub fn setup_players(
+    mut commands: Commands,
+    mut ctx: SpawnContext,
+    app_config: Res<AppConfig>,
+    match_state: Res<MatchState>,
+) {
+    let conveyor_width = ctx.game_config.conveyor_config.size.x;
+    let player_size = ctx.game_config.player_config.size;
+    let package_size = ctx.game_config.package_config.size;
+    let start_position = |player_index: PlayerIndex, game_config: &GameConfig| {
+        player_start_position(match_state.sides[player_index], &app_config, game_config)
+    };
+    PlayerSpawner::new(PlayerIndex::Player1)
+        .at(start_position(PlayerIndex::Player1, &ctx.game_config))
+        .spawn(&mut commands, &mut ctx);
+
+    for i in 0..5 {
+        PackageSpawner::new()
+            .at(Vec3::new(
+                -(app_config.base_resolution.x as f32 / 2.)
+                    + conveyor_width
+                    + (player_size / 2.)
+                    + 100.
+                    + package_size * 1.5 * i as f32,
+                0.,
+                0.,
+            ))
+            .spawn(&mut commands, &mut ctx);
+    }
+
+    PlayerSpawner::new(PlayerIndex::Player2)
+        .at(start_position(PlayerIndex::Player2, &ctx.game_config))
+        .spawn(&mut commands, &mut ctx);
+}
+
+/// The arena, laid out by `ArenaLayout`, with physics set up for a top down game.
+pub fn setup_world(
+    mut commands: Commands,
+    mut ctx: SpawnContext,
+    asset_server: Res<AssetServer>,
+    game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    arena_layout: Res<ArenaLayout>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    rapier_config.gravity = Vec2::ZERO;
+    // stepped by however much virtual time passed, so slow motion slows thrown packages along
+    // with everything else
+    rapier_config.timestep_mode = TimestepMode::Variable {
+        max_dt: 1. / 60.,
+        time_scale: 1.,
+        substeps: 1,
+    };
+    arena_layout.spawn(
+        &mut commands,
+        &mut ctx,
+        app_config.colour_palette.blinker_colours(),
+    );
+
+    spawn_hazard_zones(&mut commands, &game_config);
+
+    let conveyor_walkway = arena_layout.walkway;
+    commands.spawn((
+        RigidBody::KinematicPositionBased,
+        Sensor,
+        Collider::cuboid(
+            conveyor_walkway.width() / 2.,
+            conveyor_walkway.height() / 2.,
+        ),
+        TransformBundle {
+            local: Transform::from_translation(conveyor_walkway.center().extend(0.)),
+            ..default()
+        },
+        RenderLayers::Single(EntityLayer::Debugging),
+    ));
+
+    let texture_pack = game_config.get_texture_pack();
+    let (background_path, background_source) = if let Some(texture) =
+        &game_config.get_layout().background_override
+    {
+        (
+            texture.choose_texture(None).path.clone(),
+            format!("layout '{}'", game_config.selected_layout),
+        )
+    } else {
+        let background_sprite = texture_pack.choose_texture_for(TextureTarget::Background, None);
+        (
+            format!("{}/{}", texture_pack.root, background_sprite.path),
+            format!("texture pack '{}'", game_config.selected_texture_pack),
+        )
+    };
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(arena_layout.play_area.size()),
+                ..default()
+            },
+            transform: Transform {
+                translation: arena_layout.play_area.center().extend(0.),
+                ..default()
+            },
+            texture: asset_server.load(&background_path),
+            ..default()
+        },
+        RenderLayers::Single(EntityLayer::Background),
+        TrackedTexture::new(
+            TextureTarget::Background,
+            background_source,
+            background_path,
+        ),
+        ShiftTint,
+    ));
+}
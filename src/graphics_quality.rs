@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy_rapier2d::render::DebugRenderContext;
+
+use crate::{AppConfig, GraphicsQuality, SpriteLayerOptions};
+
+/// The quality in force, starts from `AppConfig::graphics_quality` and can be switched while
+/// playing. Effects check this rather than the config so a switch takes hold straight away.
+#[derive(Resource)]
+pub struct QualitySettings {
+    pub quality: GraphicsQuality,
+    low_quality_max_flashes: usize,
+}
+
+impl QualitySettings {
+    pub fn new(app_config: &AppConfig) -> Self {
+        Self {
+            quality: app_config.graphics_quality,
+            low_quality_max_flashes: app_config.low_quality_max_flashes,
+        }
+    }
+
+    pub fn full_effects(&self) -> bool {
+        self.quality == GraphicsQuality::High
+    }
+
+    /// Run condition for effects that are skipped entirely in low quality.
+    pub fn wants_full_effects(quality_settings: Res<QualitySettings>) -> bool {
+        quality_settings.full_effects()
+    }
+
+    pub fn max_screen_flashes(&self) -> usize {
+        match self.quality {
+            GraphicsQuality::High => usize::MAX,
+            GraphicsQuality::Low => self.low_quality_max_flashes,
+        }
+    }
+}
+
+pub fn toggle_graphics_quality(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    app_config: Res<AppConfig>,
+    mut quality_settings: ResMut<QualitySettings>,
+) {
+    if !keyboard_input.just_pressed(app_config.graphics_quality_toggle_key) {
+        return;
+    }
+    quality_settings.quality = match quality_settings.quality {
+        GraphicsQuality::High => GraphicsQuality::Low,
+        GraphicsQuality::Low => GraphicsQuality::High,
+    };
+    info!("Graphics quality is now {:?}", quality_settings.quality);
+}
+
+/// Brings the render side in line with the quality whenever it changes, y-sorting gives way to
+/// each layer's plain z and the collider outlines go with it.
+pub fn apply_graphics_quality(
+    app_config: Res<AppConfig>,
+    quality_settings: Res<QualitySettings>,
+    mut sprite_layer_options: ResMut<SpriteLayerOptions>,
+    debug_render_context: Option<ResMut<DebugRenderContext>>,
+) {
+    if !quality_settings.is_changed() {
+        return;
+    }
+    sprite_layer_options.y_sort = quality_settings.full_effects();
+    if let Some(mut debug_render_context) = debug_render_context {
+        debug_render_context.enabled =
+            app_config.debug_physics_render && quality_settings.full_effects();
+    }
+}
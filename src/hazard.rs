@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{EntityLayer, GameConfig, HazardKind, Package, Player, RenderLayers};
+
+#[derive(Component)]
+pub struct HazardZone {
+    pub kind: HazardKind,
+}
+
+/// The hazard zones a player is standing in, combined. Overlapping zones take the slowest speed
+/// and the least grip, while their drifts add up.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ZoneOccupancy {
+    pub speed_multiplier: f32,
+    /// How quickly movement catches up with the controls, `None` off slippery floors.
+    pub grip: Option<f32>,
+    pub drift: Vec2,
+}
+
+impl Default for ZoneOccupancy {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.,
+            grip: None,
+            drift: Vec2::ZERO,
+        }
+    }
+}
+
+impl ZoneOccupancy {
+    pub fn add(&mut self, kind: HazardKind) {
+        match kind {
+            HazardKind::Slippery { grip } => {
+                self.grip = Some(self.grip.map_or(grip, |current| current.min(grip)));
+            }
+            HazardKind::Sticky { speed_multiplier } => {
+                self.speed_multiplier = self.speed_multiplier.min(speed_multiplier);
+            }
+            HazardKind::ConveyorFloor { drift } => self.drift += drift,
+        }
+    }
+}
+
+impl HazardKind {
+    fn decal_colour(&self) -> Color {
+        match self {
+            HazardKind::Slippery { .. } => Color::rgba(0.6, 0.85, 1., 0.35),
+            HazardKind::Sticky { .. } => Color::rgba(0.45, 0.3, 0.1, 0.35),
+            HazardKind::ConveyorFloor { .. } => Color::rgba(1., 0.85, 0.2, 0.3),
+        }
+    }
+}
+
+pub fn spawn_hazard_zones(commands: &mut Commands, game_config: &GameConfig) {
+    for hazard in &game_config.hazards {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: hazard.kind.decal_colour(),
+                    custom_size: Some(hazard.size),
+                    ..default()
+                },
+                transform: Transform::from_translation(hazard.position.extend(0.)),
+                ..default()
+            },
+            Collider::cuboid(hazard.size.x / 2., hazard.size.y / 2.),
+            Sensor,
+            HazardZone { kind: hazard.kind },
+            RenderLayers::Single(EntityLayer::FloorDecal),
+        ));
+    }
+}
+
+pub fn update_zone_occupancy(
+    rapier_context: Res<RapierContext>,
+    game_config: Res<GameConfig>,
+    zone_query: Query<&HazardZone>,
+    mut player_query: Query<(Entity, &Transform, &mut ZoneOccupancy), With<Player>>,
+) {
+    let player_area = Collider::cuboid(
+        game_config.player_config.size / 2.,
+        game_config.player_config.size / 2.,
+    );
+    for (player_entity, player_transform, mut occupancy) in &mut player_query {
+        let mut new_occupancy = ZoneOccupancy::default();
+        rapier_context.intersections_with_shape(
+            player_transform.translation.truncate(),
+            0.,
+            &player_area,
+            QueryFilter::default().exclude_collider(player_entity),
+            |colliding_entity| {
+                if let Ok(zone) = zone_query.get(colliding_entity) {
+                    new_occupancy.add(zone.kind);
+                }
+                true
+            },
+        );
+        if *occupancy != new_occupancy {
+            *occupancy = new_occupancy;
+        }
+    }
+}
+
+/// Loose packages on a conveyor floor are carried along at least as fast as its drift.
+#[allow(clippy::type_complexity)]
+pub fn drift_loose_packages(
+    rapier_context: Res<RapierContext>,
+    zone_query: Query<(&HazardZone, &Collider, &GlobalTransform)>,
+    mut package_query: Query<&mut Velocity, (With<Package>, With<RigidBody>, Without<Parent>)>,
+) {
+    let mut package_drifts = HashMap::<Entity, Vec2>::new();
+    for (zone, zone_collider, zone_transform) in &zone_query {
+        let HazardKind::ConveyorFloor { drift } = zone.kind else {
+            continue;
+        };
+        rapier_context.intersections_with_shape(
+            zone_transform.translation().truncate(),
+            0.,
+            zone_collider,
+            QueryFilter::only_dynamic(),
+            |colliding_entity| {
+                if package_query.contains(colliding_entity) {
+                    *package_drifts.entry(colliding_entity).or_default() += drift;
+                }
+                true
+            },
+        );
+    }
+
+    for (package_entity, drift) in package_drifts {
+        let Ok(mut package_velocity) = package_query.get_mut(package_entity) else {
+            continue;
+        };
+        let drift_speed = drift.length();
+        if drift_speed <= 0. {
+            continue;
+        }
+        let drift_direction = drift / drift_speed;
+        let speed_along_drift = package_velocity.linvel.dot(drift_direction);
+        if speed_along_drift < drift_speed {
+            package_velocity.linvel += drift_direction * (drift_speed - speed_along_drift);
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppConfig, CameraFollow, EntityLayer, GameConfig, GameState, MainCamera, Package, Player,
+    PlayerAccent, PlayerIndex, RenderLayers,
+};
+
+#[derive(Component)]
+pub struct NudgeArrow;
+
+/// Keeps the colour a package had before the nudge so it can be put back afterwards.
+#[derive(Component)]
+pub struct IdleNudgeHighlight {
+    pub original_colour: Color,
+}
+
+pub fn make_nudge_arrow(size: f32) -> (SpriteBundle, RenderLayers, NudgeArrow) {
+    (
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(size * 0.6, size * 0.2)),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        RenderLayers::Single(EntityLayer::Accent),
+        NudgeArrow,
+    )
+}
+
+pub fn mix_colours(from: Color, to: Color, amount: f32) -> Color {
+    let from = from.as_linear_rgba_f32();
+    let to = to.as_linear_rgba_f32();
+    Color::rgba_linear(
+        from[0] + (to[0] - from[0]) * amount,
+        from[1] + (to[1] - from[1]) * amount,
+        from[2] + (to[2] - from[2]) * amount,
+        from[3] + (to[3] - from[3]) * amount,
+    )
+}
+
+/// Where a ray from `from` along `direction` leaves `view` once it's pulled in by `inset`, a
+/// `from` outside the view is clamped back onto it.
+pub fn view_edge_point(view: Rect, from: Vec2, direction: Vec2, inset: f32) -> Vec2 {
+    let inset_view = Rect::from_center_size(
+        view.center(),
+        (view.size() - Vec2::splat(inset * 2.)).max(Vec2::ZERO),
+    );
+    let distance_to_edge = |from: f32, direction: f32, min: f32, max: f32| {
+        if direction > 0. {
+            (max - from) / direction
+        } else if direction < 0. {
+            (min - from) / direction
+        } else {
+            f32::INFINITY
+        }
+    };
+    let distance = distance_to_edge(from.x, direction.x, inset_view.min.x, inset_view.max.x).min(
+        distance_to_edge(from.y, direction.y, inset_view.min.y, inset_view.max.y),
+    );
+    let distance = if distance.is_finite() {
+        distance.max(0.)
+    } else {
+        0.
+    };
+    (from + direction * distance).clamp(inset_view.min, inset_view.max)
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_idle_nudges(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    player_query: Query<(&Player, &GlobalTransform, Option<&Children>)>,
+    camera_query: Query<
+        (
+            &GlobalTransform,
+            &OrthographicProjection,
+            Option<&CameraFollow>,
+        ),
+        With<MainCamera>,
+    >,
+    mut accent_query: Query<
+        (&mut Sprite, &mut Transform),
+        (With<PlayerAccent>, Without<NudgeArrow>, Without<Package>),
+    >,
+    mut arrow_query: Query<
+        (&mut Sprite, &mut Transform, &mut Visibility),
+        (With<NudgeArrow>, Without<PlayerAccent>, Without<Package>),
+    >,
+    mut package_query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Sprite,
+            Option<&Parent>,
+            Option<&IdleNudgeHighlight>,
+        ),
+        (With<Package>, Without<PlayerAccent>, Without<NudgeArrow>),
+    >,
+) {
+    let nudge_config = &game_config.idle_nudge_config;
+    let pickup_radius = game_config.player_config.pickup_radius();
+
+    // a player is only nudged about when they are idle and there is something they could be doing
+    let mut idle_players: Vec<(PlayerIndex, Vec2)> = Vec::new();
+    let mut nudged_packages: Vec<(Entity, PlayerIndex)> = Vec::new();
+    if nudge_config.enabled && game_config.game_mode.nudges_idle_partners() {
+        for (player_info, player_transform, _) in &player_query {
+            let controls = &game_state.player_controls[player_info.player_index];
+            if controls.seconds_since_input < nudge_config.idle_seconds {
+                continue;
+            }
+
+            let player_pos = player_transform.translation().truncate();
+            let reachable_packages = package_query
+                .iter()
+                .filter(|(_, package_transform, _, package_parent, _)| {
+                    // packages already held by someone are not up for grabs
+                    package_parent.is_none_or(|parent| player_query.get(parent.get()).is_err())
+                        && package_transform
+                            .translation()
+                            .truncate()
+                            .distance(player_pos)
+                            <= pickup_radius
+                })
+                .map(|(package_entity, _, _, _, _)| (package_entity, player_info.player_index))
+                .collect::<Vec<_>>();
+
+            if !reachable_packages.is_empty() {
+                idle_players.push((player_info.player_index, player_pos));
+                nudged_packages.extend(reachable_packages);
+            }
+        }
+    }
+
+    let pulse = (time.elapsed_seconds() * nudge_config.pulse_speed).sin() * 0.5 + 0.5;
+    let afk_config = &game_config.afk_config;
+    let afk_flash_on = (time.elapsed_seconds() * afk_config.flash_speed).sin() > 0.;
+    for (player_info, player_transform, player_children) in &player_query {
+        let player_colour = game_config.player_config.per_player[player_info.player_index].colour;
+        let controls = &game_state.player_controls[player_info.player_index];
+        let is_idle = idle_players
+            .iter()
+            .any(|(player_index, _)| *player_index == player_info.player_index);
+        let afk_warning = afk_config.enabled
+            && !controls.afk
+            && controls.seconds_since_input >= afk_config.warning_seconds;
+
+        for child in player_children.into_iter().flatten() {
+            if let Ok((mut accent_sprite, mut accent_transform)) = accent_query.get_mut(*child) {
+                if controls.afk {
+                    accent_sprite.color = Color::GRAY.with_a(player_colour.a());
+                    accent_transform.scale = Vec3::ONE;
+                } else if afk_warning {
+                    // about to be marked away, a hard flash is harder to miss than the nudge pulse
+                    accent_sprite.color = if afk_flash_on {
+                        Color::WHITE
+                    } else {
+                        *player_colour
+                    };
+                    accent_transform.scale = Vec3::ONE;
+                } else if is_idle {
+                    accent_sprite.color = player_colour.with_a(0.4 + 0.6 * pulse);
+                    accent_transform.scale = Vec3::splat(1. + 0.25 * pulse);
+                } else {
+                    accent_sprite.color = *player_colour;
+                    accent_transform.scale = Vec3::ONE;
+                }
+            }
+
+            if let Ok((mut arrow_sprite, mut arrow_transform, mut arrow_visibility)) =
+                arrow_query.get_mut(*child)
+            {
+                let player_pos = player_transform.translation().truncate();
+                let nearest_idle_partner = idle_players
+                    .iter()
+                    .filter(|(player_index, _)| *player_index != player_info.player_index)
+                    .min_by(|a, b| {
+                        a.1.distance_squared(player_pos)
+                            .total_cmp(&b.1.distance_squared(player_pos))
+                    });
+
+                match nearest_idle_partner {
+                    Some((partner_index, partner_pos)) if !is_idle => {
+                        // sit on the edge of what the active player can see, pointing towards
+                        // their idle partner
+                        let direction = (*partner_pos - player_pos).normalize_or_zero();
+                        let view = camera_query
+                            .iter()
+                            .find(|(_, _, camera_follow)| {
+                                camera_follow.is_none_or(|camera_follow| {
+                                    camera_follow.player == player_info.player_index
+                                })
+                            })
+                            .map(|(camera_transform, projection, _)| {
+                                let camera_pos = camera_transform.translation().truncate();
+                                Rect::from_corners(
+                                    projection.area.min + camera_pos,
+                                    projection.area.max + camera_pos,
+                                )
+                            })
+                            .unwrap_or_else(|| {
+                                Rect::from_center_size(
+                                    Vec2::ZERO,
+                                    app_config.base_resolution.as_vec2(),
+                                )
+                            });
+                        let edge_pos = view_edge_point(
+                            view,
+                            player_pos,
+                            direction,
+                            game_config.player_config.size / 2.,
+                        );
+                        arrow_transform.translation = (edge_pos - player_pos).extend(0.);
+                        arrow_transform.rotation =
+                            Quat::from_rotation_z(direction.y.atan2(direction.x));
+                        arrow_sprite.color =
+                            *game_config.player_config.per_player[*partner_index].colour;
+                        *arrow_visibility = Visibility::Inherited;
+                    }
+                    _ => {
+                        *arrow_visibility = Visibility::Hidden;
+                    }
+                }
+            }
+        }
+    }
+
+    for (package_entity, _, mut package_sprite, _, highlight) in &mut package_query {
+        // placeholder packages are coloured rather than white, mix from whatever it had
+        let original_colour =
+            highlight.map_or(package_sprite.color, |highlight| highlight.original_colour);
+        if let Some((_, player_index)) = nudged_packages
+            .iter()
+            .find(|(nudged_entity, _)| *nudged_entity == package_entity)
+        {
+            package_sprite.color = mix_colours(
+                original_colour,
+                *game_config.player_config.per_player[*player_index].colour,
+                nudge_config.highlight_strength * (0.5 + 0.5 * pulse),
+            );
+            if highlight.is_none() {
+                commands
+                    .entity(package_entity)
+                    .insert(IdleNudgeHighlight { original_colour });
+            }
+        } else if highlight.is_some() {
+            package_sprite.color = original_colour;
+            commands
+                .entity(package_entity)
+                .remove::<IdleNudgeHighlight>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameModeKind, MoveAxis};
+
+    const PLACEHOLDER_COLOUR: Color = Color::rgb(0.8, 0.5, 0.2);
+    const VIEW: Rect = Rect {
+        min: Vec2::new(-400., -300.),
+        max: Vec2::new(400., 300.),
+    };
+
+    struct NudgeScene {
+        app: App,
+        packages: [Entity; 2],
+        arrows: [Entity; 2],
+    }
+
+    impl NudgeScene {
+        fn new(game_mode: GameModeKind) -> Self {
+            let mut game_config = GameConfig::default();
+            game_config.game_mode = game_mode;
+            let mut app = App::new();
+            app.init_resource::<Time>()
+                .insert_resource(GameState::from_config(&game_config))
+                .insert_resource(AppConfig::default())
+                .insert_resource(game_config)
+                .add_systems(Update, update_idle_nudges);
+            app.world.spawn((
+                MainCamera,
+                GlobalTransform::IDENTITY,
+                OrthographicProjection {
+                    area: VIEW,
+                    ..default()
+                },
+            ));
+
+            let mut packages = [Entity::PLACEHOLDER; 2];
+            let mut arrows = [Entity::PLACEHOLDER; 2];
+            for (index, (player_index, x)) in
+                [(PlayerIndex::Player1, -200.), (PlayerIndex::Player2, 200.)]
+                    .into_iter()
+                    .enumerate()
+            {
+                app.world
+                    .spawn((
+                        Player {
+                            pickup_cooldown_timer: Timer::from_seconds(0., TimerMode::Once),
+                            throw_timer: Timer::from_seconds(1., TimerMode::Once),
+                            player_index,
+                            last_pressed_axis: MoveAxis::Horizontal,
+                            move_velocity: Vec2::ZERO,
+                        },
+                        GlobalTransform::from_translation(Vec3::new(x, 0., 0.)),
+                    ))
+                    .with_children(|builder| {
+                        arrows[index] = builder.spawn(make_nudge_arrow(32.)).id();
+                    });
+                packages[index] = app
+                    .world
+                    .spawn((
+                        Package,
+                        Sprite {
+                            color: PLACEHOLDER_COLOUR,
+                            ..default()
+                        },
+                        GlobalTransform::from_translation(Vec3::new(x + 5., 0., 0.)),
+                    ))
+                    .id();
+            }
+            Self {
+                app,
+                packages,
+                arrows,
+            }
+        }
+
+        fn idle_for(&mut self, player_index: PlayerIndex, seconds: f32) {
+            self.app.world.resource_mut::<GameState>().player_controls[player_index]
+                .seconds_since_input = seconds;
+        }
+
+        fn idle_window(&self) -> f32 {
+            self.app
+                .world
+                .resource::<GameConfig>()
+                .idle_nudge_config
+                .idle_seconds
+        }
+
+        fn highlighted(&self) -> [bool; 2] {
+            self.packages
+                .map(|package| self.app.world.get::<IdleNudgeHighlight>(package).is_some())
+        }
+
+        fn package_colour(&self, index: usize) -> Color {
+            self.app
+                .world
+                .get::<Sprite>(self.packages[index])
+                .unwrap()
+                .color
+        }
+
+        fn arrow(&self, index: usize) -> Option<Vec3> {
+            let arrow = self.arrows[index];
+            (*self.app.world.get::<Visibility>(arrow).unwrap() != Visibility::Hidden)
+                .then(|| self.app.world.get::<Transform>(arrow).unwrap().translation)
+        }
+    }
+
+    #[test]
+    fn each_player_has_their_own_idle_window() {
+        let mut scene = NudgeScene::new(GameModeKind::Team);
+        let idle_window = scene.idle_window();
+        scene.idle_for(PlayerIndex::Player1, idle_window + 0.1);
+        scene.idle_for(PlayerIndex::Player2, idle_window - 0.1);
+        scene.app.update();
+
+        assert_eq!(scene.highlighted(), [true, false]);
+        assert_ne!(scene.package_colour(0), PLACEHOLDER_COLOUR);
+        assert_eq!(scene.package_colour(1), PLACEHOLDER_COLOUR);
+        assert_eq!(scene.arrow(0), None);
+        // Player2 is active, their arrow sits on the left edge of the view pointing at Player1
+        let size = scene.app.world.resource::<GameConfig>().player_config.size;
+        let arrow = scene.arrow(1).expect("the active player sees an arrow");
+        assert!((arrow.x - (VIEW.min.x + size / 2. - 200.)).abs() < 0.001);
+        assert_eq!(arrow.y, 0.);
+
+        // once both are idle there's nobody to point at them
+        scene.idle_for(PlayerIndex::Player2, idle_window + 0.1);
+        scene.app.update();
+        assert_eq!(scene.highlighted(), [true, true]);
+        assert_eq!(scene.arrow(0), None);
+        assert_eq!(scene.arrow(1), None);
+    }
+
+    #[test]
+    fn nudges_clear_on_input() {
+        let mut scene = NudgeScene::new(GameModeKind::Team);
+        let idle_window = scene.idle_window();
+        scene.idle_for(PlayerIndex::Player1, idle_window * 2.);
+        scene.app.update();
+        scene.app.update();
+        assert_eq!(scene.highlighted(), [true, false]);
+        assert!(scene.arrow(1).is_some());
+
+        scene.idle_for(PlayerIndex::Player1, 0.);
+        scene.app.update();
+        assert_eq!(scene.highlighted(), [false, false]);
+        assert_eq!(scene.package_colour(0), PLACEHOLDER_COLOUR);
+        assert_eq!(scene.arrow(1), None);
+    }
+
+    #[test]
+    fn competitive_mode_never_nudges() {
+        let mut scene = NudgeScene::new(GameModeKind::Versus);
+        scene.idle_for(PlayerIndex::Player1, 600.);
+        for _ in 0..3 {
+            scene.app.update();
+        }
+        assert_eq!(scene.highlighted(), [false, false]);
+        assert_eq!(scene.package_colour(0), PLACEHOLDER_COLOUR);
+        assert_eq!(scene.arrow(0), None);
+        assert_eq!(scene.arrow(1), None);
+    }
+
+    #[test]
+    fn arrows_sit_on_the_view_edge() {
+        assert_eq!(
+            view_edge_point(VIEW, Vec2::new(100., 0.), Vec2::X, 10.),
+            Vec2::new(390., 0.)
+        );
+        assert!(
+            view_edge_point(VIEW, Vec2::ZERO, Vec2::new(1., 1.).normalize(), 0.)
+                .abs_diff_eq(Vec2::new(300., 300.), 0.001)
+        );
+        // off screen players are pulled back onto it
+        assert_eq!(
+            view_edge_point(VIEW, Vec2::new(-500., 0.), Vec2::NEG_X, 0.),
+            Vec2::new(-400., 0.)
+        );
+        assert_eq!(
+            view_edge_point(VIEW, Vec2::ZERO, Vec2::ZERO, 0.),
+            Vec2::ZERO
+        );
+    }
+}
@@ -0,0 +1,587 @@
+use bevy::{
+    asset::LoadState,
+    ecs::system::SystemParam,
+    input::gamepad::{GamepadAxisType, GamepadButtonType},
+    prelude::*,
+    sprite::Anchor,
+};
+
+use crate::{
+    AttractMode, AxisDirection, EntityLayer, GameConfig, GameState, KeyAction, KeyBind, Package,
+    PackagePickedUp, Player, PlayerIndex, RenderLayers, SessionStarted, SpriteSheetSource,
+    StringKey, StringTable, TextureTarget,
+};
+
+/// Glyphs per pad family in the `InputGlyphs` sheet. Each family takes one row, Xbox first then
+/// PlayStation, Nintendo and finally generic pads, and every row lays its glyphs out the same way:
+///
+/// | columns | glyphs                                                  |
+/// |---------|---------------------------------------------------------|
+/// | 0-3     | face buttons: south, east, north, west                  |
+/// | 4-7     | left bumper, left trigger, right bumper, right trigger |
+/// | 8-9     | select, start                                           |
+/// | 10-11   | left stick click, right stick click                     |
+/// | 12-15   | d-pad up, down, left, right                             |
+/// | 16-19   | left stick up, down, left, right                        |
+/// | 20-23   | right stick up, down, left, right                       |
+pub const GLYPHS_PER_FAMILY: usize = 24;
+
+/// Readable names for every glyph in the sheet, in the same order, drawn when the sheet isn't.
+const GLYPH_LABELS: [[&str; GLYPHS_PER_FAMILY]; 4] = [
+    [
+        "A",
+        "B",
+        "Y",
+        "X",
+        "LB",
+        "LT",
+        "RB",
+        "RT",
+        "View",
+        "Menu",
+        "LS",
+        "RS",
+        "D-Pad Up",
+        "D-Pad Down",
+        "D-Pad Left",
+        "D-Pad Right",
+        "LS Up",
+        "LS Down",
+        "LS Left",
+        "LS Right",
+        "RS Up",
+        "RS Down",
+        "RS Left",
+        "RS Right",
+    ],
+    [
+        "Cross",
+        "Circle",
+        "Triangle",
+        "Square",
+        "L1",
+        "L2",
+        "R1",
+        "R2",
+        "Share",
+        "Options",
+        "L3",
+        "R3",
+        "D-Pad Up",
+        "D-Pad Down",
+        "D-Pad Left",
+        "D-Pad Right",
+        "L Up",
+        "L Down",
+        "L Left",
+        "L Right",
+        "R Up",
+        "R Down",
+        "R Left",
+        "R Right",
+    ],
+    [
+        "B",
+        "A",
+        "X",
+        "Y",
+        "L",
+        "ZL",
+        "R",
+        "ZR",
+        "-",
+        "+",
+        "LS",
+        "RS",
+        "D-Pad Up",
+        "D-Pad Down",
+        "D-Pad Left",
+        "D-Pad Right",
+        "LS Up",
+        "LS Down",
+        "LS Left",
+        "LS Right",
+        "RS Up",
+        "RS Down",
+        "RS Left",
+        "RS Right",
+    ],
+    [
+        "South",
+        "East",
+        "North",
+        "West",
+        "L1",
+        "L2",
+        "R1",
+        "R2",
+        "Select",
+        "Start",
+        "L3",
+        "R3",
+        "D-Pad Up",
+        "D-Pad Down",
+        "D-Pad Left",
+        "D-Pad Right",
+        "L Up",
+        "L Down",
+        "L Left",
+        "L Right",
+        "R Up",
+        "R Down",
+        "R Left",
+        "R Right",
+    ],
+];
+
+/// Whose button markings a pad has, guessed from the name it reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadFamily {
+    Xbox,
+    PlayStation,
+    Nintendo,
+    Generic,
+}
+
+impl PadFamily {
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let named = |hints: &[&str]| hints.iter().any(|hint| name.contains(hint));
+        if named(&["xbox", "xinput", "microsoft"]) {
+            PadFamily::Xbox
+        } else if named(&[
+            "playstation",
+            "dualshock",
+            "dualsense",
+            "sony",
+            "ps3",
+            "ps4",
+            "ps5",
+        ]) {
+            PadFamily::PlayStation
+        } else if named(&["nintendo", "switch", "joy-con", "joycon"]) {
+            PadFamily::Nintendo
+        } else {
+            PadFamily::Generic
+        }
+    }
+
+    fn first_glyph(&self) -> usize {
+        *self as usize * GLYPHS_PER_FAMILY
+    }
+}
+
+/// How to show a bound control, the name of a key or a glyph from the texture pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingGlyph {
+    Text(String),
+    /// Index into the texture pack's `InputGlyphs` sheet.
+    Atlas(usize),
+}
+
+impl BindingGlyph {
+    /// Name of the control, for when there's no sheet to draw the glyph from.
+    pub fn label(&self) -> String {
+        match self {
+            BindingGlyph::Text(text) => text.clone(),
+            BindingGlyph::Atlas(index) => GLYPH_LABELS
+                .get(index / GLYPHS_PER_FAMILY)
+                .map_or("?", |labels| labels[index % GLYPHS_PER_FAMILY])
+                .to_string(),
+        }
+    }
+}
+
+fn button_glyph(button: GamepadButtonType) -> Option<usize> {
+    Some(match button {
+        GamepadButtonType::South => 0,
+        GamepadButtonType::East => 1,
+        GamepadButtonType::North => 2,
+        GamepadButtonType::West => 3,
+        GamepadButtonType::LeftTrigger => 4,
+        GamepadButtonType::LeftTrigger2 => 5,
+        GamepadButtonType::RightTrigger => 6,
+        GamepadButtonType::RightTrigger2 => 7,
+        GamepadButtonType::Select => 8,
+        GamepadButtonType::Start => 9,
+        GamepadButtonType::LeftThumb => 10,
+        GamepadButtonType::RightThumb => 11,
+        GamepadButtonType::DPadUp => 12,
+        GamepadButtonType::DPadDown => 13,
+        GamepadButtonType::DPadLeft => 14,
+        GamepadButtonType::DPadRight => 15,
+        _ => return None,
+    })
+}
+
+fn axis_glyph(axis: GamepadAxisType, direction: AxisDirection) -> Option<usize> {
+    let positive = direction == AxisDirection::Positive;
+    Some(match axis {
+        GamepadAxisType::LeftStickY => 16 + usize::from(!positive),
+        GamepadAxisType::LeftStickX => 18 + usize::from(positive),
+        GamepadAxisType::RightStickY => 20 + usize::from(!positive),
+        GamepadAxisType::RightStickX => 22 + usize::from(positive),
+        // analogue triggers look the same as digital ones
+        GamepadAxisType::LeftZ => 5,
+        GamepadAxisType::RightZ => 7,
+        _ => return None,
+    })
+}
+
+pub fn key_label(key_code: KeyCode) -> String {
+    let label = match key_code {
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => "Shift",
+        KeyCode::ControlLeft | KeyCode::ControlRight => "Ctrl",
+        KeyCode::AltLeft | KeyCode::AltRight => "Alt",
+        KeyCode::ArrowUp => "Up",
+        KeyCode::ArrowDown => "Down",
+        KeyCode::ArrowLeft => "Left",
+        KeyCode::ArrowRight => "Right",
+        KeyCode::Backslash => "\\",
+        KeyCode::Slash => "/",
+        KeyCode::Comma => ",",
+        KeyCode::Period => ".",
+        KeyCode::Semicolon => ";",
+        KeyCode::Quote => "'",
+        KeyCode::BracketLeft => "[",
+        KeyCode::BracketRight => "]",
+        KeyCode::Minus => "-",
+        KeyCode::Equal => "=",
+        KeyCode::Backquote => "`",
+        _ => {
+            let name = format!("{:?}", key_code);
+            return match name.strip_prefix("Key").or(name.strip_prefix("Digit")) {
+                Some(character) => character.to_string(),
+                None => name.replace("Numpad", "Num "),
+            };
+        }
+    };
+    label.to_string()
+}
+
+pub fn mouse_button_label(mouse_button: MouseButton) -> String {
+    match mouse_button {
+        MouseButton::Left => "LMB".to_string(),
+        MouseButton::Right => "RMB".to_string(),
+        MouseButton::Middle => "MMB".to_string(),
+        other => format!("Mouse {:?}", other),
+    }
+}
+
+/// Everything needed to work out what a player's controls look like, taken by a system as one
+/// parameter.
+#[derive(SystemParam)]
+pub struct ControlBindings<'w> {
+    pub game_config: Res<'w, GameConfig>,
+    pub game_state: Res<'w, GameState>,
+    pub gamepads: Res<'w, Gamepads>,
+}
+
+impl ControlBindings<'_> {
+    /// What to show for the control bound to `action`: a glyph for the pad the player has while
+    /// one is assigned, otherwise the key or mouse button they'd press.
+    pub fn binding_display(&self, action: KeyAction, player_index: PlayerIndex) -> BindingGlyph {
+        let key_bind = &self.game_config.get_key_map(player_index)[action];
+        let binds = [&key_bind.priamry, &key_bind.secondary];
+        let player_control = &self.game_state.player_controls[player_index];
+
+        let pad_glyph = |pad_family: PadFamily| {
+            binds.iter().find_map(|bind| match bind {
+                KeyBind::ControllerButton(button) => Some(
+                    button_glyph(*button)
+                        .map(|glyph| BindingGlyph::Atlas(pad_family.first_glyph() + glyph))
+                        .unwrap_or_else(|| BindingGlyph::Text(format!("{:?}", button))),
+                ),
+                KeyBind::ControllerAxis((axis, direction)) => {
+                    // a pad standing in a button for a missing axis should show that button
+                    let glyph = match player_control.pad_capabilities.fallback_for(
+                        *axis,
+                        *direction,
+                        &self.game_config.pad_axis_fallbacks,
+                    ) {
+                        Some(button) => button_glyph(button),
+                        None => axis_glyph(*axis, *direction),
+                    };
+                    Some(
+                        glyph
+                            .map(|glyph| BindingGlyph::Atlas(pad_family.first_glyph() + glyph))
+                            .unwrap_or_else(|| BindingGlyph::Text(format!("{:?}", axis))),
+                    )
+                }
+                _ => None,
+            })
+        };
+        let desk_glyph = || {
+            binds.iter().find_map(|bind| match bind {
+                KeyBind::Key(key_code) => Some(BindingGlyph::Text(key_label(*key_code))),
+                KeyBind::MouseButton(mouse_button) => {
+                    Some(BindingGlyph::Text(mouse_button_label(*mouse_button)))
+                }
+                _ => None,
+            })
+        };
+
+        match player_control.pad {
+            Some(pad) => pad_glyph(
+                self.gamepads
+                    .name(pad)
+                    .map_or(PadFamily::Generic, PadFamily::from_name),
+            )
+            .or_else(desk_glyph),
+            None => desk_glyph().or_else(|| pad_glyph(PadFamily::Generic)),
+        }
+        .unwrap_or_else(|| BindingGlyph::Text("?".to_string()))
+    }
+}
+
+/// "Hold <glyph> to charge" under a player, shown the first few times they pick up a package
+/// each session.
+#[derive(Component)]
+pub struct ChargeHint {
+    pub player: PlayerIndex,
+    pub uses: u32,
+    alpha: f32,
+    /// What the hint was last laid out for, and whether the glyph was drawn from the sheet.
+    shown: Option<(BindingGlyph, bool)>,
+}
+
+#[derive(Component)]
+pub enum ChargeHintText {
+    Before,
+    After,
+}
+
+#[derive(Component)]
+pub struct ChargeHintGlyph {
+    pub glyph_count: usize,
+}
+
+pub fn spawn_charge_hint(
+    builder: &mut ChildBuilder,
+    player_index: PlayerIndex,
+    asset_server: &AssetServer,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    game_config: &GameConfig,
+) {
+    let texture_pack = game_config.get_texture_pack();
+    let glyph_sprite = texture_pack.choose_texture_for(TextureTarget::InputGlyphs, None);
+    let (atlas_layout, glyph_count) = glyph_sprite.atlas_layout("InputGlyphs");
+    let sprite_path = format!("{}/{}", texture_pack.root, glyph_sprite.path);
+    let glyph_size = game_config.input_hint_config.glyph_size;
+    let text_style = TextStyle {
+        font_size: glyph_size * 0.8,
+        color: Color::WHITE.with_a(0.),
+        ..default()
+    };
+
+    builder
+        .spawn((
+            SpatialBundle {
+                transform: Transform::from_translation(Vec3::new(
+                    0.,
+                    -game_config.player_config.size * 0.9,
+                    0.,
+                )),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ChargeHint {
+                player: player_index,
+                uses: 0,
+                alpha: 0.,
+                shown: None,
+            },
+        ))
+        .with_children(|builder| {
+            for part in [ChargeHintText::Before, ChargeHintText::After] {
+                builder.spawn((
+                    Text2dBundle {
+                        text: Text::from_section("", text_style.clone()),
+                        transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                        ..default()
+                    },
+                    part,
+                ));
+            }
+            builder.spawn((
+                SpriteSheetBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(glyph_size)),
+                        color: Color::WHITE.with_a(0.),
+                        ..default()
+                    },
+                    atlas: TextureAtlas {
+                        layout: texture_atlas_layouts.add(atlas_layout),
+                        index: 0,
+                    },
+                    texture: asset_server.load(&sprite_path),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::TrajectoryPreview),
+                ChargeHintGlyph { glyph_count },
+                SpriteSheetSource {
+                    source: format!("texture pack '{}'", game_config.selected_texture_pack),
+                    path: sprite_path,
+                    expected_size: glyph_sprite.expected_image_size().unwrap_or_default(),
+                },
+            ));
+        });
+}
+
+// the glyph is looked up every frame so rebinding or swapping pads shows straight away
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_charge_hints(
+    time: Res<Time>,
+    control_bindings: ControlBindings,
+    string_table: Res<StringTable>,
+    attract_mode: Res<AttractMode>,
+    asset_server: Res<AssetServer>,
+    mut session_events: EventReader<SessionStarted>,
+    mut pickup_events: EventReader<PackagePickedUp>,
+    player_query: Query<Option<&Children>, With<Player>>,
+    package_query: Query<(), With<Package>>,
+    mut hint_query: Query<(&mut ChargeHint, &mut Visibility, &Parent, &Children)>,
+    mut text_query: Query<
+        (&ChargeHintText, &mut Text, &mut Anchor, &mut Transform),
+        Without<ChargeHintGlyph>,
+    >,
+    mut glyph_query: Query<
+        (
+            &ChargeHintGlyph,
+            &mut TextureAtlas,
+            &Handle<Image>,
+            &mut Sprite,
+            &mut Visibility,
+        ),
+        Without<ChargeHint>,
+    >,
+) {
+    let hint_config = &control_bindings.game_config.input_hint_config;
+    let new_session = session_events.read().count() > 0;
+    let pickups = pickup_events
+        .read()
+        .map(|picked_up| picked_up.player)
+        .collect::<Vec<_>>();
+
+    for (mut charge_hint, mut hint_visibility, player_entity, hint_children) in &mut hint_query {
+        if new_session {
+            charge_hint.uses = 0;
+        }
+        let player_index = charge_hint.player;
+        let picked_up = pickups
+            .iter()
+            .filter(|player| **player == player_index)
+            .count();
+        charge_hint.uses += picked_up as u32;
+
+        let is_holding = player_query
+            .get(player_entity.get())
+            .ok()
+            .flatten()
+            .is_some_and(|children| children.iter().any(|child| package_query.contains(*child)));
+        let wants_hint = hint_config.enabled
+            && !attract_mode.active
+            && is_holding
+            && charge_hint.uses <= hint_config.charge_hint_uses;
+        let fade_step = time.delta_seconds() / hint_config.fade_seconds.max(f32::EPSILON);
+        charge_hint.alpha = if wants_hint {
+            (charge_hint.alpha + fade_step).min(1.)
+        } else {
+            (charge_hint.alpha - fade_step).max(0.)
+        };
+        let visible = charge_hint.alpha > 0.;
+        let visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *hint_visibility != visibility {
+            *hint_visibility = visibility;
+        }
+        if !visible {
+            continue;
+        }
+
+        let glyph = control_bindings.binding_display(KeyAction::PickupOrThrow, player_index);
+        let mut draw_glyph = false;
+        for child in hint_children.iter() {
+            if let Ok((hint_glyph, mut atlas, image, mut sprite, mut glyph_visibility)) =
+                glyph_query.get_mut(*child)
+            {
+                let sheet_failed =
+                    matches!(asset_server.get_load_state(image), Some(LoadState::Failed));
+                draw_glyph = match glyph {
+                    BindingGlyph::Atlas(index)
+                        if index < hint_glyph.glyph_count && !sheet_failed =>
+                    {
+                        if atlas.index != index {
+                            atlas.index = index;
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+                if sprite.color.a() != charge_hint.alpha {
+                    sprite.color.set_a(charge_hint.alpha);
+                }
+                let glyph_visibility_now = if draw_glyph {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+                if *glyph_visibility != glyph_visibility_now {
+                    *glyph_visibility = glyph_visibility_now;
+                }
+            }
+        }
+
+        let layout = (glyph, draw_glyph);
+        let relayout = charge_hint.shown.as_ref() != Some(&layout);
+        let half_gap = hint_config.glyph_size * 0.6;
+        for child in hint_children.iter() {
+            let Ok((part, mut text, mut anchor, mut transform)) = text_query.get_mut(*child) else {
+                continue;
+            };
+            if relayout {
+                // without a glyph to sit between them, the whole hint goes in the first text
+                let (value, new_anchor, x) = match (part, draw_glyph) {
+                    (ChargeHintText::Before, true) => (
+                        string_table.tr(StringKey::ChargeHintBefore).to_string(),
+                        Anchor::CenterRight,
+                        -half_gap,
+                    ),
+                    (ChargeHintText::After, true) => (
+                        string_table.tr(StringKey::ChargeHintAfter).to_string(),
+                        Anchor::CenterLeft,
+                        half_gap,
+                    ),
+                    (ChargeHintText::Before, false) => (
+                        format!(
+                            "{}[{}]{}",
+                            string_table.tr(StringKey::ChargeHintBefore),
+                            layout.0.label(),
+                            string_table.tr(StringKey::ChargeHintAfter)
+                        ),
+                        Anchor::Center,
+                        0.,
+                    ),
+                    (ChargeHintText::After, false) => (String::new(), Anchor::Center, 0.),
+                };
+                if let Some(section) = text.sections.first_mut() {
+                    section.value = value;
+                }
+                *anchor = new_anchor;
+                transform.translation.x = x;
+            }
+            if let Some(section) = text.sections.first_mut() {
+                if section.style.color.a() != charge_hint.alpha {
+                    section.style.color.set_a(charge_hint.alpha);
+                }
+            }
+        }
+        if relayout {
+            charge_hint.shown = Some(layout);
+        }
+    }
+}
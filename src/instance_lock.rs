@@ -0,0 +1,303 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, prelude::*, sprite::Anchor, text::Text2dBounds};
+
+use crate::{AppConfig, DataOutputPolicy, EntityLayer, RenderLayers, StringKey, StringTable};
+
+const LOCK_FILENAME: &str = "play_nice.lock";
+
+/// Held for as long as this instance is the one allowed to write files, the lock file is removed
+/// when released or dropped.
+#[derive(Resource)]
+pub struct InstanceLock {
+    path: Option<PathBuf>,
+}
+
+pub enum InstanceLockOutcome {
+    Acquired(InstanceLock),
+    HeldBy(u32),
+}
+
+/// Present while another instance holds the lock and the player hasn't chosen what to do yet.
+#[derive(Resource)]
+pub struct InstanceConflict {
+    pub other_pid: u32,
+}
+
+#[derive(Component)]
+pub struct InstanceConflictPrompt;
+
+impl InstanceLockOutcome {
+    /// Running alongside an instance that holds the lock, as the conflict prompt's run anyway
+    /// does, leaves every file to that instance.
+    pub fn output_policy(&self) -> DataOutputPolicy {
+        match self {
+            InstanceLockOutcome::Acquired(_) => DataOutputPolicy::Full,
+            InstanceLockOutcome::HeldBy(_) => DataOutputPolicy::Restricted,
+        }
+    }
+}
+
+impl InstanceLock {
+    pub fn release(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+pub fn data_directory() -> PathBuf {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".local").join("share"))
+            })
+    };
+    base.unwrap_or_else(std::env::temp_dir).join("play-nice")
+}
+
+pub fn instance_lock_path() -> PathBuf {
+    data_directory().join(LOCK_FILENAME)
+}
+
+pub fn is_process_alive(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        Path::new("/proc").join(pid.to_string()).exists()
+    } else if cfg!(windows) {
+        std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .map_or(true, |output| {
+                String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+            })
+    } else {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .status()
+            .map_or(true, |status| status.success())
+    }
+}
+
+/// Tries to claim the lock file for `pid`, a lock left behind by a process that is no longer
+/// alive (e.g. after a crash) is treated as stale and taken over.
+pub fn acquire_instance_lock(
+    path: &Path,
+    pid: u32,
+    is_alive: impl Fn(u32) -> bool,
+) -> std::io::Result<InstanceLockOutcome> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    for _ in 0..2 {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(mut file) => {
+                write!(file, "{}", pid)?;
+                return Ok(InstanceLockOutcome::Acquired(InstanceLock {
+                    path: Some(path.to_path_buf()),
+                }));
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                let owner = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|contents| contents.trim().parse::<u32>().ok());
+                match owner {
+                    Some(owner) if owner != pid && is_alive(owner) => {
+                        return Ok(InstanceLockOutcome::HeldBy(owner));
+                    }
+                    _ => {
+                        // stale or unreadable, clear it and try again
+                        std::fs::remove_file(path)?;
+                    }
+                }
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("could not claim instance lock {}", path.display()),
+    ))
+}
+
+pub fn release_instance_lock_on_exit(
+    mut instance_lock: ResMut<InstanceLock>,
+    mut exit_events: EventReader<AppExit>,
+) {
+    if exit_events.read().next().is_some() {
+        instance_lock.release();
+    }
+}
+
+pub fn no_instance_conflict(instance_conflict: Option<Res<InstanceConflict>>) -> bool {
+    instance_conflict.is_none()
+}
+
+pub fn show_instance_conflict_prompt(
+    mut commands: Commands,
+    app_config: Res<AppConfig>,
+    string_table: Res<StringTable>,
+    instance_conflict: Option<Res<InstanceConflict>>,
+) {
+    let Some(instance_conflict) = instance_conflict else {
+        return;
+    };
+    warn!(
+        "Play Nice is already running (pid {}), waiting for a decision",
+        instance_conflict.other_pid
+    );
+
+    let resolution = app_config.base_resolution.as_vec2();
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK.with_a(0.85),
+                custom_size: Some(resolution),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::Single(EntityLayer::Overlay),
+        InstanceConflictPrompt,
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                string_table.tr(StringKey::InstanceConflictMessage),
+                TextStyle {
+                    font_size: 28.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            text_anchor: Anchor::Center,
+            text_2d_bounds: Text2dBounds { size: resolution },
+            transform: Transform::from_translation(Vec3::new(0., 0., 100.)),
+            ..default()
+        },
+        InstanceConflictPrompt,
+    ));
+}
+
+pub fn handle_instance_conflict_prompt(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    instance_conflict: Option<Res<InstanceConflict>>,
+    prompt_query: Query<Entity, With<InstanceConflictPrompt>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if instance_conflict.is_none() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        exit_events.send(AppExit);
+    } else if keyboard_input.just_pressed(KeyCode::KeyR) {
+        // file output was already restricted at startup, all that's left is to let play begin
+        warn!("Running alongside another instance, file output is disabled");
+        commands.remove_resource::<InstanceConflict>();
+        for prompt_entity in &prompt_query {
+            commands.entity(prompt_entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lock path of its own for each test, so they can run side by side.
+    fn test_lock_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("play-nice-test-{}", std::process::id()))
+            .join(format!("{}.lock", name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn acquired(outcome: std::io::Result<InstanceLockOutcome>) -> InstanceLock {
+        match outcome.unwrap() {
+            InstanceLockOutcome::Acquired(instance_lock) => instance_lock,
+            InstanceLockOutcome::HeldBy(owner) => panic!("lock held by {}", owner),
+        }
+    }
+
+    #[test]
+    fn second_instance_is_told_who_holds_the_lock() {
+        let path = test_lock_path("contention");
+        let first = acquired(acquire_instance_lock(&path, 100, |_| true));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "100");
+
+        let outcome = acquire_instance_lock(&path, 200, |_| true).unwrap();
+        assert!(matches!(outcome, InstanceLockOutcome::HeldBy(100)));
+
+        // releasing it lets the next one in
+        drop(first);
+        assert!(!path.exists());
+        let _second = acquired(acquire_instance_lock(&path, 200, |_| true));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "200");
+    }
+
+    #[test]
+    fn lock_left_by_a_dead_process_is_taken_over() {
+        let path = test_lock_path("stale");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "4242").unwrap();
+
+        let _lock = acquired(acquire_instance_lock(&path, 200, |pid| pid != 4242));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "200");
+    }
+
+    #[test]
+    fn lock_without_a_real_owner_is_taken_over() {
+        let path = test_lock_path("fake_pid");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        // not a pid at all
+        std::fs::write(&path, "not a pid").unwrap();
+        let lock = acquired(acquire_instance_lock(&path, 200, |_| true));
+        drop(lock);
+
+        // our own pid, left from before a restart that reused it
+        std::fs::write(&path, "200").unwrap();
+        let _lock = acquired(acquire_instance_lock(&path, 200, |_| true));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "200");
+    }
+
+    #[test]
+    fn running_anyway_restricts_output() {
+        let path = test_lock_path("run_anyway");
+        let _first = acquired(acquire_instance_lock(&path, 100, |_| true));
+
+        let outcome = acquire_instance_lock(&path, 200, |_| true).unwrap();
+        assert_eq!(outcome.output_policy(), DataOutputPolicy::Restricted);
+        // and the instance that does hold it keeps writing
+        let own = InstanceLockOutcome::Acquired(InstanceLock { path: None });
+        assert_eq!(own.output_policy(), DataOutputPolicy::Full);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::dynamics::RigidBody;
+
+use crate::{
+    attach_package_to_conveyor, attach_package_to_player, Conveyor, EntityLayer, GameConfig,
+    GameState, InteractionFailed, Metrics, Package, PackagePickedUp, PackageReturned, Player,
+    PlayerIndex, RenderLayers, ScoreChanged,
+};
+
+/// Ordered by priority, a package touching an outgoing belt is delivered before anyone can grab
+/// it off the belt's mouth, and takes the last space on a belt before a player can place there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InteractionKind {
+    Capture,
+    Pickup,
+    /// A held package set straight onto an outgoing belt.
+    Place,
+    /// A held package handed back to an incoming belt.
+    Return,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum InteractionActor {
+    Player(Entity, PlayerIndex),
+    Conveyor(Entity),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InteractionIntent {
+    pub actor: InteractionActor,
+    pub package: Entity,
+    pub kind: InteractionKind,
+    /// How far the actor is from the package, the nearer claimant wins within a kind.
+    pub distance: f32,
+    /// The belt the package ends up on, a claim that would overfill it loses.
+    pub onto: Option<Entity>,
+}
+
+/// Interactions that move a package between holders are gathered here first and only applied
+/// once every claim for the tick is known, so two claims on the same package can't both go through.
+///
+/// Claims on one package resolve by kind priority, then distance, and an exact tie between the
+/// players goes to whichever didn't win the last tie. Claims onto a belt then take its free space
+/// in the same order, and one that finds it full falls to the package's next claim.
+#[derive(Resource, Default)]
+pub struct InteractionIntents {
+    intents: Vec<InteractionIntent>,
+    last_tie_winner: Option<PlayerIndex>,
+}
+
+impl InteractionIntents {
+    pub fn push(&mut self, intent: InteractionIntent) {
+        self.intents.push(intent);
+    }
+
+    /// Whether `player` has reached for a package this tick.
+    pub fn has_claim_from(&self, player: PlayerIndex) -> bool {
+        self.intents.iter().any(|intent| {
+            matches!(intent.actor, InteractionActor::Player(_, claimant) if claimant == player)
+        })
+    }
+
+    /// The winning claim on each package, if any is left once the belts are full, followed by the
+    /// claims that lost. `belt_room` is how many more packages each belt takes this tick.
+    pub fn resolve(
+        &mut self,
+        belt_room: &BTreeMap<Entity, usize>,
+    ) -> Vec<(Option<InteractionIntent>, Vec<InteractionIntent>)> {
+        let mut by_package = BTreeMap::<Entity, Vec<InteractionIntent>>::new();
+        for intent in self.intents.drain(..) {
+            by_package.entry(intent.package).or_default().push(intent);
+        }
+
+        let mut ranked = Vec::new();
+        for (_, mut claims) in by_package {
+            claims.sort_by(|a, b| a.kind.cmp(&b.kind).then(a.distance.total_cmp(&b.distance)));
+
+            let tied_players = claims
+                .iter()
+                .take_while(|claim| {
+                    claim.kind == claims[0].kind && claim.distance == claims[0].distance
+                })
+                .filter_map(|claim| match claim.actor {
+                    InteractionActor::Player(_, player) => Some(player),
+                    InteractionActor::Conveyor(_) => None,
+                })
+                .collect::<Vec<_>>();
+            if tied_players.len() > 1 {
+                let winner = *tied_players
+                    .iter()
+                    .find(|player| Some(**player) != self.last_tie_winner)
+                    .unwrap_or(&tied_players[0]);
+                self.last_tie_winner = Some(winner);
+                if let Some(winner_index) = claims.iter().position(|claim| {
+                    matches!(claim.actor, InteractionActor::Player(_, player) if player == winner)
+                }) {
+                    claims.swap(0, winner_index);
+                }
+            }
+            ranked.push(claims);
+        }
+
+        // packages with the strongest claim take belt space first
+        ranked.sort_by(|a, b| {
+            a[0].kind
+                .cmp(&b[0].kind)
+                .then(a[0].distance.total_cmp(&b[0].distance))
+        });
+        let mut belt_room = belt_room.clone();
+        let mut resolved = Vec::new();
+        for mut claims in ranked {
+            let winner_index = claims.iter().position(|claim| match claim.onto {
+                Some(belt) => belt_room.get(&belt).is_some_and(|room| *room > 0),
+                None => true,
+            });
+            let winner = winner_index.map(|index| claims.remove(index));
+            if let Some(room) = winner
+                .and_then(|winner| winner.onto)
+                .and_then(|belt| belt_room.get_mut(&belt))
+            {
+                *room -= 1;
+            }
+            resolved.push((winner, claims));
+        }
+        resolved
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn resolve_interactions(
+    mut commands: Commands,
+    mut interaction_intents: ResMut<InteractionIntents>,
+    mut player_query: Query<&mut Player>,
+    mut package_query: Query<
+        (
+            &mut Transform,
+            &mut RenderLayers,
+            Option<&Parent>,
+            Has<RigidBody>,
+        ),
+        (With<Package>, Without<Player>),
+    >,
+    mut conveyor_query: Query<(Entity, &mut Conveyor)>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    mut failed_events: EventWriter<InteractionFailed>,
+    mut picked_up_events: EventWriter<PackagePickedUp>,
+    mut returned_events: EventWriter<PackageReturned>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut metrics: Option<ResMut<Metrics>>,
+) {
+    let package_size = game_config.package_config.size;
+    let belt_room = conveyor_query
+        .iter()
+        .map(|(conveyor_entity, conveyor_info)| {
+            (
+                conveyor_entity,
+                conveyor_info
+                    .capacity(package_size)
+                    .saturating_sub(conveyor_info.package_count),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for (winner, losers) in interaction_intents.resolve(&belt_room) {
+        for loser in losers {
+            if let InteractionActor::Player(_, player) = loser.actor {
+                debug!(
+                    "{:?} lost {:?} of {:?} to {:?}",
+                    player,
+                    loser.kind,
+                    loser.package,
+                    winner.map(|winner| winner.actor)
+                );
+                failed_events.send(InteractionFailed {
+                    player,
+                    package: loser.package,
+                    kind: loser.kind,
+                });
+            }
+        }
+        let Some(winner) = winner else {
+            continue;
+        };
+
+        let stack_height = match (winner.actor, winner.kind) {
+            (InteractionActor::Player(player_entity, _), InteractionKind::Pickup) => package_query
+                .iter()
+                .filter(|(_, _, package_parent, _)| {
+                    package_parent.is_some_and(|parent| parent.get() == player_entity)
+                })
+                .count(),
+            _ => 0,
+        };
+
+        let Ok((mut package_transform, mut package_layers, package_parent, package_rigid_body)) =
+            package_query.get_mut(winner.package)
+        else {
+            continue;
+        };
+
+        match (winner.actor, winner.onto) {
+            (InteractionActor::Player(player_entity, player_index), None) => {
+                let Ok(mut player_info) = player_query.get_mut(player_entity) else {
+                    continue;
+                };
+                if let Some((_, mut conveyor_info)) =
+                    package_parent.and_then(|parent| conveyor_query.get_mut(parent.get()).ok())
+                {
+                    conveyor_info.package_count -= 1;
+                }
+                attach_package_to_player(
+                    &mut commands,
+                    player_entity,
+                    &mut player_info,
+                    winner.package,
+                    &mut package_transform,
+                    &mut package_layers,
+                    package_rigid_body,
+                    stack_height,
+                    &game_config,
+                );
+                picked_up_events.send(PackagePickedUp {
+                    player: player_index,
+                    package: winner.package,
+                });
+                if let Some(metrics) = metrics.as_mut() {
+                    metrics.per_player[player_index].picked_up += 1;
+                }
+            }
+            (InteractionActor::Player(_, player_index), Some(conveyor_entity)) => {
+                let Ok((_, mut conveyor_info)) = conveyor_query.get_mut(conveyor_entity) else {
+                    continue;
+                };
+                if let RenderLayers::Multi(layers) = package_layers.as_mut() {
+                    layers.remove(&EntityLayer::HeldObject);
+                }
+                attach_package_to_conveyor(
+                    &mut commands,
+                    conveyor_entity,
+                    &mut conveyor_info,
+                    winner.package,
+                    &mut package_transform,
+                    &game_config,
+                );
+                if winner.kind == InteractionKind::Return {
+                    for recipient in game_config.game_mode.multiplier_recipients(player_index) {
+                        let recipient_score = &mut game_state.player_scores[recipient];
+                        recipient_score.multiplier = (recipient_score.multiplier
+                            - game_config.player_config.return_multiplier_penalty)
+                            .max(1.);
+                        score_events.send(ScoreChanged { player: recipient });
+                    }
+                    returned_events.send(PackageReturned {
+                        player: player_index,
+                        package: winner.package,
+                        conveyor: conveyor_entity,
+                    });
+                    debug!(
+                        "{:?} returned a package to {:?}",
+                        player_index, conveyor_entity
+                    );
+                } else if let Some(metrics) = metrics.as_mut() {
+                    metrics.per_player[player_index].placed += 1;
+                }
+            }
+            (InteractionActor::Conveyor(conveyor_entity), _) => {
+                let Ok((_, mut conveyor_info)) = conveyor_query.get_mut(conveyor_entity) else {
+                    continue;
+                };
+                attach_package_to_conveyor(
+                    &mut commands,
+                    conveyor_entity,
+                    &mut conveyor_info,
+                    winner.package,
+                    &mut package_transform,
+                    &game_config,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER1: Entity = Entity::from_raw(1);
+    const PLAYER2: Entity = Entity::from_raw(2);
+    const BELT: Entity = Entity::from_raw(10);
+    const OTHER_BELT: Entity = Entity::from_raw(11);
+    const PACKAGE: Entity = Entity::from_raw(20);
+    const OTHER_PACKAGE: Entity = Entity::from_raw(21);
+
+    fn player(entity: Entity) -> InteractionActor {
+        let player_index = if entity == PLAYER1 {
+            PlayerIndex::Player1
+        } else {
+            PlayerIndex::Player2
+        };
+        InteractionActor::Player(entity, player_index)
+    }
+
+    fn pickup(player_entity: Entity, package: Entity, distance: f32) -> InteractionIntent {
+        InteractionIntent {
+            actor: player(player_entity),
+            package,
+            kind: InteractionKind::Pickup,
+            distance,
+            onto: None,
+        }
+    }
+
+    fn capture(belt: Entity, package: Entity) -> InteractionIntent {
+        InteractionIntent {
+            actor: InteractionActor::Conveyor(belt),
+            package,
+            kind: InteractionKind::Capture,
+            distance: 0.,
+            onto: Some(belt),
+        }
+    }
+
+    fn place(
+        player_entity: Entity,
+        package: Entity,
+        kind: InteractionKind,
+        belt: Entity,
+        distance: f32,
+    ) -> InteractionIntent {
+        InteractionIntent {
+            actor: player(player_entity),
+            package,
+            kind,
+            distance,
+            onto: Some(belt),
+        }
+    }
+
+    fn room(belts: &[(Entity, usize)]) -> BTreeMap<Entity, usize> {
+        belts.iter().copied().collect()
+    }
+
+    /// Who won each package, in package order.
+    fn winners(
+        intents: &mut InteractionIntents,
+        claims: &[InteractionIntent],
+        belt_room: &BTreeMap<Entity, usize>,
+    ) -> Vec<(Entity, Option<InteractionKind>, usize)> {
+        for claim in claims {
+            intents.push(*claim);
+        }
+        let mut resolved = intents
+            .resolve(belt_room)
+            .into_iter()
+            .map(|(winner, losers)| {
+                let package = winner.map_or(losers[0].package, |winner| winner.package);
+                (package, winner.map(|winner| winner.kind), losers.len())
+            })
+            .collect::<Vec<_>>();
+        resolved.sort_by_key(|(package, _, _)| *package);
+        resolved
+    }
+
+    fn winning_actor(
+        intents: &mut InteractionIntents,
+        claims: &[InteractionIntent],
+    ) -> Option<Entity> {
+        for claim in claims {
+            intents.push(*claim);
+        }
+        let (winner, _) = intents.resolve(&room(&[(BELT, 1)])).remove(0);
+        winner.map(|winner| match winner.actor {
+            InteractionActor::Player(entity, _) | InteractionActor::Conveyor(entity) => entity,
+        })
+    }
+
+    #[test]
+    fn nearer_player_wins_a_contested_pickup() {
+        let mut intents = InteractionIntents::default();
+        let claims = [pickup(PLAYER1, PACKAGE, 30.), pickup(PLAYER2, PACKAGE, 10.)];
+        assert_eq!(winning_actor(&mut intents, &claims), Some(PLAYER2));
+        assert!(intents.intents.is_empty());
+    }
+
+    #[test]
+    fn exact_ties_alternate_between_players() {
+        let mut intents = InteractionIntents::default();
+        let claims = [pickup(PLAYER1, PACKAGE, 10.), pickup(PLAYER2, PACKAGE, 10.)];
+        let first = winning_actor(&mut intents, &claims);
+        let second = winning_actor(&mut intents, &claims);
+        let third = winning_actor(&mut intents, &claims);
+        assert_ne!(first, second);
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn capture_beats_a_nearer_pickup() {
+        let mut intents = InteractionIntents::default();
+        let claims = [pickup(PLAYER1, PACKAGE, 0.), capture(BELT, PACKAGE)];
+        assert_eq!(winning_actor(&mut intents, &claims), Some(BELT));
+    }
+
+    #[test]
+    fn capture_onto_a_full_belt_leaves_the_package_to_a_pickup() {
+        let mut intents = InteractionIntents::default();
+        let claims = [capture(BELT, PACKAGE), pickup(PLAYER1, PACKAGE, 20.)];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[(BELT, 0)])),
+            vec![(PACKAGE, Some(InteractionKind::Pickup), 1)]
+        );
+    }
+
+    #[test]
+    fn capture_takes_the_last_space_before_a_place() {
+        let mut intents = InteractionIntents::default();
+        let claims = [
+            place(PLAYER1, PACKAGE, InteractionKind::Place, BELT, 5.),
+            capture(BELT, OTHER_PACKAGE),
+        ];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[(BELT, 1)])),
+            vec![
+                (PACKAGE, None, 1),
+                (OTHER_PACKAGE, Some(InteractionKind::Capture), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn nearer_place_takes_the_last_space() {
+        let mut intents = InteractionIntents::default();
+        let claims = [
+            place(PLAYER1, PACKAGE, InteractionKind::Place, BELT, 40.),
+            place(PLAYER2, OTHER_PACKAGE, InteractionKind::Place, BELT, 15.),
+        ];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[(BELT, 1)])),
+            vec![
+                (PACKAGE, None, 1),
+                (OTHER_PACKAGE, Some(InteractionKind::Place), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn places_and_captures_all_fit_a_belt_with_room() {
+        let mut intents = InteractionIntents::default();
+        let claims = [
+            place(PLAYER1, PACKAGE, InteractionKind::Place, BELT, 40.),
+            capture(BELT, OTHER_PACKAGE),
+        ];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[(BELT, 2)])),
+            vec![
+                (PACKAGE, Some(InteractionKind::Place), 0),
+                (OTHER_PACKAGE, Some(InteractionKind::Capture), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn return_to_a_full_belt_fails_without_touching_others() {
+        let mut intents = InteractionIntents::default();
+        let claims = [
+            place(PLAYER1, PACKAGE, InteractionKind::Return, OTHER_BELT, 5.),
+            place(PLAYER2, OTHER_PACKAGE, InteractionKind::Place, BELT, 5.),
+        ];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[(BELT, 1), (OTHER_BELT, 0)])),
+            vec![
+                (PACKAGE, None, 1),
+                (OTHER_PACKAGE, Some(InteractionKind::Place), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn claims_on_different_packages_both_go_through() {
+        let mut intents = InteractionIntents::default();
+        let claims = [
+            pickup(PLAYER1, PACKAGE, 10.),
+            pickup(PLAYER2, OTHER_PACKAGE, 10.),
+        ];
+        assert_eq!(
+            winners(&mut intents, &claims, &room(&[])),
+            vec![
+                (PACKAGE, Some(InteractionKind::Pickup), 0),
+                (OTHER_PACKAGE, Some(InteractionKind::Pickup), 0)
+            ]
+        );
+        assert!(!intents.has_claim_from(PlayerIndex::Player1));
+    }
+}
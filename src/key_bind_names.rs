@@ -0,0 +1,714 @@
+use bevy::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{AxisDirection, KeyBind, PadAxisFallback};
+
+/// Furthest a misspelt binding can be from a real name and still be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+const MAX_SUGGESTIONS: usize = 3;
+
+/// A binding's name as written in the config, e.g. "left_shift", "gamepad_rt" or
+/// "left_stick_up". These stay the same whatever Bevy calls its variants, the older
+/// `Key = "ShiftLeft"` tables still load but every binding with a name is written by name.
+impl KeyBind {
+    pub fn name(&self) -> Option<String> {
+        match self {
+            KeyBind::Key(key_code) => KEY_NAMES
+                .iter()
+                .find(|(_, known)| known == key_code)
+                .map(|(name, _)| name.to_string()),
+            KeyBind::ControllerButton(GamepadButtonType::Other(index)) => {
+                Some(format!("gamepad_button_{}", index))
+            }
+            KeyBind::ControllerButton(button) => BUTTON_NAMES
+                .iter()
+                .find(|(_, known)| known == button)
+                .map(|(name, _)| name.to_string()),
+            KeyBind::ControllerAxis((GamepadAxisType::Other(index), direction)) => Some(format!(
+                "gamepad_axis_{}_{}",
+                index,
+                match direction {
+                    AxisDirection::Positive => "positive",
+                    AxisDirection::Negative => "negative",
+                }
+            )),
+            KeyBind::ControllerAxis((axis, direction)) => AXIS_NAMES
+                .iter()
+                .find(|(_, known_axis, known_direction)| {
+                    known_axis == axis && known_direction == direction
+                })
+                .map(|(name, _, _)| name.to_string()),
+            KeyBind::MouseButton(MouseButton::Other(index)) => {
+                Some(format!("mouse_button_{}", index))
+            }
+            KeyBind::MouseButton(mouse_button) => MOUSE_NAMES
+                .iter()
+                .find(|(_, known)| known == mouse_button)
+                .map(|(name, _)| name.to_string()),
+        }
+    }
+
+    pub fn from_name(text: &str) -> Result<Self, String> {
+        let name = text.trim().to_ascii_lowercase();
+        if let Some(key_bind) = Self::from_numbered_name(&name) {
+            return Ok(key_bind);
+        }
+        all_names()
+            .find(|(known, _)| *known == name)
+            .map(|(_, key_bind)| key_bind)
+            .ok_or_else(|| unknown_name_message(text))
+    }
+
+    /// The buttons and axes Bevy only knows by number.
+    fn from_numbered_name(name: &str) -> Option<Self> {
+        if let Some(index) = name.strip_prefix("gamepad_button_") {
+            return index
+                .parse()
+                .ok()
+                .map(|index| KeyBind::ControllerButton(GamepadButtonType::Other(index)));
+        }
+        if let Some(rest) = name.strip_prefix("gamepad_axis_") {
+            let (index, direction) = rest.split_once('_')?;
+            let direction = match direction {
+                "positive" => AxisDirection::Positive,
+                "negative" => AxisDirection::Negative,
+                _ => return None,
+            };
+            return index
+                .parse()
+                .ok()
+                .map(|index| KeyBind::ControllerAxis((GamepadAxisType::Other(index), direction)));
+        }
+        if let Some(index) = name.strip_prefix("mouse_button_") {
+            return index
+                .parse()
+                .ok()
+                .map(|index| KeyBind::MouseButton(MouseButton::Other(index)));
+        }
+        None
+    }
+}
+
+/// Every named binding, keys first.
+pub fn all_names() -> impl Iterator<Item = (&'static str, KeyBind)> {
+    KEY_NAMES
+        .iter()
+        .map(|(name, key_code)| (*name, KeyBind::Key(*key_code)))
+        .chain(
+            BUTTON_NAMES
+                .iter()
+                .map(|(name, button)| (*name, KeyBind::ControllerButton(*button))),
+        )
+        .chain(
+            AXIS_NAMES.iter().map(|(name, axis, direction)| {
+                (*name, KeyBind::ControllerAxis((*axis, *direction)))
+            }),
+        )
+        .chain(
+            MOUSE_NAMES
+                .iter()
+                .map(|(name, mouse_button)| (*name, KeyBind::MouseButton(*mouse_button))),
+        )
+}
+
+fn unknown_name_message(text: &str) -> String {
+    let wanted = text.trim().to_ascii_lowercase();
+    let mut near_matches = all_names()
+        .map(|(name, _)| (edit_distance(&wanted, name), name))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE.min(wanted.len() / 2 + 1))
+        .collect::<Vec<_>>();
+    near_matches.sort();
+    let suggestions = near_matches
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| format!("'{}'", name))
+        .collect::<Vec<_>>();
+    match suggestions.as_slice() {
+        [] => format!(
+            "unknown binding '{}', expected a key like \"w\" or \"left_shift\", a pad control like \
+             \"gamepad_south\", \"gamepad_rt\" or \"left_stick_up\", or a mouse button like \
+             \"mouse_left\"",
+            text
+        ),
+        [only] => format!("unknown binding '{}', did you mean {}?", text, only),
+        [rest @ .., last] => format!(
+            "unknown binding '{}', did you mean {} or {}?",
+            text,
+            rest.join(", "),
+            last
+        ),
+    }
+}
+
+/// Levenshtein distance, counted in bytes since every name is ASCII.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_byte) in a.bytes().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_byte) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_byte != *b_byte);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The bindings as Bevy's variants name them, which is how configs were written before
+/// bindings had names of their own.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "KeyBind")]
+enum LegacyKeyBind {
+    Key(KeyCode),
+    ControllerButton(GamepadButtonType),
+    ControllerAxis((GamepadAxisType, AxisDirection)),
+    MouseButton(MouseButton),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyBindRepr {
+    Name(String),
+    Legacy(LegacyKeyBind),
+}
+
+impl<'de> Deserialize<'de> for KeyBind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match KeyBindRepr::deserialize(deserializer)? {
+            KeyBindRepr::Name(name) => KeyBind::from_name(&name).map_err(de::Error::custom)?,
+            KeyBindRepr::Legacy(LegacyKeyBind::Key(key_code)) => KeyBind::Key(key_code),
+            KeyBindRepr::Legacy(LegacyKeyBind::ControllerButton(button)) => {
+                KeyBind::ControllerButton(button)
+            }
+            KeyBindRepr::Legacy(LegacyKeyBind::ControllerAxis(axis)) => {
+                KeyBind::ControllerAxis(axis)
+            }
+            KeyBindRepr::Legacy(LegacyKeyBind::MouseButton(mouse_button)) => {
+                KeyBind::MouseButton(mouse_button)
+            }
+        })
+    }
+}
+
+impl Serialize for KeyBind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(name) = self.name() {
+            return serializer.serialize_str(&name);
+        }
+        // only keys Bevy couldn't identify have no name
+        match self {
+            KeyBind::Key(key_code) => LegacyKeyBind::Key(*key_code),
+            KeyBind::ControllerButton(button) => LegacyKeyBind::ControllerButton(*button),
+            KeyBind::ControllerAxis(axis) => LegacyKeyBind::ControllerAxis(*axis),
+            KeyBind::MouseButton(mouse_button) => LegacyKeyBind::MouseButton(*mouse_button),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A fallback as the config writes it, `axis = "gamepad_lt"` with `button = "gamepad_lt_button"`,
+/// or the older form spelling out Bevy's variants.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PadAxisFallbackRepr {
+    // tried first, its direction field keeps it from swallowing the named form
+    Legacy {
+        axis: GamepadAxisType,
+        direction: AxisDirection,
+        button: GamepadButtonType,
+    },
+    Named {
+        axis: String,
+        button: String,
+    },
+}
+
+impl PadAxisFallback {
+    pub fn from_names(axis: &str, button: &str) -> Result<Self, String> {
+        let (axis, direction) = match KeyBind::from_name(axis)? {
+            KeyBind::ControllerAxis(axis) => axis,
+            _ => {
+                return Err(format!(
+                    "'{}' isn't a pad stick or trigger, a fallback's axis has to be one like \
+                     \"gamepad_lt\"",
+                    axis
+                ))
+            }
+        };
+        let button = match KeyBind::from_name(button)? {
+            KeyBind::ControllerButton(button) => button,
+            _ => {
+                return Err(format!(
+                    "'{}' isn't a pad button, a fallback's button has to be one like \
+                     \"gamepad_lt_button\"",
+                    button
+                ))
+            }
+        };
+        Ok(Self {
+            axis,
+            direction,
+            button,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for PadAxisFallback {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match PadAxisFallbackRepr::deserialize(deserializer)? {
+            PadAxisFallbackRepr::Legacy {
+                axis,
+                direction,
+                button,
+            } => Ok(Self {
+                axis,
+                direction,
+                button,
+            }),
+            PadAxisFallbackRepr::Named { axis, button } => {
+                Self::from_names(&axis, &button).map_err(de::Error::custom)
+            }
+        }
+    }
+}
+
+impl Serialize for PadAxisFallback {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let axis = KeyBind::ControllerAxis((self.axis, self.direction)).name();
+        let button = KeyBind::ControllerButton(self.button).name();
+        match (axis, button) {
+            (Some(axis), Some(button)) => PadAxisFallbackRepr::Named { axis, button },
+            _ => PadAxisFallbackRepr::Legacy {
+                axis: self.axis,
+                direction: self.direction,
+                button: self.button,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Pad buttons by position, so the names read the same whichever family of pad is plugged in.
+const BUTTON_NAMES: [(&str, GamepadButtonType); 19] = [
+    ("gamepad_south", GamepadButtonType::South),
+    ("gamepad_east", GamepadButtonType::East),
+    ("gamepad_north", GamepadButtonType::North),
+    ("gamepad_west", GamepadButtonType::West),
+    ("gamepad_c", GamepadButtonType::C),
+    ("gamepad_z", GamepadButtonType::Z),
+    ("gamepad_lb", GamepadButtonType::LeftTrigger),
+    ("gamepad_lt_button", GamepadButtonType::LeftTrigger2),
+    ("gamepad_rb", GamepadButtonType::RightTrigger),
+    ("gamepad_rt_button", GamepadButtonType::RightTrigger2),
+    ("gamepad_select", GamepadButtonType::Select),
+    ("gamepad_start", GamepadButtonType::Start),
+    ("gamepad_mode", GamepadButtonType::Mode),
+    ("gamepad_left_stick_press", GamepadButtonType::LeftThumb),
+    ("gamepad_right_stick_press", GamepadButtonType::RightThumb),
+    ("dpad_up", GamepadButtonType::DPadUp),
+    ("dpad_down", GamepadButtonType::DPadDown),
+    ("dpad_left", GamepadButtonType::DPadLeft),
+    ("dpad_right", GamepadButtonType::DPadRight),
+];
+
+/// One direction of a stick or trigger each, triggers read their analogue axis.
+const AXIS_NAMES: [(&str, GamepadAxisType, AxisDirection); 12] = [
+    (
+        "left_stick_up",
+        GamepadAxisType::LeftStickY,
+        AxisDirection::Positive,
+    ),
+    (
+        "left_stick_down",
+        GamepadAxisType::LeftStickY,
+        AxisDirection::Negative,
+    ),
+    (
+        "left_stick_left",
+        GamepadAxisType::LeftStickX,
+        AxisDirection::Negative,
+    ),
+    (
+        "left_stick_right",
+        GamepadAxisType::LeftStickX,
+        AxisDirection::Positive,
+    ),
+    (
+        "right_stick_up",
+        GamepadAxisType::RightStickY,
+        AxisDirection::Positive,
+    ),
+    (
+        "right_stick_down",
+        GamepadAxisType::RightStickY,
+        AxisDirection::Negative,
+    ),
+    (
+        "right_stick_left",
+        GamepadAxisType::RightStickX,
+        AxisDirection::Negative,
+    ),
+    (
+        "right_stick_right",
+        GamepadAxisType::RightStickX,
+        AxisDirection::Positive,
+    ),
+    (
+        "gamepad_lt",
+        GamepadAxisType::LeftZ,
+        AxisDirection::Positive,
+    ),
+    (
+        "gamepad_lt_negative",
+        GamepadAxisType::LeftZ,
+        AxisDirection::Negative,
+    ),
+    (
+        "gamepad_rt",
+        GamepadAxisType::RightZ,
+        AxisDirection::Positive,
+    ),
+    (
+        "gamepad_rt_negative",
+        GamepadAxisType::RightZ,
+        AxisDirection::Negative,
+    ),
+];
+
+const MOUSE_NAMES: [(&str, MouseButton); 5] = [
+    ("mouse_left", MouseButton::Left),
+    ("mouse_right", MouseButton::Right),
+    ("mouse_middle", MouseButton::Middle),
+    ("mouse_back", MouseButton::Back),
+    ("mouse_forward", MouseButton::Forward),
+];
+
+/// Every key that can be bound, by the name the config uses for it.
+const KEY_NAMES: [(&str, KeyCode); 194] = [
+    ("backquote", KeyCode::Backquote),
+    ("backslash", KeyCode::Backslash),
+    ("left_bracket", KeyCode::BracketLeft),
+    ("right_bracket", KeyCode::BracketRight),
+    ("comma", KeyCode::Comma),
+    ("0", KeyCode::Digit0),
+    ("1", KeyCode::Digit1),
+    ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3),
+    ("4", KeyCode::Digit4),
+    ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6),
+    ("7", KeyCode::Digit7),
+    ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+    ("equal", KeyCode::Equal),
+    ("intl_backslash", KeyCode::IntlBackslash),
+    ("intl_ro", KeyCode::IntlRo),
+    ("intl_yen", KeyCode::IntlYen),
+    ("a", KeyCode::KeyA),
+    ("b", KeyCode::KeyB),
+    ("c", KeyCode::KeyC),
+    ("d", KeyCode::KeyD),
+    ("e", KeyCode::KeyE),
+    ("f", KeyCode::KeyF),
+    ("g", KeyCode::KeyG),
+    ("h", KeyCode::KeyH),
+    ("i", KeyCode::KeyI),
+    ("j", KeyCode::KeyJ),
+    ("k", KeyCode::KeyK),
+    ("l", KeyCode::KeyL),
+    ("m", KeyCode::KeyM),
+    ("n", KeyCode::KeyN),
+    ("o", KeyCode::KeyO),
+    ("p", KeyCode::KeyP),
+    ("q", KeyCode::KeyQ),
+    ("r", KeyCode::KeyR),
+    ("s", KeyCode::KeyS),
+    ("t", KeyCode::KeyT),
+    ("u", KeyCode::KeyU),
+    ("v", KeyCode::KeyV),
+    ("w", KeyCode::KeyW),
+    ("x", KeyCode::KeyX),
+    ("y", KeyCode::KeyY),
+    ("z", KeyCode::KeyZ),
+    ("minus", KeyCode::Minus),
+    ("period", KeyCode::Period),
+    ("quote", KeyCode::Quote),
+    ("semicolon", KeyCode::Semicolon),
+    ("slash", KeyCode::Slash),
+    ("left_alt", KeyCode::AltLeft),
+    ("right_alt", KeyCode::AltRight),
+    ("backspace", KeyCode::Backspace),
+    ("caps_lock", KeyCode::CapsLock),
+    ("context_menu", KeyCode::ContextMenu),
+    ("left_ctrl", KeyCode::ControlLeft),
+    ("right_ctrl", KeyCode::ControlRight),
+    ("enter", KeyCode::Enter),
+    ("left_super", KeyCode::SuperLeft),
+    ("right_super", KeyCode::SuperRight),
+    ("left_shift", KeyCode::ShiftLeft),
+    ("right_shift", KeyCode::ShiftRight),
+    ("space", KeyCode::Space),
+    ("tab", KeyCode::Tab),
+    ("convert", KeyCode::Convert),
+    ("kana_mode", KeyCode::KanaMode),
+    ("lang_1", KeyCode::Lang1),
+    ("lang_2", KeyCode::Lang2),
+    ("lang_3", KeyCode::Lang3),
+    ("lang_4", KeyCode::Lang4),
+    ("lang_5", KeyCode::Lang5),
+    ("non_convert", KeyCode::NonConvert),
+    ("delete", KeyCode::Delete),
+    ("end", KeyCode::End),
+    ("help", KeyCode::Help),
+    ("home", KeyCode::Home),
+    ("insert", KeyCode::Insert),
+    ("page_down", KeyCode::PageDown),
+    ("page_up", KeyCode::PageUp),
+    ("down", KeyCode::ArrowDown),
+    ("left", KeyCode::ArrowLeft),
+    ("right", KeyCode::ArrowRight),
+    ("up", KeyCode::ArrowUp),
+    ("num_lock", KeyCode::NumLock),
+    ("numpad_0", KeyCode::Numpad0),
+    ("numpad_1", KeyCode::Numpad1),
+    ("numpad_2", KeyCode::Numpad2),
+    ("numpad_3", KeyCode::Numpad3),
+    ("numpad_4", KeyCode::Numpad4),
+    ("numpad_5", KeyCode::Numpad5),
+    ("numpad_6", KeyCode::Numpad6),
+    ("numpad_7", KeyCode::Numpad7),
+    ("numpad_8", KeyCode::Numpad8),
+    ("numpad_9", KeyCode::Numpad9),
+    ("numpad_add", KeyCode::NumpadAdd),
+    ("numpad_backspace", KeyCode::NumpadBackspace),
+    ("numpad_clear", KeyCode::NumpadClear),
+    ("numpad_clear_entry", KeyCode::NumpadClearEntry),
+    ("numpad_comma", KeyCode::NumpadComma),
+    ("numpad_decimal", KeyCode::NumpadDecimal),
+    ("numpad_divide", KeyCode::NumpadDivide),
+    ("numpad_enter", KeyCode::NumpadEnter),
+    ("numpad_equal", KeyCode::NumpadEqual),
+    ("numpad_hash", KeyCode::NumpadHash),
+    ("numpad_memory_add", KeyCode::NumpadMemoryAdd),
+    ("numpad_memory_clear", KeyCode::NumpadMemoryClear),
+    ("numpad_memory_recall", KeyCode::NumpadMemoryRecall),
+    ("numpad_memory_store", KeyCode::NumpadMemoryStore),
+    ("numpad_memory_subtract", KeyCode::NumpadMemorySubtract),
+    ("numpad_multiply", KeyCode::NumpadMultiply),
+    ("numpad_paren_left", KeyCode::NumpadParenLeft),
+    ("numpad_paren_right", KeyCode::NumpadParenRight),
+    ("numpad_star", KeyCode::NumpadStar),
+    ("numpad_subtract", KeyCode::NumpadSubtract),
+    ("escape", KeyCode::Escape),
+    ("fn", KeyCode::Fn),
+    ("fn_lock", KeyCode::FnLock),
+    ("print_screen", KeyCode::PrintScreen),
+    ("scroll_lock", KeyCode::ScrollLock),
+    ("pause", KeyCode::Pause),
+    ("browser_back", KeyCode::BrowserBack),
+    ("browser_favorites", KeyCode::BrowserFavorites),
+    ("browser_forward", KeyCode::BrowserForward),
+    ("browser_home", KeyCode::BrowserHome),
+    ("browser_refresh", KeyCode::BrowserRefresh),
+    ("browser_search", KeyCode::BrowserSearch),
+    ("browser_stop", KeyCode::BrowserStop),
+    ("eject", KeyCode::Eject),
+    ("launch_app_1", KeyCode::LaunchApp1),
+    ("launch_app_2", KeyCode::LaunchApp2),
+    ("launch_mail", KeyCode::LaunchMail),
+    ("media_play_pause", KeyCode::MediaPlayPause),
+    ("media_select", KeyCode::MediaSelect),
+    ("media_stop", KeyCode::MediaStop),
+    ("media_track_next", KeyCode::MediaTrackNext),
+    ("media_track_previous", KeyCode::MediaTrackPrevious),
+    ("power", KeyCode::Power),
+    ("sleep", KeyCode::Sleep),
+    ("audio_volume_down", KeyCode::AudioVolumeDown),
+    ("audio_volume_mute", KeyCode::AudioVolumeMute),
+    ("audio_volume_up", KeyCode::AudioVolumeUp),
+    ("wake_up", KeyCode::WakeUp),
+    ("meta", KeyCode::Meta),
+    ("hyper", KeyCode::Hyper),
+    ("turbo", KeyCode::Turbo),
+    ("abort", KeyCode::Abort),
+    ("resume", KeyCode::Resume),
+    ("suspend", KeyCode::Suspend),
+    ("again", KeyCode::Again),
+    ("copy", KeyCode::Copy),
+    ("cut", KeyCode::Cut),
+    ("find", KeyCode::Find),
+    ("open", KeyCode::Open),
+    ("paste", KeyCode::Paste),
+    ("props", KeyCode::Props),
+    ("select", KeyCode::Select),
+    ("undo", KeyCode::Undo),
+    ("hiragana", KeyCode::Hiragana),
+    ("katakana", KeyCode::Katakana),
+    ("f1", KeyCode::F1),
+    ("f2", KeyCode::F2),
+    ("f3", KeyCode::F3),
+    ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6),
+    ("f7", KeyCode::F7),
+    ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9),
+    ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11),
+    ("f12", KeyCode::F12),
+    ("f13", KeyCode::F13),
+    ("f14", KeyCode::F14),
+    ("f15", KeyCode::F15),
+    ("f16", KeyCode::F16),
+    ("f17", KeyCode::F17),
+    ("f18", KeyCode::F18),
+    ("f19", KeyCode::F19),
+    ("f20", KeyCode::F20),
+    ("f21", KeyCode::F21),
+    ("f22", KeyCode::F22),
+    ("f23", KeyCode::F23),
+    ("f24", KeyCode::F24),
+    ("f25", KeyCode::F25),
+    ("f26", KeyCode::F26),
+    ("f27", KeyCode::F27),
+    ("f28", KeyCode::F28),
+    ("f29", KeyCode::F29),
+    ("f30", KeyCode::F30),
+    ("f31", KeyCode::F31),
+    ("f32", KeyCode::F32),
+    ("f33", KeyCode::F33),
+    ("f34", KeyCode::F34),
+    ("f35", KeyCode::F35),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Fallbacks {
+        pad_axis_fallbacks: Vec<PadAxisFallback>,
+    }
+
+    fn fallback_names(fallback: &PadAxisFallback) -> (Option<String>, Option<String>) {
+        (
+            KeyBind::ControllerAxis((fallback.axis, fallback.direction)).name(),
+            KeyBind::ControllerButton(fallback.button).name(),
+        )
+    }
+
+    #[test]
+    fn every_name_round_trips() {
+        for (name, key_bind) in all_names() {
+            assert_eq!(key_bind.name().as_deref(), Some(name));
+            assert_eq!(
+                KeyBind::from_name(name).unwrap().name().as_deref(),
+                Some(name)
+            );
+        }
+        for name in [
+            "gamepad_button_7",
+            "gamepad_axis_3_negative",
+            "mouse_button_9",
+        ] {
+            assert_eq!(
+                KeyBind::from_name(name).unwrap().name().as_deref(),
+                Some(name)
+            );
+        }
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut names = all_names().map(|(name, _)| name).collect::<Vec<_>>();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn unknown_name_suggests_the_nearest() {
+        let message = KeyBind::from_name("left_shfit").err().unwrap();
+        assert!(message.contains("'left_shift'"), "{}", message);
+        let message = KeyBind::from_name("gamepad_souht").err().unwrap();
+        assert!(message.contains("'gamepad_south'"), "{}", message);
+        // nothing close, so only the examples
+        let message = KeyBind::from_name("xyzzy_plugh").err().unwrap();
+        assert!(!message.contains("did you mean"), "{}", message);
+    }
+
+    #[test]
+    fn pad_fallbacks_round_trip_by_name() {
+        let written = toml::to_string(&Fallbacks {
+            pad_axis_fallbacks: vec![PadAxisFallback {
+                axis: GamepadAxisType::RightZ,
+                direction: AxisDirection::Positive,
+                button: GamepadButtonType::RightTrigger2,
+            }],
+        })
+        .unwrap();
+        assert!(written.contains("axis = \"gamepad_rt\""), "{}", written);
+        assert!(
+            written.contains("button = \"gamepad_rt_button\""),
+            "{}",
+            written
+        );
+
+        let read = toml::from_str::<Fallbacks>(&written).unwrap();
+        assert_eq!(
+            fallback_names(&read.pad_axis_fallbacks[0]),
+            (
+                Some("gamepad_rt".to_string()),
+                Some("gamepad_rt_button".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn pad_fallbacks_still_read_bevy_names() {
+        let read = toml::from_str::<Fallbacks>(
+            r#"
+            [[pad_axis_fallbacks]]
+            axis = "LeftZ"
+            direction = "Positive"
+            button = "LeftTrigger2"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            fallback_names(&read.pad_axis_fallbacks[0]),
+            (
+                Some("gamepad_lt".to_string()),
+                Some("gamepad_lt_button".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_pad_fallback_name_suggests_the_nearest() {
+        let error = PadAxisFallback::from_names("gamepad_ltt", "gamepad_lt_button")
+            .err()
+            .unwrap();
+        assert!(error.contains("'gamepad_lt'"), "{}", error);
+
+        // a real name, just not the right kind of control
+        let error = PadAxisFallback::from_names("gamepad_lt_button", "gamepad_lt")
+            .err()
+            .unwrap();
+        assert!(error.contains("isn't a pad stick or trigger"), "{}", error);
+
+        let error = toml::from_str::<Fallbacks>(
+            r#"
+            [[pad_axis_fallbacks]]
+            axis = "gamepad_rt"
+            button = "gamepad_rt_buton"
+            "#,
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(error.contains("'gamepad_rt_button'"), "{}", error);
+    }
+}
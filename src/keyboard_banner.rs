@@ -0,0 +1,276 @@
+use bevy::{input::gamepad::GamepadEvent, prelude::*, sprite::Anchor, text::Text2dBounds};
+use enum_map::Enum;
+
+use crate::{
+    key_label, AppConfig, ControlBindings, EntityLayer, GameConfig, KeyAction, KeyBind,
+    KeyMapPreset, PadFallbackApplied, PlayerIndex, StringKey, StringTable,
+};
+
+const BANNER_FONT_SIZE: f32 = 20.;
+
+/// Each action on a player's line of the banner, movement is shown as one entry.
+const BANNER_ACTIONS: [(StringKey, KeyAction); 5] = [
+    (StringKey::BindingSprint, KeyAction::Sprint),
+    (StringKey::BindingPickupOrThrow, KeyAction::PickupOrThrow),
+    (StringKey::BindingDrop, KeyAction::Drop),
+    (StringKey::BindingInteract, KeyAction::Interact),
+    (StringKey::BindingEmote, KeyAction::Emote),
+];
+
+/// Both players' keyboard bindings, put up at launch when no pad is connected so a pair sharing
+/// one keyboard can see whose keys are whose. Notices about pads reading a fallback button share
+/// the same spot.
+#[derive(Resource, Default)]
+pub struct KeyboardBanner {
+    /// Runs while the banner is up.
+    timer: Option<Timer>,
+    preset_applied: bool,
+    notices: Vec<String>,
+    /// Runs while there are notices up, restarted by each new one.
+    notice_timer: Option<Timer>,
+}
+
+impl KeyboardBanner {
+    pub fn is_showing(&self) -> bool {
+        self.timer.is_some()
+    }
+}
+
+#[derive(Component)]
+pub struct KeyboardBannerText;
+
+pub fn setup_keyboard_banner(
+    mut commands: Commands,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    gamepads: Res<Gamepads>,
+    mut keyboard_banner: ResMut<KeyboardBanner>,
+) {
+    let banner_seconds = game_config.input_hint_config.keyboard_banner_seconds;
+    if banner_seconds > 0. && gamepads.iter().next().is_none() {
+        info!("No gamepads connected, showing both players' keyboard bindings");
+        keyboard_banner.timer = Some(Timer::from_seconds(banner_seconds, TimerMode::Once));
+    }
+
+    let resolution = app_config.base_resolution.as_vec2();
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: BANNER_FONT_SIZE,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            text_anchor: Anchor::BottomCenter,
+            text_2d_bounds: Text2dBounds {
+                size: Vec2::new(resolution.x * 0.8, resolution.y),
+            },
+            transform: Transform::from_translation(Vec3::new(
+                0.,
+                -resolution.y / 2. + BANNER_FONT_SIZE * 2.,
+                EntityLayer::Overlay.z(),
+            )),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        KeyboardBannerText,
+    ));
+}
+
+/// Detection runs again whenever a pad connects, once there's one to play with the banner gets
+/// out of the way.
+pub fn hide_keyboard_banner_on_pad(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    gamepads: Res<Gamepads>,
+    mut keyboard_banner: ResMut<KeyboardBanner>,
+) {
+    let connections = gamepad_events
+        .read()
+        .filter(|event| matches!(event, GamepadEvent::Connection(_)))
+        .count();
+    if connections == 0 || gamepads.iter().next().is_none() {
+        return;
+    }
+    if keyboard_banner.timer.take().is_some() {
+        info!("A gamepad connected, hiding the keyboard bindings");
+    }
+}
+
+/// While the banner is up its key moves both players onto the shared keyboard preset, written
+/// into the live config so everything reading the bindings follows.
+pub fn switch_to_shared_keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut game_config: ResMut<GameConfig>,
+    mut keyboard_banner: ResMut<KeyboardBanner>,
+) {
+    if !keyboard_banner.is_showing()
+        || keyboard_banner.preset_applied
+        || !keyboard_input.just_pressed(game_config.input_hint_config.shared_keyboard_key)
+    {
+        return;
+    }
+
+    for (player_index, per_player) in game_config.player_config.per_player.iter_mut() {
+        per_player.key_map = KeyMapPreset::SharedKeyboard.key_map(player_index);
+    }
+    keyboard_banner.preset_applied = true;
+    // shown for a full stretch again so the new bindings can be read
+    if let Some(timer) = keyboard_banner.timer.as_mut() {
+        timer.reset();
+    }
+    info!("Switched both players to the shared keyboard preset");
+}
+
+/// Tells the players when a pad's missing axis is being read from a button instead, a trigger
+/// that reads as a button can't be pressed halfway and that's worth knowing before it matters.
+pub fn queue_pad_fallback_notices(
+    mut fallback_events: EventReader<PadFallbackApplied>,
+    game_config: Res<GameConfig>,
+    string_table: Res<StringTable>,
+    mut keyboard_banner: ResMut<KeyboardBanner>,
+) {
+    let notice_seconds = game_config.input_hint_config.pad_fallback_notice_seconds;
+    for event in fallback_events.read() {
+        if notice_seconds <= 0. {
+            continue;
+        }
+        let notice = format!(
+            "P{}{}{}{}{}",
+            event.player.into_usize() + 1,
+            string_table.tr(StringKey::PadFallbackNoticeBefore),
+            KeyBind::ControllerButton(event.button).name(),
+            string_table.tr(StringKey::PadFallbackNoticeAfter),
+            KeyBind::ControllerAxis((event.axis, event.direction)).name()
+        );
+        // the same pad can fall back for more than one action bound to the axis
+        if !keyboard_banner.notices.contains(&notice) {
+            keyboard_banner.notices.push(notice);
+        }
+        keyboard_banner.notice_timer = Some(Timer::from_seconds(notice_seconds, TimerMode::Once));
+    }
+}
+
+fn banner_text(
+    control_bindings: &ControlBindings,
+    string_table: &StringTable,
+    preset_applied: bool,
+) -> String {
+    let label = |action: KeyAction, player_index: PlayerIndex| {
+        control_bindings
+            .binding_display(action, player_index)
+            .label()
+    };
+
+    let mut lines = vec![string_table
+        .tr(if preset_applied {
+            StringKey::KeyboardBannerPresetApplied
+        } else {
+            StringKey::KeyboardBannerTitle
+        })
+        .to_string()];
+    for player_index in (0..PlayerIndex::LENGTH).map(PlayerIndex::from_usize) {
+        let movement = [
+            KeyAction::MoveUp,
+            KeyAction::MoveLeft,
+            KeyAction::MoveDown,
+            KeyAction::MoveRight,
+        ]
+        .map(|action| label(action, player_index))
+        .join("/");
+        let mut line = format!(
+            "P{}   {}{}",
+            player_index.into_usize() + 1,
+            string_table.tr(StringKey::BindingMove),
+            movement
+        );
+        for (string_key, action) in BANNER_ACTIONS {
+            line.push_str(&format!(
+                "   {}{}",
+                string_table.tr(string_key),
+                label(action, player_index)
+            ));
+        }
+        lines.push(line);
+    }
+    if !preset_applied {
+        lines.push(format!(
+            "[{}]{}",
+            key_label(
+                control_bindings
+                    .game_config
+                    .input_hint_config
+                    .shared_keyboard_key
+            ),
+            string_table.tr(StringKey::KeyboardBannerPresetHint)
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Keeps the banner's text in step with the live bindings and any pad notices, and fades it out
+/// as the last of them runs out.
+pub fn update_keyboard_banner(
+    time: Res<Time<Real>>,
+    control_bindings: ControlBindings,
+    string_table: Res<StringTable>,
+    mut keyboard_banner: ResMut<KeyboardBanner>,
+    mut banner_query: Query<(&mut Text, &mut Visibility), With<KeyboardBannerText>>,
+) {
+    let Ok((mut text, mut visibility)) = banner_query.get_single_mut() else {
+        return;
+    };
+
+    let bindings_seconds = keyboard_banner.timer.as_mut().map_or(0., |timer| {
+        timer.tick(time.delta());
+        timer.remaining_secs()
+    });
+    if bindings_seconds <= 0. {
+        keyboard_banner.timer = None;
+    }
+    let notice_seconds = keyboard_banner.notice_timer.as_mut().map_or(0., |timer| {
+        timer.tick(time.delta());
+        timer.remaining_secs()
+    });
+    if notice_seconds <= 0. {
+        keyboard_banner.notice_timer = None;
+        keyboard_banner.notices.clear();
+    }
+    let remaining_seconds = bindings_seconds.max(notice_seconds);
+    if remaining_seconds <= 0. {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    }
+
+    if *visibility != Visibility::Inherited {
+        *visibility = Visibility::Inherited;
+    }
+    let mut lines = keyboard_banner.notices.clone();
+    if bindings_seconds > 0. {
+        lines.push(banner_text(
+            &control_bindings,
+            &string_table,
+            keyboard_banner.preset_applied,
+        ));
+    }
+    let value = lines.join("\n");
+    let fade_seconds = control_bindings.game_config.input_hint_config.fade_seconds;
+    let alpha = if fade_seconds > 0. {
+        (remaining_seconds / fade_seconds).min(1.)
+    } else {
+        1.
+    };
+    let Some(section) = text.sections.first_mut() else {
+        return;
+    };
+    if section.value != value {
+        section.value = value;
+    }
+    if section.style.color.a() != alpha {
+        section.style.color.set_a(alpha);
+    }
+}
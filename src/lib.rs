@@ -1,23 +1,121 @@
+mod afk;
+mod arena_layout;
+mod asset_fallback;
+mod attract_mode;
+mod buffer_budget;
+mod bug_report;
+mod camera;
+mod cash_out;
+mod cleanup;
+mod colour_palette;
+mod config_changes;
+mod config_colour;
 mod configuration;
 mod conveyor;
+mod emote;
+mod events;
+mod feedback;
+mod forklift;
+mod frame_dump;
+mod frame_timing;
+mod friendly_fire;
 mod game_mode;
+mod gameplay;
+mod graphics_quality;
+mod hazard;
+mod idle_nudge;
+mod input_glyphs;
+mod instance_lock;
+mod interaction;
+mod key_bind_names;
+mod keyboard_banner;
+mod localization;
+mod match_state;
+mod metrics;
 mod package;
+mod package_pool;
+mod pad_calibration;
 mod player;
+mod player_brain;
+mod powerup;
 mod random;
 mod render_layers;
+mod reproduction;
+mod results_sink;
+mod round_stats;
+mod shift;
+mod spawn_context;
+mod sprint_trail;
 mod sprite_animation;
 mod sprite_render_layers;
+mod sprite_sheet_validation;
 mod supervisor;
+#[cfg(feature = "test-support")]
+mod test_support;
+mod time_dilation;
+mod timer_debug;
+mod trajectory;
+mod tutorial;
 mod user_input;
+mod wave_modifier;
 
+pub use afk::*;
+pub use arena_layout::*;
+pub use asset_fallback::*;
+pub use attract_mode::*;
+pub use buffer_budget::*;
+pub use bug_report::*;
+pub use camera::*;
+pub use cash_out::*;
+pub use cleanup::*;
+pub use colour_palette::*;
+pub use config_changes::*;
+pub use config_colour::*;
 pub use configuration::*;
 pub use conveyor::*;
+pub use emote::*;
+pub use events::*;
+pub use feedback::*;
+pub use forklift::*;
+pub use frame_dump::*;
+pub use frame_timing::*;
+pub use friendly_fire::*;
 pub use game_mode::*;
+pub use gameplay::*;
+pub use graphics_quality::*;
+pub use hazard::*;
+pub use idle_nudge::*;
+pub use input_glyphs::*;
+pub use instance_lock::*;
+pub use interaction::*;
+pub use key_bind_names::*;
+pub use keyboard_banner::*;
+pub use localization::*;
+pub use match_state::*;
+pub use metrics::*;
 pub use package::*;
+pub use package_pool::*;
+pub use pad_calibration::*;
 pub use player::*;
+pub use player_brain::*;
+pub use powerup::*;
 pub use random::*;
 pub use render_layers::*;
+pub use reproduction::*;
+pub use results_sink::*;
+pub use round_stats::*;
+pub use shift::*;
+pub use spawn_context::*;
+pub use sprint_trail::*;
 pub use sprite_animation::*;
 pub use sprite_render_layers::*;
+pub use sprite_sheet_validation::*;
 pub use supervisor::*;
+#[cfg(feature = "test-support")]
+pub use test_support::*;
+pub use time_dilation::*;
+pub use timer_debug::*;
+pub use trajectory::*;
+pub use tutorial::*;
 pub use user_input::*;
+pub use wave_modifier::*;
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use enum_map::{Enum, EnumMap};
+
+use crate::AppConfig;
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Every piece of UI text, matching on this keeps the built in English table complete.
+#[derive(Debug, Enum, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StringKey {
+    TeamScoreLabel,
+    PlayerScoreLabel,
+    NextWaveLabel,
+    LeadLabel,
+    TiedValue,
+    InstanceConflictMessage,
+    StatsDelivered,
+    StatsHighestMultiplier,
+    StatsLongestStreak,
+    StatsTimesCaught,
+    StatsDistanceTravelled,
+    StatsAverageThrowCharge,
+    StatsSeed,
+    StatsWave,
+    StatsCopySeed,
+    MatchRoundLabel,
+    MatchWinnerLabel,
+    MatchDrawnValue,
+    MatchTotalLabel,
+    ChargeHintBefore,
+    ChargeHintAfter,
+    TutorialWalkToBelt,
+    TutorialPickUpBefore,
+    TutorialPickUpAfter,
+    TutorialChargeBefore,
+    TutorialChargeAfter,
+    TutorialDeliver,
+    TutorialStepDone,
+    TutorialFinished,
+    TutorialExitHint,
+    KeyboardBannerTitle,
+    KeyboardBannerPresetHint,
+    KeyboardBannerPresetApplied,
+    PadFallbackNoticeBefore,
+    PadFallbackNoticeAfter,
+    BindingMove,
+    BindingSprint,
+    BindingPickupOrThrow,
+    BindingDrop,
+    BindingInteract,
+    BindingEmote,
+    WaveModifierHeavyFreight,
+    WaveModifierFragileGoods,
+    WaveModifierRushOrder,
+    WaveModifierAudit,
+}
+
+impl StringKey {
+    pub fn english(&self) -> &'static str {
+        match self {
+            StringKey::TeamScoreLabel => "Team Score: ",
+            StringKey::PlayerScoreLabel => "Score: ",
+            StringKey::NextWaveLabel => "Next Wave: ",
+            StringKey::LeadLabel => "Lead: ",
+            StringKey::TiedValue => "Tied",
+            StringKey::InstanceConflictMessage => {
+                "Play Nice is already running\n\n[Q] Quit\n[R] Run anyway (unsafe, nothing will be saved)"
+            }
+            StringKey::StatsDelivered => "Delivered: ",
+            StringKey::StatsHighestMultiplier => "Best multiplier: ",
+            StringKey::StatsLongestStreak => "Longest streak: ",
+            StringKey::StatsTimesCaught => "Caught: ",
+            StringKey::StatsDistanceTravelled => "Distance: ",
+            StringKey::StatsAverageThrowCharge => "Average throw: ",
+            StringKey::StatsSeed => "Seed ",
+            StringKey::StatsWave => "Wave ",
+            StringKey::StatsCopySeed => " Copy",
+            StringKey::MatchRoundLabel => "Round ",
+            StringKey::MatchWinnerLabel => "Match winner: ",
+            StringKey::MatchDrawnValue => "Match drawn",
+            StringKey::MatchTotalLabel => "Match total: ",
+            StringKey::ChargeHintBefore => "Hold ",
+            StringKey::ChargeHintAfter => " to charge",
+            StringKey::TutorialWalkToBelt => "Walk over to the incoming belt",
+            StringKey::TutorialPickUpBefore => "Press ",
+            StringKey::TutorialPickUpAfter => " next to a package to pick it up",
+            StringKey::TutorialChargeBefore => "Hold ",
+            StringKey::TutorialChargeAfter => " to charge up a throw",
+            StringKey::TutorialDeliver => "Throw the package onto your belt by the wall",
+            StringKey::TutorialStepDone => "Nice!",
+            StringKey::TutorialFinished => "That's the job, the shift starts now!",
+            StringKey::TutorialExitHint => " Skip tutorial",
+            StringKey::KeyboardBannerTitle => "No gamepad found, both players are on the keyboard",
+            StringKey::KeyboardBannerPresetHint => " Switch to shared keyboard controls",
+            StringKey::KeyboardBannerPresetApplied => "Switched to shared keyboard controls",
+            StringKey::PadFallbackNoticeBefore => " pad, using ",
+            StringKey::PadFallbackNoticeAfter => " instead of ",
+            StringKey::BindingMove => "Move ",
+            StringKey::BindingSprint => "Sprint ",
+            StringKey::BindingPickupOrThrow => "Pick up/Throw ",
+            StringKey::BindingDrop => "Drop ",
+            StringKey::BindingInteract => "Repair ",
+            StringKey::BindingEmote => "Emote ",
+            StringKey::WaveModifierHeavyFreight => "Heavy freight",
+            StringKey::WaveModifierFragileGoods => "Fragile goods",
+            StringKey::WaveModifierRushOrder => "Rush order",
+            StringKey::WaveModifierAudit => "Audit",
+        }
+    }
+}
+
+/// UI text for the configured language, anything the language file leaves out falls back to
+/// English.
+#[derive(Resource, Default)]
+pub struct StringTable {
+    strings: EnumMap<StringKey, Option<String>>,
+}
+
+impl StringTable {
+    pub fn language_file(locale_directory: &Path, language: &str) -> PathBuf {
+        locale_directory.join(format!("{}.toml", language))
+    }
+
+    pub fn load(app_config: &AppConfig) -> Self {
+        if app_config.language == DEFAULT_LANGUAGE {
+            return Self::default();
+        }
+
+        let path = Self::language_file(&app_config.locale_directory, &app_config.language);
+        let entries = match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| Ok(toml::from_str::<HashMap<String, String>>(&contents)?))
+        {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn!(
+                    "Could not load strings for \"{}\" from {}, using English: {}",
+                    app_config.language,
+                    path.display(),
+                    error
+                );
+                return Self::default();
+            }
+        };
+
+        let mut strings = EnumMap::<StringKey, Option<String>>::default();
+        for (name, value) in entries {
+            match (0..StringKey::LENGTH)
+                .map(StringKey::from_usize)
+                .find(|key| format!("{:?}", key) == name)
+            {
+                Some(key) => strings[key] = Some(value),
+                None => warn!("{} has an unknown string key \"{}\"", path.display(), name),
+            }
+        }
+
+        // reported here once rather than every time the string is shown
+        for (key, value) in &strings {
+            if value.is_none() {
+                warn!(
+                    "No \"{}\" string for {:?}, using English",
+                    app_config.language, key
+                );
+            }
+        }
+
+        Self { strings }
+    }
+
+    pub fn tr(&self, key: StringKey) -> &str {
+        self.strings[key].as_deref().unwrap_or(key.english())
+    }
+}
@@ -1,4 +1,6 @@
 use bevy::{
+    input::InputSystem,
+    log::LogPlugin,
     prelude::*,
     render::camera::ScalingMode,
     sprite::Anchor,
@@ -6,25 +8,171 @@ use bevy::{
     window::WindowResolution,
 };
 use bevy_rapier2d::prelude::*;
-use enum_map::{enum_map, EnumMap};
+use clap::Parser;
+use enum_map::enum_map;
 use std::path::PathBuf;
 
 use play_nice::*;
 
-#[derive(Component)]
-pub struct WallTag;
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Write the default config to this path and exit, for packagers wanting a reference file.
+    #[arg(long, value_name = "PATH")]
+    write_default_config: Option<PathBuf>,
+    /// Seed for the random number generator, overriding the config. Takes a reproduction
+    /// string from the results screen too.
+    #[arg(long, value_parser = parse_seed)]
+    seed: Option<u64>,
+    /// Base resolution as WIDTHxHEIGHT, overriding the config.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_resolution)]
+    resolution: Option<UVec2>,
+    /// Texture pack to use, overriding the config.
+    #[arg(long, value_name = "NAME")]
+    texture_pack: Option<String>,
+    /// Start in the tutorial that walks a new player through the game.
+    #[arg(long)]
+    tutorial: bool,
+    /// Dump rendered frames to this directory as numbered PNGs, on top of any frame_dump in the
+    /// config.
+    #[arg(long, value_name = "DIR", env = "RECORD_FRAMES")]
+    record_frames: Option<PathBuf>,
+    /// Fixed tick to start recording at, counted from launch.
+    #[arg(long, value_name = "TICK", requires = "record_frames")]
+    record_start_tick: Option<u64>,
+    /// Fixed tick to stop recording at, recording runs until exit without it.
+    #[arg(long, value_name = "TICK", requires = "record_frames")]
+    record_end_tick: Option<u64>,
+}
+
+fn parse_resolution(value: &str) -> Result<UVec2, String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {}", value))?;
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<u32>()
+            .map_err(|error| format!("{}: {}", part, error))
+    };
+    Ok(UVec2::new(parse(width)?, parse(height)?))
+}
 
 fn main() -> anyhow::Result<()> {
+    install_panic_hook();
     dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    if let Some(path) = cli.write_default_config {
+        write_default_config(&path)?;
+        println!("wrote default config to {}", path.display());
+        return Ok(());
+    }
 
-    let config_path = dotenv::var("CONFIG_PATH").ok().map(|s| PathBuf::from(s));
-    let config = read_config(config_path)?;
+    // a second instance must not touch any files the first one owns, including the config
+    let lock_outcome =
+        acquire_instance_lock(&instance_lock_path(), std::process::id(), is_process_alive)?;
+    let output_policy = lock_outcome.output_policy();
+    let (instance_lock, instance_conflict) = match lock_outcome {
+        InstanceLockOutcome::Acquired(instance_lock) => (Some(instance_lock), None),
+        InstanceLockOutcome::HeldBy(other_pid) => (None, Some(InstanceConflict { other_pid })),
+    };
 
+    let config_path = dotenv::var("CONFIG_PATH").ok().map(PathBuf::from);
+    let overrides = ConfigOverrides {
+        rng_seed: cli.seed,
+        base_resolution: cli.resolution,
+        texture_pack: cli.texture_pack,
+        tutorial: cli.tutorial,
+    };
+    let (mut config, config_layers) = read_config(config_path, &overrides, output_policy)?;
     let rng = Rand::new(&config.app.rng_seed);
+    // a seed picked from entropy is recorded too, so reports and the results screen can name it
+    config.app.rng_seed = Some(rng.seed());
+    let report_directory = config_layers
+        .iter()
+        .find_map(|layer| match layer {
+            ConfigLayer::File(path) => path.parent().map(|parent| parent.to_path_buf()),
+            _ => None,
+        })
+        .unwrap_or_else(config_directory);
+    BugReportContext::new(&config, &report_directory, output_policy)?.install();
+    set_log_buffer_lines(config.app.log_buffer_lines);
+    config.game.player_config.validate()?;
+    config.game.shift_config.validate()?;
+    config.game.validate_layout()?;
+    config.game.validate_texture_selection()?;
+    config.app.colour_palette.apply(&mut config.game);
+    let match_state = MatchState::default();
+    let mut arena_layout = ArenaLayout::new(&config.app, &config.game, &match_state.sides);
+    arena_layout.validate()?;
+    let tutorial = config
+        .app
+        .tutorial
+        .start_in_tutorial
+        .then(|| Tutorial::new(PlayerIndex::Player1));
+    if let Some(tutorial) = &tutorial {
+        arena_layout = arena_layout.for_tutorial(match_state.sides[tutorial.player]);
+        arena_layout.validate()?;
+    }
+
+    let metrics = config
+        .app
+        .metrics_path
+        .clone()
+        .filter(|_| output_policy.allows_writes())
+        .map(|path| Metrics::new(path, config.app.metrics_interval_seconds));
 
-    App::new()
-        .add_plugins(
-            DefaultPlugins.set(WindowPlugin {
+    let results_reporter = ResultsReporter::from_config(&config, output_policy);
+    let session_seed = SessionSeed::new(rng.seed(), &config);
+
+    let game_state = GameState::from_config(&config.game);
+    let mut attract_mode = AttractMode::new(&config.game);
+    if tutorial.is_some() {
+        attract_mode.active = false;
+    }
+    let string_table = StringTable::load(&config.app);
+    let buffer_budgets = BufferBudgets::new(config.app.observability_memory_budget_mb);
+    let camera_mode = config.app.camera_mode;
+    let debug_overlays = config.app.debug_overlays;
+    let debug_physics_render = config.app.debug_physics_render;
+    let powerups_enabled = config.game.powerups.is_some();
+    let forklift_enabled = config.game.forklift_config.enabled;
+    let wave_modifiers_enabled = config.game.wave_modifier_config.enabled;
+
+    if let Some(record_frames) = cli.record_frames {
+        let frame_dump_config = config.app.frame_dump.get_or_insert(FrameDumpConfig {
+            directory: PathBuf::new(),
+            every_n_ticks: 1,
+            start_tick: 0,
+            end_tick: None,
+        });
+        frame_dump_config.directory = record_frames;
+        if let Some(start_tick) = cli.record_start_tick {
+            frame_dump_config.start_tick = start_tick;
+        }
+        if cli.record_end_tick.is_some() {
+            frame_dump_config.end_tick = cli.record_end_tick;
+        }
+        if let Some(end_tick) = frame_dump_config.end_tick {
+            anyhow::ensure!(
+                end_tick >= frame_dump_config.start_tick,
+                "frame recording would end at tick {} before it starts at tick {}",
+                end_tick,
+                frame_dump_config.start_tick
+            );
+        }
+    }
+    let frame_dump = config
+        .app
+        .frame_dump
+        .clone()
+        .filter(|_| output_policy.allows_writes())
+        .map(FrameDump::new)
+        .transpose()?;
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
                 primary_window: Some(Window {
                     resolution: WindowResolution::new(
                         config.app.base_resolution.x as f32,
@@ -35,63 +183,271 @@ fn main() -> anyhow::Result<()> {
                     ..default()
                 }),
                 ..default()
+            })
+            .set(LogPlugin {
+                filter: log_filter(&config.app),
+                update_subscriber: Some(add_ring_buffer_layer),
+                ..default()
             }),
-        )
-        .add_plugins(SpriteLayerPlugin::<RenderLayers>::default())
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
-            config.game.package_config.size * 2.,
-        ))
-        .add_plugins(RapierDebugRenderPlugin {
+    )
+    .add_plugins(SpriteLayerPlugin::<RenderLayers>::default())
+    .add_plugins(GameplayPlugin)
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+        config.game.pixels_per_meter(),
+    ))
+    .insert_resource(QualitySettings::new(&config.app))
+    .insert_resource(arena_layout)
+    .insert_resource(match_state)
+    .insert_resource(config.app)
+    .insert_resource(config.game)
+    .insert_resource(CosmeticRand::new(&rng))
+    .insert_resource(rng)
+    .insert_resource(session_seed)
+    .insert_resource(attract_mode)
+    .insert_resource(string_table)
+    .insert_resource(buffer_budgets)
+    .insert_resource(output_policy)
+    .init_resource::<CameraShake>()
+    .init_resource::<LoadedGameAssets>()
+    .init_resource::<PadCalibrationScreen>()
+    .init_resource::<ConfigChangeLog>()
+    .init_resource::<ArenaGeometry>()
+    .init_resource::<ShiftState>()
+    .init_resource::<KeyboardBanner>()
+    .init_resource::<TimeScale>()
+    .insert_resource(game_state)
+    .insert_resource(ConfigLayers(config_layers))
+    .add_systems(
+        Startup,
+        (
+            log_config_layers,
+            setup_camera,
+            setup_supervisor,
+            spawn_trajectory_previews,
+            show_instance_conflict_prompt,
+            setup_keyboard_banner,
+            setup_heartbeat_vignette,
+        ),
+    )
+    .add_systems(PreUpdate, sample_controller_input.after(InputSystem))
+    .add_systems(
+        Update,
+        (
+            animate_sprite_maps,
+            select_sprite_facing_index,
+            update_score_multipiers,
+            update_scores,
+            (update_wave_preview, update_match_display),
+            track_config_changes,
+            (update_idle_nudges, update_charge_hints),
+            update_front_grab_zones,
+            (
+                update_capacity_indicators,
+                update_belt_arrows,
+                pace_belt_animations.before(animate_sprite_maps),
+                tint_packages,
+            ),
+            (
+                cache_arena_geometry,
+                update_trajectory_previews,
+                update_aim_lock_glows,
+            )
+                .chain(),
+            (start_shifts, fade_shift_title_cards).chain(),
+            (
+                reset_delivery_streaks,
+                reset_round_stats,
+                record_round_events,
+                show_round_stats,
+            )
+                .chain(),
+            (handle_instance_conflict_prompt, handle_window_focus),
+            (
+                toggle_pad_calibration,
+                sample_pad_calibration,
+                update_stick_markers.run_if(PadCalibrationScreen::is_open),
+            )
+                .chain(),
+            update_cursor_aim,
+            (
+                hide_keyboard_banner_on_pad,
+                switch_to_shared_keyboard,
+                queue_pad_fallback_notices,
+                update_keyboard_banner,
+            )
+                .chain(),
+            (validate_sprite_sheets, verify_game_assets),
+            (
+                (queue_feedback, apply_camera_shake, fade_screen_flashes).chain(),
+                (
+                    spawn_sprint_trails.run_if(QualitySettings::wants_full_effects),
+                    fade_sprint_trails,
+                )
+                    .chain(),
+                (
+                    trigger_slow_motion,
+                    apply_time_scale,
+                    update_heartbeat_vignette,
+                )
+                    .chain(),
+            ),
+            rumble_feedback,
+            bevy::window::close_on_esc,
+        ),
+    );
+    app.add_systems(
+        Update,
+        (
+            update_bug_report_summary,
+            report_bug_on_key,
+            count_seeded_waves,
+            copy_reproduction_string,
+        ),
+    )
+    .add_systems(
+        Update,
+        (toggle_graphics_quality, apply_graphics_quality).chain(),
+    )
+    .add_systems(Last, log_buffer_budgets_on_exit);
+
+    if debug_physics_render {
+        app.add_plugins(RapierDebugRenderPlugin {
             mode: DebugRenderMode::all(),
             ..default()
-        })
-        .insert_resource(config.app)
-        .insert_resource(config.game)
-        .insert_resource(rng)
-        .insert_resource(GameState {
-            player_scores: default(),
-            package_wave_timer: Timer::from_seconds(5., TimerMode::Once),
-            player_controls: default(),
-        })
-        .add_systems(
-            Startup,
-            (setup_camera, setup_world, setup_supervisor, setup_players),
-        )
-        .add_systems(
+        });
+    }
+
+    if let Some(instance_lock) = instance_lock {
+        app.insert_resource(instance_lock)
+            .add_systems(Last, release_instance_lock_on_exit);
+    }
+    if let Some(instance_conflict) = instance_conflict {
+        app.insert_resource(instance_conflict);
+    }
+
+    if let Some(metrics) = metrics {
+        app.insert_resource(metrics)
+            .add_systems(Update, write_metrics)
+            .add_systems(Last, write_metrics_summary_on_exit);
+    }
+
+    if let Some(results_reporter) = results_reporter {
+        app.insert_resource(results_reporter)
+            .add_systems(Update, report_round_results);
+    }
+
+    if let Some(frame_dump) = frame_dump {
+        app.insert_resource(frame_dump)
+            .add_systems(FixedFirst, count_frame_dump_ticks)
+            .add_systems(Last, (capture_frame_dump, pace_frame_dump).chain());
+    }
+
+    if powerups_enabled {
+        app.init_resource::<PowerupSpawner>().add_systems(
             FixedUpdate,
             (
-                gamepad_connected,
-                update_controller_mappings,
-                spawn_package_wave,
-                move_player,
-                update_conveyors,
-                player_charge_throw,
-                throw_package,
-                check_for_delivered_packages,
-                update_supervisor,
-                check_supervisor_can_see_players,
+                spawn_powerups,
+                expire_powerups,
+                collect_powerups,
+                update_active_modifiers,
             )
-                .chain(),
-        )
-        .add_systems(
-            PostUpdate,
-            (pickup_package, collect_packages_on_outgoing_conveyors),
-        )
-        .add_systems(
+                .chain()
+                .after(contain_players)
+                .before(player_charge_throw)
+                .run_if(no_instance_conflict)
+                .run_if(not_in_tutorial),
+        );
+    }
+
+    if forklift_enabled {
+        app.init_resource::<ForkliftSchedule>().add_systems(
+            FixedUpdate,
+            (spawn_forklift, update_forklift)
+                .chain()
+                .after(contain_players)
+                .before(player_charge_throw)
+                .run_if(no_instance_conflict)
+                .run_if(not_in_tutorial),
+        );
+    }
+
+    if wave_modifiers_enabled {
+        app.add_systems(
+            FixedUpdate,
+            (
+                weigh_wave_cargo,
+                break_fragile_packages,
+                resolve_wave_modifier,
+            )
+                .chain()
+                .after(check_for_delivered_packages)
+                .run_if(no_instance_conflict),
+        );
+    }
+
+    if let Some(tutorial) = tutorial {
+        app.insert_resource(tutorial)
+            .add_systems(Startup, setup_tutorial)
+            .add_systems(
+                FixedUpdate,
+                feed_tutorial_belt
+                    .before(update_conveyors)
+                    .run_if(no_instance_conflict)
+                    .run_if(in_tutorial),
+            )
+            .add_systems(
+                Update,
+                (
+                    check_tutorial_steps,
+                    update_tutorial_guidance,
+                    exit_tutorial,
+                )
+                    .chain()
+                    .run_if(in_tutorial),
+            );
+    }
+
+    if debug_overlays {
+        app.init_resource::<TimerDebugOverlay>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        toggle_timer_debug_overlay,
+                        update_timer_debug_overlay.run_if(TimerDebugOverlay::is_visible),
+                    )
+                        .chain(),
+                    check_screen_ui_band,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                audit_round_entities.after(despawn_round_entities),
+            );
+    }
+
+    if camera_mode == CameraMode::SplitVertical {
+        app.add_systems(
             Update,
             (
-                animate_sprite_maps,
-                select_sprite_facing_index,
-                update_score_multipiers,
-                update_scores,
-                bevy::window::close_on_esc,
+                follow_players_with_cameras.before(apply_camera_shake),
+                update_split_viewports,
+                assign_score_ui_to_cameras,
             ),
-        )
-        .run();
+        );
+    }
+
+    app.run();
 
     Ok(())
 }
 
+fn log_config_layers(config_layers: Res<ConfigLayers>) {
+    for layer in &config_layers.0 {
+        info!("config layer: {:?}", layer);
+    }
+}
+
 fn setup_camera(mut commands: Commands, app_config: Res<AppConfig>) {
     // default projection has 0.1 near and 1000. far, but Camera2dBundle defaults to -1000. near and 1000. far
     // start with the bundle defaults and mutate the projection scaling mode
@@ -100,229 +456,72 @@ fn setup_camera(mut commands: Commands, app_config: Res<AppConfig>) {
         width: app_config.base_resolution.x as f32,
         height: app_config.base_resolution.y as f32,
     };
-    commands.spawn(camera_bundle);
+    match app_config.camera_mode {
+        CameraMode::Shared => {
+            commands.spawn((camera_bundle, MainCamera, CameraAnchor::default()));
+        }
+        CameraMode::SplitVertical => spawn_split_cameras(&mut commands, &app_config),
+    }
 }
 
-fn setup_players(
+#[allow(clippy::too_many_arguments)]
+fn setup_supervisor(
     mut commands: Commands,
+    mut ctx: SpawnContext,
     asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     app_config: Res<AppConfig>,
     game_config: Res<GameConfig>,
-    mut rng: ResMut<Rand>,
+    string_table: Res<StringTable>,
+    arena_layout: Res<ArenaLayout>,
+    tutorial: Option<Res<Tutorial>>,
 ) {
-    spawn_player(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        Vec3::new(
-            -(app_config.base_resolution.x as f32 / 2.)
-                + game_config.conveyor_config.size.x
-                + (game_config.player_config.size / 2.),
-            0.,
-            0.,
-        ),
-        PlayerIndex::Player1,
-        &mut rng,
-        &game_config,
-    );
-
-    for i in 0..5 {
-        spawn_package(
-            &mut commands,
-            &asset_server,
-            &game_config,
-            Vec3::new(
-                -(app_config.base_resolution.x as f32 / 2.)
-                    + game_config.conveyor_config.size.x
-                    + (game_config.player_config.size / 2.)
-                    + 100.
-                    + game_config.package_config.size * 1.5 * i as f32,
-                0.,
-                0.,
-            ),
-        );
+    // the tutorial has nobody watching, the supervisors turn up when it ends
+    if tutorial.is_none() {
+        spawn_supervisors(&mut commands, &mut ctx);
     }
 
-    spawn_player(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        Vec3::new(
-            (app_config.base_resolution.x as f32 / 2.)
-                - game_config.conveyor_config.size.x
-                - (game_config.player_config.size / 2.),
-            0.,
-            0.,
-        ),
-        PlayerIndex::Player2,
-        &mut rng,
-        &game_config,
-    );
-}
-
-fn setup_world(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    game_config: Res<GameConfig>,
-    app_config: Res<AppConfig>,
-    mut rapier_config: ResMut<RapierConfiguration>,
-) {
-    rapier_config.gravity = Vec2::ZERO;
-    let conveyor_walkway_size = Vec2::new(
-        game_config.conveyor_config.size.x * 2.,
-        game_config.supervisor_config.office_sprite_size.y as f32,
-    );
-    let incoming_belt_length = app_config.base_resolution.y as f32
-        - game_config.supervisor_config.office_sprite_size.y as f32
-        - conveyor_walkway_size.y;
-    let outgoing_belt_length = app_config.base_resolution.y as f32
-        - game_config.supervisor_config.office_sprite_size.y as f32;
-    spawn_conveyor(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        &game_config,
-        Vec3::new(-game_config.conveyor_config.size.x / 2., 0., 0.),
-        incoming_belt_length,
-        PlayAreaAligment::Left,
-        ConveyorLabelTag::Incoming,
-    );
-    spawn_conveyor(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        &game_config,
-        Vec3::new(
-            -(app_config.base_resolution.x as f32 / 2.) + (game_config.conveyor_config.size.x / 2.),
-            -(app_config.base_resolution.y as f32 / 2.) + (outgoing_belt_length / 2.),
-            0.,
-        ),
-        outgoing_belt_length,
-        PlayAreaAligment::Left,
-        ConveyorLabelTag::Outgoing(PlayerIndex::Player1),
-    );
-
-    spawn_conveyor(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        &game_config,
-        Vec3::new(game_config.conveyor_config.size.x / 2., 0., 0.),
-        incoming_belt_length,
-        PlayAreaAligment::Right,
-        ConveyorLabelTag::Incoming,
-    );
-    spawn_conveyor(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        &game_config,
-        Vec3::new(
-            (app_config.base_resolution.x as f32 / 2.) - (game_config.conveyor_config.size.x / 2.),
-            -(app_config.base_resolution.y as f32 / 2.) + (outgoing_belt_length / 2.),
-            0.,
-        ),
-        outgoing_belt_length,
-        PlayAreaAligment::Right,
-        ConveyorLabelTag::Outgoing(PlayerIndex::Player2),
-    );
-
-    spawn_walls(
-        &mut commands,
-        &app_config,
-        &game_config,
-        incoming_belt_length,
-    );
-
-    let conveyor_walkway_pos = Vec2::new(
-        0.,
-        -((app_config.base_resolution.y as f32 / 2.) - (conveyor_walkway_size.y / 2.)),
-    );
-
-    commands.spawn((
-        RigidBody::KinematicPositionBased,
-        Sensor,
-        Collider::cuboid(conveyor_walkway_size.x / 2., conveyor_walkway_size.y / 2.),
-        TransformBundle {
-            local: Transform::from_translation(conveyor_walkway_pos.extend(0.)),
-            ..default()
-        },
-        RenderLayers::Single(EntityLayer::Debugging),
-    ));
-
-    let texture_pack = game_config.get_texture_pack();
-    let background_sprite = texture_pack.choose_texture_for(TextureTarget::Background, None);
+    // faint floor tint marking where carrying a package in view gets it confiscated
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
-                custom_size: Some(Vec2::new(
-                    app_config.base_resolution.x as f32,
-                    app_config.base_resolution.y as f32,
-                )),
+                color: Color::RED.with_a(game_config.supervisor_config.restricted_zone_tint_alpha),
+                custom_size: Some(arena_layout.restricted_zone.size()),
                 ..default()
             },
-            transform: Transform {
-                translation: Vec3::new(0., 0., 0.),
-                ..default()
-            },
-            texture: asset_server
-                .load(&format!("{}/{}", texture_pack.root, background_sprite.path)),
+            transform: Transform::from_translation(
+                arena_layout.restricted_zone.center().extend(0.),
+            ),
             ..default()
         },
-        RenderLayers::Single(EntityLayer::Background),
+        RenderLayers::Single(EntityLayer::Furniture),
     ));
-}
-
-fn setup_supervisor(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    app_config: Res<AppConfig>,
-    game_config: Res<GameConfig>,
-    mut rng: ResMut<Rand>,
-) {
-    spawn_supervisor(
-        &mut commands,
-        &asset_server,
-        &mut texture_atlas_layouts,
-        Vec3::new(0., game_config.supervisor_config.monitoring_y_pos, 0.),
-        &mut rng,
-        &game_config,
-    );
 
     let texture_pack = game_config.get_texture_pack();
     let display_sprite = texture_pack.choose_texture_for(TextureTarget::ScoreDisplay, None);
-    let display_sprite_handle =
-        asset_server.load(&format!("{}/{}", texture_pack.root, display_sprite.path));
-    let team_display_size = Vec2::new(
-        game_config.supervisor_config.office_sprite_size.x as f32 * 0.5,
-        24.,
-    );
-    let team_display_pos = Vec2::new(
-        0.,
-        -(game_config.supervisor_config.office_sprite_size.y as f32 / 2.),
+    let display_sprite_path = format!("{}/{}", texture_pack.root, display_sprite.path);
+    let display_sprite_handle = asset_server.load(&display_sprite_path);
+    let texture_source = format!("texture pack '{}'", game_config.selected_texture_pack);
+    let display_texture = TrackedTexture::new(
+        TextureTarget::ScoreDisplay,
+        &texture_source,
+        display_sprite_path,
     );
+    let displays = &arena_layout.displays;
+    let team_display_size = displays.team.size();
+    let team_display_pos = Vec2::new(displays.team.center().x, displays.team.min.y);
     let team_display_border: f32 = 6.;
-    let player_displays_size = enum_map! {
-        PlayerIndex::Player1 => Vec2::new(
-            game_config.supervisor_config.office_sprite_size.x as f32 * 0.5,
-            24.,
-        ),
-        PlayerIndex::Player2 => Vec2::new(
-            game_config.supervisor_config.office_sprite_size.x as f32 * 0.5,
-            24.,
-        ),
-    };
+    let wave_preview_size = displays.wave_preview.size();
+    let wave_preview_pos = Vec2::new(
+        displays.wave_preview.center().x,
+        displays.wave_preview.min.y,
+    );
+    let match_display_pos = Vec2::new(displays.match_score.center().x, displays.match_score.min.y);
+    let player_displays_size = displays.players.map(|_, display| display.size());
     let player_displays_pos = enum_map! {
-        PlayerIndex::Player1 => Vec2::new(
-            -(app_config.base_resolution.x as f32 / 2.) + (player_displays_size[PlayerIndex::Player1].x * 0.5),
-            12.,
-        ),
+        PlayerIndex::Player1 => displays.players[PlayerIndex::Player1].min,
         PlayerIndex::Player2 => Vec2::new(
-            (app_config.base_resolution.x as f32 / 2.) - (player_displays_size[PlayerIndex::Player2].x * 0.5),
-            12.,
+            displays.players[PlayerIndex::Player2].max.x,
+            displays.players[PlayerIndex::Player2].min.y,
         ),
     };
 
@@ -332,34 +531,40 @@ fn setup_supervisor(
         PlayerIndex::Player1 => 6.,
         PlayerIndex::Player2 => 6.,
     };
-    let supervisor_office_sprite =
-        texture_pack.choose_texture_for(TextureTarget::SupervisorOffice, None);
+    let (supervisor_office_path, office_source) =
+        if let Some(texture) = &game_config.get_layout().office_override {
+            (
+                texture.choose_texture(None).path.clone(),
+                format!("layout '{}'", game_config.selected_layout),
+            )
+        } else {
+            let supervisor_office_sprite =
+                texture_pack.choose_texture_for(TextureTarget::SupervisorOffice, None);
+            (
+                format!("{}/{}", texture_pack.root, supervisor_office_sprite.path),
+                texture_source,
+            )
+        };
     commands
         .spawn((
             SpriteBundle {
                 sprite: Sprite {
-                    custom_size: Some(Vec2::new(
-                        app_config.base_resolution.x as f32,
-                        game_config.supervisor_config.office_sprite_size.y as f32,
-                    )),
+                    custom_size: Some(arena_layout.office.size()),
                     ..default()
                 },
                 transform: Transform {
-                    translation: Vec3::new(
-                        0.,
-                        (app_config.base_resolution.y as f32 / 2.)
-                            - (game_config.supervisor_config.office_sprite_size.y as f32 / 2.),
-                        0.,
-                    ),
+                    translation: arena_layout.office.center().extend(0.),
                     ..default()
                 },
-                texture: asset_server.load(&format!(
-                    "{}/{}",
-                    texture_pack.root, supervisor_office_sprite.path
-                )),
+                texture: asset_server.load(&supervisor_office_path),
                 ..default()
             },
             RenderLayers::Single(EntityLayer::OfficeLevelFurniture),
+            TrackedTexture::new(
+                TextureTarget::SupervisorOffice,
+                office_source,
+                supervisor_office_path,
+            ),
         ))
         .with_children(|builder| {
             builder
@@ -368,162 +573,155 @@ fn setup_supervisor(
                     team_display_size,
                     Anchor::BottomCenter,
                     &display_sprite_handle,
+                    &display_texture,
                 ))
                 .with_children(|builder| {
-                    builder.spawn((
-                        make_score_text(
-                            "Team Score: ",
-                            team_colour,
-                            team_display_size - Vec2::new(team_display_border * 2., 0.),
-                            team_display_size.x / 2. - team_display_border,
-                        ),
-                        PlayerScoreTag::All,
-                    ));
+                    if app_config.ui_mode == UiMode::WorldSpace {
+                        builder.spawn((
+                            make_score_text(
+                                string_table.tr(game_config.game_mode.overall_label()),
+                                *team_colour,
+                                team_display_size - Vec2::new(team_display_border * 2., 0.),
+                                team_display_size.x / 2. - team_display_border,
+                            ),
+                            PlayerScoreTag::All,
+                        ));
+                    }
                 });
 
+            // next wave preview sits just above the team score
             builder
                 .spawn(make_display_sprite(
-                    player_displays_pos[PlayerIndex::Player1],
-                    player_displays_size[PlayerIndex::Player1],
-                    Anchor::BottomLeft,
+                    wave_preview_pos,
+                    wave_preview_size,
+                    Anchor::BottomCenter,
                     &display_sprite_handle,
+                    &display_texture,
                 ))
                 .with_children(|builder| {
                     builder.spawn((
                         make_score_text(
-                            "Score: ",
-                            player_configs[PlayerIndex::Player1].colour,
-                            player_displays_size[PlayerIndex::Player1]
-                                - Vec2::new(player_displays_border[PlayerIndex::Player1] * 2., 0.),
-                            player_displays_size[PlayerIndex::Player1].x
-                                - player_displays_border[PlayerIndex::Player1],
+                            string_table.tr(StringKey::NextWaveLabel),
+                            *team_colour,
+                            wave_preview_size - Vec2::new(team_display_border * 2., 0.),
+                            wave_preview_size.x / 2. - team_display_border,
                         ),
-                        PlayerScoreTag::Player(PlayerIndex::Player1),
+                        WavePreviewText,
+                    ));
+                    builder.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: *team_colour,
+                                custom_size: Some(Vec2::new(
+                                    wave_preview_size.x - team_display_border * 2.,
+                                    WAVE_PREVIEW_BAR_HEIGHT,
+                                )),
+                                anchor: Anchor::BottomLeft,
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(
+                                -wave_preview_size.x / 2. + team_display_border,
+                                0.,
+                                SCREEN_UI_TEXT_Z,
+                            )),
+                            ..default()
+                        },
+                        WavePreviewBar {
+                            full_width: wave_preview_size.x - team_display_border * 2.,
+                        },
                     ));
                 });
 
+            // rounds won only mean something once rounds can end
+            if game_config.match_config.round_seconds.is_some() {
+                builder
+                    .spawn(make_display_sprite(
+                        match_display_pos,
+                        wave_preview_size,
+                        Anchor::BottomCenter,
+                        &display_sprite_handle,
+                        &display_texture,
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn((
+                            make_score_text(
+                                string_table.tr(StringKey::MatchRoundLabel),
+                                *team_colour,
+                                wave_preview_size - Vec2::new(team_display_border * 2., 0.),
+                                wave_preview_size.x / 2. - team_display_border,
+                            ),
+                            MatchScoreText,
+                        ));
+                    });
+            }
+
             builder
-                .spawn(make_display_sprite(
-                    player_displays_pos[PlayerIndex::Player2],
-                    player_displays_size[PlayerIndex::Player2],
-                    Anchor::BottomRight,
-                    &display_sprite_handle,
+                .spawn((
+                    make_display_sprite(
+                        player_displays_pos[PlayerIndex::Player1],
+                        player_displays_size[PlayerIndex::Player1],
+                        Anchor::BottomLeft,
+                        &display_sprite_handle,
+                        &display_texture,
+                    ),
+                    PlayerScoreDisplay {
+                        player: PlayerIndex::Player1,
+                    },
                 ))
                 .with_children(|builder| {
-                    builder.spawn((
-                        make_score_text(
-                            "Score: ",
-                            player_configs[PlayerIndex::Player2].colour,
-                            player_displays_size[PlayerIndex::Player2]
-                                - Vec2::new(player_displays_border[PlayerIndex::Player2] * 2., 0.),
-                            -player_displays_border[PlayerIndex::Player2],
-                        ),
-                        PlayerScoreTag::Player(PlayerIndex::Player2),
-                    ));
+                    if app_config.ui_mode == UiMode::WorldSpace {
+                        builder.spawn((
+                            make_score_text(
+                                string_table.tr(StringKey::PlayerScoreLabel),
+                                *player_configs[PlayerIndex::Player1].colour,
+                                player_displays_size[PlayerIndex::Player1]
+                                    - Vec2::new(
+                                        player_displays_border[PlayerIndex::Player1] * 2.,
+                                        0.,
+                                    ),
+                                player_displays_size[PlayerIndex::Player1].x
+                                    - player_displays_border[PlayerIndex::Player1],
+                            ),
+                            PlayerScoreTag::Player(PlayerIndex::Player1),
+                        ));
+                    }
+                });
+
+            builder
+                .spawn((
+                    make_display_sprite(
+                        player_displays_pos[PlayerIndex::Player2],
+                        player_displays_size[PlayerIndex::Player2],
+                        Anchor::BottomRight,
+                        &display_sprite_handle,
+                        &display_texture,
+                    ),
+                    PlayerScoreDisplay {
+                        player: PlayerIndex::Player2,
+                    },
+                ))
+                .with_children(|builder| {
+                    if app_config.ui_mode == UiMode::WorldSpace {
+                        builder.spawn((
+                            make_score_text(
+                                string_table.tr(StringKey::PlayerScoreLabel),
+                                *player_configs[PlayerIndex::Player2].colour,
+                                player_displays_size[PlayerIndex::Player2]
+                                    - Vec2::new(
+                                        player_displays_border[PlayerIndex::Player2] * 2.,
+                                        0.,
+                                    ),
+                                -player_displays_border[PlayerIndex::Player2],
+                            ),
+                            PlayerScoreTag::Player(PlayerIndex::Player2),
+                        ));
+                    }
                 });
         });
-}
 
-fn spawn_walls(
-    commands: &mut Commands,
-    app_config: &Res<AppConfig>,
-    game_config: &Res<GameConfig>,
-    incoming_belt_length: f32,
-) {
-    fn make_wall(
-        pos: Vec2,
-        size: Vec2,
-        angle_in_radians: f32,
-    ) -> (RigidBody, TransformBundle, Collider, WallTag) {
-        (
-            RigidBody::Fixed,
-            TransformBundle {
-                local: Transform::from_translation(pos.extend(0.))
-                    .with_rotation(Quat::from_rotation_z(angle_in_radians)),
-                ..default()
-            },
-            Collider::cuboid(size.x / 2., size.y / 2.),
-            WallTag,
-        )
+    if app_config.ui_mode == UiMode::ScreenSpace {
+        spawn_screen_space_scores(&mut commands, &app_config, &game_config, &string_table);
     }
-
-    let wall_width = 10.;
-    let wall_half_width = wall_width / 2.;
-    // right wall
-    commands.spawn(make_wall(
-        Vec2::new(app_config.base_resolution.x as f32 / 2., 0.),
-        Vec2::new(app_config.base_resolution.y as f32, wall_half_width),
-        f32::to_radians(90.),
-    ));
-
-    // left wall
-    commands.spawn(make_wall(
-        Vec2::new(-(app_config.base_resolution.x as f32) / 2., 0.),
-        Vec2::new(app_config.base_resolution.y as f32, wall_half_width),
-        f32::to_radians(90.),
-    ));
-
-    let conveyor_width_adjusted = game_config.conveyor_config.size.x - wall_half_width;
-    // wall at incoming conveyor right -> top wall
-    commands.spawn(make_wall(
-        Vec2::new(
-            conveyor_width_adjusted,
-            (incoming_belt_length / 2.)
-                + (game_config.supervisor_config.office_sprite_size.y as f32 / 2.),
-        ),
-        Vec2::new(
-            game_config.supervisor_config.office_sprite_size.y as f32,
-            wall_width,
-        ),
-        f32::to_radians(90.),
-    ));
-
-    // wall at incoming conveyor left -> top wall
-    commands.spawn(make_wall(
-        Vec2::new(
-            -conveyor_width_adjusted,
-            (incoming_belt_length / 2.)
-                + (game_config.supervisor_config.office_sprite_size.y as f32 / 2.),
-        ),
-        Vec2::new(
-            game_config.supervisor_config.office_sprite_size.y as f32,
-            wall_width,
-        ),
-        f32::to_radians(90.),
-    ));
-
-    let top_wall_width = (app_config.base_resolution.x as f32 / 2.) - conveyor_width_adjusted;
-    let top_wall_half_width = top_wall_width / 2.;
-    // top wall right
-    commands.spawn(make_wall(
-        Vec2::new(
-            conveyor_width_adjusted + top_wall_half_width,
-            (app_config.base_resolution.y as f32 / 2.)
-                - (game_config.supervisor_config.office_sprite_size.y as f32 / 2.)
-                + wall_half_width,
-        ),
-        Vec2::new(top_wall_width, wall_width),
-        0.,
-    ));
-
-    // top wall left
-    commands.spawn(make_wall(
-        Vec2::new(
-            -(conveyor_width_adjusted + top_wall_half_width),
-            (app_config.base_resolution.y as f32 / 2.)
-                - (game_config.supervisor_config.office_sprite_size.y as f32 / 2.)
-                + wall_half_width,
-        ),
-        Vec2::new(top_wall_width as f32, wall_width),
-        0.,
-    ));
-
-    // bottom wall
-    commands.spawn(make_wall(
-        Vec2::new(0., -(app_config.base_resolution.y as f32) / 2.),
-        Vec2::new(app_config.base_resolution.x as f32, wall_width),
-        0.,
-    ));
 }
 
 fn make_display_sprite(
@@ -531,12 +729,13 @@ fn make_display_sprite(
     size: Vec2,
     anchor: Anchor,
     sprite_handle: &Handle<Image>,
-) -> (SpriteBundle, RenderLayers) {
+    tracked_texture: &TrackedTexture,
+) -> (SpriteBundle, RenderLayers, TrackedTexture) {
     (
         SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(size),
-                anchor: anchor,
+                anchor,
                 ..default()
             },
             transform: Transform {
@@ -546,10 +745,105 @@ fn make_display_sprite(
             texture: sprite_handle.clone(),
             ..default()
         },
-        RenderLayers::Single(EntityLayer::OfficeLevelAccent),
+        RenderLayers::Single(EntityLayer::ScreenUi),
+        tracked_texture.clone(),
     )
 }
 
+fn spawn_screen_space_scores(
+    commands: &mut Commands,
+    app_config: &AppConfig,
+    game_config: &GameConfig,
+    string_table: &StringTable,
+) {
+    let player_configs = &game_config.player_config.per_player;
+    let root_node = || NodeBundle {
+        style: Style {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::FlexStart,
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        ..default()
+    };
+    let player_text = |player: PlayerIndex| {
+        (
+            make_score_ui_text(
+                string_table.tr(StringKey::PlayerScoreLabel),
+                *player_configs[player].colour,
+            ),
+            PlayerScoreTag::Player(player),
+        )
+    };
+    let team_text = || {
+        (
+            make_score_ui_text(
+                string_table.tr(game_config.game_mode.overall_label()),
+                *game_config.team_colour,
+            ),
+            PlayerScoreTag::All,
+        )
+    };
+
+    match app_config.camera_mode {
+        CameraMode::Shared => {
+            commands.spawn(root_node()).with_children(|builder| {
+                builder.spawn(player_text(PlayerIndex::Player1));
+                builder.spawn(team_text());
+                builder.spawn(player_text(PlayerIndex::Player2));
+            });
+        }
+        // each half gets its own root so the readouts sit inside that player's viewport, the team
+        // score goes on the inner edge of the left half
+        CameraMode::SplitVertical => {
+            commands
+                .spawn((
+                    root_node(),
+                    ScoreUiRoot {
+                        player: PlayerIndex::Player1,
+                    },
+                ))
+                .with_children(|builder| {
+                    builder.spawn(player_text(PlayerIndex::Player1));
+                    builder.spawn(team_text());
+                });
+            commands
+                .spawn((
+                    root_node(),
+                    ScoreUiRoot {
+                        player: PlayerIndex::Player2,
+                    },
+                ))
+                .with_children(|builder| {
+                    builder.spawn(player_text(PlayerIndex::Player2));
+                });
+        }
+    }
+}
+
+fn make_score_ui_text(score_text: &str, colour: Color) -> TextBundle {
+    TextBundle::from_sections([
+        TextSection::new(
+            score_text,
+            TextStyle {
+                font_size: 20.0,
+                color: colour,
+                ..default()
+            },
+        ),
+        TextSection::new(
+            "0",
+            TextStyle {
+                font_size: 20.0,
+                color: colour,
+                ..default()
+            },
+        ),
+    ])
+}
+
 fn make_score_text(score_text: &str, colour: Color, bounds: Vec2, x_pos: f32) -> Text2dBundle {
     Text2dBundle {
         text: Text::from_sections([
@@ -557,7 +851,7 @@ fn make_score_text(score_text: &str, colour: Color, bounds: Vec2, x_pos: f32) ->
                 score_text,
                 TextStyle {
                     font_size: 20.0,
-                    color: colour.clone(),
+                    color: colour,
                     ..default()
                 },
             ),
@@ -572,12 +866,9 @@ fn make_score_text(score_text: &str, colour: Color, bounds: Vec2, x_pos: f32) ->
         ])
         .with_justify(JustifyText::Right),
         text_anchor: Anchor::BottomRight,
-        text_2d_bounds: Text2dBounds {
-            size: bounds,
-            ..default()
-        },
+        text_2d_bounds: Text2dBounds { size: bounds },
         transform: Transform {
-            translation: Vec3::new(x_pos, 0., 100.),
+            translation: Vec3::new(x_pos, 0., SCREEN_UI_TEXT_Z),
             ..default()
         },
         ..default()
@@ -0,0 +1,444 @@
+use bevy::{prelude::*, sprite::Anchor};
+use enum_map::{enum_map, EnumMap};
+
+use crate::{
+    player_start_position, AimLockGlow, AppConfig, ArenaLayout, AttractMode, CapacityIndicator,
+    ConveyorLabelTag, GameConfig, GameModeKind, GameState, MatchConfig, PlayAreaAligment, Player,
+    PlayerIndex, RoundEnded, RoundResult, ScoreChanged, SessionStarted, SidesSwapped, StringKey,
+    StringTable,
+};
+
+/// Which end of the office each player works, their outgoing belt and score display go with them.
+pub type SideAssignment = EnumMap<PlayerIndex, PlayAreaAligment>;
+
+pub fn player_on_side(sides: &SideAssignment, side: PlayAreaAligment) -> Option<PlayerIndex> {
+    sides
+        .iter()
+        .find(|(_, player_side)| **player_side == side)
+        .map(|(player_index, _)| player_index)
+}
+
+/// A player's score display in the office, mirrored to the other end when sides swap.
+#[derive(Component)]
+pub struct PlayerScoreDisplay {
+    pub player: PlayerIndex,
+}
+
+#[derive(Component)]
+pub struct MatchScoreText;
+
+/// Progress through a best of N match. A match starts whenever play does and is abandoned if
+/// attract mode takes over before it's decided.
+#[derive(Resource)]
+pub struct MatchState {
+    /// The round being played, counted from 1.
+    pub round: usize,
+    pub results: Vec<RoundResult>,
+    pub wins: EnumMap<PlayerIndex, u32>,
+    pub sides: SideAssignment,
+    pub in_progress: bool,
+    pub finished: bool,
+    round_timer: Option<Timer>,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            round: 1,
+            results: Vec::new(),
+            wins: default(),
+            sides: enum_map! {
+                PlayerIndex::Player1 => PlayAreaAligment::Left,
+                PlayerIndex::Player2 => PlayAreaAligment::Right,
+            },
+            in_progress: false,
+            finished: false,
+            round_timer: None,
+        }
+    }
+}
+
+impl MatchState {
+    fn start(&mut self, match_config: &MatchConfig) {
+        self.round = 1;
+        self.results.clear();
+        self.wins = default();
+        self.in_progress = true;
+        self.finished = false;
+        self.round_timer = match_config
+            .round_seconds
+            .map(|round_seconds| Timer::from_seconds(round_seconds, TimerMode::Once));
+    }
+
+    fn record_round(&mut self, result: RoundResult) {
+        if let RoundResult::Winner(player_index) = result {
+            self.wins[player_index] += 1;
+        }
+        self.results.push(result);
+    }
+
+    /// Every round has been played, or someone has won more than half of them.
+    fn is_decided(&self, rounds_per_match: usize) -> bool {
+        self.results.len() >= rounds_per_match
+            || self
+                .wins
+                .values()
+                .any(|wins| *wins as usize * 2 > rounds_per_match)
+    }
+
+    /// Seconds left in the round being played, `None` when rounds aren't timed or no match is
+    /// under way.
+    pub fn round_seconds_left(&self) -> Option<f32> {
+        self.round_timer
+            .as_ref()
+            .filter(|_| self.in_progress)
+            .map(Timer::remaining_secs)
+    }
+
+    /// Whoever won the most rounds, `None` if that's shared.
+    pub fn winner(&self) -> Option<PlayerIndex> {
+        let most_wins = self.wins.values().copied().max()?;
+        let mut leaders = self.wins.iter().filter(|(_, wins)| **wins == most_wins);
+        match (leaders.next(), leaders.next()) {
+            (Some((player_index, _)), None) => Some(player_index),
+            _ => None,
+        }
+    }
+
+    /// Team totals from every round played so far.
+    pub fn team_total(&self) -> i64 {
+        self.results
+            .iter()
+            .map(|result| match result {
+                RoundResult::Team { total } => *total,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// How the match ended, for the results screen.
+    pub fn summary(&self, game_mode: GameModeKind, string_table: &StringTable) -> String {
+        match game_mode {
+            GameModeKind::Team => format!(
+                "{}{}",
+                string_table.tr(StringKey::MatchTotalLabel),
+                self.team_total()
+            ),
+            GameModeKind::Versus => match self.winner() {
+                Some(player_index) => format!(
+                    "{}{:?}",
+                    string_table.tr(StringKey::MatchWinnerLabel),
+                    player_index
+                ),
+                None => string_table.tr(StringKey::MatchDrawnValue).to_string(),
+            },
+        }
+    }
+}
+
+// runs straight after attract mode, which decides whether anyone is playing at all
+#[allow(clippy::too_many_arguments)]
+pub fn update_match(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut match_state: ResMut<MatchState>,
+    mut game_state: ResMut<GameState>,
+    mut session_events: EventWriter<SessionStarted>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut swap_events: EventWriter<SidesSwapped>,
+    mut round_events: EventWriter<RoundEnded>,
+) {
+    let match_config = &game_config.match_config;
+    if attract_mode.active {
+        match_state.in_progress = false;
+        return;
+    }
+    if !match_state.in_progress {
+        match_state.start(match_config);
+        return;
+    }
+
+    let Some(round_timer) = match_state.round_timer.as_mut() else {
+        return;
+    };
+    round_timer.tick(time.delta());
+    if !round_timer.just_finished() {
+        return;
+    }
+    let round_seconds = round_timer.duration().as_secs_f32();
+
+    let result = game_state.round_result(game_config.game_mode);
+    info!("Round {} over: {:?}", match_state.round, result);
+    round_events.send(RoundEnded {
+        round: match_state.round,
+        result,
+        scores: EnumMap::from_fn(|player_index| {
+            game_state.player_scores[player_index].score.points()
+        }),
+        duration_seconds: round_seconds,
+    });
+    match_state.record_round(result);
+    if match_state.is_decided(match_config.rounds_per_match) {
+        info!(
+            "Match over after {} rounds, wins {:?}",
+            match_state.results.len(),
+            match_state.wins
+        );
+        match_state.in_progress = false;
+        match_state.finished = true;
+        // the round stats overlay doubles as the results screen while the demo plays behind it
+        attract_mode.start(match_config.results_seconds);
+        return;
+    }
+
+    match_state.round += 1;
+    if let Some(round_timer) = match_state.round_timer.as_mut() {
+        round_timer.reset();
+    }
+    if match_config.swap_sides {
+        for (_, side) in match_state.sides.iter_mut() {
+            *side = side.opposite();
+        }
+        swap_events.send(SidesSwapped);
+    }
+    game_state.player_scores = default();
+    for (player_index, _) in game_state.player_scores.iter() {
+        score_events.send(ScoreChanged {
+            player: player_index,
+        });
+    }
+    session_events.send(SessionStarted);
+}
+
+/// Moves everything that belongs to a side over to whoever works it now: outgoing belts change
+/// hands, players are put back at their new end and the score displays follow them.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn swap_player_sides(
+    mut swap_events: EventReader<SidesSwapped>,
+    match_state: Res<MatchState>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    mut arena_layout: ResMut<ArenaLayout>,
+    mut conveyor_query: Query<(&mut ConveyorLabelTag, &Children)>,
+    mut accent_query: Query<
+        (&mut Sprite, Option<&AimLockGlow>),
+        (
+            Or<(With<CapacityIndicator>, With<AimLockGlow>)>,
+            Without<PlayerScoreDisplay>,
+        ),
+    >,
+    mut player_query: Query<(&Player, &mut Transform), Without<PlayerScoreDisplay>>,
+    mut display_query: Query<
+        (&PlayerScoreDisplay, &mut Transform, &mut Sprite, &Children),
+        Without<Player>,
+    >,
+    mut display_child_query: Query<
+        &mut Transform,
+        (
+            Without<PlayerScoreDisplay>,
+            Without<Player>,
+            Without<Sprite>,
+        ),
+    >,
+) {
+    if swap_events.read().count() == 0 {
+        return;
+    }
+
+    arena_layout.assign_sides(&match_state.sides);
+    for (mut conveyor_tag, conveyor_children) in &mut conveyor_query {
+        let ConveyorLabelTag::Outgoing(old_owner) = *conveyor_tag else {
+            continue;
+        };
+        // the belt stays where it is, so it goes to whoever took the old owner's place
+        let belt_side = match_state.sides[old_owner].opposite();
+        let Some(new_owner) = player_on_side(&match_state.sides, belt_side) else {
+            continue;
+        };
+        *conveyor_tag = ConveyorLabelTag::Outgoing(new_owner);
+        let owner_colour = game_config.player_config.per_player[new_owner].colour;
+        for child in conveyor_children.iter() {
+            if let Ok((mut accent_sprite, aim_lock_glow)) = accent_query.get_mut(*child) {
+                accent_sprite.color = if aim_lock_glow.is_some() {
+                    owner_colour.with_a(0.35)
+                } else {
+                    *owner_colour
+                };
+            }
+        }
+    }
+
+    for (player_info, mut player_transform) in &mut player_query {
+        player_transform.translation = player_start_position(
+            match_state.sides[player_info.player_index],
+            &app_config,
+            &game_config,
+        );
+    }
+
+    // both displays share a size, so mirroring each one lands it exactly where the other was
+    for (_, mut display_transform, mut display_sprite, display_children) in &mut display_query {
+        let width = display_sprite.custom_size.map_or(0., |size| size.x);
+        let child_shift = match display_sprite.anchor {
+            Anchor::BottomLeft => {
+                display_sprite.anchor = Anchor::BottomRight;
+                -width
+            }
+            Anchor::BottomRight => {
+                display_sprite.anchor = Anchor::BottomLeft;
+                width
+            }
+            _ => 0.,
+        };
+        display_transform.translation.x = -display_transform.translation.x;
+        for child in display_children.iter() {
+            if let Ok(mut child_transform) = display_child_query.get_mut(*child) {
+                child_transform.translation.x += child_shift;
+            }
+        }
+    }
+}
+
+/// Rounds won so far, left to right in the order the players stand.
+pub fn update_match_display(
+    game_config: Res<GameConfig>,
+    match_state: Res<MatchState>,
+    mut text_query: Query<&mut Text, With<MatchScoreText>>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+
+    let rounds_per_match = game_config.match_config.rounds_per_match;
+    let round = match_state.round.min(rounds_per_match);
+    let readout = match game_config.game_mode {
+        GameModeKind::Team => format!("{}/{}", round, rounds_per_match),
+        GameModeKind::Versus => {
+            let wins_on = |side| {
+                player_on_side(&match_state.sides, side)
+                    .map_or(0, |player_index| match_state.wins[player_index])
+            };
+            format!(
+                "{}/{}  {} - {}",
+                round,
+                rounds_per_match,
+                wins_on(PlayAreaAligment::Left),
+                wins_on(PlayAreaAligment::Right)
+            )
+        }
+    };
+    for mut text in &mut text_query {
+        if let Some(section) = text.sections.get_mut(1) {
+            if section.value != readout {
+                section.value = readout.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RoundReport, Score, ScoreFloor};
+
+    /// Plays a one round match in `game_mode` where Player1 is penalised below zero and Player2
+    /// scores a little, returning the round's end event and the match as the results screen sees
+    /// it.
+    fn play_negative_round(game_mode: GameModeKind) -> (RoundEnded, MatchState) {
+        let mut game_config = GameConfig::default();
+        game_config.game_mode = game_mode;
+        game_config.score_config.score_floor = ScoreFloor::AllowNegative;
+        game_config.match_config = MatchConfig {
+            round_seconds: Some(1.),
+            rounds_per_match: 1,
+            swap_sides: false,
+            results_seconds: 1.,
+        };
+        let mut attract_mode = AttractMode::new(&game_config);
+        attract_mode.active = false;
+
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<SessionStarted>()
+            .add_event::<ScoreChanged>()
+            .add_event::<SidesSwapped>()
+            .add_event::<RoundEnded>()
+            .insert_resource(GameState::from_config(&game_config))
+            .insert_resource(game_config)
+            .insert_resource(attract_mode)
+            .init_resource::<MatchState>()
+            .add_systems(Update, update_match);
+        app.update();
+
+        let score_floor = app.world.resource::<GameConfig>().score_config.score_floor;
+        let mut game_state = app.world.resource_mut::<GameState>();
+        let player_one = &mut game_state.player_scores[PlayerIndex::Player1].score;
+        player_one.add_with_multiplier(10., 1.);
+        player_one.apply_penalty(Score::from_points(25.5), score_floor);
+        game_state.player_scores[PlayerIndex::Player2]
+            .score
+            .add_with_multiplier(5., 1.);
+
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(std::time::Duration::from_secs(1));
+        app.update();
+
+        let round_ended = app
+            .world
+            .resource_mut::<Events<RoundEnded>>()
+            .drain()
+            .next()
+            .expect("the round ended");
+        let match_state = app.world.remove_resource::<MatchState>().unwrap();
+        assert!(match_state.finished);
+        (round_ended, match_state)
+    }
+
+    fn exported(round_ended: &RoundEnded, game_mode: GameModeKind) -> serde_json::Value {
+        serde_json::to_value(RoundReport {
+            machine: "test".to_string(),
+            seed: None,
+            config_hash: String::new(),
+            game_mode,
+            round: round_ended.round,
+            result: round_ended.result,
+            scores: round_ended.scores,
+            duration_seconds: round_ended.duration_seconds,
+            started_at: 0,
+            ended_at: 0,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn negative_scores_agree_from_the_round_to_the_results() {
+        let string_table = StringTable::default();
+
+        let (round_ended, match_state) = play_negative_round(GameModeKind::Team);
+        // 10 - 25.5 is -15.5, which shows as -16 wherever it's shown
+        assert_eq!(round_ended.scores[PlayerIndex::Player1], -16);
+        assert_eq!(round_ended.result, RoundResult::Team { total: -11 });
+        let export = exported(&round_ended, GameModeKind::Team);
+        assert_eq!(export["scores"]["Player1"], -16);
+        assert_eq!(export["result"]["Team"]["total"], -11);
+        assert_eq!(match_state.results, [RoundResult::Team { total: -11 }]);
+        assert_eq!(match_state.team_total(), -11);
+        assert!(match_state
+            .summary(GameModeKind::Team, &string_table)
+            .ends_with("-11"));
+
+        let (round_ended, match_state) = play_negative_round(GameModeKind::Versus);
+        assert_eq!(round_ended.scores[PlayerIndex::Player1], -16);
+        assert_eq!(
+            round_ended.result,
+            RoundResult::Winner(PlayerIndex::Player2)
+        );
+        let export = exported(&round_ended, GameModeKind::Versus);
+        assert_eq!(export["scores"]["Player1"], -16);
+        assert_eq!(export["scores"]["Player2"], 5);
+        assert_eq!(match_state.winner(), Some(PlayerIndex::Player2));
+        assert_eq!(match_state.wins[PlayerIndex::Player1], 0);
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use enum_map::EnumMap;
+
+use crate::{ConfigChangeLog, GameConfig, GameState, PlayerIndex, RoundStats};
+
+#[derive(Default, Clone, Copy)]
+pub struct PlayerMetrics {
+    pub picked_up: u32,
+    pub thrown: u32,
+    pub placed: u32,
+    pub delivered: u32,
+    pub stolen: u32,
+    pub confiscated: u32,
+    pub stunned: u32,
+    pub stuns_caused: u32,
+}
+
+/// Balancing counters, only inserted when `AppConfig::metrics_path` is set so the hooks in the
+/// gameplay systems cost nothing otherwise.
+#[derive(Resource)]
+pub struct Metrics {
+    pub per_player: EnumMap<PlayerIndex, PlayerMetrics>,
+    pub waves_spawned: u32,
+    pub wave_start_occupancy_total: f32,
+    pub incoming_blocked_seconds: f32,
+    pub elapsed_seconds: f32,
+    pub shift_name: Option<String>,
+    /// Set when gameplay config changed during the current round.
+    pub modified_mid_round: bool,
+    pub output_path: PathBuf,
+    pub write_timer: Timer,
+}
+
+impl Metrics {
+    pub fn new(output_path: PathBuf, write_interval_seconds: f32) -> Self {
+        Self {
+            per_player: default(),
+            waves_spawned: 0,
+            wave_start_occupancy_total: 0.,
+            incoming_blocked_seconds: 0.,
+            elapsed_seconds: 0.,
+            shift_name: None,
+            modified_mid_round: false,
+            output_path,
+            write_timer: Timer::from_seconds(write_interval_seconds, TimerMode::Repeating),
+        }
+    }
+
+    pub fn average_wave_start_occupancy(&self) -> f32 {
+        if self.waves_spawned == 0 {
+            0.
+        } else {
+            self.wave_start_occupancy_total / self.waves_spawned as f32
+        }
+    }
+
+    pub fn csv_header() -> String {
+        let mut columns = vec!["elapsed_seconds".to_string()];
+        for (player_index, _) in EnumMap::<PlayerIndex, ()>::default() {
+            for counter in [
+                "picked_up",
+                "thrown",
+                "placed",
+                "delivered",
+                "stolen",
+                "confiscated",
+                "stunned",
+                "stuns_caused",
+            ] {
+                columns.push(format!("{:?}_{}", player_index, counter));
+            }
+        }
+        for (player_index, _) in EnumMap::<PlayerIndex, ()>::default() {
+            for stat in [
+                "round_highest_multiplier",
+                "round_longest_streak",
+                "round_times_caught",
+                "round_distance_travelled",
+                "round_average_throw_charge",
+            ] {
+                columns.push(format!("{:?}_{}", player_index, stat));
+            }
+        }
+        columns.extend([
+            "waves_spawned".to_string(),
+            "average_wave_start_occupancy".to_string(),
+            "incoming_blocked_seconds".to_string(),
+            "shift".to_string(),
+            "modified_mid_round".to_string(),
+        ]);
+        columns.join(",")
+    }
+
+    pub fn to_csv_row(&self, round_stats: &RoundStats) -> String {
+        let mut columns = vec![format!("{:.2}", self.elapsed_seconds)];
+        for (_, player_metrics) in &self.per_player {
+            columns.extend(
+                [
+                    player_metrics.picked_up,
+                    player_metrics.thrown,
+                    player_metrics.placed,
+                    player_metrics.delivered,
+                    player_metrics.stolen,
+                    player_metrics.confiscated,
+                    player_metrics.stunned,
+                    player_metrics.stuns_caused,
+                ]
+                .map(|counter| counter.to_string()),
+            );
+        }
+        for player_stats in round_stats.per_player.values() {
+            columns.extend([
+                format!("{:.1}", player_stats.highest_multiplier),
+                player_stats.longest_streak.to_string(),
+                player_stats.times_caught.to_string(),
+                format!("{:.0}", player_stats.distance_travelled),
+                format!("{:.3}", player_stats.average_throw_charge()),
+            ]);
+        }
+        columns.extend([
+            self.waves_spawned.to_string(),
+            format!("{:.3}", self.average_wave_start_occupancy()),
+            format!("{:.2}", self.incoming_blocked_seconds),
+            self.shift_name.clone().unwrap_or_default(),
+            self.modified_mid_round.to_string(),
+        ]);
+        columns.join(",")
+    }
+
+    fn append_row(&self, round_stats: &RoundStats) -> std::io::Result<()> {
+        let write_header = std::fs::metadata(&self.output_path).map_or(true, |m| m.len() == 0);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Path::new(&self.output_path))?;
+        if write_header {
+            writeln!(file, "{}", Self::csv_header())?;
+        }
+        writeln!(file, "{}", self.to_csv_row(round_stats))
+    }
+}
+
+pub fn write_metrics(time: Res<Time>, mut metrics: ResMut<Metrics>, round_stats: Res<RoundStats>) {
+    metrics.elapsed_seconds += time.delta_seconds();
+    metrics.write_timer.tick(time.delta());
+    if !metrics.write_timer.just_finished() {
+        return;
+    }
+
+    if let Err(error) = metrics.append_row(&round_stats) {
+        warn!(
+            "failed to write metrics to {}: {}",
+            metrics.output_path.display(),
+            error
+        );
+    }
+}
+
+pub fn write_metrics_summary_on_exit(
+    metrics: Res<Metrics>,
+    round_stats: Res<RoundStats>,
+    config_change_log: Res<ConfigChangeLog>,
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    mut exit_events: EventReader<AppExit>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    if let Err(error) = metrics.append_row(&round_stats) {
+        warn!(
+            "failed to write metrics to {}: {}",
+            metrics.output_path.display(),
+            error
+        );
+    }
+
+    let minutes = (metrics.elapsed_seconds / 60.).max(f32::EPSILON);
+    for (player_index, player_metrics) in &metrics.per_player {
+        info!(
+            "{:?}: {:.1} picked up/min, {:.1} thrown/min, {:.1} delivered/min",
+            player_index,
+            player_metrics.picked_up as f32 / minutes,
+            player_metrics.thrown as f32 / minutes,
+            player_metrics.delivered as f32 / minutes,
+        );
+    }
+    info!(
+        "{} waves in {:.1} minutes, average belt occupancy at wave start {:.1}%, incoming belts blocked for {:.1}s",
+        metrics.waves_spawned,
+        minutes,
+        metrics.average_wave_start_occupancy() * 100.,
+        metrics.incoming_blocked_seconds
+    );
+    info!(
+        "{:?} round result: {:?}",
+        game_config.game_mode,
+        game_state.round_result(game_config.game_mode)
+    );
+
+    if let Some(summary) = config_change_log.summary() {
+        info!(
+            "{}{}",
+            summary,
+            if config_change_log.modified_mid_round() {
+                ", round marked as modified"
+            } else {
+                ""
+            }
+        );
+        for change in &config_change_log.changes {
+            info!(
+                "  {:.1}s {}: {} -> {}",
+                change.game_time, change.field_path, change.old_value, change.new_value
+            );
+        }
+    }
+}
@@ -1,18 +1,184 @@
 use crate::{
-    calculate_attach_point_on_conveyor, random::*, Conveyor, ConveyorLabelTag, EntityLayer,
-    GameConfig, GameState, RenderLayers, TextureTarget,
+    acquire_package, calculate_attach_point_on_conveyor, held_packages, mix_colours, random::*,
+    BeltRegistry, Conveyor, ConveyorLabelTag, EntityLayer, GameConfig, GameState,
+    IdleNudgeHighlight, Metrics, PackageConfig, PackagePool, PackageWentStale, Player,
+    RenderLayers, RoundScoped, SpawnContext, StringTable, TextureTarget, TrackedTexture, WaveCargo,
+    WaveModifiers, WaveSpawned,
 };
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use std::time::Duration;
+
+pub const PACKAGE_DENSITY: f32 = 500.;
+pub const PACKAGE_LINEAR_DAMPING: f32 = 1.;
 
 #[derive(Component)]
 pub struct Package;
 
+/// How long a package has lain loose, its delivery value falls with it. Added the first time a
+/// package is loose, held while it's carried or on an outgoing belt, and dropped when it's back on
+/// an incoming belt.
+#[derive(Component, Default)]
+pub struct Freshness {
+    pub loose_seconds: f32,
+}
+
+impl Freshness {
+    /// How far the package has decayed, 0 when fresh and 1 at its minimum value.
+    pub fn decay(&self, package_config: &PackageConfig) -> f32 {
+        if !package_config.decay_enabled || package_config.decay_seconds <= 0. {
+            return 0.;
+        }
+        (self.loose_seconds / package_config.decay_seconds).clamp(0., 1.)
+    }
+
+    /// Fraction of its base value the package is still worth.
+    pub fn value_fraction(&self, package_config: &PackageConfig) -> f32 {
+        1. - self.decay(package_config) * (1. - package_config.min_value_fraction.clamp(0., 1.))
+    }
+
+    /// Returns true on the tick the package reaches its minimum value.
+    pub fn tick(&mut self, delta_seconds: f32, package_config: &PackageConfig) -> bool {
+        let was_stale = self.decay(package_config) >= 1.;
+        self.loose_seconds += delta_seconds;
+        !was_stale && self.decay(package_config) >= 1.
+    }
+}
+
+/// How many packages each incoming conveyor gets in the next wave, decided when the countdown
+/// starts so the preview and the spawn always agree.
+#[derive(Resource, Default)]
+pub struct PlannedWave {
+    pub counts: Option<Vec<(Entity, usize)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveHoldReason {
+    IncomingBeltsOccupied,
+}
+
+/// Countdown until the next package wave, all changes go through named operations so the wave
+/// lifecycle can be followed in the log.
+pub struct WaveTimer {
+    timer: Timer,
+    hold_reason: Option<WaveHoldReason>,
+    since_last_wave: Option<Duration>,
+}
+
+impl WaveTimer {
+    pub fn new(duration_seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration_seconds, TimerMode::Once),
+            hold_reason: None,
+            since_last_wave: None,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.timer.duration()
+    }
+
+    /// Changes the countdown length, time already elapsed on the current countdown is kept.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.timer.set_duration(duration);
+        self.log_transition("set_duration");
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.timer.remaining()
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.timer.fraction()
+    }
+
+    pub fn hold_reason(&self) -> Option<WaveHoldReason> {
+        self.hold_reason
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.hold_reason.is_some()
+    }
+
+    /// Time since the last wave spawned, `None` until the first one.
+    pub fn since_last_wave(&self) -> Option<Duration> {
+        self.since_last_wave
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.tick_countdown(delta, 1.);
+    }
+
+    /// Ticks with the countdown taking `countdown_scale` times as long as its duration.
+    pub fn tick_countdown(&mut self, delta: Duration, countdown_scale: f32) {
+        self.timer.tick(delta.div_f32(countdown_scale));
+        if let Some(since_last_wave) = self.since_last_wave.as_mut() {
+            *since_last_wave += delta;
+        }
+    }
+
+    /// Restart the countdown from the full duration, clearing any hold.
+    pub fn begin_countdown(&mut self) {
+        self.timer.reset();
+        self.timer.unpause();
+        self.hold_reason = None;
+        self.log_transition("begin_countdown");
+    }
+
+    /// Stop the countdown where it is until `resume` is called.
+    pub fn hold(&mut self, reason: WaveHoldReason) {
+        if let Some(current_reason) = self.hold_reason {
+            warn!(
+                "wave timer already held for {:?}, replacing with {:?}",
+                current_reason, reason
+            );
+        }
+        self.timer.pause();
+        self.hold_reason = Some(reason);
+        self.log_transition("hold");
+    }
+
+    pub fn resume(&mut self) {
+        if self.hold_reason.is_none() {
+            warn!("wave timer resumed without being held, ignoring");
+            return;
+        }
+        self.timer.unpause();
+        self.hold_reason = None;
+        self.log_transition("resume");
+    }
+
+    /// Returns true once when the countdown completes, after which the timer is reset and held
+    /// until the incoming belts are clear.
+    pub fn consume_finished(&mut self) -> bool {
+        if !self.timer.finished() {
+            return false;
+        }
+        self.timer.reset();
+        self.since_last_wave = Some(Duration::ZERO);
+        self.log_transition("consume_finished");
+        self.hold(WaveHoldReason::IncomingBeltsOccupied);
+        true
+    }
+
+    fn log_transition(&self, operation: &str) {
+        if cfg!(debug_assertions) {
+            debug!(
+                "wave timer {}: remaining {:.2}s, held {:?}",
+                operation,
+                self.timer.remaining_secs(),
+                self.hold_reason
+            );
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct PackageBundle {
     pub sprite_bundle: SpriteBundle,
     pub package: Package,
     pub render_layers: RenderLayers,
+    pub round_scoped: RoundScoped,
 }
 
 #[derive(Bundle)]
@@ -25,6 +191,15 @@ pub struct PackagePhysicsBundle {
     pub friction: Friction,
     pub restitution: Restitution,
     pub impulse: ExternalImpulse,
+    pub velocity: Velocity,
+    pub sleeping: Sleeping,
+    pub settling: Settling,
+}
+
+/// How long a loose package has been all but still.
+#[derive(Component, Default)]
+pub struct Settling {
+    pub still_seconds: f32,
 }
 
 impl Default for PackageBundle {
@@ -33,6 +208,7 @@ impl Default for PackageBundle {
             sprite_bundle: SpriteBundle::default(),
             package: Package,
             render_layers: RenderLayers::Multi(maplit::btreeset! {EntityLayer::Object}),
+            round_scoped: RoundScoped,
         }
     }
 }
@@ -41,9 +217,9 @@ impl Default for PackagePhysicsBundle {
     fn default() -> Self {
         Self {
             rigid_body: RigidBody::Dynamic,
-            mass_props: ColliderMassProperties::Density(500.),
+            mass_props: ColliderMassProperties::Density(PACKAGE_DENSITY),
             damping: Damping {
-                linear_damping: 1.,
+                linear_damping: PACKAGE_LINEAR_DAMPING,
                 ..default()
             },
             collider: Collider::default(),
@@ -57,120 +233,551 @@ impl Default for PackagePhysicsBundle {
                 ..default()
             },
             impulse: ExternalImpulse::default(),
+            velocity: Velocity::default(),
+            sleeping: Sleeping::default(),
+            settling: Settling::default(),
         }
     }
 }
 
+/// Spawns a loose package, at the origin unless told otherwise.
+#[derive(Default)]
+pub struct PackageSpawner {
+    position: Vec3,
+}
+
+impl PackageSpawner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn at(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands, ctx: &mut SpawnContext) -> Entity {
+        self.spawn_with(
+            commands,
+            &mut ctx.package_pool,
+            &ctx.asset_server,
+            &ctx.game_config,
+        )
+    }
+
+    fn spawn_with(
+        self,
+        commands: &mut Commands,
+        package_pool: &mut PackagePool,
+        asset_server: &AssetServer,
+        game_config: &GameConfig,
+    ) -> Entity {
+        let package_pos = self.position;
+        let texture_pack = game_config.get_texture_pack();
+        let package_sprite = texture_pack.choose_texture_for(TextureTarget::Package, None);
+        let package_sprite_path = format!("{}/{}", texture_pack.root, package_sprite.path);
+        acquire_package(commands, package_pool)
+            .insert((
+                PackageBundle {
+                    sprite_bundle: SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::new(
+                                game_config.package_config.size,
+                                game_config.package_config.size,
+                            )),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: package_pos,
+                            ..default()
+                        },
+                        texture: asset_server.load(&package_sprite_path),
+                        ..default()
+                    },
+                    package: Package,
+                    render_layers: RenderLayers::Multi(maplit::btreeset! {EntityLayer::Object}),
+                    round_scoped: RoundScoped,
+                },
+                PackagePhysicsBundle {
+                    rigid_body: RigidBody::Dynamic,
+                    collider: Collider::cuboid(
+                        game_config.package_config.size / 2.,
+                        game_config.package_config.size / 2.,
+                    ),
+                    locked_axes: LockedAxes::ROTATION_LOCKED,
+                    ..default()
+                },
+                TrackedTexture::new(
+                    TextureTarget::Package,
+                    format!("texture pack '{}'", game_config.selected_texture_pack),
+                    package_sprite_path,
+                ),
+            ))
+            .id()
+    }
+}
+
+#[deprecated(note = "use PackageSpawner")]
 pub fn spawn_package(
     commands: &mut Commands,
+    package_pool: &mut PackagePool,
     asset_server: &Res<AssetServer>,
     game_config: &Res<GameConfig>,
     package_pos: Vec3,
 ) {
-    let texture_pack = game_config.get_texture_pack();
-    let package_sprite = texture_pack.choose_texture_for(TextureTarget::Package, None);
-    commands.spawn((
-        PackageBundle {
-            sprite_bundle: SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(
-                        game_config.package_config.size,
-                        game_config.package_config.size,
-                    )),
-                    ..default()
-                },
-                transform: Transform {
-                    translation: package_pos,
-                    ..default()
-                },
-                texture: asset_server
-                    .load(&format!("{}/{}", texture_pack.root, package_sprite.path)),
-                ..default()
-            },
-            package: Package,
-            render_layers: RenderLayers::Multi(maplit::btreeset! {EntityLayer::Object}),
-        },
-        PackagePhysicsBundle {
-            rigid_body: RigidBody::Dynamic,
-            collider: Collider::cuboid(
-                game_config.package_config.size / 2.,
-                game_config.package_config.size / 2.,
-            ),
-            locked_axes: LockedAxes::ROTATION_LOCKED,
-            ..default()
-        },
-    ));
+    PackageSpawner::new().at(package_pos).spawn_with(
+        commands,
+        package_pool,
+        asset_server,
+        game_config,
+    );
+}
+
+/// How many packages the players still at the controls can deliver between them, `None` while
+/// nobody is away.
+fn active_delivery_capacity(
+    game_state: &GameState,
+    belt_registry: &BeltRegistry,
+    capacity_of: impl Fn(Entity) -> usize,
+) -> Option<usize> {
+    if !game_state
+        .player_controls
+        .values()
+        .any(|controls| controls.afk)
+    {
+        return None;
+    }
+    Some(
+        game_state
+            .player_controls
+            .iter()
+            .filter(|(_, controls)| !controls.afk)
+            .flat_map(|(player_index, _)| belt_registry.outgoing(player_index))
+            .map(|belt| capacity_of(*belt))
+            .sum(),
+    )
+}
+
+/// Takes packages off the fullest belts until the wave fits in `capacity`.
+fn shrink_wave(counts: &mut [(Entity, usize)], capacity: usize) {
+    while counts.iter().map(|(_, count)| count).sum::<usize>() > capacity {
+        if let Some((_, count)) = counts.iter_mut().max_by_key(|(_, count)| *count) {
+            *count -= 1;
+        }
+    }
+}
+
+/// Splits a wave of `total` packages between belts given as `(package_count, capacity)`. Each
+/// belt is weighted by the inverse of its backlog, blended with a random weight by `fairness`,
+/// and packages are handed out one at a time to whichever belt is furthest below its share.
+/// Belts never go over capacity, so the result only falls short of `total` when every belt is
+/// full.
+pub fn split_wave(
+    total: usize,
+    belts: &[(usize, usize)],
+    fairness: f32,
+    rng: &mut impl Rng,
+) -> Vec<usize> {
+    let fairness = fairness.clamp(0., 1.);
+    let weights = belts
+        .iter()
+        .map(|(package_count, _)| {
+            let even = 1. / (1 + package_count) as f32;
+            let random = rng.gen_range(0.0..1.0f32);
+            fairness * even + (1. - fairness) * random
+        })
+        .collect::<Vec<_>>();
+    let mut counts = vec![0; belts.len()];
+    for _ in 0..total {
+        let next = belts
+            .iter()
+            .enumerate()
+            .filter(|(i, (package_count, capacity))| package_count + counts[*i] < *capacity)
+            .map(|(i, _)| (i, weights[i] / (counts[i] + 1) as f32))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((i, _)) = next else {
+            break;
+        };
+        counts[i] += 1;
+    }
+    counts
 }
 
+pub fn plan_package_wave(
+    mut planned_wave: ResMut<PlannedWave>,
+    conveyor_query: Query<(Entity, &Conveyor, &ConveyorLabelTag)>,
+    game_state: Res<GameState>,
+    belt_registry: Res<BeltRegistry>,
+    game_config: Res<GameConfig>,
+    mut rng: ResMut<Rand>,
+    mut wave_modifiers: ResMut<WaveModifiers>,
+) {
+    if planned_wave.counts.is_some() {
+        return;
+    }
+
+    let package_size = game_config.package_config.size;
+    let (belt_entities, belts): (Vec<_>, Vec<_>) = conveyor_query
+        .iter()
+        .filter(|(_, _, tag)| matches!(**tag, ConveyorLabelTag::Incoming))
+        .map(|(conveyor_entity, conveyor_info, _)| {
+            // a broken belt can't take anything until it's repaired
+            let capacity = if conveyor_info.is_broken() {
+                0
+            } else {
+                conveyor_info.capacity(package_size)
+            };
+            (conveyor_entity, (conveyor_info.package_count, capacity))
+        })
+        .unzip();
+    let total_capacity = belts.iter().map(|(_, capacity)| capacity).sum::<usize>();
+    let total = rng.gen_range(total_capacity / 2..=total_capacity);
+    let fairness = game_config
+        .package_config
+        .wave_fairness
+        .unwrap_or_else(|| game_config.game_mode.default_wave_fairness());
+    let mut counts = belt_entities
+        .into_iter()
+        .zip(split_wave(total, &belts, fairness, &mut **rng))
+        .collect::<Vec<_>>();
+    if let Some(capacity) = active_delivery_capacity(&game_state, &belt_registry, |belt| {
+        conveyor_query.get(belt).map_or(0, |(_, conveyor_info, _)| {
+            conveyor_info.capacity(package_size)
+        })
+    }) {
+        shrink_wave(&mut counts, capacity);
+    }
+    if !counts.is_empty() {
+        planned_wave.counts = Some(counts);
+        wave_modifiers.plan_next(&game_config.wave_modifier_config, &mut rng);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_package_wave(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut conveyor_query: Query<(Entity, &mut Conveyor, &ConveyorLabelTag)>,
     mut game_state: ResMut<GameState>,
+    mut planned_wave: ResMut<PlannedWave>,
+    belt_registry: Res<BeltRegistry>,
     time: Res<Time>,
     game_config: Res<GameConfig>,
-    mut rng: ResMut<Rand>,
+    mut wave_events: EventWriter<WaveSpawned>,
+    metrics: Option<ResMut<Metrics>>,
+    mut wave_modifiers: ResMut<WaveModifiers>,
+    mut package_pool: ResMut<PackagePool>,
 ) {
-    game_state.package_wave_timer.tick(time.delta());
-    if !game_state.package_wave_timer.finished() {
+    let countdown_scale = wave_modifiers.countdown_scale(&game_config.wave_modifier_config);
+    game_state
+        .package_wave_timer
+        .tick_countdown(time.delta(), countdown_scale);
+    if !game_state.package_wave_timer.consume_finished() {
+        return;
+    }
+    let mut planned_counts = planned_wave.counts.take().unwrap_or_default();
+    planned_counts.retain(|(conveyor_entity, _)| {
+        conveyor_query
+            .get(*conveyor_entity)
+            .is_ok_and(|(_, conveyor_info, _)| !conveyor_info.is_broken())
+    });
+    // someone may have walked away since the wave was planned
+    let package_size = game_config.package_config.size;
+    if let Some(capacity) = active_delivery_capacity(&game_state, &belt_registry, |belt| {
+        conveyor_query.get(belt).map_or(0, |(_, conveyor_info, _)| {
+            conveyor_info.capacity(package_size)
+        })
+    }) {
+        shrink_wave(&mut planned_counts, capacity);
+    }
+    // nothing fits, so there's nothing to wait on clearing either
+    if planned_counts.iter().all(|(_, count)| *count == 0) {
+        game_state.package_wave_timer.begin_countdown();
         return;
     }
 
-    game_state.package_wave_timer.reset();
-    game_state.package_wave_timer.pause();
+    if let Some(mut metrics) = metrics {
+        let (occupied, capacity) = conveyor_query
+            .iter()
+            .filter(|(_, _, tag)| matches!(**tag, ConveyorLabelTag::Incoming))
+            .fold((0., 0.), |(occupied, capacity), (_, conveyor_info, _)| {
+                (
+                    occupied + conveyor_info.package_count as f32,
+                    capacity + conveyor_info.capacity(game_config.package_config.size) as f32,
+                )
+            });
+        metrics.waves_spawned += 1;
+        if capacity > 0. {
+            metrics.wave_start_occupancy_total += occupied / capacity;
+        }
+    }
 
+    wave_events.send(WaveSpawned {
+        per_conveyor_counts: planned_counts.clone(),
+    });
+    let cargo = wave_modifiers.start_wave();
+    for (conveyor_entity, package_count) in planned_counts {
+        let Ok((_, mut conveyor_info, _)) = conveyor_query.get_mut(conveyor_entity) else {
+            continue;
+        };
+        load_conveyor(
+            &mut commands,
+            conveyor_entity,
+            &mut conveyor_info,
+            package_count,
+            cargo,
+            &mut package_pool,
+            &asset_server,
+            &game_config,
+        );
+    }
+}
+
+/// Puts `package_count` new packages at the start of a belt and sets it running, tagged with
+/// the wave's modifier if it has one.
+#[allow(clippy::too_many_arguments)]
+pub fn load_conveyor(
+    commands: &mut Commands,
+    conveyor_entity: Entity,
+    conveyor_info: &mut Conveyor,
+    package_count: usize,
+    cargo: Option<WaveCargo>,
+    package_pool: &mut PackagePool,
+    asset_server: &AssetServer,
+    game_config: &GameConfig,
+) {
     let texture_pack = game_config.get_texture_pack();
     let package_sprite = texture_pack.choose_texture_for(TextureTarget::Package, None);
     let package_sprite_path = format!("{}/{}", texture_pack.root, package_sprite.path);
-    for (conveyor_entity, mut conveyor_info, _) in
-        conveyor_query.iter_mut().filter(|(_, _, tag)| match **tag {
-            ConveyorLabelTag::Incoming => true,
-            _ => false,
-        })
-    {
-        let max_packages_per_row =
-            (conveyor_info.belt_region.x / game_config.package_config.size).floor();
-        let max_packages_rows =
-            (conveyor_info.belt_region.y / game_config.package_config.size).floor();
-        let max_package_count = (max_packages_per_row * max_packages_rows) as usize;
-        let min_package_count = (max_package_count as f32 * 0.5).floor() as usize;
-        let package_count = rng.gen_range(min_package_count..=max_package_count);
-        let offset = Vec2::new(0., conveyor_info.belt_region.y);
-        for _ in 0..package_count {
-            let package_local_translation = calculate_attach_point_on_conveyor(
-                &conveyor_info,
-                offset,
-                game_config.package_config.size,
-            )
-            .extend(0.);
-            commands.entity(conveyor_entity).with_children(|builder| {
-                builder.spawn(PackageBundle {
-                    sprite_bundle: SpriteBundle {
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(
-                                game_config.package_config.size,
-                                game_config.package_config.size,
-                            )),
-                            ..default()
-                        },
-                        transform: Transform {
-                            translation: package_local_translation,
-                            ..default()
-                        },
-                        texture: asset_server.load(&package_sprite_path),
+    let package_source = format!("texture pack '{}'", game_config.selected_texture_pack);
+    let offset = Vec2::new(0., conveyor_info.belt_region.y);
+    for _ in 0..package_count {
+        let package_local_translation = calculate_attach_point_on_conveyor(
+            conveyor_info,
+            offset,
+            game_config.package_config.size,
+        )
+        .extend(0.);
+        let mut package = acquire_package(commands, package_pool);
+        package.insert((
+            PackageBundle {
+                sprite_bundle: SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(
+                            game_config.package_config.size,
+                            game_config.package_config.size,
+                        )),
                         ..default()
                     },
-                    package: Package,
-                    render_layers: RenderLayers::Multi(maplit::btreeset! {EntityLayer::Object}),
-                });
-            });
+                    transform: Transform {
+                        translation: package_local_translation,
+                        ..default()
+                    },
+                    texture: asset_server.load(&package_sprite_path),
+                    ..default()
+                },
+                package: Package,
+                render_layers: RenderLayers::Multi(maplit::btreeset! {EntityLayer::Object}),
+                round_scoped: RoundScoped,
+            },
+            TrackedTexture::new(
+                TextureTarget::Package,
+                &package_source,
+                &package_sprite_path,
+            ),
+        ));
+        if let Some(cargo) = cargo {
+            package.insert(cargo);
+        }
+        let package_entity = package.id();
+        commands.entity(conveyor_entity).add_child(package_entity);
+
+        conveyor_info.package_count += 1;
+    }
 
-            conveyor_info.package_count += 1;
+    conveyor_info.start_run();
+}
+
+pub const WAVE_PREVIEW_BAR_HEIGHT: f32 = 3.;
+
+#[derive(Component)]
+pub struct WavePreviewText;
+
+/// Shrinks as the countdown to the next wave runs out.
+#[derive(Component)]
+pub struct WavePreviewBar {
+    pub full_width: f32,
+}
+
+pub fn update_wave_preview(
+    game_state: Res<GameState>,
+    planned_wave: Res<PlannedWave>,
+    wave_modifiers: Res<WaveModifiers>,
+    string_table: Res<StringTable>,
+    conveyor_query: Query<&GlobalTransform, With<Conveyor>>,
+    mut text_query: Query<&mut Text, With<WavePreviewText>>,
+    mut bar_query: Query<(&WavePreviewBar, &mut Sprite)>,
+) {
+    // read left to right, the same order the belts appear in
+    let mut preview = planned_wave.counts.as_ref().map_or_else(
+        || "-".to_string(),
+        |counts| {
+            let mut counts = counts
+                .iter()
+                .filter_map(|(conveyor_entity, count)| {
+                    conveyor_query
+                        .get(*conveyor_entity)
+                        .ok()
+                        .map(|transform| (transform.translation().x, *count))
+                })
+                .collect::<Vec<_>>();
+            counts.sort_by(|a, b| a.0.total_cmp(&b.0));
+            counts
+                .iter()
+                .map(|(_, count)| count.to_string())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        },
+    );
+    if let Some(modifier) = wave_modifiers.next() {
+        preview.push_str(&format!("  {}", string_table.tr(modifier.name())));
+    }
+    for mut text in &mut text_query {
+        if let Some(section) = text.sections.get_mut(1) {
+            if section.value != preview {
+                section.value = preview.clone();
+            }
+        }
+    }
+
+    let remaining = 1. - game_state.package_wave_timer.fraction();
+    for (bar, mut bar_sprite) in &mut bar_query {
+        let width = bar.full_width * remaining.clamp(0., 1.);
+        if bar_sprite.custom_size.map(|size| size.x) != Some(width) {
+            bar_sprite.custom_size = Some(Vec2::new(width, WAVE_PREVIEW_BAR_HEIGHT));
+        }
+    }
+}
+
+/// Puts a loose package to sleep once it has crept along under `settle_speed` for
+/// `settle_seconds`. Damping alone leaves packages jittering just fast enough that Rapier never
+/// sleeps them, and every awake one adds to the step. Belts, drifts, pushes and collisions all
+/// wake a package again, picking it up drops its physics altogether.
+#[allow(clippy::type_complexity)]
+pub fn settle_packages(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut package_query: Query<
+        (&Velocity, &mut Sleeping, &mut Settling),
+        (With<Package>, Without<Parent>),
+    >,
+) {
+    let package_config = &game_config.package_config;
+    for (velocity, mut sleeping, mut settling) in &mut package_query {
+        if velocity.linvel.length() >= package_config.settle_speed {
+            settling.still_seconds = 0.;
+            // something woke it, it can be put back to sleep once it's still again
+            if sleeping.sleeping {
+                sleeping.sleeping = false;
+            }
+            continue;
+        }
+        if sleeping.sleeping {
+            continue;
         }
+        settling.still_seconds += time.delta_seconds();
+        if settling.still_seconds >= package_config.settle_seconds {
+            sleeping.sleeping = true;
+        }
+    }
+}
+
+/// Loose packages decay, carried ones and those waiting on an outgoing belt hold their value, and
+/// a package put back on an incoming belt starts fresh.
+#[allow(clippy::type_complexity)]
+pub fn decay_package_freshness(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut package_query: Query<(Entity, Option<&Parent>, Option<&mut Freshness>), With<Package>>,
+    conveyor_query: Query<&ConveyorLabelTag>,
+    mut stale_events: EventWriter<PackageWentStale>,
+) {
+    let package_config = &game_config.package_config;
+    if !package_config.decay_enabled {
+        return;
+    }
+
+    for (package_entity, parent, freshness) in &mut package_query {
+        match (parent, freshness) {
+            (None, Some(mut freshness)) => {
+                let went_stale = freshness.tick(time.delta_seconds(), package_config);
+                if went_stale {
+                    stale_events.send(PackageWentStale {
+                        package: package_entity,
+                    });
+                }
+            }
+            (None, None) => {
+                commands.entity(package_entity).insert(Freshness::default());
+            }
+            (Some(parent), Some(_))
+                if conveyor_query.get(parent.get()) == Ok(&ConveyorLabelTag::Incoming) =>
+            {
+                commands.entity(package_entity).remove::<Freshness>();
+            }
+            _ => {}
+        }
+    }
+}
 
-        conveyor_info.idle_timer.pause();
-        conveyor_info.active_timer.reset();
-        conveyor_info.active_timer.unpause();
+/// Decayed packages fade towards a dull brown so players can see which ones are losing value, and
+/// packages carried under another are see-through.
+#[allow(clippy::type_complexity)]
+pub fn tint_packages(
+    game_config: Res<GameConfig>,
+    mut package_query: Query<
+        (
+            Entity,
+            Option<&Freshness>,
+            &mut Sprite,
+            &Handle<Image>,
+            Option<&Parent>,
+        ),
+        (With<Package>, Without<IdleNudgeHighlight>),
+    >,
+    holder_query: Query<&Children, With<Player>>,
+    all_packages: Query<(), With<Package>>,
+) {
+    const STALE_COLOUR: Color = Color::rgb(0.55, 0.5, 0.42);
+    const BURIED_ALPHA: f32 = 0.5;
+
+    for (package_entity, freshness, mut package_sprite, image_handle, package_parent) in
+        &mut package_query
+    {
+        // a placeholder already shows its colour in place of the texture, leave it alone
+        if *image_handle == Handle::default() {
+            continue;
+        }
+        let decay = freshness.map_or(0., |freshness| freshness.decay(&game_config.package_config));
+        let mut colour = if decay > 0. {
+            mix_colours(Color::WHITE, STALE_COLOUR, decay)
+        } else {
+            Color::WHITE
+        };
+        // everything under the top of a carried stack fades back so it's clear which is thrown
+        let buried = package_parent
+            .and_then(|parent| holder_query.get(parent.get()).ok())
+            .and_then(|children| {
+                held_packages(Some(children), |child| all_packages.contains(child)).last()
+            })
+            .is_some_and(|top_package| top_package != package_entity);
+        if buried {
+            colour.set_a(BURIED_ALPHA);
+        }
+        if package_sprite.color != colour {
+            package_sprite.color = colour;
+        }
     }
 }
 
@@ -202,3 +809,164 @@ pub fn deactivate_package_physics(commands: &mut Commands, package_entity: Entit
         .entity(package_entity)
         .remove::<PackagePhysicsBundle>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlayerIndex;
+
+    const BACKLOGS: [&[(usize, usize)]; 4] = [
+        &[(0, 10), (0, 10)],
+        &[(8, 10), (0, 10)],
+        &[(3, 4), (0, 20), (19, 20)],
+        &[(0, 1)],
+    ];
+
+    #[test]
+    fn split_waves_fill_up_to_the_room_left() {
+        let mut rng = Rand::new(&Some(2076));
+        for belts in BACKLOGS {
+            let room = belts
+                .iter()
+                .map(|(package_count, capacity)| capacity - package_count)
+                .sum::<usize>();
+            for total in 0..=40 {
+                for fairness in [0., 0.5, 1.] {
+                    let counts = split_wave(total, belts, fairness, &mut *rng);
+                    for ((package_count, capacity), count) in belts.iter().zip(&counts) {
+                        assert!(package_count + count <= *capacity, "{:?}", counts);
+                    }
+                    assert_eq!(counts.iter().sum::<usize>(), total.min(room));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fair_waves_favour_the_emptier_belt() {
+        let mut rng = Rand::new(&Some(2076));
+        let counts = split_wave(10, &[(8, 20), (0, 20)], 1., &mut *rng);
+        assert!(counts[1] > counts[0], "{:?}", counts);
+
+        // the same seed splits the same way
+        let belts = [(3, 4), (0, 20), (19, 20)];
+        assert_eq!(
+            split_wave(12, &belts, 0.5, &mut *Rand::new(&Some(2076))),
+            split_wave(12, &belts, 0.5, &mut *Rand::new(&Some(2076)))
+        );
+    }
+
+    #[test]
+    fn a_wave_with_nowhere_to_go_rearms_the_timer() {
+        let game_config = GameConfig::default();
+        let package_size = game_config.package_config.size;
+        let mut game_state = GameState::from_config(&game_config);
+        game_state.package_wave_timer = WaveTimer::new(1.);
+        game_state.package_wave_timer.tick(Duration::from_secs(1));
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_event::<WaveSpawned>()
+            .insert_resource(game_state)
+            .insert_resource(game_config)
+            .init_resource::<PlannedWave>()
+            .init_resource::<BeltRegistry>()
+            .init_resource::<WaveModifiers>()
+            .init_resource::<PackagePool>()
+            .add_systems(Update, spawn_package_wave);
+        let mut full_belt = || {
+            let mut conveyor = Conveyor::new(
+                Vec2::new(package_size * 2., package_size * 6.),
+                -1.,
+                100.,
+                0.,
+                None,
+            );
+            conveyor.package_count = conveyor.capacity(package_size);
+            app.world.spawn((conveyor, ConveyorLabelTag::Incoming)).id()
+        };
+        let belts = [full_belt(), full_belt()];
+
+        let split = split_wave(8, &[(12, 12), (12, 12)], 0.5, &mut *Rand::new(&Some(2076)));
+        assert_eq!(split, [0, 0]);
+        app.world.resource_mut::<PlannedWave>().counts =
+            Some(belts.into_iter().zip(split).collect());
+        app.update();
+
+        let wave_timer = &app.world.resource::<GameState>().package_wave_timer;
+        assert!(!wave_timer.is_held());
+        assert_eq!(wave_timer.remaining(), wave_timer.duration());
+        assert!(app.world.resource::<Events<WaveSpawned>>().is_empty());
+    }
+    #[test]
+    fn freshness_only_runs_down_while_loose() {
+        let mut game_config = GameConfig::default();
+        game_config.package_config.decay_enabled = true;
+        game_config.package_config.decay_seconds = 4.;
+        game_config.package_config.min_value_fraction = 0.4;
+
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<PackageWentStale>()
+            .insert_resource(game_config)
+            .add_systems(Update, decay_package_freshness);
+        let player = app.world.spawn_empty().id();
+        let outgoing = app
+            .world
+            .spawn(ConveyorLabelTag::Outgoing(PlayerIndex::Player1))
+            .id();
+        let incoming = app.world.spawn(ConveyorLabelTag::Incoming).id();
+        let package = app.world.spawn(Package).id();
+
+        let run_for = |app: &mut App, seconds: u64| {
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs(seconds));
+            app.update();
+        };
+        let loose_seconds = |app: &App| {
+            app.world
+                .get::<Freshness>(package)
+                .map(|freshness| freshness.loose_seconds)
+        };
+
+        // dropped, the first tick only starts the clock
+        run_for(&mut app, 1);
+        assert_eq!(loose_seconds(&app), Some(0.));
+        run_for(&mut app, 1);
+        assert_eq!(loose_seconds(&app), Some(1.));
+
+        // picked up, carried and thrown again
+        app.world.entity_mut(package).set_parent(player);
+        run_for(&mut app, 5);
+        run_for(&mut app, 5);
+        assert_eq!(loose_seconds(&app), Some(1.));
+        app.world.entity_mut(package).remove_parent();
+        run_for(&mut app, 2);
+        assert_eq!(loose_seconds(&app), Some(3.));
+        let package_config = &app.world.resource::<GameConfig>().package_config;
+        let value_fraction = app
+            .world
+            .get::<Freshness>(package)
+            .unwrap()
+            .value_fraction(package_config);
+        assert!((value_fraction - 0.55).abs() < 0.001);
+
+        // waiting on an outgoing belt holds it too
+        app.world.entity_mut(package).set_parent(outgoing);
+        run_for(&mut app, 5);
+        assert_eq!(loose_seconds(&app), Some(3.));
+        assert!(app.world.resource::<Events<PackageWentStale>>().is_empty());
+
+        // going stale is only reported once
+        app.world.entity_mut(package).remove_parent();
+        run_for(&mut app, 2);
+        run_for(&mut app, 2);
+        assert_eq!(app.world.resource::<Events<PackageWentStale>>().len(), 1);
+
+        // back on an incoming belt it starts fresh
+        app.world.entity_mut(package).set_parent(incoming);
+        run_for(&mut app, 1);
+        assert_eq!(loose_seconds(&app), None);
+    }
+}
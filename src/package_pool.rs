@@ -0,0 +1,119 @@
+use bevy::{
+    ecs::system::{Command, EntityCommands},
+    prelude::*,
+};
+
+use crate::GameConfig;
+
+/// Where parked packages wait, well clear of the arena in case one is ever shown.
+const PARK_POSITION: Vec3 = Vec3::new(0., -10000., 0.);
+
+/// Package entities made ahead of time and handed out again once their package is gone, so a
+/// big wave reuses what's there instead of spawning it all in one frame.
+#[derive(Resource, Default)]
+pub struct PackagePool {
+    parked: Vec<Entity>,
+    /// More than this many parked and the rest are despawned as usual.
+    capacity: usize,
+}
+
+impl PackagePool {
+    pub fn parked(&self) -> usize {
+        self.parked.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Marks a package entity that's parked in the pool. Nothing but its sprite is kept, so it
+/// doesn't show up as a package anywhere.
+#[derive(Component)]
+pub struct PooledPackage;
+
+fn parked_package() -> (SpriteBundle, PooledPackage) {
+    (
+        SpriteBundle {
+            transform: Transform::from_translation(PARK_POSITION),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PooledPackage,
+    )
+}
+
+pub fn setup_package_pool(
+    mut commands: Commands,
+    game_config: Res<GameConfig>,
+    mut package_pool: ResMut<PackagePool>,
+) {
+    let pool_size = game_config.package_config.pool_size;
+    package_pool.capacity = pool_size;
+    package_pool.parked = (0..pool_size)
+        .map(|_| commands.spawn(parked_package()).id())
+        .collect();
+    debug!("Parked {} packages up front", pool_size);
+}
+
+/// A parked package if the pool has one, otherwise a new entity, ready for a package bundle to
+/// be inserted.
+pub fn acquire_package<'a>(
+    commands: &'a mut Commands,
+    package_pool: &mut PackagePool,
+) -> EntityCommands<'a> {
+    match package_pool.parked.pop() {
+        Some(package_entity) => {
+            let mut package = commands.entity(package_entity);
+            package.remove::<PooledPackage>();
+            package
+        }
+        None => {
+            if package_pool.capacity > 0 {
+                debug!("Package pool is empty, spawning a new package");
+            }
+            commands.spawn_empty()
+        }
+    }
+}
+
+/// Strips a package back to a hidden sprite and parks it, or despawns it when the pool is full.
+/// Anything it was carrying goes, and it's taken off whatever belt or player it was on.
+pub fn park_package(world: &mut World, package_entity: Entity) {
+    let Some(mut package) = world.get_entity_mut(package_entity) else {
+        return;
+    };
+    // released twice in one frame, it's already parked
+    if package.contains::<PooledPackage>() {
+        return;
+    }
+    let has_room = world
+        .get_resource::<PackagePool>()
+        .is_some_and(|package_pool| package_pool.parked.len() < package_pool.capacity);
+    let mut package = world.entity_mut(package_entity);
+    if !has_room {
+        package.despawn_recursive();
+        return;
+    }
+    package.remove_parent();
+    package.despawn_descendants();
+    package.retain::<SpriteBundle>();
+    package.insert(parked_package());
+    world
+        .resource_mut::<PackagePool>()
+        .parked
+        .push(package_entity);
+}
+
+struct ReleasePackage(Entity);
+
+impl Command for ReleasePackage {
+    fn apply(self, world: &mut World) {
+        park_package(world, self.0);
+    }
+}
+
+/// Used instead of despawning a package, hands it back to the pool.
+pub fn release_package(commands: &mut Commands, package_entity: Entity) {
+    commands.add(ReleasePackage(package_entity));
+}
@@ -0,0 +1,371 @@
+use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
+
+use crate::{
+    save_config_value, AppConfig, AxisOffset, CalibrationConfig, ConfigLayers, DataOutputPolicy,
+    EntityLayer, PadCalibration, RenderLayers,
+};
+
+const CALIBRATED_AXES: [GamepadAxisType; 6] = [
+    GamepadAxisType::LeftStickX,
+    GamepadAxisType::LeftStickY,
+    GamepadAxisType::RightStickX,
+    GamepadAxisType::RightStickY,
+    GamepadAxisType::LeftZ,
+    GamepadAxisType::RightZ,
+];
+const MIN_DEADZONE: f32 = 0.02;
+const MAX_DEADZONE: f32 = 0.5;
+/// Added on top of the widest drift seen while sampling, sticks wander a little more over time.
+const DEADZONE_MARGIN: f32 = 0.05;
+const STICK_BOX_SIZE: f32 = 120.;
+const STICK_DOT_SIZE: f32 = 10.;
+
+impl CalibrationConfig {
+    pub fn for_pad(&self, name: Option<&str>, id: usize) -> Option<&PadCalibration> {
+        let name = name?;
+        self.pads
+            .iter()
+            .find(|pad_calibration| pad_calibration.id == id && pad_calibration.name == name)
+    }
+
+    /// Takes off the pad's resting offset, then its deadzone, stretching what's left so a full
+    /// push either way still reads as 1. Unknown pads only get the default deadzone.
+    pub fn apply(
+        &self,
+        pad_calibration: Option<&PadCalibration>,
+        axis: GamepadAxisType,
+        value: f32,
+    ) -> f32 {
+        let (offset, deadzone) = match pad_calibration {
+            Some(pad_calibration) => (
+                pad_calibration
+                    .axis_offsets
+                    .iter()
+                    .find(|axis_offset| axis_offset.axis == axis)
+                    .map_or(0., |axis_offset| axis_offset.offset),
+                pad_calibration.deadzone,
+            ),
+            None => (0., self.default_deadzone),
+        };
+        let centred = value - offset;
+        // an off centre stick has less travel on one side than the other
+        let travel = 1. - offset * centred.signum();
+        if centred.abs() <= deadzone || travel <= deadzone {
+            0.
+        } else {
+            (centred.signum() * (centred.abs() - deadzone) / (travel - deadzone)).clamp(-1., 1.)
+        }
+    }
+}
+
+/// Each axis is offset by its average resting value, the deadzone covers the widest drift either
+/// side of that.
+pub fn calibrate_pad(
+    name: &str,
+    id: usize,
+    axis_samples: &[(GamepadAxisType, Vec<f32>)],
+) -> PadCalibration {
+    let mut axis_offsets = Vec::new();
+    let mut widest_drift: f32 = 0.;
+    for (axis, samples) in axis_samples {
+        if samples.is_empty() {
+            continue;
+        }
+        let offset = samples.iter().sum::<f32>() / samples.len() as f32;
+        widest_drift = samples
+            .iter()
+            .map(|sample| (sample - offset).abs())
+            .fold(widest_drift, f32::max);
+        axis_offsets.push(AxisOffset {
+            axis: *axis,
+            offset,
+        });
+    }
+    PadCalibration {
+        name: name.to_string(),
+        id,
+        deadzone: (widest_drift + DEADZONE_MARGIN).clamp(MIN_DEADZONE, MAX_DEADZONE),
+        axis_offsets,
+    }
+}
+
+#[derive(Default, PartialEq, Eq)]
+enum CalibrationPhase {
+    #[default]
+    Hidden,
+    Sampling,
+    Verifying,
+}
+
+#[derive(Resource, Default)]
+pub struct PadCalibrationScreen {
+    phase: CalibrationPhase,
+    sample_timer: Timer,
+    samples: HashMap<Gamepad, HashMap<GamepadAxisType, Vec<f32>>>,
+}
+
+impl PadCalibrationScreen {
+    pub fn is_open(screen: Res<PadCalibrationScreen>) -> bool {
+        screen.phase != CalibrationPhase::Hidden
+    }
+}
+
+#[derive(Component)]
+pub struct PadCalibrationElement;
+
+#[derive(Component)]
+pub struct PadCalibrationPrompt;
+
+/// Marks where a stick currently points, `axes` are its x and y and `centre` is where it rests.
+#[derive(Component)]
+pub struct StickMarker {
+    pub gamepad: Gamepad,
+    pub axes: (GamepadAxisType, GamepadAxisType),
+    pub centre: Vec2,
+}
+
+pub fn toggle_pad_calibration(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    app_config: Res<AppConfig>,
+    mut screen: ResMut<PadCalibrationScreen>,
+    element_query: Query<Entity, With<PadCalibrationElement>>,
+) {
+    if !keyboard_input.just_pressed(app_config.calibration.toggle_key) {
+        return;
+    }
+
+    if screen.phase != CalibrationPhase::Hidden {
+        screen.phase = CalibrationPhase::Hidden;
+        for element in &element_query {
+            commands.entity(element).despawn_recursive();
+        }
+        return;
+    }
+
+    screen.phase = CalibrationPhase::Sampling;
+    screen.sample_timer =
+        Timer::from_seconds(app_config.calibration.sample_seconds, TimerMode::Once);
+    screen.samples.clear();
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK.with_a(0.85),
+                    custom_size: Some(app_config.base_resolution.as_vec2()),
+                    ..default()
+                },
+                ..default()
+            },
+            RenderLayers::Single(EntityLayer::Overlay),
+            PadCalibrationElement,
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "Calibrating, leave every stick and trigger at rest",
+                        TextStyle {
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_justify(JustifyText::Center),
+                    text_anchor: Anchor::TopCenter,
+                    transform: Transform::from_translation(Vec3::new(
+                        0.,
+                        app_config.base_resolution.y as f32 * 0.4,
+                        0.1,
+                    )),
+                    ..default()
+                },
+                PadCalibrationPrompt,
+            ));
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn sample_pad_calibration(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut app_config: ResMut<AppConfig>,
+    config_layers: Res<ConfigLayers>,
+    output_policy: Res<DataOutputPolicy>,
+    mut screen: ResMut<PadCalibrationScreen>,
+    mut prompt_query: Query<&mut Text, With<PadCalibrationPrompt>>,
+    element_query: Query<Entity, With<PadCalibrationElement>>,
+) {
+    if screen.phase != CalibrationPhase::Sampling {
+        return;
+    }
+
+    for gamepad in gamepads.iter() {
+        for axis_type in CALIBRATED_AXES {
+            if let Some(value) = gamepad_axes.get(GamepadAxis { gamepad, axis_type }) {
+                screen
+                    .samples
+                    .entry(gamepad)
+                    .or_default()
+                    .entry(axis_type)
+                    .or_default()
+                    .push(value);
+            }
+        }
+    }
+
+    screen.sample_timer.tick(time.delta());
+    if !screen.sample_timer.just_finished() {
+        return;
+    }
+
+    let calibration = &mut app_config.calibration;
+    for gamepad in gamepads.iter() {
+        let Some(name) = gamepads.name(gamepad) else {
+            continue;
+        };
+        let axis_samples = screen
+            .samples
+            .remove(&gamepad)
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let pad_calibration = calibrate_pad(name, gamepad.id, &axis_samples);
+        info!(
+            "calibrated pad {} ({}), deadzone {:.2}",
+            name, gamepad.id, pad_calibration.deadzone
+        );
+        calibration
+            .pads
+            .retain(|existing| existing.id != gamepad.id || existing.name != name);
+        calibration.pads.push(pad_calibration);
+    }
+
+    if output_policy.allows_writes() {
+        let local_config_path = config_layers.local_override_path();
+        if let Err(error) = toml::Value::try_from(&calibration.pads)
+            .map_err(anyhow::Error::from)
+            .and_then(|pads| save_config_value(&local_config_path, "app.calibration.pads", pads))
+        {
+            warn!(
+                "could not save pad calibration to {}: {}",
+                local_config_path.display(),
+                error
+            );
+        }
+    }
+
+    screen.phase = CalibrationPhase::Verifying;
+    for mut prompt in &mut prompt_query {
+        prompt.sections[0].value = format!(
+            "Calibrated, move the sticks to check them\n[{:?}] Close",
+            calibration.toggle_key
+        );
+    }
+
+    // one row per pad, left stick then right stick
+    let Some(screen_root) = element_query.iter().next() else {
+        return;
+    };
+    commands.entity(screen_root).with_children(|builder| {
+        for (row, gamepad) in gamepads.iter().enumerate() {
+            let row_y = -(row as f32) * (STICK_BOX_SIZE + 40.);
+            builder.spawn(Text2dBundle {
+                text: Text::from_section(
+                    format!(
+                        "{} ({})",
+                        gamepads.name(gamepad).unwrap_or("unknown"),
+                        gamepad.id
+                    ),
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::CenterRight,
+                transform: Transform::from_translation(Vec3::new(
+                    -STICK_BOX_SIZE * 1.5,
+                    row_y,
+                    0.1,
+                )),
+                ..default()
+            });
+            let deadzone = calibration
+                .for_pad(gamepads.name(gamepad), gamepad.id)
+                .map_or(calibration.default_deadzone, |pad_calibration| {
+                    pad_calibration.deadzone
+                });
+            for (column, axes) in [
+                (GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY),
+                (GamepadAxisType::RightStickX, GamepadAxisType::RightStickY),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let centre = Vec2::new((column as f32 - 0.5) * (STICK_BOX_SIZE + 20.), row_y);
+                builder.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::DARK_GRAY,
+                        custom_size: Some(Vec2::splat(STICK_BOX_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(centre.extend(0.1)),
+                    ..default()
+                });
+                builder.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::GRAY,
+                        custom_size: Some(Vec2::splat(STICK_BOX_SIZE * deadzone)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(centre.extend(0.2)),
+                    ..default()
+                });
+                builder.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::WHITE,
+                            custom_size: Some(Vec2::splat(STICK_DOT_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(centre.extend(0.3)),
+                        ..default()
+                    },
+                    StickMarker {
+                        gamepad,
+                        axes,
+                        centre,
+                    },
+                ));
+            }
+        }
+    });
+}
+
+/// Shows each stick as the game reads it, after calibration.
+pub fn update_stick_markers(
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    app_config: Res<AppConfig>,
+    mut marker_query: Query<(&StickMarker, &mut Transform)>,
+) {
+    let calibration = &app_config.calibration;
+    for (marker, mut marker_transform) in &mut marker_query {
+        let pad_calibration = calibration.for_pad(gamepads.name(marker.gamepad), marker.gamepad.id);
+        let read_axis = |axis_type| {
+            let value = gamepad_axes
+                .get(GamepadAxis {
+                    gamepad: marker.gamepad,
+                    axis_type,
+                })
+                .unwrap_or(0.);
+            calibration.apply(pad_calibration, axis_type, value)
+        };
+        let position = Vec2::new(read_axis(marker.axes.0), read_axis(marker.axes.1));
+        marker_transform.translation =
+            (marker.centre + position * (STICK_BOX_SIZE - STICK_DOT_SIZE) / 2.).extend(0.3);
+    }
+}
@@ -1,30 +1,78 @@
 use bevy::prelude::*;
 use bevy_rapier2d::{
     control::{KinematicCharacterController, KinematicCharacterControllerOutput},
-    dynamics::RigidBody,
-    geometry::Collider,
+    dynamics::{ExternalImpulse, RigidBody},
+    geometry::{Collider, CollisionGroups, Group},
     pipeline::QueryFilter,
     plugin::RapierContext,
 };
+use enum_map::{Enum, EnumMap};
 
 use crate::{
-    activate_package_physics, deactivate_package_physics, random::*, AnimationData, Conveyor,
-    ConveyorLabelTag, EntityLayer, FacingDirection, GameConfig, GameState, KeyAction, Package,
-    PlayerIndex, RenderLayers, TextureTarget,
+    activate_package_physics, deactivate_package_physics, find_aim_lock, make_nudge_arrow,
+    random::*, spawn_charge_hint, ActiveModifier, AimAssist, AnimationData, AppConfig,
+    BeltRegistry, ButtonState, Conveyor, ConveyorLabelTag, CursorAim, EmoteState, EntityLayer,
+    FacingBias, FacingDirection, FriendlyFireRecord, GameConfig, GameModeKind, GameState,
+    InteractionActor, InteractionIntent, InteractionIntents, InteractionKind, KeyAction, Metrics,
+    Package, PackageThrown, PlayerBrain, PlayerControls, PlayerIndex, PlayerPattern, PowerupKind,
+    RenderLayers, SpawnContext, SpawnTrail, SpriteSheetSource, Stunned, TextureTarget, ThrownBy,
+    TrackedTexture, ZoneOccupancy, PACKAGE_DENSITY, PACKAGE_LINEAR_DAMPING,
 };
 
+pub const PLAYER_COLLISION_GROUP: Group = Group::GROUP_1;
+
+#[derive(Component)]
+pub struct WallTag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayAreaAligment {
     Left,
     Right,
 }
 
+impl PlayAreaAligment {
+    pub fn opposite(&self) -> Self {
+        match self {
+            PlayAreaAligment::Left => PlayAreaAligment::Right,
+            PlayAreaAligment::Right => PlayAreaAligment::Left,
+        }
+    }
+}
+
+/// Where a player working `side` starts a round, just in front of their outgoing belt.
+pub fn player_start_position(
+    side: PlayAreaAligment,
+    app_config: &AppConfig,
+    game_config: &GameConfig,
+) -> Vec3 {
+    let edge_offset = (app_config.base_resolution.x as f32 / 2.)
+        - game_config.conveyor_config.size.x
+        - (game_config.player_config.size / 2.);
+    match side {
+        PlayAreaAligment::Left => Vec3::new(-edge_offset, 0., 0.),
+        PlayAreaAligment::Right => Vec3::new(edge_offset, 0., 0.),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MoveAxis {
+    Horizontal,
+    Vertical,
+}
+
 #[derive(Component)]
 pub struct Player {
     pub pickup_cooldown_timer: Timer,
     pub throw_timer: Timer,
     pub player_index: PlayerIndex,
+    pub last_pressed_axis: MoveAxis,
+    /// Only lags behind the controls on slippery floors.
+    pub move_velocity: Vec2,
 }
 
+#[derive(Component)]
+pub struct PlayerAccent;
+
 impl PlayAreaAligment {
     pub fn get_blink_position_modifier(&self, conveyor_tag: &ConveyorLabelTag) -> f32 {
         match conveyor_tag {
@@ -40,105 +88,273 @@ impl PlayAreaAligment {
     }
 }
 
-pub fn spawn_player(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    texture_atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
-    player_pos: Vec3,
-    player_index: PlayerIndex,
-    rng: &mut ResMut<Rand>,
-    game_config: &Res<GameConfig>,
+/// Marks drawn over the player's accent in a darker shade of their colour.
+fn spawn_accent_pattern(
+    builder: &mut ChildBuilder,
+    pattern: PlayerPattern,
+    colour: Color,
+    accent_size: f32,
 ) {
-    let (player_sprite, sprite_path) = if let Some(texture) =
-        &game_config.player_config.per_player[player_index].sprite_override
-    {
-        let sprite = texture.choose_texture(Some(rng));
-        (sprite, sprite.path.clone())
-    } else {
-        let texture_pack = game_config.get_texture_pack();
-        let sprite = texture_pack.choose_texture_for(TextureTarget::AllPlayers, Some(rng));
-        (sprite, format!("{}/{}", texture_pack.root, sprite.path))
-    };
-
-    let texture_handle: Handle<Image> = asset_server.load(&sprite_path);
-    let sprite_size = player_sprite
-        .cell_resolution
-        .expect("Player sprite must have a cell resolution")
-        .as_vec2();
-    let sprite_grid = player_sprite
-        .grid_dimensions
-        .expect("Player sprite must have grid dimensions");
-    let frame_count = sprite_grid.x * sprite_grid.y;
-    let atlas_layout = TextureAtlasLayout::from_grid(
-        sprite_size,
-        sprite_grid.x as usize,
-        sprite_grid.y as usize,
-        None,
-        None,
+    let mark_colour = Color::rgba(
+        colour.r() * 0.35,
+        colour.g() * 0.35,
+        colour.b() * 0.35,
+        colour.a(),
     );
-    let animation_indices = AnimationData {
-        start_frame: 0,
-        frame_count: frame_count as usize,
-        pause: true,
-        facing_direction: FacingDirection::Down,
+    let mark_size = accent_size / 8.;
+    let marks = match pattern {
+        PlayerPattern::Stripes => (0..4)
+            .map(|column| {
+                (
+                    Vec2::new(-accent_size / 2. + mark_size * (2 * column + 1) as f32, 0.),
+                    Vec2::new(mark_size, accent_size),
+                )
+            })
+            .collect::<Vec<_>>(),
+        PlayerPattern::Dots => (0..9)
+            .map(|index| {
+                let cell = Vec2::new((index % 3) as f32 - 1., (index / 3) as f32 - 1.);
+                (cell * (accent_size / 3.), Vec2::splat(mark_size))
+            })
+            .collect::<Vec<_>>(),
     };
-    commands
-        .spawn((
-            RigidBody::KinematicPositionBased,
-            SpriteSheetBundle {
+    for (position, size) in marks {
+        builder.spawn((
+            SpriteBundle {
                 sprite: Sprite {
-                    custom_size: Some(Vec2::new(
-                        game_config.player_config.size,
-                        game_config.player_config.size,
-                    )),
-                    ..default()
-                },
-                atlas: TextureAtlas {
-                    layout: texture_atlas_layouts.add(atlas_layout),
-                    index: animation_indices.start_frame,
-                },
-                texture: texture_handle,
-                transform: Transform {
-                    translation: player_pos,
+                    custom_size: Some(size),
+                    color: mark_colour,
                     ..default()
                 },
+                transform: Transform::from_translation(position.extend(0.)),
                 ..default()
             },
-            Player {
-                pickup_cooldown_timer: Timer::from_seconds(0.3, TimerMode::Once),
-                throw_timer: Timer::from_seconds(1., TimerMode::Once),
-                player_index: player_index,
-            },
-            Collider::cuboid(
-                game_config.player_config.size / 2.,
-                game_config.player_config.size / 2.,
-            ),
-            KinematicCharacterController::default(),
-            RenderLayers::Single(EntityLayer::Player),
-            animation_indices,
-        ))
-        .with_children(|builder| {
-            builder.spawn((
-                SpriteBundle {
+            RenderLayers::Single(EntityLayer::AccentPattern),
+        ));
+    }
+}
+
+/// Spawns a player at the origin in their configured colour unless told otherwise.
+pub struct PlayerSpawner {
+    player_index: PlayerIndex,
+    position: Vec3,
+    colour: Option<Color>,
+}
+
+impl PlayerSpawner {
+    pub fn new(player_index: PlayerIndex) -> Self {
+        Self {
+            player_index,
+            position: Vec3::ZERO,
+            colour: None,
+        }
+    }
+
+    pub fn at(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_colour(mut self, colour: Color) -> Self {
+        self.colour = Some(colour);
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands, ctx: &mut SpawnContext) -> Entity {
+        self.spawn_with(
+            commands,
+            &ctx.asset_server,
+            &mut ctx.texture_atlas_layouts,
+            &mut ctx.rng,
+            &ctx.game_config,
+        )
+    }
+
+    fn spawn_with(
+        self,
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+        rng: &mut Rand,
+        game_config: &GameConfig,
+    ) -> Entity {
+        let player_pos = self.position;
+        let player_index = self.player_index;
+        let player_colour = self
+            .colour
+            .unwrap_or(*game_config.player_config.per_player[player_index].colour);
+        let texture_pack = game_config.get_texture_pack();
+        let texture_key = player_index.into_usize() as u64;
+        let (player_sprite, sprite_path, sprite_source) = if let Some(texture) =
+            &game_config.player_config.per_player[player_index].sprite_override
+        {
+            let sprite =
+                texture.select_texture(game_config.texture_selection, texture_key, Some(rng));
+            (
+                sprite,
+                sprite.path.clone(),
+                format!("{:?} sprite_override", player_index),
+            )
+        } else {
+            let sprite = texture_pack.select_texture_for(
+                TextureTarget::AllPlayers,
+                game_config.texture_selection,
+                texture_key,
+                Some(rng),
+            );
+            (
+                sprite,
+                format!("{}/{}", texture_pack.root, sprite.path),
+                format!("texture pack '{}'", game_config.selected_texture_pack),
+            )
+        };
+
+        let (atlas_layout, frame_count) = player_sprite.atlas_layout("Player");
+        let sprite_size = texture_pack.display_size(player_sprite, game_config.player_config.size);
+        let texture_handle: Handle<Image> = asset_server.load(&sprite_path);
+        let animation_indices = AnimationData {
+            start_frame: 0,
+            frame_count,
+            pause: true,
+            facing_direction: FacingDirection::Down,
+        };
+        commands
+            .spawn((
+                RigidBody::KinematicPositionBased,
+                SpriteSheetBundle {
                     sprite: Sprite {
-                        custom_size: Some(Vec2::new(
-                            game_config.player_config.size * 1.2,
-                            game_config.player_config.size * 1.2,
-                        )),
-                        color: game_config.player_config.per_player[player_index].colour,
+                        custom_size: Some(sprite_size),
                         ..default()
                     },
+                    atlas: TextureAtlas {
+                        layout: texture_atlas_layouts.add(atlas_layout),
+                        index: animation_indices.start_frame,
+                    },
+                    texture: texture_handle,
                     transform: Transform {
-                        translation: Vec3::ZERO,
+                        translation: player_pos,
                         ..default()
                     },
                     ..default()
                 },
-                RenderLayers::Single(EntityLayer::Accent),
-            ));
-        });
+                Player {
+                    pickup_cooldown_timer: Timer::from_seconds(
+                        game_config.player_config.pickup_cooldown_seconds,
+                        TimerMode::Once,
+                    ),
+                    throw_timer: Timer::from_seconds(1., TimerMode::Once),
+                    player_index,
+                    last_pressed_axis: MoveAxis::Horizontal,
+                    move_velocity: Vec2::ZERO,
+                },
+                PlayerBrain::default(),
+                FriendlyFireRecord::default(),
+                ZoneOccupancy::default(),
+                TrackedTexture::new(TextureTarget::AllPlayers, &sprite_source, &sprite_path),
+                SpriteSheetSource {
+                    source: sprite_source,
+                    path: sprite_path,
+                    expected_size: player_sprite.expected_image_size().unwrap_or_default(),
+                },
+                Collider::cuboid(
+                    game_config.player_config.size / 2.,
+                    game_config.player_config.size / 2.,
+                ),
+                CollisionGroups::new(PLAYER_COLLISION_GROUP, Group::ALL),
+                KinematicCharacterController {
+                    // pushing packages is handled by push_packages so it can be tuned
+                    apply_impulse_to_dynamic_bodies: false,
+                    filter_groups: (!game_config.players_collide()).then(|| {
+                        CollisionGroups::new(Group::ALL, Group::ALL - PLAYER_COLLISION_GROUP)
+                    }),
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::Player),
+                animation_indices,
+                SpawnTrail::new(&game_config.feedback_config.sprint_trail),
+                EmoteState::new(&game_config.emote_config),
+            ))
+            .with_children(|builder| {
+                builder.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::new(
+                                game_config.player_config.size * 1.2,
+                                game_config.player_config.size * 1.2,
+                            )),
+                            color: player_colour,
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: Vec3::ZERO,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    RenderLayers::Single(EntityLayer::Accent),
+                    PlayerAccent,
+                ));
+                if let Some(pattern) = game_config.player_config.per_player[player_index].pattern {
+                    spawn_accent_pattern(
+                        builder,
+                        pattern,
+                        player_colour,
+                        game_config.player_config.size * 1.2,
+                    );
+                }
+                builder.spawn(make_nudge_arrow(game_config.player_config.size));
+                spawn_charge_hint(
+                    builder,
+                    player_index,
+                    asset_server,
+                    texture_atlas_layouts,
+                    game_config,
+                );
+            })
+            .id()
+    }
 }
 
+#[deprecated(note = "use PlayerSpawner")]
+pub fn spawn_player(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    texture_atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
+    player_pos: Vec3,
+    player_index: PlayerIndex,
+    rng: &mut ResMut<Rand>,
+    game_config: &Res<GameConfig>,
+) {
+    PlayerSpawner::new(player_index).at(player_pos).spawn_with(
+        commands,
+        asset_server,
+        texture_atlas_layouts,
+        rng,
+        game_config,
+    );
+}
+
+/// Shoves a player along without their say for a moment.
+#[derive(Component)]
+pub struct Knockback {
+    pub velocity: Vec2,
+    pub timer: Timer,
+}
+
+pub fn update_knockbacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut knockback_query: Query<(Entity, &mut Knockback)>,
+) {
+    for (player_entity, mut knockback) in &mut knockback_query {
+        knockback.timer.tick(time.delta());
+        if knockback.timer.finished() {
+            commands.entity(player_entity).remove::<Knockback>();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn move_player(
     game_state: Res<GameState>,
     game_config: Res<GameConfig>,
@@ -146,373 +362,868 @@ pub fn move_player(
         (
             &mut KinematicCharacterController,
             &mut AnimationData,
-            &Player,
+            &mut Player,
+            &ZoneOccupancy,
+            Option<&ActiveModifier>,
+            Option<&Knockback>,
+            Option<&Children>,
+            Has<Stunned>,
         ),
         With<Player>,
     >,
+    package_query: Query<(), With<Package>>,
     time: Res<Time>,
 ) {
-    for (mut character_controller, mut player_anim_data, player_data) in &mut query {
+    for (
+        mut character_controller,
+        mut player_anim_data,
+        mut player_data,
+        occupancy,
+        active_modifier,
+        knockback,
+        player_children,
+        is_stunned,
+    ) in &mut query
+    {
+        if is_stunned {
+            character_controller.translation = None;
+            player_data.move_velocity = Vec2::ZERO;
+            continue;
+        }
+        if let Some(knockback) = knockback {
+            character_controller.translation = Some(knockback.velocity * time.delta_seconds());
+            player_data.move_velocity = Vec2::ZERO;
+            continue;
+        }
+
         let player_control_state = &game_state.player_controls[player_data.player_index].state;
         let sprinting = player_control_state[KeyAction::Sprint].pressed();
-        // bias to facing horizontally TODO: remove this bias
-        let mut new_facing_direction: Option<FacingDirection> = None;
+        let mut vertical_facing_direction: Option<FacingDirection> = None;
+        let mut horizontal_facing_direction: Option<FacingDirection> = None;
         let mut direction: Vec2 = Vec2::ZERO;
 
         if player_control_state[KeyAction::MoveUp].pressed() {
-            new_facing_direction = Some(FacingDirection::Up);
+            vertical_facing_direction = Some(FacingDirection::Up);
             direction.y = 1.;
         } else if player_control_state[KeyAction::MoveDown].pressed() {
-            new_facing_direction = Some(FacingDirection::Down);
+            vertical_facing_direction = Some(FacingDirection::Down);
             direction.y = -1.;
         }
 
         if player_control_state[KeyAction::MoveLeft].pressed() {
-            new_facing_direction = Some(FacingDirection::Left);
+            horizontal_facing_direction = Some(FacingDirection::Left);
             direction.x = -1.;
         } else if player_control_state[KeyAction::MoveRight].pressed() {
-            new_facing_direction = Some(FacingDirection::Right);
+            horizontal_facing_direction = Some(FacingDirection::Right);
             direction.x = 1.;
         }
 
-        new_facing_direction.map(|f| player_anim_data.facing_direction = f);
-        character_controller.translation = Some(
-            direction.normalize_or_zero()
-                * game_config.player_config.move_speed
-                * if sprinting {
-                    game_config.player_config.sprint_move_modifier
-                } else {
-                    1.
+        // remember which axis was pressed most recently, horizontal wins if both go down on the same frame
+        if player_control_state[KeyAction::MoveUp].just_pressed()
+            || player_control_state[KeyAction::MoveDown].just_pressed()
+        {
+            player_data.last_pressed_axis = MoveAxis::Vertical;
+        }
+        if player_control_state[KeyAction::MoveLeft].just_pressed()
+            || player_control_state[KeyAction::MoveRight].just_pressed()
+        {
+            player_data.last_pressed_axis = MoveAxis::Horizontal;
+        }
+
+        let new_facing_direction = match (vertical_facing_direction, horizontal_facing_direction) {
+            (Some(vertical), Some(horizontal)) => {
+                // moving diagonally, pick an axis to face based on the configured bias
+                let preferred_axis = match game_config.player_config.facing_bias {
+                    FacingBias::LastPressed => player_data.last_pressed_axis,
+                    FacingBias::Horizontal => MoveAxis::Horizontal,
+                    FacingBias::Vertical => MoveAxis::Vertical,
+                };
+                match preferred_axis {
+                    MoveAxis::Horizontal => Some(horizontal),
+                    MoveAxis::Vertical => Some(vertical),
                 }
-                * time.delta_seconds(),
-        );
+            }
+            (vertical, horizontal) => vertical.or(horizontal),
+        };
+
+        if let Some(facing_direction) = new_facing_direction {
+            player_anim_data.facing_direction = facing_direction;
+        }
+        let target_velocity = direction.normalize_or_zero()
+            * game_config.player_config.move_speed
+            * if sprinting {
+                game_config.player_config.sprint_move_modifier
+            } else {
+                1.
+            }
+            * occupancy.speed_multiplier
+            * ActiveModifier::speed_multiplier(active_modifier, &game_config)
+            * game_config.player_config.carry_speed_multiplier(
+                held_packages(player_children, |child| package_query.contains(child)).count(),
+            );
+        player_data.move_velocity = match occupancy.grip {
+            Some(grip) => player_data
+                .move_velocity
+                .lerp(target_velocity, (grip * time.delta_seconds()).min(1.)),
+            None => target_velocity,
+        };
+        character_controller.translation =
+            Some((player_data.move_velocity + occupancy.drift) * time.delta_seconds());
     }
 }
 
+pub fn push_packages(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    player_query: Query<&KinematicCharacterControllerOutput, With<Player>>,
+    mut package_query: Query<&mut ExternalImpulse, (With<Package>, Without<Player>)>,
+) {
+    let package_size = game_config.package_config.size;
+    let package_mass = PACKAGE_DENSITY * (package_size / game_config.pixels_per_meter()).powi(2);
+    for player_output in &player_query {
+        let delta_seconds = time.delta_seconds();
+        if delta_seconds <= 0. {
+            continue;
+        }
+        let player_velocity = player_output.desired_translation / delta_seconds;
+
+        // a steady push against linear damping settles at push_strength times the player's
+        // speed, so sprinting into a package shoves it twice as fast
+        let push = player_velocity
+            * game_config.player_config.push_strength
+            * package_mass
+            * PACKAGE_LINEAR_DAMPING
+            * delta_seconds;
+        // the controller can report the same package several times in one move
+        let mut pushed_packages = Vec::new();
+        for collision in &player_output.collisions {
+            if pushed_packages.contains(&collision.entity) {
+                continue;
+            }
+            if let Ok(mut package_impulse) = package_query.get_mut(collision.entity) {
+                package_impulse.impulse += push;
+                pushed_packages.push(collision.entity);
+            }
+        }
+    }
+}
+
+/// Packages a player is carrying in the order they were picked up, the last is thrown first.
+pub fn held_packages<'a>(
+    player_children: Option<&'a Children>,
+    is_package: impl Fn(Entity) -> bool + 'a,
+) -> impl Iterator<Item = Entity> + 'a {
+    player_children
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(move |child| is_package(*child))
+}
+
+/// Moves a package into a player's hands, wherever it was before. `stack_height` is how many
+/// they're already carrying, the new package goes on top of those.
+#[allow(clippy::too_many_arguments)]
+pub fn attach_package_to_player(
+    commands: &mut Commands,
+    player_entity: Entity,
+    player_info: &mut Player,
+    package_entity: Entity,
+    package_transform: &mut Transform,
+    package_layers: &mut RenderLayers,
+    package_rigid_body: bool,
+    stack_height: usize,
+    game_config: &GameConfig,
+) {
+    package_transform.translation = Vec3::new(
+        0.,
+        game_config.player_config.size / 2.
+            + stack_height as f32 * game_config.package_config.size * 0.6,
+        0.,
+    );
+    if let RenderLayers::Multi(layers) = package_layers {
+        layers.insert(EntityLayer::HeldObject);
+    }
+    commands.entity(player_entity).add_child(package_entity);
+    player_info.throw_timer.reset();
+    player_info.pickup_cooldown_timer.reset();
+    if package_rigid_body {
+        deactivate_package_physics(commands, package_entity);
+    }
+}
+
+/// A player reaching for a package, as candidate selection sees them.
+pub struct PickupReach {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Already carrying as many packages as they can.
+    pub hands_full: bool,
+    /// Where they clicked when the pickup came from the mouse.
+    pub clicked_at: Option<Vec2>,
+    /// Half the size of a package, a click inside it picks that package.
+    pub click_half_extent: f32,
+    /// Everything the pickup sensor overlaps.
+    pub sensed: Vec<Entity>,
+    /// Everything the player's last move ran into.
+    pub touched: Vec<Entity>,
+}
+
+/// A package that might be picked up.
+pub struct PickupPackage {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub parent: Option<Entity>,
+    /// Position along its parent, used to find the front package of a belt.
+    pub local_y: f32,
+}
+
+/// An incoming belt whose packages can be picked up.
+pub struct PickupConveyor {
+    pub entity: Entity,
+    pub direction: f32,
+    pub exit_point: Vec2,
+    pub front_grab_zone: Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickupCandidate {
+    pub package: Entity,
+    pub distance: f32,
+}
+
+/// The package a player reaching for one should claim, if any: the one they clicked on, otherwise
+/// the nearest in reach.
+pub fn find_pickup_candidate(
+    reach: &PickupReach,
+    packages: &[PickupPackage],
+    conveyors: &[PickupConveyor],
+) -> Option<PickupCandidate> {
+    if reach.hands_full {
+        return None;
+    }
+
+    let reachable_conveyors = conveyors
+        .iter()
+        .filter(|conveyor| {
+            reach.sensed.contains(&conveyor.entity) || reach.touched.contains(&conveyor.entity)
+        })
+        .map(|conveyor| conveyor.entity)
+        .collect::<Vec<_>>();
+
+    // standing just past the end of an incoming belt offers its front package, which is treated
+    // as sitting at the belt's exit so a loose package that is closer still wins
+    let front_packages = conveyors
+        .iter()
+        .filter(|conveyor| conveyor.front_grab_zone.contains(reach.position))
+        .filter_map(|conveyor| {
+            packages
+                .iter()
+                .filter(|package| package.parent == Some(conveyor.entity))
+                .max_by(|a, b| {
+                    (a.local_y * conveyor.direction).total_cmp(&(b.local_y * conveyor.direction))
+                })
+                .map(|package| (package.entity, conveyor.exit_point))
+        })
+        .collect::<Vec<_>>();
+
+    // loose packages and packages sitting on a reachable incoming conveyor all compete, the
+    // nearest one within reach wins no matter which conveyor it happens to be on
+    let candidates = packages
+        .iter()
+        .filter_map(|package| {
+            if let Some((_, exit_point)) = front_packages
+                .iter()
+                .find(|(front_package, _)| *front_package == package.entity)
+            {
+                return Some((package, exit_point.distance(reach.position)));
+            }
+
+            let is_candidate = match package.parent {
+                Some(parent) => reachable_conveyors.contains(&parent),
+                None => reach.sensed.contains(&package.entity),
+            };
+            let distance = package.position.distance(reach.position);
+            (is_candidate && distance <= reach.radius).then_some((package, distance))
+        })
+        .collect::<Vec<_>>();
+
+    // clicking on a particular package in reach takes that one rather than the nearest
+    let clicked = reach.clicked_at.and_then(|cursor_pos| {
+        candidates.iter().copied().find(|(package, _)| {
+            (package.position - cursor_pos).abs().max_element() <= reach.click_half_extent
+        })
+    });
+    clicked
+        .or_else(|| {
+            candidates
+                .iter()
+                .copied()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        })
+        .map(|(package, distance)| PickupCandidate {
+            package: package.entity,
+            distance,
+        })
+}
+
+/// Claims the nearest package in reach for each player pressing pickup, the claims are settled by
+/// `resolve_interactions`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn pickup_package(
-    mut commands: Commands,
     rapier_context: Res<RapierContext>,
-    mut player_query: Query<
+    player_query: Query<
         (
             Entity,
-            &mut Player,
+            &Player,
             &Transform,
             &KinematicCharacterControllerOutput,
+            Option<&ActiveModifier>,
             Option<&Children>,
         ),
-        With<Player>,
+        (With<Player>, Without<Stunned>),
     >,
-    mut package_query: Query<
-        (
-            Entity,
-            &mut Transform,
-            &mut RenderLayers,
-            Option<&Parent>,
-            Has<RigidBody>,
-        ),
+    package_query: Query<
+        (Entity, &Transform, &GlobalTransform, Option<&Parent>),
         (With<Package>, Without<Player>),
     >,
-    mut conveyor_query: Query<(Entity, &mut Conveyor, &ConveyorLabelTag)>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform)>,
+    belt_registry: Res<BeltRegistry>,
+    cursor_aim: Res<CursorAim>,
     game_state: Res<GameState>,
     game_config: Res<GameConfig>,
+    mut interaction_intents: ResMut<InteractionIntents>,
 ) {
-    for (player_entity, mut player_info, player_transform, player_output, player_children) in
-        player_query.iter_mut()
+    let mut packages = None;
+    let mut conveyors = None;
+    for (
+        player_entity,
+        player_info,
+        player_transform,
+        player_output,
+        active_modifier,
+        player_children,
+    ) in player_query.iter()
     {
-        let player_wants_to_pickup = game_state.player_controls[player_info.player_index].state
-            [KeyAction::PickupOrThrow]
-            .just_pressed();
-        if !player_wants_to_pickup {
+        let player_controls = &game_state.player_controls[player_info.player_index];
+        if !player_controls.state[KeyAction::PickupOrThrow].just_pressed() {
             continue;
         }
 
-        if player_children.map_or(false, |children| {
-            children
-                .iter()
-                .find(|child| package_query.get(**child).is_ok())
-                .is_some()
-        }) {
-            // player is already holding a package, don't pick up another
-            continue;
-        }
-
-        let colliding_conveyors = conveyor_query.iter_mut().filter_map(
-            |(conveyor_entity, conveyor_info, conveyor_label)| {
-                if conveyor_label != &ConveyorLabelTag::Incoming {
-                    return None;
-                }
-
-                if let Some(collision) = player_output.collisions.iter().find_map(|collision| {
-                    if collision.entity == conveyor_entity {
-                        Some(collision)
-                    } else {
-                        None
-                    }
-                }) {
-                    Some((
-                        conveyor_entity,
-                        conveyor_info,
-                        conveyor_label,
-                        collision.toi,
-                    ))
-                } else {
-                    None
-                }
-            },
-        );
-
-        let most_recent_conveyor_collision = colliding_conveyors.max_by(|a, b| {
-            a.3.toi
-                .partial_cmp(&b.3.toi)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        let package = if let Some((conveyor_entity, conveyor_info, conveyor_label, _)) =
-            most_recent_conveyor_collision
-        {
-            if let Some((
-                package_entity,
-                package_transform,
-                package_layers,
-                package_parent,
-                package_rigid_body,
-            )) = package_query.iter_mut().find(|(_, _, _, parent, _)| {
-                parent.map_or(false, |parent| parent.get() == conveyor_entity)
-            }) {
-                Some((
-                    package_entity,
-                    package_transform,
-                    package_layers,
-                    package_parent,
-                    package_rigid_body,
-                    Some((conveyor_entity, conveyor_info, conveyor_label)),
-                ))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-
-        let package = if let Some(package) = package {
-            Some(package)
-        } else {
-            let sensor_area = Collider::ball(game_config.player_config.size * 2.5);
-            let filter = QueryFilter {
-                exclude_collider: Some(player_entity),
-                ..default()
-            };
-            let mut nearby_packages = Vec::new();
-            let mut nearby_conveyors = Vec::new();
+        let position = player_transform.translation.truncate();
+        let radius = game_config.player_config.pickup_radius()
+            * ActiveModifier::pickup_radius_multiplier(active_modifier, &game_config);
+        let hands_full = held_packages(player_children, |child| package_query.contains(child))
+            .count()
+            >= game_config.player_config.max_carry;
+        let mut sensed = Vec::new();
+        if !hands_full {
             rapier_context.intersections_with_shape(
-                player_transform.translation.truncate(),
+                position,
                 0.,
-                &sensor_area,
-                filter,
+                &Collider::ball(radius),
+                QueryFilter {
+                    exclude_collider: Some(player_entity),
+                    ..default()
+                },
                 |colliding_entity| {
-                    if package_query.get(colliding_entity).is_ok() {
-                        nearby_packages.push(colliding_entity);
-                    } else if conveyor_query.get(colliding_entity).is_ok() {
-                        nearby_conveyors.push(colliding_entity);
-                    }
+                    sensed.push(colliding_entity);
                     true
                 },
             );
+        }
+        let reach = PickupReach {
+            position,
+            radius,
+            hands_full,
+            clicked_at: cursor_aim
+                .world_position
+                .filter(|_| player_controls.triggered_by_mouse(KeyAction::PickupOrThrow)),
+            click_half_extent: game_config.package_config.size / 2.,
+            sensed,
+            touched: player_output
+                .collisions
+                .iter()
+                .map(|collision| collision.entity)
+                .collect(),
+        };
 
-            if !nearby_packages.is_empty() {
-                // find nearest pacakge
-                let candidate = package_query
-                    .iter_mut()
-                    .filter(|(package_entity, _, _, _, _)| nearby_packages.contains(package_entity))
-                    .min_by(|a, b| {
-                        let sq_dist_a =
-                            a.1.translation
-                                .truncate()
-                                .distance_squared(player_transform.translation.truncate());
-                        let sq_dist_b =
-                            b.1.translation
-                                .truncate()
-                                .distance_squared(player_transform.translation.truncate());
-                        sq_dist_a
-                            .partial_cmp(&sq_dist_b)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                if let Some((
-                    package_entity,
-                    package_transform,
-                    package_layers,
-                    package_parent,
-                    package_rigid_body,
-                )) = candidate
-                {
-                    Some((
+        // only gathered once someone actually reaches for a package
+        let packages = packages.get_or_insert_with(|| {
+            package_query
+                .iter()
+                .map(
+                    |(
                         package_entity,
                         package_transform,
-                        package_layers,
+                        package_global_transform,
                         package_parent,
-                        package_rigid_body,
-                        None,
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                // get first incoming conveyor
-                let candidate = conveyor_query
-                    .iter_mut()
-                    .filter(|(conveyor_entity, _, conveyor_label)| {
-                        nearby_conveyors.contains(conveyor_entity)
-                            && **conveyor_label == ConveyorLabelTag::Incoming
+                    )| {
+                        PickupPackage {
+                            entity: package_entity,
+                            position: package_global_transform.translation().truncate(),
+                            parent: package_parent.map(|parent| parent.get()),
+                            local_y: package_transform.translation.y,
+                        }
+                    },
+                )
+                .collect::<Vec<_>>()
+        });
+        let conveyors = conveyors.get_or_insert_with(|| {
+            belt_registry
+                .incoming()
+                .iter()
+                .filter_map(|conveyor_entity| {
+                    let (conveyor_info, conveyor_transform) =
+                        conveyor_query.get(*conveyor_entity).ok()?;
+                    let conveyor_pos = conveyor_transform.translation().truncate();
+                    Some(PickupConveyor {
+                        entity: *conveyor_entity,
+                        direction: conveyor_info.direction,
+                        exit_point: conveyor_info.exit_point(conveyor_pos),
+                        front_grab_zone: conveyor_info.front_grab_zone(
+                            conveyor_pos,
+                            game_config.conveyor_config.front_grab_zone_depth,
+                        ),
                     })
-                    .next();
-                if let Some((conveyor_entity, conveyor_info, conveyor_label)) = candidate {
-                    if let Some((
-                        package_entity,
-                        package_transform,
-                        package_layers,
-                        package_parent,
-                        package_rigid_body,
-                    )) = package_query.iter_mut().find(|(_, _, _, parent, _)| {
-                        parent.map_or(false, |parent| parent.get() == conveyor_entity)
-                    }) {
-                        Some((
-                            package_entity,
-                            package_transform,
-                            package_layers,
-                            package_parent,
-                            package_rigid_body,
-                            Some((conveyor_entity, conveyor_info, conveyor_label)),
-                        ))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            }
-        };
-
-        let (
-            package_entity,
-            mut package_transform,
-            mut package_layers,
-            package_parent,
-            package_rigid_body,
-            conveyor_holding_package,
-        ) = if let Some(package) = package {
-            package
-        } else {
-            continue;
-        };
+                })
+                .collect::<Vec<_>>()
+        });
 
-        if !package_parent.map_or(false, |_p| true)
-            || conveyor_holding_package.map_or(false, |(_, mut conveyor_info, conveyor_label)| {
-                if conveyor_label == &ConveyorLabelTag::Incoming {
-                    conveyor_info.package_count -= 1;
-                    true
-                } else {
-                    false
-                }
-            })
-        {
-            // pick up the package
-            package_transform.translation = Vec3::new(0., game_config.player_config.size / 2., 0.);
-            match package_layers.as_mut() {
-                RenderLayers::Multi(layers) => {
-                    layers.insert(EntityLayer::HeldObject);
-                }
-                _ => {}
-            }
-            commands.entity(player_entity).add_child(package_entity);
-            player_info.throw_timer.reset();
-            player_info.pickup_cooldown_timer.reset();
-            if package_rigid_body {
-                deactivate_package_physics(&mut commands, package_entity);
-            }
+        if let Some(candidate) = find_pickup_candidate(&reach, packages, conveyors) {
+            interaction_intents.push(InteractionIntent {
+                actor: InteractionActor::Player(player_entity, player_info.player_index),
+                package: candidate.package,
+                kind: InteractionKind::Pickup,
+                distance: candidate.distance,
+                onto: None,
+            });
         }
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn throw_package(
     mut commands: Commands,
-    player_query: Query<(Entity, &mut Player, &AnimationData, &Transform), With<Player>>,
+    rapier_context: Res<RapierContext>,
+    player_query: Query<
+        (
+            Entity,
+            &mut Player,
+            &AnimationData,
+            &Transform,
+            Option<&Children>,
+        ),
+        With<Player>,
+    >,
     mut package_query: Query<
         (Entity, &mut Transform, &mut RenderLayers, Option<&Parent>),
         (With<Package>, Without<Player>),
     >,
+    conveyor_query: Query<(Entity, &Conveyor, &GlobalTransform)>,
+    wall_query: Query<(), With<WallTag>>,
+    belt_registry: Res<BeltRegistry>,
+    cursor_aim: Res<CursorAim>,
+    mut interaction_intents: ResMut<InteractionIntents>,
     game_state: Res<GameState>,
     game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    mut thrown_events: EventWriter<PackageThrown>,
+    mut metrics: Option<ResMut<Metrics>>,
 ) {
+    let player_config = &game_config.player_config;
+    let returns_allowed = player_config.allow_returns
+        && (game_config.game_mode != GameModeKind::Versus || player_config.allow_returns_in_versus);
+
+    // only the package on top of each stack leaves a player's hands
+    let top_packages = player_query
+        .iter()
+        .filter_map(|(_, _, _, _, player_children)| {
+            held_packages(player_children, |child| package_query.contains(child)).last()
+        })
+        .collect::<Vec<_>>();
+
     for (package_entity, mut package_transform, mut package_layers, package_parent) in package_query
         .iter_mut()
-        .filter(|(_, _, _, package_parent)| package_parent.is_some())
+        .filter(|(package_entity, _, _, package_parent)| {
+            package_parent.is_some() && top_packages.contains(package_entity)
+        })
     {
         let package_parent = package_parent.unwrap();
 
-        if let Some((_, player_info, player_anim_data, player_transform)) = player_query
+        if let Some((_, player_info, player_anim_data, player_transform, _)) = player_query
             .iter()
-            .find(|(p, _, _, _)| p == &package_parent.get())
+            .find(|(p, _, _, _, _)| p == &package_parent.get())
         {
             let player_control_state = &game_state.player_controls[player_info.player_index].state;
             let player_wants_to_throw =
                 player_control_state[KeyAction::PickupOrThrow].just_released();
+            let player_wants_to_drop = player_control_state[KeyAction::Drop].just_pressed();
 
-            if !player_wants_to_throw || !player_info.pickup_cooldown_timer.finished() {
+            if !(player_wants_to_throw || player_wants_to_drop)
+                || !player_info.pickup_cooldown_timer.finished()
+            {
                 continue;
             }
 
-            // drop the package
-            commands.entity(package_entity).remove_parent();
-            match package_layers.as_mut() {
-                RenderLayers::Multi(layers) => {
-                    layers.remove(&EntityLayer::HeldObject);
-                    ()
+            let barely_charged = player_info.throw_timer.fraction()
+                <= game_config.player_config.direct_place_max_charge;
+            if player_wants_to_throw
+                && barely_charged
+                && (game_config.player_config.allow_direct_place || returns_allowed)
+            {
+                // barely charged while standing against an outgoing conveyor we may use, claim the
+                // nearest one with room to place the package straight onto rather than throwing it
+                let player_pos = player_transform.translation.truncate();
+                let mut touching_entities = Vec::new();
+                rapier_context.intersections_with_shape(
+                    player_pos,
+                    0.,
+                    &Collider::ball(game_config.player_config.size),
+                    QueryFilter {
+                        exclude_collider: Some(package_parent.get()),
+                        ..default()
+                    },
+                    |colliding_entity| {
+                        touching_entities.push(colliding_entity);
+                        true
+                    },
+                );
+
+                // team mode lets a player hand off onto their partner's belts too
+                let placeable_belts = (0..PlayerIndex::LENGTH)
+                    .filter(|_| game_config.player_config.allow_direct_place)
+                    .map(PlayerIndex::from_usize)
+                    .filter(|belt_owner| {
+                        game_config
+                            .game_mode
+                            .can_place_on_belt_of(player_info.player_index, *belt_owner)
+                    })
+                    .flat_map(|belt_owner| belt_registry.outgoing(belt_owner).iter())
+                    .map(|belt| (*belt, InteractionKind::Place));
+                // nowhere to deliver it, so back it goes on an incoming belt at a cost
+                let returnable_belts = belt_registry
+                    .incoming()
+                    .iter()
+                    .filter(|_| returns_allowed)
+                    .map(|belt| (*belt, InteractionKind::Return));
+                let claimed_belt = placeable_belts
+                    .chain(returnable_belts)
+                    .filter(|(belt, _)| touching_entities.contains(belt))
+                    .filter_map(|(belt, kind)| {
+                        let (_, conveyor_info, conveyor_transform) =
+                            conveyor_query.get(belt).ok()?;
+                        conveyor_info
+                            .has_free_capacity(game_config.package_config.size)
+                            .then(|| {
+                                let distance = conveyor_transform
+                                    .translation()
+                                    .truncate()
+                                    .distance(player_pos);
+                                (belt, kind, distance)
+                            })
+                    })
+                    .min_by(|(_, a_kind, a), (_, b_kind, b)| {
+                        a_kind.cmp(b_kind).then(a.total_cmp(b))
+                    });
+
+                // settled by `resolve_interactions` along with every other claim on belt space
+                if let Some((conveyor_entity, kind, distance)) = claimed_belt {
+                    interaction_intents.push(InteractionIntent {
+                        actor: InteractionActor::Player(
+                            package_parent.get(),
+                            player_info.player_index,
+                        ),
+                        package: package_entity,
+                        kind,
+                        distance,
+                        onto: Some(conveyor_entity),
+                    });
+                    continue;
                 }
-                _ => {}
             }
 
-            // calculate throw distance
-            let throw_distance = player_info.throw_timer.fraction()
-                * (1000. * game_config.player_config.throw_power);
-
-            let mut direction = player_anim_data.facing_direction.as_vector();
-            if player_control_state[KeyAction::MoveUp].pressed() {
-                direction.y = 1.;
-            } else if player_control_state[KeyAction::MoveDown].pressed() {
-                direction.y = -1.;
+            if let RenderLayers::Multi(layers) = package_layers.as_mut() {
+                layers.remove(&EntityLayer::HeldObject);
             }
+            commands.entity(package_entity).remove_parent();
 
-            if player_control_state[KeyAction::MoveLeft].pressed() {
-                direction.x = -1.;
-            } else if player_control_state[KeyAction::MoveRight].pressed() {
-                direction.x = 1.;
+            if player_wants_to_drop || (game_config.player_config.tap_to_drop && barely_charged) {
+                // set down just in front, unless that would put it inside a wall
+                let player_pos = player_transform.translation.truncate();
+                let half_package_size = game_config.package_config.size / 2.;
+                let drop_point = player_pos
+                    + player_anim_data.facing_direction.as_vector()
+                        * (game_config.player_config.size / 2. + half_package_size);
+                let mut drop_point_blocked = false;
+                rapier_context.intersections_with_shape(
+                    drop_point,
+                    0.,
+                    &Collider::cuboid(half_package_size, half_package_size),
+                    QueryFilter {
+                        exclude_collider: Some(package_parent.get()),
+                        ..default()
+                    },
+                    |colliding_entity| {
+                        drop_point_blocked |= wall_query.contains(colliding_entity);
+                        !drop_point_blocked
+                    },
+                );
+                package_transform.translation = if drop_point_blocked {
+                    player_pos
+                } else {
+                    drop_point
+                }
+                .extend(0.);
+                activate_package_physics(&mut commands, package_entity, &game_config, Vec2::ZERO);
+                continue;
             }
 
+            let player_pos = player_transform.translation.truncate();
+            let player_controls = &game_state.player_controls[player_info.player_index];
+            let mut direction = aimed_throw_direction(
+                player_pos,
+                player_anim_data.facing_direction,
+                player_controls,
+                &cursor_aim,
+            );
+            let mut impulse = direction * throw_distance(player_info, &game_config);
+            if AimAssist::applies_to(&game_config, player_controls) {
+                let own_belts = belt_registry
+                    .outgoing(player_info.player_index)
+                    .iter()
+                    .filter_map(|belt| conveyor_query.get(*belt).ok());
+                if let Some((_, assisted_direction, assisted_impulse)) = find_aim_lock(
+                    player_pos,
+                    direction,
+                    player_info.throw_timer.fraction(),
+                    own_belts,
+                    game_config.package_config.size,
+                    &AimAssist::new(&app_config, &game_config),
+                ) {
+                    direction = assisted_direction;
+                    impulse = assisted_impulse;
+                }
+            }
             package_transform.translation = player_transform.translation
                 + (direction * (game_config.player_config.size / 2.)).extend(0.);
-            activate_package_physics(
-                &mut commands,
-                package_entity,
-                &game_config,
-                direction * throw_distance,
-            );
+            if let Some(metrics) = metrics.as_mut() {
+                metrics.per_player[player_info.player_index].thrown += 1;
+            }
+            commands.entity(package_entity).insert(ThrownBy {
+                player: player_info.player_index,
+                timer: Timer::from_seconds(
+                    game_config.friendly_fire_config.thrown_window_seconds,
+                    TimerMode::Once,
+                ),
+            });
+            thrown_events.send(PackageThrown {
+                player: player_info.player_index,
+                package: package_entity,
+                charge: player_info.throw_timer.fraction(),
+            });
+            activate_package_physics(&mut commands, package_entity, &game_config, impulse);
         }
     }
 }
 
+pub fn throw_direction(
+    facing_direction: FacingDirection,
+    control_state: &EnumMap<KeyAction, ButtonState>,
+) -> Vec2 {
+    let mut direction = facing_direction.as_vector();
+    if control_state[KeyAction::MoveUp].pressed() {
+        direction.y = 1.;
+    } else if control_state[KeyAction::MoveDown].pressed() {
+        direction.y = -1.;
+    }
+
+    if control_state[KeyAction::MoveLeft].pressed() {
+        direction.x = -1.;
+    } else if control_state[KeyAction::MoveRight].pressed() {
+        direction.x = 1.;
+    }
+    direction
+}
+
+/// Throws started from a mouse button go towards the cursor, everything else, and a cursor with
+/// nowhere to aim, goes by `throw_direction`.
+pub fn aimed_throw_direction(
+    player_pos: Vec2,
+    facing_direction: FacingDirection,
+    player_controls: &PlayerControls,
+    cursor_aim: &CursorAim,
+) -> Vec2 {
+    if player_controls.triggered_by_mouse(KeyAction::PickupOrThrow) {
+        if let Some(direction) = cursor_aim
+            .world_position
+            .and_then(|cursor_pos| (cursor_pos - player_pos).try_normalize())
+        {
+            return direction;
+        }
+    }
+    throw_direction(facing_direction, &player_controls.state)
+}
+
+pub fn throw_distance(player_info: &Player, game_config: &GameConfig) -> f32 {
+    player_info.throw_timer.fraction() * (1000. * game_config.player_config.throw_power)
+}
+
+/// Charge builds for as long as pickup is held with a package in hand, from the moment it's
+/// picked up. The pickup cooldown only holds back the release, so a hold that outlasts it throws
+/// with everything it built up, cooldown included.
+#[allow(clippy::type_complexity)]
 pub fn player_charge_throw(
-    mut player_query: Query<(&mut Player, &Children), With<Player>>,
+    mut player_query: Query<
+        (&mut Player, Option<&ActiveModifier>, Option<&Children>),
+        With<Player>,
+    >,
+    package_query: Query<(), With<Package>>,
     game_state: Res<GameState>,
     time: Res<Time>,
 ) {
-    for (mut player_info, player_children) in &mut player_query {
+    for (mut player_info, active_modifier, player_children) in &mut player_query {
         player_info.pickup_cooldown_timer.tick(time.delta());
-        if player_children.len() > 0
-            && game_state.player_controls[player_info.player_index].state[KeyAction::PickupOrThrow]
-                .pressed()
-            && player_info.pickup_cooldown_timer.finished()
-        {
-            player_info.throw_timer.tick(time.delta());
+        let is_holding = player_children
+            .is_some_and(|children| children.iter().any(|child| package_query.contains(*child)));
+        let throw_state =
+            game_state.player_controls[player_info.player_index].state[KeyAction::PickupOrThrow];
+        // a fresh press charges from nothing, whatever is left in the stack after the last throw
+        if is_holding && throw_state.just_pressed() {
+            player_info.throw_timer.reset();
+        }
+        if is_holding && throw_state.pressed() {
+            let charge = if ActiveModifier::is(active_modifier, PowerupKind::InstantMaxCharge) {
+                player_info.throw_timer.duration()
+            } else {
+                time.delta()
+            };
+            player_info.throw_timer.tick(charge);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BELT: Entity = Entity::from_raw(100);
+    const OTHER_BELT: Entity = Entity::from_raw(101);
+
+    fn reach() -> PickupReach {
+        PickupReach {
+            position: Vec2::ZERO,
+            radius: 50.,
+            hands_full: false,
+            clicked_at: None,
+            click_half_extent: 8.,
+            sensed: Vec::new(),
+            touched: Vec::new(),
+        }
+    }
+
+    fn loose(index: u32, position: Vec2) -> PickupPackage {
+        PickupPackage {
+            entity: Entity::from_raw(index),
+            position,
+            parent: None,
+            local_y: 0.,
+        }
+    }
+
+    fn on_belt(index: u32, position: Vec2, belt: Entity, local_y: f32) -> PickupPackage {
+        PickupPackage {
+            parent: Some(belt),
+            local_y,
+            ..loose(index, position)
+        }
+    }
+
+    fn belt(entity: Entity) -> PickupConveyor {
+        PickupConveyor {
+            entity,
+            direction: -1.,
+            exit_point: Vec2::new(0., -500.),
+            // well away from where anyone in these cases stands
+            front_grab_zone: Rect::new(900., 900., 1000., 1000.),
+        }
+    }
+
+    struct Case {
+        name: &'static str,
+        reach: PickupReach,
+        packages: Vec<PickupPackage>,
+        conveyors: Vec<PickupConveyor>,
+        expected: Option<u32>,
+    }
+
+    #[test]
+    fn pickup_candidate_cases() {
+        let cases = [
+            Case {
+                name: "already holding",
+                reach: PickupReach {
+                    hands_full: true,
+                    sensed: vec![Entity::from_raw(1)],
+                    ..reach()
+                },
+                packages: vec![loose(1, Vec2::new(10., 0.))],
+                conveyors: Vec::new(),
+                expected: None,
+            },
+            Case {
+                name: "conveyor parented",
+                reach: PickupReach {
+                    touched: vec![BELT],
+                    ..reach()
+                },
+                packages: vec![
+                    on_belt(1, Vec2::new(20., 0.), BELT, 0.),
+                    // nearer, but on a belt they aren't touching
+                    on_belt(2, Vec2::new(5., 0.), OTHER_BELT, 0.),
+                    // nearer still, but the sensor didn't pick it up
+                    loose(3, Vec2::new(2., 0.)),
+                ],
+                conveyors: vec![belt(BELT), belt(OTHER_BELT)],
+                expected: Some(1),
+            },
+            Case {
+                name: "nearest loose",
+                reach: PickupReach {
+                    sensed: vec![
+                        Entity::from_raw(1),
+                        Entity::from_raw(2),
+                        Entity::from_raw(3),
+                    ],
+                    ..reach()
+                },
+                packages: vec![
+                    loose(1, Vec2::new(30., 0.)),
+                    loose(2, Vec2::new(0., -12.)),
+                    // sensed but out of reach
+                    loose(3, Vec2::new(80., 0.)),
+                ],
+                conveyors: Vec::new(),
+                expected: Some(2),
+            },
+            Case {
+                name: "empty",
+                reach: PickupReach {
+                    sensed: vec![BELT],
+                    ..reach()
+                },
+                packages: Vec::new(),
+                conveyors: vec![belt(BELT)],
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            let candidate = find_pickup_candidate(&case.reach, &case.packages, &case.conveyors);
+            assert_eq!(
+                candidate.map(|candidate| candidate.package),
+                case.expected.map(Entity::from_raw),
+                "{}",
+                case.name
+            );
         }
     }
 }
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use enum_map::EnumMap;
+
+use crate::{
+    random::*, BeltRegistry, ButtonState, Conveyor, GameConfig, GameState, KeyAction, Package,
+    Player,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrainState {
+    Hesitating,
+    FetchingPackage,
+    PickingUp,
+    DeliveringPackage,
+    Placing,
+}
+
+/// Drives a player in place of their controller by writing into the same control state real input
+/// would, so nothing downstream can tell the difference.
+#[derive(Component)]
+pub struct PlayerBrain {
+    pub enabled: bool,
+    state: BrainState,
+    next_state: BrainState,
+    hesitation_timer: Timer,
+    pressed: EnumMap<KeyAction, bool>,
+}
+
+impl Default for PlayerBrain {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state: BrainState::FetchingPackage,
+            next_state: BrainState::FetchingPackage,
+            hesitation_timer: Timer::from_seconds(0., TimerMode::Once),
+            pressed: EnumMap::default(),
+        }
+    }
+}
+
+impl PlayerBrain {
+    pub fn state(&self) -> BrainState {
+        self.state
+    }
+
+    fn hesitate_then(&mut self, next_state: BrainState, game_config: &GameConfig, rng: &mut Rand) {
+        let attract_config = &game_config.attract_mode_config;
+        let hesitation = rng.gen_range(
+            attract_config.min_hesitation_seconds
+                ..=attract_config
+                    .max_hesitation_seconds
+                    .max(attract_config.min_hesitation_seconds),
+        );
+        self.hesitation_timer = Timer::from_seconds(hesitation, TimerMode::Once);
+        self.state = BrainState::Hesitating;
+        self.next_state = next_state;
+    }
+}
+
+fn steer_towards(pressed: &mut EnumMap<KeyAction, bool>, from: Vec2, to: Vec2, dead_zone: f32) {
+    let delta = to - from;
+    pressed[KeyAction::MoveRight] = delta.x > dead_zone;
+    pressed[KeyAction::MoveLeft] = delta.x < -dead_zone;
+    pressed[KeyAction::MoveUp] = delta.y > dead_zone;
+    pressed[KeyAction::MoveDown] = delta.y < -dead_zone;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn drive_player_brains(
+    time: Res<Time>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    belt_registry: Res<BeltRegistry>,
+    mut rng: ResMut<Rand>,
+    mut player_query: Query<(&Player, &mut PlayerBrain, &Transform, Option<&Children>)>,
+    package_query: Query<(Entity, &GlobalTransform, Option<&Parent>), With<Package>>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform)>,
+) {
+    let package_size = game_config.package_config.size;
+    let player_size = game_config.player_config.size;
+    for (player_info, mut brain, player_transform, player_children) in &mut player_query {
+        if !brain.enabled {
+            continue;
+        }
+
+        let player_pos = player_transform.translation.truncate();
+        let is_holding = player_children
+            .is_some_and(|children| children.iter().any(|child| package_query.contains(*child)));
+
+        let mut pressed = EnumMap::<KeyAction, bool>::default();
+        match brain.state {
+            BrainState::Hesitating => {
+                brain.hesitation_timer.tick(time.delta());
+                if brain.hesitation_timer.finished() {
+                    brain.state = brain.next_state;
+                }
+            }
+            BrainState::FetchingPackage => {
+                if is_holding {
+                    brain.hesitate_then(BrainState::DeliveringPackage, &game_config, &mut rng);
+                } else {
+                    // loose packages or ones waiting on an incoming belt, whichever is closest
+                    let nearest_package = package_query
+                        .iter()
+                        .filter(|(_, _, package_parent)| {
+                            package_parent.is_none_or(|parent| {
+                                belt_registry.incoming().contains(&parent.get())
+                            })
+                        })
+                        .map(|(_, package_transform, _)| package_transform.translation().truncate())
+                        .min_by(|a, b| {
+                            a.distance_squared(player_pos)
+                                .total_cmp(&b.distance_squared(player_pos))
+                        });
+
+                    if let Some(package_pos) = nearest_package {
+                        if package_pos.distance(player_pos)
+                            <= game_config.player_config.pickup_radius() * 0.8
+                        {
+                            pressed[KeyAction::PickupOrThrow] = true;
+                            brain.state = BrainState::PickingUp;
+                        } else {
+                            steer_towards(
+                                &mut pressed,
+                                player_pos,
+                                package_pos,
+                                player_size * 0.25,
+                            );
+                        }
+                    }
+                }
+            }
+            BrainState::PickingUp => {
+                let next_state = if is_holding {
+                    BrainState::DeliveringPackage
+                } else {
+                    BrainState::FetchingPackage
+                };
+                brain.hesitate_then(next_state, &game_config, &mut rng);
+            }
+            BrainState::DeliveringPackage => {
+                if !is_holding {
+                    brain.hesitate_then(BrainState::FetchingPackage, &game_config, &mut rng);
+                } else if let Some((conveyor_info, conveyor_transform)) = belt_registry
+                    .outgoing(player_info.player_index)
+                    .iter()
+                    .filter_map(|belt| conveyor_query.get(*belt).ok())
+                    .filter(|(conveyor_info, _)| conveyor_info.has_free_capacity(package_size))
+                    .min_by(|(_, a), (_, b)| {
+                        a.translation()
+                            .truncate()
+                            .distance_squared(player_pos)
+                            .total_cmp(&b.translation().truncate().distance_squared(player_pos))
+                    })
+                {
+                    // walk alongside the belt and tap to place once pressed up against it
+                    let belt_pos = conveyor_transform.translation().truncate();
+                    let belt_half_length = conveyor_info.belt_region.y / 2.;
+                    let target = Vec2::new(
+                        belt_pos.x,
+                        player_pos.y.clamp(
+                            belt_pos.y - belt_half_length + player_size,
+                            belt_pos.y + belt_half_length - player_size,
+                        ),
+                    );
+                    let belt_half_width = game_config.conveyor_config.size.x / 2.;
+                    let is_alongside = (target.x - player_pos.x).abs()
+                        <= belt_half_width + player_size
+                        && (target.y - player_pos.y).abs() <= player_size;
+                    if is_alongside && player_info.pickup_cooldown_timer.finished() {
+                        pressed[KeyAction::PickupOrThrow] = true;
+                        brain.state = BrainState::Placing;
+                    } else {
+                        steer_towards(&mut pressed, player_pos, target, player_size * 0.25);
+                    }
+                }
+            }
+            BrainState::Placing => {
+                // releasing on the very next tick keeps the charge low enough to place rather
+                // than throw
+                brain.hesitate_then(BrainState::FetchingPackage, &game_config, &mut rng);
+            }
+        }
+
+        let player_control_state = &mut game_state.player_controls[player_info.player_index].state;
+        for (key_action, is_pressed) in pressed.iter() {
+            let was_pressed = brain.pressed[key_action.clone()];
+            player_control_state[key_action] = ButtonState {
+                pressed: *is_pressed,
+                state_changed_this_frame: *is_pressed != was_pressed,
+            };
+        }
+        brain.pressed = pressed;
+    }
+}
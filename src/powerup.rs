@@ -0,0 +1,463 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    random::*, restricted_zone_min_y, AppConfig, ArenaLayout, EntityLayer, FreezeSource,
+    GameConfig, GameState, Player, PowerupCollected, PowerupConfig, PowerupExpired, PowerupKind,
+    PowerupSpawned, RenderLayers, RoundScoped,
+};
+
+/// Random spots tried per spawn before giving up until the next one is due.
+const SPAWN_ATTEMPTS: usize = 16;
+const DURATION_BAR_HEIGHT: f32 = 3.;
+
+/// A pickup lying on the floor, despawned once its timer runs out.
+#[derive(Component)]
+pub struct Powerup {
+    pub kind: PowerupKind,
+    pub timer: Timer,
+}
+
+/// The effect a player picked up and how much of it is left, a player has at most one.
+#[derive(Component)]
+pub struct ActiveModifier {
+    pub kind: PowerupKind,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct ModifierIndicator;
+
+#[derive(Component)]
+pub struct ModifierDurationBar;
+
+/// When the next powerup is due, the first wait is picked the first time it's checked.
+#[derive(Resource, Default)]
+pub struct PowerupSpawner {
+    next_spawn: Option<Timer>,
+}
+
+impl PowerupKind {
+    pub fn colour(&self) -> Color {
+        match self {
+            PowerupKind::SpeedBoost => Color::rgb(0.3, 0.85, 1.),
+            PowerupKind::InstantMaxCharge => Color::rgb(1., 0.55, 0.1),
+            PowerupKind::MultiplierShield => Color::rgb(0.95, 0.85, 0.2),
+            PowerupKind::MagnetHands => Color::rgb(0.8, 0.35, 0.9),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            PowerupKind::SpeedBoost => ">>",
+            PowerupKind::InstantMaxCharge => "!",
+            PowerupKind::MultiplierShield => "#",
+            PowerupKind::MagnetHands => "U",
+        }
+    }
+}
+
+impl ActiveModifier {
+    pub fn is(modifier: Option<&ActiveModifier>, kind: PowerupKind) -> bool {
+        modifier.is_some_and(|modifier| modifier.kind == kind)
+    }
+
+    pub fn speed_multiplier(modifier: Option<&ActiveModifier>, game_config: &GameConfig) -> f32 {
+        match &game_config.powerups {
+            Some(powerups) if ActiveModifier::is(modifier, PowerupKind::SpeedBoost) => {
+                powerups.speed_boost_multiplier
+            }
+            _ => 1.,
+        }
+    }
+
+    pub fn pickup_radius_multiplier(
+        modifier: Option<&ActiveModifier>,
+        game_config: &GameConfig,
+    ) -> f32 {
+        match &game_config.powerups {
+            Some(powerups) if ActiveModifier::is(modifier, PowerupKind::MagnetHands) => {
+                powerups.magnet_radius_multiplier
+            }
+            _ => 1.,
+        }
+    }
+}
+
+fn random_spawn_wait(powerups: &PowerupConfig, rng: &mut Rand) -> Timer {
+    Timer::from_seconds(
+        powerups.spawn_interval_seconds * rng.gen_range(0.5..=1.5),
+        TimerMode::Once,
+    )
+}
+
+/// A random spot where a powerup of `half_size` sits wholly on the floor, outside the restricted
+/// zone and clear of anything solid or another powerup.
+fn find_clear_spot(
+    arena_layout: &ArenaLayout,
+    rapier_context: &RapierContext,
+    rng: &mut Rand,
+    half_size: f32,
+    max_y: f32,
+    other_powerups: &[Vec2],
+) -> Option<Vec2> {
+    let total_area = arena_layout
+        .floor
+        .iter()
+        .map(|floor| floor.width() * floor.height())
+        .sum::<f32>();
+    if total_area <= 0. {
+        return None;
+    }
+
+    for _ in 0..SPAWN_ATTEMPTS {
+        // weighted by area so the small strips of floor aren't favoured
+        let mut pick = rng.gen_range(0.0..total_area);
+        let Some(floor) = arena_layout.floor.iter().find(|floor| {
+            pick -= floor.width() * floor.height();
+            pick < 0.
+        }) else {
+            continue;
+        };
+        let inset = floor.inset(-half_size);
+        if inset.is_empty() {
+            continue;
+        }
+        let spot = Vec2::new(
+            rng.gen_range(inset.min.x..=inset.max.x),
+            rng.gen_range(inset.min.y..=inset.max.y),
+        );
+
+        let corners = [
+            Vec2::new(-half_size, -half_size),
+            Vec2::new(half_size, -half_size),
+            Vec2::new(-half_size, half_size),
+            Vec2::new(half_size, half_size),
+        ];
+        if spot.y + half_size >= max_y
+            || !corners
+                .iter()
+                .all(|corner| arena_layout.is_legal_position(spot + *corner))
+            || other_powerups
+                .iter()
+                .any(|other| other.distance(spot) < half_size * 4.)
+        {
+            continue;
+        }
+        let blocked = rapier_context
+            .intersection_with_shape(
+                spot,
+                0.,
+                &Collider::cuboid(half_size, half_size),
+                QueryFilter::default().exclude_sensors(),
+            )
+            .is_some();
+        if !blocked {
+            return Some(spot);
+        }
+    }
+    None
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_powerups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<Rand>,
+    mut spawner: ResMut<PowerupSpawner>,
+    rapier_context: Res<RapierContext>,
+    arena_layout: Res<ArenaLayout>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    powerup_query: Query<&Transform, With<Powerup>>,
+    mut spawned_events: EventWriter<PowerupSpawned>,
+) {
+    let Some(powerups) = &game_config.powerups else {
+        return;
+    };
+    let next_spawn = spawner
+        .next_spawn
+        .get_or_insert_with(|| random_spawn_wait(powerups, &mut rng));
+    if !next_spawn.tick(time.delta()).finished() {
+        return;
+    }
+    *next_spawn = random_spawn_wait(powerups, &mut rng);
+
+    if powerups.kinds.is_empty() || powerup_query.iter().count() >= powerups.max_on_floor {
+        return;
+    }
+    let other_powerups = powerup_query
+        .iter()
+        .map(|powerup_transform| powerup_transform.translation.truncate())
+        .collect::<Vec<_>>();
+    let Some(spot) = find_clear_spot(
+        &arena_layout,
+        &rapier_context,
+        &mut rng,
+        powerups.size / 2.,
+        restricted_zone_min_y(&app_config, &game_config),
+        &other_powerups,
+    ) else {
+        debug!("no clear floor for a powerup, skipping this one");
+        return;
+    };
+    let kind = powerups.kinds[rng.gen_range(0..powerups.kinds.len())];
+
+    let powerup_entity = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: kind.colour(),
+                    custom_size: Some(Vec2::splat(powerups.size)),
+                    ..default()
+                },
+                transform: Transform::from_translation(spot.extend(0.)),
+                ..default()
+            },
+            Collider::cuboid(powerups.size / 2., powerups.size / 2.),
+            Sensor,
+            Powerup {
+                kind,
+                timer: Timer::from_seconds(powerups.lifetime_seconds, TimerMode::Once),
+            },
+            RenderLayers::Single(EntityLayer::Object),
+            RoundScoped,
+        ))
+        .with_children(|builder| {
+            builder.spawn(Text2dBundle {
+                text: Text::from_section(
+                    kind.icon(),
+                    TextStyle {
+                        font_size: powerups.size * 0.7,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                ),
+                // the sprite is only lifted onto its layer when drawn, so the label sits above
+                // the whole object layer
+                transform: Transform::from_translation(Vec3::new(0., 0., 3.)),
+                ..default()
+            });
+        })
+        .id();
+    debug!("{:?} powerup spawned at {:?}", kind, spot);
+    spawned_events.send(PowerupSpawned {
+        powerup: powerup_entity,
+        kind,
+    });
+}
+
+pub fn expire_powerups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut powerup_query: Query<(Entity, &mut Powerup)>,
+    mut expired_events: EventWriter<PowerupExpired>,
+) {
+    for (powerup_entity, mut powerup) in &mut powerup_query {
+        if powerup.timer.tick(time.delta()).finished() {
+            commands.entity(powerup_entity).despawn_recursive();
+            expired_events.send(PowerupExpired {
+                player: None,
+                kind: powerup.kind,
+            });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn collect_powerups(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    game_config: Res<GameConfig>,
+    mut game_state: ResMut<GameState>,
+    player_query: Query<(
+        Entity,
+        &Player,
+        &Transform,
+        Option<&ActiveModifier>,
+        Option<&Children>,
+    )>,
+    powerup_query: Query<&Powerup>,
+    indicator_query: Query<(), With<ModifierIndicator>>,
+    mut collected_events: EventWriter<PowerupCollected>,
+) {
+    let Some(powerups) = &game_config.powerups else {
+        return;
+    };
+    let half_size = game_config.player_config.size / 2.;
+    let mut collected = Vec::new();
+    for (player_entity, player_info, player_transform, active_modifier, player_children) in
+        &player_query
+    {
+        let mut touched = None;
+        rapier_context.intersections_with_shape(
+            player_transform.translation.truncate(),
+            0.,
+            &Collider::cuboid(half_size, half_size),
+            QueryFilter::default().exclude_collider(player_entity),
+            |colliding_entity| {
+                if powerup_query.contains(colliding_entity)
+                    && !collected.contains(&colliding_entity)
+                {
+                    touched = Some(colliding_entity);
+                    return false;
+                }
+                true
+            },
+        );
+        let Some(powerup_entity) = touched else {
+            continue;
+        };
+        let Ok(powerup) = powerup_query.get(powerup_entity) else {
+            continue;
+        };
+        collected.push(powerup_entity);
+        commands.entity(powerup_entity).despawn_recursive();
+
+        let player_index = player_info.player_index;
+        // a shield covers every multiplier the holder's deliveries feed
+        let recipients = game_config.game_mode.multiplier_recipients(player_index);
+        if ActiveModifier::is(active_modifier, PowerupKind::MultiplierShield) {
+            for recipient in &recipients {
+                game_state.player_scores[*recipient]
+                    .multiplier_freeze
+                    .unfreeze(FreezeSource::Shield);
+            }
+        }
+        if powerup.kind == PowerupKind::MultiplierShield {
+            for recipient in recipients {
+                game_state.freeze_multiplier(
+                    recipient,
+                    FreezeSource::Shield,
+                    powerups.modifier_seconds,
+                );
+            }
+        }
+
+        // whatever the player had is replaced, indicator and all
+        for child in player_children.into_iter().flatten() {
+            if indicator_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        let player_size = game_config.player_config.size;
+        commands
+            .entity(player_entity)
+            .insert(ActiveModifier {
+                kind: powerup.kind,
+                timer: Timer::from_seconds(powerups.modifier_seconds, TimerMode::Once),
+            })
+            .with_children(|builder| {
+                builder
+                    .spawn((
+                        SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                            0.,
+                            player_size * 0.95,
+                            0.,
+                        ))),
+                        ModifierIndicator,
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn(Text2dBundle {
+                            text: Text::from_section(
+                                powerup.kind.icon(),
+                                TextStyle {
+                                    font_size: 16.,
+                                    color: powerup.kind.colour(),
+                                    ..default()
+                                },
+                            ),
+                            transform: Transform::from_translation(Vec3::new(0., 6., 1.)),
+                            ..default()
+                        });
+                        builder.spawn((
+                            SpriteBundle {
+                                sprite: Sprite {
+                                    color: powerup.kind.colour(),
+                                    custom_size: Some(Vec2::new(player_size, DURATION_BAR_HEIGHT)),
+                                    anchor: Anchor::CenterLeft,
+                                    ..default()
+                                },
+                                transform: Transform::from_translation(Vec3::new(
+                                    -player_size / 2.,
+                                    -4.,
+                                    0.,
+                                )),
+                                ..default()
+                            },
+                            ModifierDurationBar,
+                            RenderLayers::Single(EntityLayer::TrajectoryPreview),
+                        ));
+                    });
+            });
+        info!("{:?} picked up {:?}", player_index, powerup.kind);
+        collected_events.send(PowerupCollected {
+            player: player_index,
+            kind: powerup.kind,
+        });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_active_modifiers(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut game_state: ResMut<GameState>,
+    mut player_query: Query<(Entity, &Player, &mut ActiveModifier, Option<&Children>)>,
+    indicator_query: Query<&Children, With<ModifierIndicator>>,
+    mut bar_query: Query<&mut Transform, With<ModifierDurationBar>>,
+    mut expired_events: EventWriter<PowerupExpired>,
+) {
+    let mut running_shields = Vec::new();
+    for (player_entity, player_info, mut modifier, player_children) in &mut player_query {
+        let expired = modifier.timer.tick(time.delta()).finished();
+        for child in player_children.into_iter().flatten() {
+            let Ok(indicator_children) = indicator_query.get(*child) else {
+                continue;
+            };
+            if expired {
+                commands.entity(*child).despawn_recursive();
+                continue;
+            }
+            for indicator_child in indicator_children {
+                if let Ok(mut bar_transform) = bar_query.get_mut(*indicator_child) {
+                    bar_transform.scale.x = modifier.timer.fraction_remaining();
+                }
+            }
+        }
+        if !expired {
+            if modifier.kind == PowerupKind::MultiplierShield {
+                running_shields.push((player_info.player_index, modifier.timer.remaining_secs()));
+            }
+            continue;
+        }
+
+        commands.entity(player_entity).remove::<ActiveModifier>();
+        if modifier.kind == PowerupKind::MultiplierShield {
+            for recipient in game_config
+                .game_mode
+                .multiplier_recipients(player_info.player_index)
+            {
+                game_state.player_scores[recipient]
+                    .multiplier_freeze
+                    .unfreeze(FreezeSource::Shield);
+            }
+        }
+        debug!(
+            "{:?}'s {:?} ran out",
+            player_info.player_index, modifier.kind
+        );
+        expired_events.send(PowerupExpired {
+            player: Some(player_info.player_index),
+            kind: modifier.kind,
+        });
+    }
+
+    // one shield running out mustn't lift a shared multiplier another is still covering
+    for (player_index, remaining_seconds) in running_shields {
+        for recipient in game_config.game_mode.multiplier_recipients(player_index) {
+            game_state.freeze_multiplier(recipient, FreezeSource::Shield, remaining_seconds);
+        }
+    }
+}
@@ -6,12 +6,40 @@ pub use rand::prelude::*;
 use rand_chacha::ChaCha8Rng;
 
 #[derive(Resource, Deref, DerefMut)]
-pub struct Rand(ChaCha8Rng);
+pub struct Rand {
+    #[deref]
+    rng: ChaCha8Rng,
+    seed: u64,
+}
 
 impl Rand {
+    /// Seeds from entropy when there's no seed, picking a seed first so it can be reported.
     pub fn new(seed: &Option<u64>) -> Self {
-        Self(seed.map_or(ChaCha8Rng::from_entropy(), |seed| {
-            ChaCha8Rng::seed_from_u64(seed)
-        }))
+        // kept below 2^63 so it still fits a toml integer when pasted into a config
+        let seed = seed.unwrap_or_else(|| ChaCha8Rng::from_entropy().next_u64() >> 1);
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    /// The seed actually used, whether it came from the config or not.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// Draws for choices that only change how things look. A separate stream from the same seed, so
+/// picking a cosmetic never moves a gameplay roll.
+#[derive(Resource, Deref, DerefMut)]
+pub struct CosmeticRand(ChaCha8Rng);
+
+impl CosmeticRand {
+    const STREAM: u64 = 1;
+
+    pub fn new(rand: &Rand) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(rand.seed());
+        rng.set_stream(Self::STREAM);
+        Self(rng)
     }
 }
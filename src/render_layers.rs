@@ -1,21 +1,39 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 
-use bevy::ecs::component::Component;
+use bevy::prelude::*;
 
-use crate::LayerIndex;
+use crate::{y_sorted_z_indices, LayerIndex};
 
+/// Draw order for sprites, lowest first. Each layer's z comes from `LAYER_Z`, which must list
+/// the layers in this same order.
+///
+/// Text has no layer, its z is whatever its transform says, so labels meant to sit on a layered
+/// sprite need a z inside that sprite's layer band.
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, PartialEq, Eq)]
 pub enum EntityLayer {
     Background,
+    FloorDecal,
     Debugging,
     Furniture,
+    /// Painted on the belts, under whatever they carry.
+    BeltMarking,
     Object,
     Accent,
+    AccentPattern,
+    PlayerTrail,
     Player,
     HeldObject,
+    /// Speech bubbles over the players' heads.
+    Emote,
+    TrajectoryPreview,
     OfficeLevelFurniture,
     OfficeLevelAccent,
     SuperVisor,
+    ScreenFlash,
+    Overlay,
+    /// Score displays and anything else that has to read clearly over the play field. Kept
+    /// `SCREEN_UI_GAP` clear of every other layer.
+    ScreenUi,
 }
 
 #[derive(Debug, Clone, Component)]
@@ -24,29 +42,163 @@ pub enum RenderLayers {
     Multi(BTreeSet<EntityLayer>),
 }
 
+/// The y-sort in `SpriteLayerPlugin` spreads a layer's sprites over `[z, z + 1)`, so layers any
+/// closer than this would interleave.
+pub const MIN_LAYER_GAP: f32 = 1.;
+
+/// How far below `ScreenUi` every other layer has to stay, y-sort included.
+pub const SCREEN_UI_GAP: f32 = 10.;
+
+/// Base z of every layer, in `EntityLayer` order.
+pub const LAYER_Z: [(EntityLayer, f32); 19] = [
+    (EntityLayer::Background, -2.),
+    (EntityLayer::FloorDecal, -1.),
+    (EntityLayer::Debugging, 0.),
+    (EntityLayer::Furniture, 1.),
+    (EntityLayer::BeltMarking, 2.),
+    (EntityLayer::Object, 3.),
+    (EntityLayer::Accent, 4.),
+    (EntityLayer::AccentPattern, 5.),
+    (EntityLayer::PlayerTrail, 19.),
+    (EntityLayer::Player, 20.),
+    (EntityLayer::HeldObject, 21.),
+    (EntityLayer::Emote, 22.),
+    (EntityLayer::TrajectoryPreview, 23.),
+    (EntityLayer::OfficeLevelFurniture, 24.),
+    (EntityLayer::OfficeLevelAccent, 25.),
+    (EntityLayer::SuperVisor, 26.),
+    (EntityLayer::ScreenFlash, 27.),
+    (EntityLayer::Overlay, 28.),
+    (EntityLayer::ScreenUi, 40.),
+];
+
+/// z for text and unlayered sprites on a `ScreenUi` sprite, past anything the y-sort gives the
+/// layer. Local to the display, which sits at z 0 under a parent at z 0.
+pub const SCREEN_UI_TEXT_Z: f32 = EntityLayer::ScreenUi.z() + MIN_LAYER_GAP;
+
+const fn layer_z_is_valid() -> bool {
+    let mut index = 0;
+    while index < LAYER_Z.len() {
+        if LAYER_Z[index].0 as usize != index {
+            return false;
+        }
+        if index > 0 && LAYER_Z[index].1 - LAYER_Z[index - 1].1 < MIN_LAYER_GAP {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+const _: () = assert!(
+    layer_z_is_valid(),
+    "LAYER_Z must follow EntityLayer's order with at least MIN_LAYER_GAP between layers"
+);
+
+const _: () = assert!(
+    EntityLayer::ScreenUi.z() - EntityLayer::Overlay.z() >= SCREEN_UI_GAP + MIN_LAYER_GAP,
+    "ScreenUi must be the top layer, SCREEN_UI_GAP clear of the y-sorted band below it"
+);
+
+impl EntityLayer {
+    pub const fn z(&self) -> f32 {
+        LAYER_Z[*self as usize].1
+    }
+}
+
 impl LayerIndex for RenderLayers {
     fn as_z_coordinate(&self) -> f32 {
-        fn as_z_coordinate_internal(layer: &EntityLayer) -> f32 {
-            match layer {
-                EntityLayer::Background => -1.,
-                EntityLayer::Debugging => 0.,
-                EntityLayer::Furniture => 1.,
-                EntityLayer::Object => 2.,
-                EntityLayer::Accent => 3.,
-                EntityLayer::Player => 20.,
-                EntityLayer::HeldObject => 21.,
-                EntityLayer::OfficeLevelFurniture => 22.,
-                EntityLayer::OfficeLevelAccent => 23.,
-                EntityLayer::SuperVisor => 24.,
-            }
+        match self {
+            RenderLayers::Single(layer) => layer.z(),
+            // layers are ordered by z, so the last is the highest
+            RenderLayers::Multi(layers) => layers.last().map_or(0., EntityLayer::z),
+        }
+    }
+}
+
+/// Warns when a sprite on any other layer y-sorts into the band kept for `ScreenUi`, once each
+/// time it gets there. Only added with the debug overlays, it redoes the render world's sort.
+pub fn check_screen_ui_band(
+    layer_query: Query<(Entity, &GlobalTransform, &RenderLayers)>,
+    mut in_band: Local<HashSet<Entity>>,
+) {
+    let band_start = EntityLayer::ScreenUi.z() - SCREEN_UI_GAP;
+    let screen_ui_z = EntityLayer::ScreenUi.z();
+    let z_indices = y_sorted_z_indices(layer_query.iter().map(|(entity, transform, layers)| {
+        (entity, transform.translation().y, layers.as_z_coordinate())
+    }));
+    let mut now_in_band = HashSet::new();
+    for (entity, _, layers) in &layer_query {
+        if layers.as_z_coordinate() == screen_ui_z {
+            continue;
+        }
+        let Some(z) = z_indices.get(&entity).copied() else {
+            continue;
+        };
+        if z < band_start {
+            continue;
         }
+        if !in_band.contains(&entity) {
+            warn!(
+                "{:?} on {:?} is drawn at z {:.3}, inside the ScreenUi band from {}",
+                entity, layers, z, band_start
+            );
+        }
+        now_in_band.insert(entity);
+    }
+    *in_band = now_in_band;
+}
 
-        match self {
-            RenderLayers::Single(layer) => as_z_coordinate_internal(layer),
-            RenderLayers::Multi(layers) => layers
-                .iter()
-                .max_by(|a, b| as_z_coordinate_internal(a).total_cmp(&&as_z_coordinate_internal(b)))
-                .map_or(0., |l| as_z_coordinate_internal(l)),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTITY_COUNT: u32 = 1000;
+
+    /// Highest z any entity on `lower` ends up at and lowest z on `upper`, with the entities
+    /// dealt out over `layer_z` and spread up and down the screen.
+    fn band_edges(layer_z: &[f32], lower: f32, upper: f32) -> (f32, f32) {
+        let z_indices = y_sorted_z_indices((0..ENTITY_COUNT).map(|i| {
+            let y = ((i * 7919) % ENTITY_COUNT) as f32 - 500.;
+            (Entity::from_raw(i), y, layer_z[i as usize % layer_z.len()])
+        }));
+        let z_indices = &z_indices;
+        let on_layer = |layer: f32| {
+            (0..ENTITY_COUNT)
+                .filter(move |i| layer_z[*i as usize % layer_z.len()] == layer)
+                .map(move |i| z_indices[&Entity::from_raw(i)])
+        };
+        (
+            on_layer(lower).fold(f32::MIN, f32::max),
+            on_layer(upper).fold(f32::MAX, f32::min),
+        )
+    }
+
+    #[test]
+    fn y_sort_never_swaps_adjacent_layers() {
+        for pair in LAYER_Z.windows(2) {
+            let [(lower_layer, lower), (upper_layer, upper)] = [pair[0], pair[1]];
+            let (lower_top, upper_bottom) = band_edges(&[lower, upper], lower, upper);
+            assert!(
+                lower_top < upper_bottom,
+                "{:?} reaches {} over {:?} starting at {}",
+                lower_layer,
+                lower_top,
+                upper_layer,
+                upper_bottom
+            );
         }
+
+        let all_z = LAYER_Z.map(|(_, z)| z);
+        for pair in all_z.windows(2) {
+            let (lower_top, upper_bottom) = band_edges(&all_z, pair[0], pair[1]);
+            assert!(lower_top < upper_bottom);
+        }
+    }
+
+    #[test]
+    fn half_a_layer_apart_does_swap() {
+        let (lower_top, upper_bottom) = band_edges(&[0., 0.5], 0., 0.5);
+        assert!(lower_top > upper_bottom);
     }
 }
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::{config_hash, AppConfig, AttractMode, Config, WaveSpawned};
+
+/// What it takes to set a session up again, shown on the results screen and copied as a
+/// reproduction string like `seed=12345 wave=7 config=0123456789abcdef`.
+#[derive(Resource)]
+pub struct SessionSeed {
+    /// The seed the random number generator started from, including one picked from entropy.
+    pub seed: u64,
+    /// `config_hash` of the config the session was started with.
+    pub config_hash: String,
+    /// Waves played since launch, the attract mode demo's left out.
+    pub wave: u32,
+}
+
+impl SessionSeed {
+    pub fn new(seed: u64, config: &Config) -> Self {
+        Self {
+            seed,
+            config_hash: config_hash(config),
+            wave: 0,
+        }
+    }
+
+    pub fn reproduction_string(&self) -> String {
+        format!(
+            "seed={} wave={} config={}",
+            self.seed, self.wave, self.config_hash
+        )
+    }
+}
+
+/// A bare seed, or the seed out of a whole reproduction string so a reported one can be pasted
+/// straight into `rng_seed` or `--seed`.
+pub fn parse_seed(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let seed = value
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix("seed="))
+        .unwrap_or(value);
+    seed.parse::<u64>()
+        .map_err(|error| format!("{}: {}", seed, error))
+}
+
+pub fn count_seeded_waves(
+    attract_mode: Res<AttractMode>,
+    mut session_seed: ResMut<SessionSeed>,
+    mut wave_events: EventReader<WaveSpawned>,
+) {
+    let waves = wave_events.read().count() as u32;
+    if !attract_mode.active {
+        session_seed.wave += waves;
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> Result<(), String> {
+    Err("this build has no clipboard feature".to_string())
+}
+
+/// Copies the reproduction string on `copy_seed_key`, it's logged as well so it can still be
+/// picked out of the log when the clipboard can't be reached.
+pub fn copy_reproduction_string(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    app_config: Res<AppConfig>,
+    session_seed: Res<SessionSeed>,
+) {
+    if !keyboard_input.just_pressed(app_config.copy_seed_key) {
+        return;
+    }
+    let reproduction = session_seed.reproduction_string();
+    match copy_to_clipboard(&reproduction) {
+        Ok(()) => info!("Copied '{}' to the clipboard", reproduction),
+        Err(error) => warn!(
+            "Couldn't copy '{}' to the clipboard: {}",
+            reproduction, error
+        ),
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use enum_map::EnumMap;
+use serde::Serialize;
+
+use crate::{
+    AppConfig, Config, DataOutputPolicy, GameConfig, GameModeKind, PlayerIndex, RoundEnded,
+    RoundResult,
+};
+
+/// Tries per sink before a report is given up on, each retry waits twice as long as the last.
+const SEND_ATTEMPTS: u32 = 3;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// One finished round, as sent to every results sink.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundReport {
+    pub machine: String,
+    pub seed: Option<u64>,
+    pub config_hash: String,
+    pub game_mode: GameModeKind,
+    pub round: usize,
+    pub result: RoundResult,
+    pub scores: EnumMap<PlayerIndex, i64>,
+    pub duration_seconds: f32,
+    /// Seconds since the unix epoch.
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+/// Somewhere finished rounds are reported to. Sends happen off the main thread, so a sink may
+/// block for as long as it needs.
+pub trait ResultsSink: Send + Sync {
+    fn describe(&self) -> String;
+    fn send(&self, report: &RoundReport) -> anyhow::Result<()>;
+}
+
+/// Appends each report as a line of JSON.
+pub struct FileResultsSink {
+    pub path: PathBuf,
+}
+
+impl ResultsSink for FileResultsSink {
+    fn describe(&self) -> String {
+        format!("results file {}", self.path.display())
+    }
+
+    fn send(&self, report: &RoundReport) -> anyhow::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(report)?)?;
+        Ok(())
+    }
+}
+
+/// POSTs each report as JSON to the configured endpoint, anything but a 2xx answer counts as a
+/// failed send.
+#[cfg(feature = "results-webhook")]
+pub struct HttpResultsSink {
+    pub url: String,
+    pub timeout: Duration,
+}
+
+#[cfg(feature = "results-webhook")]
+impl ResultsSink for HttpResultsSink {
+    fn describe(&self) -> String {
+        format!("results webhook {}", self.url)
+    }
+
+    fn send(&self, report: &RoundReport) -> anyhow::Result<()> {
+        let body = serde_json::to_string(report)?;
+        match ureq::post(&self.url)
+            .timeout(self.timeout)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) => {
+                anyhow::bail!("endpoint answered {} {}", status, response.status_text())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Sends every finished round to the configured sinks, only inserted when at least one is set.
+#[derive(Resource)]
+pub struct ResultsReporter {
+    sinks: Vec<Arc<dyn ResultsSink>>,
+    machine: String,
+    seed: Option<u64>,
+    config_hash: String,
+}
+
+impl ResultsReporter {
+    pub fn new(
+        sinks: Vec<Arc<dyn ResultsSink>>,
+        machine: String,
+        seed: Option<u64>,
+        config_hash: String,
+    ) -> Self {
+        Self {
+            sinks,
+            machine,
+            seed,
+            config_hash,
+        }
+    }
+
+    /// Whichever sinks the app config asks for, `None` when it asks for none.
+    pub fn from_config(
+        config: &Config,
+        output_policy: DataOutputPolicy,
+    ) -> Option<ResultsReporter> {
+        let app_config = &config.app;
+        let mut sinks: Vec<Arc<dyn ResultsSink>> = Vec::new();
+        if let Some(path) = app_config
+            .results_path
+            .clone()
+            .filter(|_| output_policy.allows_writes())
+        {
+            sinks.push(Arc::new(FileResultsSink { path }));
+        }
+        if let Some(url) = &app_config.results_webhook {
+            #[cfg(feature = "results-webhook")]
+            sinks.push(Arc::new(HttpResultsSink {
+                url: url.clone(),
+                timeout: Duration::from_secs_f32(app_config.results_webhook_timeout_seconds),
+            }));
+            #[cfg(not(feature = "results-webhook"))]
+            warn!(
+                "results_webhook is set to {} but this build has no results-webhook feature, round results won't be posted",
+                url
+            );
+        }
+        if sinks.is_empty() {
+            return None;
+        }
+        Some(ResultsReporter::new(
+            sinks,
+            machine_name(app_config),
+            app_config.rng_seed,
+            config_hash(config),
+        ))
+    }
+
+    /// Hands the report to every sink on its own thread, so a slow or unreachable sink can't
+    /// hold up the game or the other sinks.
+    pub fn submit(&self, report: RoundReport) {
+        let report = Arc::new(report);
+        for sink in &self.sinks {
+            let sink = sink.clone();
+            let report = report.clone();
+            let spawned = std::thread::Builder::new()
+                .name("results-sink".to_string())
+                .spawn(move || send_with_retries(sink.as_ref(), &report, FIRST_RETRY_DELAY));
+            if let Err(error) = spawned {
+                warn!("couldn't start a thread to report round results: {}", error);
+            }
+        }
+    }
+}
+
+/// Whether the report got through.
+fn send_with_retries(
+    sink: &dyn ResultsSink,
+    report: &RoundReport,
+    first_retry_delay: Duration,
+) -> bool {
+    let mut delay = first_retry_delay;
+    for attempt in 1..=SEND_ATTEMPTS {
+        match sink.send(report) {
+            Ok(()) => {
+                debug!("round {} reported to {}", report.round, sink.describe());
+                return true;
+            }
+            Err(error) if attempt < SEND_ATTEMPTS => {
+                debug!(
+                    "reporting round {} to {} failed, attempt {} of {}: {}",
+                    report.round,
+                    sink.describe(),
+                    attempt,
+                    SEND_ATTEMPTS,
+                    error
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(error) => {
+                warn!(
+                    "giving up reporting round {} to {} after {} attempts: {}",
+                    report.round,
+                    sink.describe(),
+                    SEND_ATTEMPTS,
+                    error
+                );
+            }
+        }
+    }
+    false
+}
+
+/// The configured name, or the host's, so results from several cabinets can be told apart.
+fn machine_name(app_config: &AppConfig) -> String {
+    app_config
+        .machine_name
+        .clone()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|hostname| hostname.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// FNV-1a of the merged config as TOML, stable across builds so machines running the same
+/// config report the same hash. The seed is left out, it's reported on its own and pasting a
+/// reported seed into the config shouldn't change the hash.
+pub fn config_hash(config: &Config) -> String {
+    let mut serialized =
+        toml::Value::try_from(config).unwrap_or_else(|_| toml::Value::Table(toml::Table::new()));
+    if let Some(app) = serialized
+        .get_mut("app")
+        .and_then(toml::Value::as_table_mut)
+    {
+        app.remove("rng_seed");
+    }
+    let hash = serialized
+        .to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{:016x}", hash)
+}
+
+pub fn report_round_results(
+    reporter: Res<ResultsReporter>,
+    game_config: Res<GameConfig>,
+    mut round_events: EventReader<RoundEnded>,
+) {
+    for event in round_events.read() {
+        let ended_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let started_at =
+            ended_at.saturating_sub(Duration::from_secs_f32(event.duration_seconds.max(0.)));
+        reporter.submit(RoundReport {
+            machine: reporter.machine.clone(),
+            seed: reporter.seed,
+            config_hash: reporter.config_hash.clone(),
+            game_mode: game_config.game_mode,
+            round: event.round,
+            result: event.result,
+            scores: event.scores,
+            duration_seconds: event.duration_seconds,
+            started_at: started_at.as_secs(),
+            ended_at: ended_at.as_secs(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// Fails its first `failures` sends and counts every attempt.
+    struct FlakySink {
+        failures: u32,
+        attempts: AtomicU32,
+    }
+
+    impl FlakySink {
+        fn new(failures: u32) -> Self {
+            Self {
+                failures,
+                attempts: AtomicU32::new(0),
+            }
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(Ordering::SeqCst)
+        }
+    }
+
+    impl ResultsSink for FlakySink {
+        fn describe(&self) -> String {
+            "flaky sink".to_string()
+        }
+
+        fn send(&self, _report: &RoundReport) -> anyhow::Result<()> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= self.failures {
+                anyhow::bail!("attempt {} refused", attempt);
+            }
+            Ok(())
+        }
+    }
+
+    fn report() -> RoundReport {
+        RoundReport {
+            machine: "test".to_string(),
+            seed: Some(1),
+            config_hash: "0123456789abcdef".to_string(),
+            game_mode: GameModeKind::Team,
+            round: 1,
+            result: RoundResult::Team { total: 0 },
+            scores: EnumMap::default(),
+            duration_seconds: 60.,
+            started_at: 0,
+            ended_at: 60,
+        }
+    }
+
+    #[test]
+    fn sends_once_when_the_sink_accepts() {
+        let sink = FlakySink::new(0);
+        assert!(send_with_retries(&sink, &report(), Duration::ZERO));
+        assert_eq!(sink.attempts(), 1);
+    }
+
+    #[test]
+    fn retries_until_the_sink_accepts() {
+        let sink = FlakySink::new(SEND_ATTEMPTS - 1);
+        assert!(send_with_retries(&sink, &report(), Duration::ZERO));
+        assert_eq!(sink.attempts(), SEND_ATTEMPTS);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        let sink = FlakySink::new(u32::MAX);
+        assert!(!send_with_retries(&sink, &report(), Duration::ZERO));
+        assert_eq!(sink.attempts(), SEND_ATTEMPTS);
+    }
+
+    #[test]
+    fn file_sink_appends_a_line_per_report() {
+        let path = std::env::temp_dir().join(format!(
+            "play-nice-test-{}-results.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let sink = FileResultsSink { path: path.clone() };
+        assert!(send_with_retries(&sink, &report(), Duration::ZERO));
+        assert!(send_with_retries(&sink, &report(), Duration::ZERO));
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written.lines().count(), 2);
+        assert!(written.lines().all(|line| line.contains("\"round\":1")));
+    }
+}
@@ -0,0 +1,349 @@
+use bevy::{prelude::*, sprite::Anchor, text::Text2dBounds};
+use bevy_rapier2d::control::KinematicCharacterControllerOutput;
+use enum_map::{Enum, EnumMap};
+
+use crate::{
+    key_label, AppConfig, AttractMode, EntityLayer, GameConfig, MatchState, MultiplierChanged,
+    PackageConfiscated, PackageDelivered, PackageThrown, Player, PlayerIndex, RenderLayers,
+    SessionSeed, SessionStarted, StreakAchieved, StringKey, StringTable,
+};
+
+pub const HEATMAP_COLUMNS: usize = 8;
+pub const HEATMAP_ROWS: usize = 4;
+const HEATMAP_SAMPLE_SECONDS: f32 = 1.;
+
+/// Seconds a player spent in each cell of the floor, row 0 is the top of the screen.
+pub type Heatmap = [[u32; HEATMAP_COLUMNS]; HEATMAP_ROWS];
+
+#[derive(Clone, Copy)]
+pub struct PlayerRoundStats {
+    pub delivered: u32,
+    pub highest_multiplier: f32,
+    /// Deliveries in the best streak tier reached.
+    pub longest_streak: usize,
+    pub times_caught: u32,
+    pub distance_travelled: f32,
+    pub throws: u32,
+    pub total_throw_charge: f32,
+    pub heatmap: Heatmap,
+}
+
+impl Default for PlayerRoundStats {
+    fn default() -> Self {
+        Self {
+            delivered: 0,
+            highest_multiplier: 1.,
+            longest_streak: 0,
+            times_caught: 0,
+            distance_travelled: 0.,
+            throws: 0,
+            total_throw_charge: 0.,
+            heatmap: default(),
+        }
+    }
+}
+
+impl PlayerRoundStats {
+    pub fn average_throw_charge(&self) -> f32 {
+        if self.throws == 0 {
+            0.
+        } else {
+            self.total_throw_charge / self.throws as f32
+        }
+    }
+}
+
+/// How each player did this round, shown once the round ends and cleared when the next session
+/// starts. Nothing is recorded while attract mode is playing.
+#[derive(Resource)]
+pub struct RoundStats {
+    pub per_player: EnumMap<PlayerIndex, PlayerRoundStats>,
+    pub played_seconds: f32,
+    heatmap_timer: Timer,
+}
+
+impl Default for RoundStats {
+    fn default() -> Self {
+        Self {
+            per_player: default(),
+            played_seconds: 0.,
+            heatmap_timer: Timer::from_seconds(HEATMAP_SAMPLE_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RoundStatsOverlay;
+
+/// The heatmap cell `position` falls in, positions off the floor count towards the nearest edge.
+pub fn heatmap_cell(position: Vec2, arena_size: Vec2) -> (usize, usize) {
+    let normalised = position / arena_size + Vec2::splat(0.5);
+    let column = ((normalised.x * HEATMAP_COLUMNS as f32).floor() as isize)
+        .clamp(0, HEATMAP_COLUMNS as isize - 1) as usize;
+    let row = (((1. - normalised.y) * HEATMAP_ROWS as f32).floor() as isize)
+        .clamp(0, HEATMAP_ROWS as isize - 1) as usize;
+    (row, column)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_round_events(
+    attract_mode: Res<AttractMode>,
+    game_config: Res<GameConfig>,
+    mut round_stats: ResMut<RoundStats>,
+    mut delivered_events: EventReader<PackageDelivered>,
+    mut thrown_events: EventReader<PackageThrown>,
+    mut confiscated_events: EventReader<PackageConfiscated>,
+    mut multiplier_events: EventReader<MultiplierChanged>,
+    mut streak_events: EventReader<StreakAchieved>,
+) {
+    if attract_mode.active {
+        delivered_events.clear();
+        thrown_events.clear();
+        confiscated_events.clear();
+        multiplier_events.clear();
+        streak_events.clear();
+        return;
+    }
+
+    for delivered in delivered_events.read() {
+        round_stats.per_player[delivered.player].delivered += 1;
+    }
+    for thrown in thrown_events.read() {
+        let player_stats = &mut round_stats.per_player[thrown.player];
+        player_stats.throws += 1;
+        player_stats.total_throw_charge += thrown.charge;
+    }
+    for confiscated in confiscated_events.read() {
+        round_stats.per_player[confiscated.player].times_caught += 1;
+    }
+    for multiplier_changed in multiplier_events.read() {
+        let player_stats = &mut round_stats.per_player[multiplier_changed.player];
+        player_stats.highest_multiplier =
+            player_stats.highest_multiplier.max(multiplier_changed.new);
+    }
+    for streak in streak_events.read() {
+        let Some(tier) = game_config.score_config.streaks.get(streak.tier) else {
+            continue;
+        };
+        let player_stats = &mut round_stats.per_player[streak.player];
+        player_stats.longest_streak = player_stats.longest_streak.max(tier.deliveries);
+    }
+}
+
+// runs in the fixed schedule so each controller move is counted exactly once
+pub fn record_player_travel(
+    time: Res<Time>,
+    attract_mode: Res<AttractMode>,
+    app_config: Res<AppConfig>,
+    mut round_stats: ResMut<RoundStats>,
+    player_query: Query<(
+        &Player,
+        &Transform,
+        Option<Ref<KinematicCharacterControllerOutput>>,
+    )>,
+) {
+    if attract_mode.active {
+        return;
+    }
+
+    round_stats.played_seconds += time.delta_seconds();
+    round_stats.heatmap_timer.tick(time.delta());
+    let sample_heatmap = round_stats.heatmap_timer.just_finished();
+    let arena_size = app_config.base_resolution.as_vec2();
+    for (player_info, player_transform, controller_output) in &player_query {
+        let player_stats = &mut round_stats.per_player[player_info.player_index];
+        if let Some(controller_output) = controller_output.filter(|output| output.is_changed()) {
+            player_stats.distance_travelled += controller_output.effective_translation.length();
+        }
+        if sample_heatmap {
+            let (row, column) = heatmap_cell(player_transform.translation.truncate(), arena_size);
+            player_stats.heatmap[row][column] += 1;
+        }
+    }
+}
+
+pub fn reset_round_stats(
+    mut commands: Commands,
+    mut session_events: EventReader<SessionStarted>,
+    mut round_stats: ResMut<RoundStats>,
+    overlay_query: Query<Entity, With<RoundStatsOverlay>>,
+) {
+    if session_events.read().count() == 0 {
+        return;
+    }
+    *round_stats = RoundStats::default();
+    for overlay in &overlay_query {
+        commands.entity(overlay).despawn_recursive();
+    }
+}
+
+/// The round is over once nobody is left at the controls, show how it went over the attract
+/// mode demo until someone starts playing again.
+#[allow(clippy::too_many_arguments)]
+pub fn show_round_stats(
+    mut commands: Commands,
+    attract_mode: Res<AttractMode>,
+    round_stats: Res<RoundStats>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    string_table: Res<StringTable>,
+    match_state: Res<MatchState>,
+    session_seed: Res<SessionSeed>,
+    overlay_query: Query<(), With<RoundStatsOverlay>>,
+) {
+    if !attract_mode.active || round_stats.played_seconds <= 0. || !overlay_query.is_empty() {
+        return;
+    }
+
+    let resolution = app_config.base_resolution.as_vec2();
+    let column_size = Vec2::new(resolution.x * 0.4, resolution.y * 0.6);
+    let cell_size = Vec2::new(
+        column_size.x / HEATMAP_COLUMNS as f32,
+        column_size.y / HEATMAP_ROWS as f32,
+    );
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK.with_a(0.75),
+                    custom_size: Some(resolution * Vec2::new(0.9, 0.75)),
+                    ..default()
+                },
+                ..default()
+            },
+            RenderLayers::Single(EntityLayer::Overlay),
+            RoundStatsOverlay,
+        ))
+        .with_children(|builder| {
+            // after the last round of a match the overlay is its results screen too
+            if match_state.finished {
+                builder.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        match_state.summary(game_config.game_mode, &string_table),
+                        TextStyle {
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    text_anchor: Anchor::BottomCenter,
+                    transform: Transform::from_translation(Vec3::new(
+                        0.,
+                        column_size.y / 2. + 4.,
+                        0.2,
+                    )),
+                    ..default()
+                });
+            }
+            // tucked in the corner, for passing on anything worth playing again
+            builder.spawn(Text2dBundle {
+                text: Text::from_section(
+                    format!(
+                        "{}{}   {}{}   [{}]{}",
+                        string_table.tr(StringKey::StatsSeed),
+                        session_seed.seed,
+                        string_table.tr(StringKey::StatsWave),
+                        session_seed.wave,
+                        key_label(app_config.copy_seed_key),
+                        string_table.tr(StringKey::StatsCopySeed)
+                    ),
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE.with_a(0.6),
+                        ..default()
+                    },
+                ),
+                text_anchor: Anchor::BottomRight,
+                transform: Transform::from_translation(Vec3::new(
+                    resolution.x * 0.45 - 8.,
+                    -resolution.y * 0.375 + 8.,
+                    0.2,
+                )),
+                ..default()
+            });
+            for (column_index, (player_index, player_stats)) in
+                round_stats.per_player.iter().enumerate()
+            {
+                let column_x =
+                    (column_index as f32 + 0.5) / PlayerIndex::LENGTH as f32 * resolution.x * 0.9
+                        - resolution.x * 0.45;
+                let colour = game_config.player_config.per_player[player_index].colour;
+
+                // the heatmap sits behind the numbers, brighter where the player spent longer
+                let busiest_cell = player_stats
+                    .heatmap
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                for (row, cells) in player_stats.heatmap.iter().enumerate() {
+                    for (column, seconds) in cells.iter().enumerate() {
+                        builder.spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color: colour.with_a(0.6 * *seconds as f32 / busiest_cell as f32),
+                                custom_size: Some(cell_size),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(Vec3::new(
+                                column_x - column_size.x / 2. + (column as f32 + 0.5) * cell_size.x,
+                                column_size.y / 2. - (row as f32 + 0.5) * cell_size.y,
+                                0.1,
+                            )),
+                            ..default()
+                        });
+                    }
+                }
+
+                let lines = [
+                    format!("{:?}", player_index),
+                    format!(
+                        "{}{}",
+                        string_table.tr(StringKey::StatsDelivered),
+                        player_stats.delivered
+                    ),
+                    format!(
+                        "{}x{:.1}",
+                        string_table.tr(StringKey::StatsHighestMultiplier),
+                        player_stats.highest_multiplier
+                    ),
+                    format!(
+                        "{}{}",
+                        string_table.tr(StringKey::StatsLongestStreak),
+                        player_stats.longest_streak
+                    ),
+                    format!(
+                        "{}{}",
+                        string_table.tr(StringKey::StatsTimesCaught),
+                        player_stats.times_caught
+                    ),
+                    format!(
+                        "{}{:.0}m",
+                        string_table.tr(StringKey::StatsDistanceTravelled),
+                        player_stats.distance_travelled / game_config.pixels_per_meter()
+                    ),
+                    format!(
+                        "{}{:.0}%",
+                        string_table.tr(StringKey::StatsAverageThrowCharge),
+                        player_stats.average_throw_charge() * 100.
+                    ),
+                ];
+                builder.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        lines.join("\n"),
+                        TextStyle {
+                            font_size: 22.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_justify(JustifyText::Left),
+                    text_anchor: Anchor::Center,
+                    text_2d_bounds: Text2dBounds { size: column_size },
+                    transform: Transform::from_translation(Vec3::new(column_x, 0., 0.2)),
+                    ..default()
+                });
+            }
+        });
+}
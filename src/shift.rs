@@ -0,0 +1,357 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::{
+    random::*, GameConfig, GameState, Metrics, SessionStarted, ShiftDefinition, ShiftSelection,
+    Supervisor,
+};
+
+const TITLE_CARD_SECONDS: f32 = 3.;
+
+/// Marks sprites that take on the current shift's tint.
+#[derive(Component)]
+pub struct ShiftTint;
+
+#[derive(Component)]
+pub struct ShiftTitleCard {
+    pub timer: Timer,
+}
+
+/// Everything a shift changed, kept so it can be put back exactly when the shift ends.
+struct AppliedShift {
+    wave_duration: Duration,
+    supervisor_durations: Vec<(Entity, Duration, Duration)>,
+}
+
+#[derive(Resource, Default)]
+pub struct ShiftState {
+    pub current: Option<ShiftDefinition>,
+    sessions_started: usize,
+    applied: Option<AppliedShift>,
+}
+
+/// Monday is 0, counted from the unix epoch which fell on a Thursday.
+fn current_weekday() -> usize {
+    let days_since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / (60 * 60 * 24));
+    ((days_since_epoch + 3) % 7) as usize
+}
+
+pub fn select_shift(
+    selection: ShiftSelection,
+    shift_count: usize,
+    sessions_started: usize,
+    weekday: usize,
+    rng: &mut impl Rng,
+) -> Option<usize> {
+    if shift_count == 0 {
+        return None;
+    }
+
+    Some(match selection {
+        ShiftSelection::Sequential => sessions_started % shift_count,
+        ShiftSelection::Weekday => weekday % shift_count,
+        ShiftSelection::Random => rng.gen_range(0..shift_count),
+    })
+}
+
+pub fn start_first_session(mut session_events: EventWriter<SessionStarted>) {
+    session_events.send(SessionStarted);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_shifts(
+    mut commands: Commands,
+    mut session_events: EventReader<SessionStarted>,
+    mut shift_state: ResMut<ShiftState>,
+    mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    mut cosmetic_rng: ResMut<CosmeticRand>,
+    mut metrics: Option<ResMut<Metrics>>,
+    mut supervisor_query: Query<(Entity, &mut Supervisor)>,
+    mut tint_query: Query<&mut Sprite, With<ShiftTint>>,
+    title_card_query: Query<Entity, With<ShiftTitleCard>>,
+) {
+    if session_events.read().count() == 0 {
+        return;
+    }
+
+    // the previous shift ends with its session, undo everything it changed before choosing again
+    if let Some(applied) = shift_state.applied.take() {
+        game_state
+            .package_wave_timer
+            .set_duration(applied.wave_duration);
+        for (supervisor_entity, monitoring_duration, distracted_duration) in
+            applied.supervisor_durations
+        {
+            if let Ok((_, mut supervisor)) = supervisor_query.get_mut(supervisor_entity) {
+                supervisor
+                    .monitoring_timer
+                    .set_duration(monitoring_duration);
+                supervisor
+                    .distracted_timer
+                    .set_duration(distracted_duration);
+            }
+        }
+        for mut sprite in &mut tint_query {
+            sprite.color = Color::WHITE;
+        }
+    }
+    for title_card in &title_card_query {
+        commands.entity(title_card).despawn_recursive();
+    }
+
+    let shift_config = &game_config.shift_config;
+    let shift = select_shift(
+        shift_config.selection,
+        shift_config.shifts.len(),
+        shift_state.sessions_started,
+        current_weekday(),
+        &mut **cosmetic_rng,
+    )
+    .map(|index| shift_config.shifts[index].clone());
+    shift_state.sessions_started += 1;
+    shift_state.current = shift.clone();
+
+    let Some(shift) = shift else {
+        return;
+    };
+    info!("Starting shift: {}", shift.name);
+
+    let modifiers = &shift.modifiers;
+    let wave_duration = game_state.package_wave_timer.duration();
+    game_state
+        .package_wave_timer
+        .set_duration(wave_duration.mul_f32(modifiers.wave_countdown_scale));
+
+    let mut supervisor_durations = Vec::new();
+    for (supervisor_entity, mut supervisor) in &mut supervisor_query {
+        let monitoring_duration = supervisor.monitoring_timer.duration();
+        let distracted_duration = supervisor.distracted_timer.duration();
+        supervisor
+            .monitoring_timer
+            .set_duration(monitoring_duration.mul_f32(modifiers.supervisor_cycle_scale));
+        supervisor
+            .distracted_timer
+            .set_duration(distracted_duration.mul_f32(modifiers.supervisor_cycle_scale));
+        supervisor_durations.push((supervisor_entity, monitoring_duration, distracted_duration));
+    }
+
+    for mut sprite in &mut tint_query {
+        sprite.color = *shift.tint;
+    }
+
+    shift_state.applied = Some(AppliedShift {
+        wave_duration,
+        supervisor_durations,
+    });
+
+    if let Some(metrics) = metrics.as_mut() {
+        metrics.shift_name = Some(shift.name.clone());
+    }
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                shift.name.clone(),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_translation(Vec3::new(0., 0., 100.)),
+            ..default()
+        },
+        ShiftTitleCard {
+            timer: Timer::from_seconds(TITLE_CARD_SECONDS, TimerMode::Once),
+        },
+    ));
+}
+
+pub fn fade_shift_title_cards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut title_card_query: Query<(Entity, &mut ShiftTitleCard, &mut Text)>,
+) {
+    for (title_card_entity, mut title_card, mut text) in &mut title_card_query {
+        title_card.timer.tick(time.delta());
+        if title_card.timer.finished() {
+            commands.entity(title_card_entity).despawn_recursive();
+            continue;
+        }
+
+        // hold for the first half then fade out
+        let alpha = (title_card.timer.fraction_remaining() * 2.).min(1.);
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ShiftConfig, ShiftModifiers, WaveTimer};
+
+    fn shift(
+        name: &str,
+        wave_countdown_scale: f32,
+        supervisor_cycle_scale: f32,
+    ) -> ShiftDefinition {
+        ShiftDefinition {
+            name: name.to_string(),
+            tint: Color::rgb(0.5, 0.5, 1.).into(),
+            modifiers: ShiftModifiers {
+                wave_countdown_scale,
+                supervisor_cycle_scale,
+            },
+        }
+    }
+
+    fn selections(selection: ShiftSelection, weekday: usize, rng: &mut impl Rng) -> Vec<usize> {
+        (0..7)
+            .map(|sessions_started| {
+                select_shift(selection, 3, sessions_started, weekday, rng).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selection_follows_the_policy() {
+        let mut rng = CosmeticRand::new(&Rand::new(&Some(2052)));
+        assert_eq!(
+            selections(ShiftSelection::Sequential, 0, &mut *rng),
+            [0, 1, 2, 0, 1, 2, 0]
+        );
+        assert_eq!(selections(ShiftSelection::Weekday, 4, &mut *rng), [1; 7]);
+        let random = selections(ShiftSelection::Random, 0, &mut *rng);
+        assert!(random.iter().all(|index| *index < 3));
+
+        let mut same_seed = CosmeticRand::new(&Rand::new(&Some(2052)));
+        selections(ShiftSelection::Sequential, 0, &mut *same_seed);
+        assert_eq!(
+            selections(ShiftSelection::Random, 0, &mut *same_seed),
+            random
+        );
+
+        for selection in [
+            ShiftSelection::Sequential,
+            ShiftSelection::Weekday,
+            ShiftSelection::Random,
+        ] {
+            assert_eq!(select_shift(selection, 0, 3, 4, &mut *rng), None);
+        }
+    }
+
+    #[test]
+    fn random_selection_leaves_gameplay_rolls_alone() {
+        let seed = Some(2052);
+        let mut untouched = Rand::new(&seed);
+        let mut rng = Rand::new(&seed);
+        let mut cosmetic_rng = CosmeticRand::new(&rng);
+        for sessions_started in 0..10 {
+            select_shift(
+                ShiftSelection::Random,
+                5,
+                sessions_started,
+                0,
+                &mut *cosmetic_rng,
+            );
+        }
+        assert_eq!(rng.next_u64(), untouched.next_u64());
+    }
+
+    #[test]
+    fn shift_modifiers_revert_when_the_session_ends() {
+        let mut game_config = GameConfig::default();
+        game_config.shift_config = ShiftConfig {
+            selection: ShiftSelection::Sequential,
+            shifts: vec![
+                shift("Friday Crunch", 0.5, 2.),
+                shift("Night Shift", 1.5, 0.25),
+            ],
+        };
+        let mut game_state = GameState::from_config(&game_config);
+        game_state.package_wave_timer = WaveTimer::new(8.);
+
+        let mut app = App::new();
+        app.add_event::<SessionStarted>()
+            .insert_resource(CosmeticRand::new(&Rand::new(&Some(2052))))
+            .insert_resource(game_state)
+            .insert_resource(game_config)
+            .init_resource::<ShiftState>()
+            .add_systems(Update, start_shifts);
+        let supervisor = app
+            .world
+            .spawn(Supervisor {
+                field_of_view: 1.,
+                monitoring_timer: Timer::from_seconds(4., TimerMode::Once),
+                distracted_timer: Timer::from_seconds(6., TimerMode::Once),
+                home_x: 0.,
+                patrol_range: 0.,
+            })
+            .id();
+        let tinted = app.world.spawn((Sprite::default(), ShiftTint)).id();
+
+        let durations = |world: &World| {
+            let supervisor = world.get::<Supervisor>(supervisor).unwrap();
+            (
+                world.resource::<GameState>().package_wave_timer.duration(),
+                supervisor.monitoring_timer.duration(),
+                supervisor.distracted_timer.duration(),
+                world.get::<Sprite>(tinted).unwrap().color,
+            )
+        };
+        let start_session = |app: &mut App| {
+            app.world.send_event(SessionStarted);
+            app.update();
+            app.world
+                .resource::<ShiftState>()
+                .current
+                .as_ref()
+                .map(|shift| shift.name.clone())
+        };
+
+        assert_eq!(start_session(&mut app).as_deref(), Some("Friday Crunch"));
+        assert_eq!(
+            durations(&app.world),
+            (
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+                Duration::from_secs(12),
+                Color::rgb(0.5, 0.5, 1.)
+            )
+        );
+
+        // the second shift scales from what the first found, not from what it left
+        assert_eq!(start_session(&mut app).as_deref(), Some("Night Shift"));
+        assert_eq!(
+            durations(&app.world),
+            (
+                Duration::from_secs(12),
+                Duration::from_secs(1),
+                Duration::from_millis(1500),
+                Color::rgb(0.5, 0.5, 1.)
+            )
+        );
+
+        app.world
+            .resource_mut::<GameConfig>()
+            .shift_config
+            .shifts
+            .clear();
+        assert_eq!(start_session(&mut app), None);
+        assert_eq!(
+            durations(&app.world),
+            (
+                Duration::from_secs(8),
+                Duration::from_secs(4),
+                Duration::from_secs(6),
+                Color::WHITE
+            )
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{random::*, GameConfig, PackagePool};
+
+/// What the entity spawners need from the world, taken by a system as one parameter.
+#[derive(SystemParam)]
+pub struct SpawnContext<'w> {
+    pub asset_server: Res<'w, AssetServer>,
+    pub texture_atlas_layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub colour_materials: ResMut<'w, Assets<ColorMaterial>>,
+    pub game_config: Res<'w, GameConfig>,
+    pub rng: ResMut<'w, Rand>,
+    pub package_pool: ResMut<'w, PackagePool>,
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use bevy_rapier2d::control::KinematicCharacterControllerOutput;
+
+use crate::{
+    EntityLayer, GameConfig, GameState, KeyAction, Player, RenderLayers, RoundScoped,
+    SprintTrailConfig, Stunned,
+};
+
+/// Counts down to a player's next afterimage while they sprint.
+#[derive(Component)]
+pub struct SpawnTrail {
+    pub timer: Timer,
+}
+
+impl SpawnTrail {
+    pub fn new(sprint_trail: &SprintTrailConfig) -> Self {
+        Self {
+            timer: Timer::from_seconds(sprint_trail.trail_interval.max(0.), TimerMode::Repeating),
+        }
+    }
+}
+
+/// One afterimage. They're kept once spawned and reused after fading rather than despawned, a
+/// sprinting player would otherwise churn through a new entity every few frames.
+#[derive(Component)]
+pub struct TrailGhost {
+    pub fade_timer: Timer,
+    pub colour: Color,
+    pub in_use: bool,
+}
+
+/// Leaves a copy of the player's current frame behind them every `trail_interval` while they
+/// sprint faster than `min_speed`. Held sprint doesn't count while standing still or pressed
+/// up against a wall.
+#[allow(clippy::type_complexity)]
+pub fn spawn_sprint_trails(
+    mut commands: Commands,
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    game_config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    mut player_query: Query<(
+        &Player,
+        &mut SpawnTrail,
+        &Transform,
+        &Sprite,
+        &TextureAtlas,
+        &Handle<Image>,
+        Option<&KinematicCharacterControllerOutput>,
+        Has<Stunned>,
+    )>,
+    mut ghost_query: Query<
+        (
+            &mut TrailGhost,
+            &mut Transform,
+            &mut Sprite,
+            &mut TextureAtlas,
+            &mut Handle<Image>,
+            &mut Visibility,
+        ),
+        Without<Player>,
+    >,
+) {
+    let sprint_trail = &game_config.feedback_config.sprint_trail;
+    if sprint_trail.trail_interval <= 0. || fixed_time.delta_seconds() <= 0. {
+        return;
+    }
+
+    let mut free_ghosts = ghost_query
+        .iter_mut()
+        .filter(|(ghost, ..)| !ghost.in_use)
+        .collect::<Vec<_>>();
+    for (
+        player_info,
+        mut spawn_trail,
+        player_transform,
+        player_sprite,
+        player_atlas,
+        player_texture,
+        controller_output,
+        is_stunned,
+    ) in &mut player_query
+    {
+        let player_index = player_info.player_index;
+        let sprinting = game_state.player_controls[player_index].state[KeyAction::Sprint].pressed();
+        // the controller reports how far the last physics step actually moved them
+        let speed = controller_output.map_or(0., |output| {
+            output.effective_translation.length() / fixed_time.delta_seconds()
+        });
+        if !sprinting || is_stunned || speed < sprint_trail.min_speed {
+            spawn_trail.timer.reset();
+            continue;
+        }
+        if !spawn_trail.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let colour = *game_config.player_config.per_player[player_index].colour;
+        let fade_timer = Timer::from_seconds(sprint_trail.trail_lifetime, TimerMode::Once);
+        let ghost_sprite = Sprite {
+            color: colour.with_a(sprint_trail.start_alpha),
+            ..player_sprite.clone()
+        };
+        let ghost_transform = Transform::from_translation(player_transform.translation);
+        if let Some((
+            mut ghost,
+            mut transform,
+            mut sprite,
+            mut atlas,
+            mut texture,
+            mut visibility,
+        )) = free_ghosts.pop()
+        {
+            ghost.fade_timer = fade_timer;
+            ghost.colour = colour;
+            ghost.in_use = true;
+            *transform = ghost_transform;
+            *sprite = ghost_sprite;
+            *atlas = player_atlas.clone();
+            *texture = player_texture.clone();
+            *visibility = Visibility::Inherited;
+        } else {
+            commands.spawn((
+                SpriteSheetBundle {
+                    sprite: ghost_sprite,
+                    atlas: player_atlas.clone(),
+                    texture: player_texture.clone(),
+                    transform: ghost_transform,
+                    ..default()
+                },
+                TrailGhost {
+                    fade_timer,
+                    colour,
+                    in_use: true,
+                },
+                RenderLayers::Single(EntityLayer::PlayerTrail),
+                RoundScoped,
+            ));
+        }
+    }
+}
+
+/// Fades afterimages out over `trail_lifetime`, then hides them until they're needed again.
+pub fn fade_sprint_trails(
+    time: Res<Time>,
+    game_config: Res<GameConfig>,
+    mut ghost_query: Query<(&mut TrailGhost, &mut Sprite, &mut Visibility)>,
+) {
+    let start_alpha = game_config.feedback_config.sprint_trail.start_alpha;
+    for (mut ghost, mut sprite, mut visibility) in &mut ghost_query {
+        if !ghost.in_use {
+            continue;
+        }
+        ghost.fade_timer.tick(time.delta());
+        if ghost.fade_timer.finished() {
+            ghost.in_use = false;
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        sprite.color = ghost
+            .colour
+            .with_a(start_alpha * ghost.fade_timer.fraction_remaining());
+    }
+}
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::Conveyor;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FacingDirection {
     Up,
     Down,
@@ -66,7 +66,7 @@ fn update_sprite_z_coordinate<Layer: LayerIndex>(
     if options.y_sort {
         let z_index_map = map_z_indices(transform_query, layer_query);
         for (sprite_entity, sprite) in extracted_sprites.sprites.iter_mut() {
-            if let Some(z) = z_index_map.get(&sprite_entity) {
+            if let Some(z) = z_index_map.get(sprite_entity) {
                 set_sprite_coordinate(sprite, *z);
             }
         }
@@ -95,32 +95,34 @@ fn set_sprite_coordinate(sprite: &mut ExtractedSprite, z: f32) {
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct ZIndexSortKey(Reverse<OrderedFloat<f32>>);
 
-impl ZIndexSortKey {
-    fn new(transform: &GlobalTransform) -> Self {
-        Self(Reverse(OrderedFloat(transform.translation().y)))
-    }
-}
-
 fn map_z_indices<Layer: LayerIndex>(
     transform_query: Extract<Query<(Entity, &GlobalTransform), With<Layer>>>,
     layer_query: Extract<Query<&Layer>>,
 ) -> HashMap<Entity, f32> {
-    let mut all_entities = transform_query
-        .iter()
-        .map(|(entity, transform)| (ZIndexSortKey::new(transform), entity))
+    y_sorted_z_indices(transform_query.iter().map(|(entity, transform)| {
+        (
+            entity,
+            transform.translation().y,
+            layer_query.get(entity).unwrap().as_z_coordinate(),
+        )
+    }))
+}
+
+/// Final z of each `(entity, y, layer z)`, lower on screen is drawn in front. Every entity gets
+/// less than 1.0 added to its layer's z, so layers `MIN_LAYER_GAP` apart never swap.
+pub fn y_sorted_z_indices(
+    entities: impl Iterator<Item = (Entity, f32, f32)>,
+) -> HashMap<Entity, f32> {
+    let mut all_entities = entities
+        .map(|(entity, y, layer_z)| (ZIndexSortKey(Reverse(OrderedFloat(y))), entity, layer_z))
         .collect::<Vec<_>>();
 
-    all_entities.par_sort_unstable();
+    all_entities.par_sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
 
     let scale_factor = 1.0 / all_entities.len() as f32;
     all_entities
         .into_iter()
         .enumerate()
-        .map(|(i, (_, entity))| {
-            (
-                entity,
-                layer_query.get(entity).unwrap().as_z_coordinate() + i as f32 * scale_factor,
-            )
-        })
+        .map(|(i, (_, entity, layer_z))| (entity, layer_z + i as f32 * scale_factor))
         .collect()
 }
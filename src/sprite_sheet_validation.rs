@@ -0,0 +1,50 @@
+use bevy::{prelude::*, utils::HashSet};
+
+/// Where a sprite sheet came from and the image size its grid expects, checked against the
+/// image once it has loaded.
+#[derive(Component)]
+pub struct SpriteSheetSource {
+    pub source: String,
+    pub path: String,
+    pub expected_size: UVec2,
+}
+
+pub fn validate_sprite_sheets(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    images: Res<Assets<Image>>,
+    sheet_query: Query<(Ref<SpriteSheetSource>, &Handle<Image>)>,
+    mut reported: Local<HashSet<String>>,
+) {
+    let loaded = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (sheet_source, image_handle) in &sheet_query {
+        // sheets sharing an image that loaded earlier won't see another load event
+        if !sheet_source.is_added() && !loaded.contains(&image_handle.id()) {
+            continue;
+        }
+        let Some(image) = images.get(image_handle) else {
+            continue;
+        };
+        let image_size = image.size();
+        let expected_size = sheet_source.expected_size;
+        if (expected_size.x > image_size.x || expected_size.y > image_size.y)
+            && reported.insert(sheet_source.path.clone())
+        {
+            error!(
+                "sprite sheet '{}' from {} needs at least {}x{} pixels for its grid but the image is {}x{}, check its grid_dimensions and cell_resolution",
+                sheet_source.path,
+                sheet_source.source,
+                expected_size.x,
+                expected_size.y,
+                image_size.x,
+                image_size.y
+            );
+        }
+    }
+}
@@ -1,16 +1,207 @@
 use crate::{
-    random::*, AnimationData, AppConfig, EntityLayer, FacingDirection, GameConfig, Player,
-    RenderLayers, TextureTarget,
+    held_packages, random::*, release_package, AnimationData, AppConfig, ConveyorLabelTag,
+    EntityLayer, FacingDirection, GameConfig, GameState, Knockback, Metrics, Package,
+    PackageConfiscated, Player, PlayerIndex, RenderLayers, Score, ScoreChanged, SpawnContext,
+    SpriteSheetSource, SupervisorHit, SupervisorPhaseChanged, TextureTarget, ThrownBy,
+    TrackedTexture, WaveModifiers,
 };
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use enum_map::EnumMap;
+use std::time::Duration;
 
 #[derive(Component)]
 pub struct Supervisor {
     pub field_of_view: f32,
     pub monitoring_timer: Timer,
     pub distracted_timer: Timer,
+    /// Centre of the stretch they pace while monitoring.
+    pub home_x: f32,
+    pub patrol_range: f32,
 }
 
+/// Whether any supervisor is watching the floor, kept up to date by `update_supervisor` for
+/// anything outside the supervisor's own systems. Nobody is watching while there's no supervisor.
+#[derive(Resource, Default)]
+pub struct SupervisorStatus {
+    pub monitoring: bool,
+}
+
+impl SupervisorStatus {
+    /// Whether a belt has to stand still right now, only outgoing belts in stealth games do.
+    pub fn holds_belt(&self, game_config: &GameConfig, conveyor_tag: &ConveyorLabelTag) -> bool {
+        self.monitoring
+            && game_config.stealth_outgoing
+            && matches!(conveyor_tag, ConveyorLabelTag::Outgoing(_))
+    }
+}
+
+/// Spawns the supervisor, at their monitoring position unless told otherwise.
+#[derive(Default)]
+pub struct SupervisorSpawner {
+    position: Option<Vec3>,
+    patrol_range: f32,
+    timer_offset_seconds: f32,
+    texture_key: u64,
+}
+
+impl SupervisorSpawner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn at(mut self, position: Vec3) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn patrol_range(mut self, patrol_range: f32) -> Self {
+        self.patrol_range = patrol_range;
+        self
+    }
+
+    /// Starts them this many seconds into their watch, or into their distraction once the
+    /// offset runs past the watch.
+    pub fn timer_offset(mut self, seconds: f32) -> Self {
+        self.timer_offset_seconds = seconds;
+        self
+    }
+
+    /// Who they are for `TextureSelection::StablePerEntity`, the same key keeps the same sheet.
+    pub fn texture_key(mut self, key: u64) -> Self {
+        self.texture_key = key;
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands, ctx: &mut SpawnContext) -> Entity {
+        self.spawn_with(
+            commands,
+            &ctx.asset_server,
+            &mut ctx.texture_atlas_layouts,
+            &mut ctx.rng,
+            &ctx.game_config,
+        )
+    }
+
+    fn spawn_with(
+        self,
+        commands: &mut Commands,
+        asset_server: &AssetServer,
+        texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+        rng: &mut Rand,
+        game_config: &GameConfig,
+    ) -> Entity {
+        let supervisor_start_pos = self.position.unwrap_or(Vec3::new(
+            0.,
+            game_config.supervisor_config.monitoring_y_pos,
+            0.,
+        ));
+        let texture_pack = game_config.get_texture_pack();
+        let supervisor_sprite = texture_pack.select_texture_for(
+            TextureTarget::Supervisor,
+            game_config.texture_selection,
+            self.texture_key,
+            Some(rng),
+        );
+        let sprite_path = format!("{}/{}", texture_pack.root, supervisor_sprite.path);
+        let texture_handle: Handle<Image> = asset_server.load(&sprite_path);
+        let (atlas_layout, frame_count) = supervisor_sprite.atlas_layout("Supervisor");
+        let sprite_size =
+            texture_pack.display_size(supervisor_sprite, game_config.supervisor_config.size);
+        let animation_indices = AnimationData {
+            start_frame: 0,
+            frame_count,
+            pause: true,
+            facing_direction: FacingDirection::Down,
+        };
+        let mut monitoring_timer = Timer::from_seconds(5., TimerMode::Once);
+        let mut distracted_timer = Timer::from_seconds(5., TimerMode::Once);
+        distracted_timer.pause();
+        if self.timer_offset_seconds > 0. {
+            let cycle = monitoring_timer.duration() + distracted_timer.duration();
+            let offset = Duration::from_secs_f32(self.timer_offset_seconds % cycle.as_secs_f32());
+            if offset < monitoring_timer.duration() {
+                monitoring_timer.tick(offset);
+            } else {
+                // ticked through so it reads as finished, the same as after a watch
+                monitoring_timer.tick(monitoring_timer.duration());
+                monitoring_timer.pause();
+                distracted_timer.unpause();
+                distracted_timer.tick(offset - monitoring_timer.duration());
+            }
+        }
+        commands
+            .spawn((
+                SpriteSheetBundle {
+                    sprite: Sprite {
+                        custom_size: Some(sprite_size),
+                        ..default()
+                    },
+                    atlas: TextureAtlas {
+                        layout: texture_atlas_layouts.add(atlas_layout),
+                        index: animation_indices.start_frame,
+                    },
+                    texture: texture_handle,
+                    transform: Transform {
+                        translation: supervisor_start_pos,
+                        ..default()
+                    },
+                    ..default()
+                },
+                Supervisor {
+                    field_of_view: 90.,
+                    monitoring_timer,
+                    distracted_timer,
+                    home_x: supervisor_start_pos.x,
+                    patrol_range: self.patrol_range,
+                },
+                TrackedTexture::new(
+                    TextureTarget::Supervisor,
+                    format!("texture pack '{}'", game_config.selected_texture_pack),
+                    &sprite_path,
+                ),
+                SpriteSheetSource {
+                    source: format!("texture pack '{}'", game_config.selected_texture_pack),
+                    path: sprite_path,
+                    expected_size: supervisor_sprite.expected_image_size().unwrap_or_default(),
+                },
+                RenderLayers::Single(EntityLayer::SuperVisor),
+                animation_indices,
+                RigidBody::KinematicPositionBased,
+                Collider::cuboid(
+                    game_config.supervisor_config.size / 2.,
+                    game_config.supervisor_config.size / 2.,
+                ),
+                Restitution {
+                    coefficient: 0.8,
+                    combine_rule: CoefficientCombineRule::Max,
+                },
+            ))
+            .id()
+    }
+}
+
+/// Spawns every supervisor listed in the config.
+pub fn spawn_supervisors(commands: &mut Commands, ctx: &mut SpawnContext) {
+    let supervisor_config = &ctx.game_config.supervisor_config;
+    let monitoring_y_pos = supervisor_config.monitoring_y_pos;
+    for (supervisor_index, instance) in supervisor_config
+        .supervisors
+        .clone()
+        .into_iter()
+        .enumerate()
+    {
+        SupervisorSpawner::new()
+            .at(Vec3::new(instance.monitoring_x_pos, monitoring_y_pos, 0.))
+            .patrol_range(instance.patrol_range)
+            .timer_offset(instance.timer_offset_seconds)
+            // their place in the list, so each keeps their own sheet
+            .texture_key(supervisor_index as u64)
+            .spawn(commands, ctx);
+    }
+}
+
+#[deprecated(note = "use SupervisorSpawner")]
 pub fn spawn_supervisor(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -19,92 +210,81 @@ pub fn spawn_supervisor(
     rng: &mut ResMut<Rand>,
     game_config: &Res<GameConfig>,
 ) {
-    let texture_pack = game_config.get_texture_pack();
-    let supervisor_sprite = texture_pack.choose_texture_for(TextureTarget::Supervisor, Some(rng));
-    let texture_handle: Handle<Image> =
-        asset_server.load(&format!("{}/{}", texture_pack.root, supervisor_sprite.path));
-    let sprite_size = supervisor_sprite
-        .cell_resolution
-        .expect("Supervisor sprite must have a cell resolution")
-        .as_vec2();
-    let grid_dimensions = supervisor_sprite
-        .grid_dimensions
-        .expect("SuperVisor sprite must have grid dimensions");
-    let frame_count = grid_dimensions.x * grid_dimensions.y;
-    let atlas_layout = TextureAtlasLayout::from_grid(
-        sprite_size,
-        grid_dimensions.x as usize,
-        grid_dimensions.y as usize,
-        None,
-        None,
-    );
-    let animation_indices = AnimationData {
-        start_frame: 0,
-        frame_count: frame_count as usize,
-        pause: true,
-        facing_direction: FacingDirection::Down,
-    };
-    let monitoring_timer = Timer::from_seconds(5., TimerMode::Once);
-    let mut distracted_timer = Timer::from_seconds(5., TimerMode::Once);
-    distracted_timer.pause();
-    commands.spawn((
-        SpriteSheetBundle {
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(
-                    game_config.supervisor_config.size,
-                    game_config.supervisor_config.size,
-                )),
-                ..default()
-            },
-            atlas: TextureAtlas {
-                layout: texture_atlas_layouts.add(atlas_layout),
-                index: animation_indices.start_frame,
-            },
-            texture: texture_handle,
-            transform: Transform {
-                translation: supervisor_start_pos,
-                ..default()
-            },
-            ..default()
-        },
-        Supervisor {
-            field_of_view: 90.,
-            monitoring_timer: monitoring_timer,
-            distracted_timer: distracted_timer,
-        },
-        RenderLayers::Single(EntityLayer::SuperVisor),
-        animation_indices,
-    ));
+    SupervisorSpawner::new()
+        .at(supervisor_start_pos)
+        .spawn_with(
+            commands,
+            asset_server,
+            texture_atlas_layouts,
+            rng,
+            game_config,
+        );
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_supervisor(
-    mut supervisor_query: Query<(&mut Transform, &mut AnimationData, &mut Supervisor)>,
+    mut commands: Commands,
+    mut supervisor_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut AnimationData,
+        &mut Supervisor,
+        Has<ColliderDisabled>,
+    )>,
     time: Res<Time>,
     app_config: Res<AppConfig>,
     game_config: Res<GameConfig>,
+    mut phase_events: EventWriter<SupervisorPhaseChanged>,
+    mut supervisor_status: ResMut<SupervisorStatus>,
+    wave_modifiers: Res<WaveModifiers>,
 ) {
+    let mut any_monitoring = false;
+    let monitoring_scale = wave_modifiers.monitoring_scale(&game_config.wave_modifier_config);
     let supervisor_offscreen_distraction_pos =
         (app_config.base_resolution.y as f32 / 2.) + (game_config.supervisor_config.size / 2.);
 
-    for (mut supervisor_transform, mut supervisor_anim_data, mut supervisor) in
-        &mut supervisor_query
+    for (
+        supervisor_entity,
+        mut supervisor_transform,
+        mut supervisor_anim_data,
+        mut supervisor,
+        collider_disabled,
+    ) in &mut supervisor_query
     {
-        supervisor.monitoring_timer.tick(time.delta());
+        if supervisor.monitoring_timer.paused() && supervisor.distracted_timer.paused() {
+            // spawned without either phase running, start out monitoring so the lerps below have
+            // a phase to follow
+            supervisor.monitoring_timer.reset();
+            supervisor.monitoring_timer.unpause();
+        }
+
+        supervisor
+            .monitoring_timer
+            .tick(time.delta().div_f32(monitoring_scale));
         supervisor.distracted_timer.tick(time.delta());
         if supervisor.monitoring_timer.just_finished() {
             // supervisor is now distracted
             supervisor.monitoring_timer.pause();
             supervisor.distracted_timer.reset();
             supervisor.distracted_timer.unpause();
+            phase_events.send(SupervisorPhaseChanged {
+                supervisor: supervisor_entity,
+                monitoring: false,
+            });
         }
         if supervisor.distracted_timer.just_finished() {
             // supervisor is now monitoring
             supervisor.distracted_timer.pause();
             supervisor.monitoring_timer.reset();
             supervisor.monitoring_timer.unpause();
+            phase_events.send(SupervisorPhaseChanged {
+                supervisor: supervisor_entity,
+                monitoring: true,
+            });
         }
 
         let monitoring = !supervisor.monitoring_timer.finished();
+        any_monitoring |= monitoring;
         if monitoring {
             // supervisor "distraction" complete, return to monitoring
             let t = supervisor.monitoring_timer.fraction() / 0.4;
@@ -113,6 +293,12 @@ pub fn update_supervisor(
                 t.clamp(0., 1.),
             );
             supervisor_anim_data.facing_direction = FacingDirection::Down;
+            if supervisor.patrol_range > 0. {
+                // out to one side and back across to the other over a single watch
+                let sway = (supervisor.monitoring_timer.fraction() * std::f32::consts::TAU).sin();
+                supervisor_transform.translation.x =
+                    supervisor.home_x + supervisor.patrol_range * sway;
+            }
         } else {
             // supervisor monitoring complete, "distract" them
             let t = supervisor.distracted_timer.fraction() / 0.4;
@@ -122,18 +308,139 @@ pub fn update_supervisor(
                 .lerp(supervisor_offscreen_distraction_pos, t.clamp(0., 1.));
             supervisor_anim_data.facing_direction = FacingDirection::Up;
         }
+
+        // solid for as long as any of them is on screen, decided from where the lerps above just
+        // put them so it can't fall out of step with what's drawn
+        let on_screen = supervisor_transform.translation.y
+            - game_config.supervisor_config.size / 2.
+            < app_config.base_resolution.y as f32 / 2.;
+        if on_screen && collider_disabled {
+            commands
+                .entity(supervisor_entity)
+                .remove::<ColliderDisabled>();
+        } else if !on_screen && !collider_disabled {
+            commands.entity(supervisor_entity).insert(ColliderDisabled);
+        }
+    }
+
+    if supervisor_status.monitoring != any_monitoring {
+        supervisor_status.monitoring = any_monitoring;
     }
 }
 
+/// Packages thrown into the supervisor bounce off and cost the thrower, players who walk into
+/// them are shoved back.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn handle_supervisor_contacts(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    game_config: Res<GameConfig>,
+    mut game_state: ResMut<GameState>,
+    supervisor_query: Query<&Transform, (With<Supervisor>, Without<ColliderDisabled>)>,
+    thrown_query: Query<&ThrownBy, With<Package>>,
+    player_query: Query<
+        (Entity, &Transform, &KinematicCharacterControllerOutput),
+        (With<Player>, Without<Knockback>),
+    >,
+    mut hit_events: EventWriter<SupervisorHit>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    let supervisor_config = &game_config.supervisor_config;
+    for contact_pair in rapier_context
+        .contact_pairs()
+        .filter(|pair| pair.has_any_active_contacts())
+    {
+        for (supervisor_entity, package_entity) in [
+            (contact_pair.collider1(), contact_pair.collider2()),
+            (contact_pair.collider2(), contact_pair.collider1()),
+        ] {
+            if !supervisor_query.contains(supervisor_entity) {
+                continue;
+            }
+            let Ok(thrown_by) = thrown_query.get(package_entity) else {
+                continue;
+            };
+            // one penalty per throw, however long the package stays in contact
+            commands.entity(package_entity).remove::<ThrownBy>();
+
+            let score_config = &game_config.score_config;
+            let player_score = &mut game_state.player_scores[thrown_by.player];
+            let penalty = if score_config.penalties_scale_with_multiplier {
+                supervisor_config.hit_penalty * player_score.multiplier
+            } else {
+                supervisor_config.hit_penalty
+            };
+            player_score
+                .score
+                .apply_penalty(Score::from_points(penalty as f64), score_config.score_floor);
+            score_events.send(ScoreChanged {
+                player: thrown_by.player,
+            });
+            hit_events.send(SupervisorHit {
+                player: thrown_by.player,
+                supervisor: supervisor_entity,
+            });
+        }
+    }
+
+    for (player_entity, player_transform, controller_output) in &player_query {
+        let Some(supervisor_transform) = controller_output
+            .collisions
+            .iter()
+            .find_map(|collision| supervisor_query.get(collision.entity).ok())
+        else {
+            continue;
+        };
+        let away = (player_transform.translation - supervisor_transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        commands.entity(player_entity).insert(Knockback {
+            velocity: away * supervisor_config.bump_knockback_speed,
+            timer: Timer::from_seconds(supervisor_config.bump_knockback_seconds, TimerMode::Once),
+        });
+    }
+}
+
+/// Lowest y of the strip below the office wall where carrying a package in view of the supervisor
+/// gets it confiscated.
+pub fn restricted_zone_min_y(app_config: &AppConfig, game_config: &GameConfig) -> f32 {
+    let supervisor_config = &game_config.supervisor_config;
+    (app_config.base_resolution.y as f32 / 2.)
+        - (supervisor_config.office_sprite_size.y as f32 / 2.)
+        - supervisor_config.restricted_zone_depth
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn check_supervisor_can_see_players(
-    supervisor_query: Query<(&Transform, &Supervisor)>,
-    player_query: Query<&Transform, With<Player>>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut last_caught: Local<EnumMap<PlayerIndex, Option<(Entity, Duration)>>>,
+    supervisor_query: Query<(Entity, &Transform, &Supervisor)>,
+    player_query: Query<(&Transform, &Player, Option<&Children>)>,
+    package_query: Query<(), With<Package>>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    mut game_state: ResMut<GameState>,
+    mut confiscated_events: EventWriter<PackageConfiscated>,
+    mut score_events: EventWriter<ScoreChanged>,
+    mut metrics: Option<ResMut<Metrics>>,
 ) {
-    for (supervisor_transform, supervisor) in supervisor_query
+    let supervisor_config = &game_config.supervisor_config;
+    let restricted_zone_min_y = restricted_zone_min_y(&app_config, &game_config);
+    // fresh packages land right by the office, so give players a moment to clear them out
+    let exempt = game_state
+        .package_wave_timer
+        .since_last_wave()
+        .is_some_and(|since_last_wave| {
+            since_last_wave.as_secs_f32() < supervisor_config.confiscation_exemption_seconds
+        });
+    let mut confiscated = Vec::new();
+
+    for (supervisor_entity, supervisor_transform, supervisor) in supervisor_query
         .iter()
-        .filter(|(_, s)| !s.monitoring_timer.finished())
+        .filter(|(_, _, s)| !s.monitoring_timer.finished())
     {
-        for player_transform in &mut player_query.iter() {
+        for (player_transform, player_info, player_children) in &player_query {
             let player_pos = player_transform.translation;
             let supervisor_pos = supervisor_transform.translation;
             let supervisor_facing = supervisor_transform.up();
@@ -141,6 +448,53 @@ pub fn check_supervisor_can_see_players(
             let supervisor_to_player_angle = supervisor_facing.angle_between(supervisor_to_player);
             if supervisor_to_player_angle < (supervisor.field_of_view / 2.) {
                 // player is in the supervisor's field of view
+                if exempt || player_pos.y < restricted_zone_min_y {
+                    continue;
+                }
+                // the whole stack goes, and two supervisors can spot the same player in one tick
+                let held = held_packages(player_children, |child| package_query.contains(child))
+                    .filter(|held_package| !confiscated.contains(held_package))
+                    .collect::<Vec<_>>();
+                let player_index = player_info.player_index;
+                // caught by a different supervisor moments after the last, one penalty covers it
+                let within_grace =
+                    last_caught[player_index].is_some_and(|(caught_by, caught_at)| {
+                        caught_by != supervisor_entity
+                            && (time.elapsed() - caught_at).as_secs_f32()
+                                < supervisor_config.detection_grace_seconds
+                    });
+                if !held.is_empty() && !within_grace {
+                    last_caught[player_index] = Some((supervisor_entity, time.elapsed()));
+                }
+                for held_package in held {
+                    confiscated.push(held_package);
+                    release_package(&mut commands, held_package);
+
+                    if !within_grace {
+                        let score_config = &game_config.score_config;
+                        let player_score = &mut game_state.player_scores[player_index];
+                        let penalty = if score_config.penalties_scale_with_multiplier {
+                            supervisor_config.confiscation_penalty * player_score.multiplier
+                        } else {
+                            supervisor_config.confiscation_penalty
+                        };
+                        player_score.score.apply_penalty(
+                            Score::from_points(penalty as f64),
+                            score_config.score_floor,
+                        );
+                        score_events.send(ScoreChanged {
+                            player: player_index,
+                        });
+                    }
+                    confiscated_events.send(PackageConfiscated {
+                        player: player_index,
+                        supervisor: supervisor_entity,
+                    });
+                    if let Some(metrics) = metrics.as_mut() {
+                        metrics.per_player[player_index].confiscated += 1;
+                    }
+                    info!("{:?} caught carrying a package, confiscated", player_index);
+                }
             }
         }
     }
@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    diagnostic::DiagnosticsPlugin, hierarchy::HierarchyPlugin, input::InputPlugin, prelude::*,
+    time::TimeUpdateStrategy, transform::TransformPlugin,
+};
+use bevy_rapier2d::prelude::*;
+use enum_map::EnumMap;
+
+use crate::{
+    update_controller_mappings, ArenaLayout, AttractMode, BindSample, BufferBudgets, Config,
+    Conveyor, GameState, GameplayPlugin, KeyAction, MatchState, Package, PlayerIndex, Rand,
+};
+
+/// These actions held for this many fixed ticks, everything else let go.
+#[derive(Clone)]
+pub struct ScriptStep {
+    pub ticks: u32,
+    pub held: Vec<KeyAction>,
+}
+
+/// Input played into each player's controls a fixed tick at a time, for driving a match without a
+/// controller. It's recorded the same way sampled input is, so nothing downstream can tell the
+/// difference.
+#[derive(Resource, Default)]
+pub struct InputScript {
+    steps: EnumMap<PlayerIndex, VecDeque<ScriptStep>>,
+    /// Ticks already played of each player's current step.
+    elapsed: EnumMap<PlayerIndex, u32>,
+    pressed: EnumMap<PlayerIndex, EnumMap<KeyAction, bool>>,
+}
+
+impl InputScript {
+    pub fn hold(&mut self, player_index: PlayerIndex, ticks: u32, held: &[KeyAction]) -> &mut Self {
+        self.steps[player_index].push_back(ScriptStep {
+            ticks,
+            held: held.to_vec(),
+        });
+        self
+    }
+
+    pub fn wait(&mut self, player_index: PlayerIndex, ticks: u32) -> &mut Self {
+        self.hold(player_index, ticks, &[])
+    }
+
+    /// A press on one tick and the release on the next.
+    pub fn tap(&mut self, player_index: PlayerIndex, key_action: KeyAction) -> &mut Self {
+        self.hold(player_index, 1, &[key_action])
+            .wait(player_index, 1)
+    }
+
+    /// Every step has been played, from here on the players are left with nothing held.
+    pub fn is_finished(&self) -> bool {
+        self.steps.values().all(VecDeque::is_empty)
+    }
+}
+
+/// Runs in `FixedUpdate` before `update_controller_mappings`, in place of
+/// `sample_controller_input`. The players' brains should be left disabled while a script plays.
+pub fn play_input_script(mut script: ResMut<InputScript>, mut game_state: ResMut<GameState>) {
+    let script = &mut *script;
+    for (player_index, player_controls) in game_state.player_controls.iter_mut() {
+        let mut pressed = EnumMap::<KeyAction, bool>::default();
+        if let Some(step) = script.steps[player_index].front() {
+            for key_action in &step.held {
+                pressed[key_action.clone()] = true;
+            }
+            script.elapsed[player_index] += 1;
+            if script.elapsed[player_index] >= step.ticks {
+                script.steps[player_index].pop_front();
+                script.elapsed[player_index] = 0;
+            }
+        }
+
+        for (key_action, is_pressed) in pressed.iter() {
+            let was_pressed = script.pressed[player_index][key_action.clone()];
+            player_controls.input_accumulators[key_action].record(BindSample {
+                held: *is_pressed,
+                just_pressed: *is_pressed && !was_pressed,
+                by_mouse: false,
+            });
+        }
+        script.pressed[player_index] = pressed;
+    }
+}
+
+/// The game playing `config` with nothing drawn and nobody at the controls, an `InputScript`
+/// plays for them. The first update only runs startup, each one after runs exactly one fixed
+/// tick.
+pub fn headless_app(config: Config) -> App {
+    let match_state = MatchState::default();
+    let arena_layout = ArenaLayout::new(&config.app, &config.game, &match_state.sides);
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+        DiagnosticsPlugin,
+    ))
+    // the spawners hand out handles, nothing ever loads into them
+    .init_asset::<Image>()
+    .init_asset::<TextureAtlasLayout>()
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+        config.game.pixels_per_meter(),
+    ))
+    .add_plugins(GameplayPlugin)
+    .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Time::<Fixed>::default().timestep(),
+    ))
+    .insert_resource(GameState::from_config(&config.game))
+    .insert_resource(AttractMode::new(&config.game))
+    .insert_resource(Rand::new(&config.app.rng_seed))
+    .insert_resource(BufferBudgets::new(
+        config.app.observability_memory_budget_mb,
+    ))
+    .insert_resource(arena_layout)
+    .insert_resource(match_state)
+    .insert_resource(config.app)
+    .insert_resource(config.game)
+    .init_resource::<InputScript>()
+    .add_systems(
+        FixedUpdate,
+        play_input_script.before(update_controller_mappings),
+    );
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Belts whose `package_count` disagrees with the packages actually parented to them, as
+/// `(conveyor, package_count, parented)`. Only meaningful between ticks, once commands are
+/// applied.
+pub fn conveyor_package_mismatches(world: &mut World) -> Vec<(Entity, usize, usize)> {
+    let mut package_query = world.query_filtered::<&Parent, With<Package>>();
+    let parents = package_query
+        .iter(world)
+        .map(|parent| parent.get())
+        .collect::<Vec<_>>();
+    let mut conveyor_query = world.query::<(Entity, &Conveyor)>();
+    conveyor_query
+        .iter(world)
+        .filter_map(|(conveyor_entity, conveyor_info)| {
+            let parented = parents
+                .iter()
+                .filter(|parent| **parent == conveyor_entity)
+                .count();
+            (parented != conveyor_info.package_count).then_some((
+                conveyor_entity,
+                conveyor_info.package_count,
+                parented,
+            ))
+        })
+        .collect()
+}
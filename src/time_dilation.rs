@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use enum_map::EnumMap;
+
+use crate::{
+    AppConfig, AttractMode, EntityLayer, GameConfig, GameModeKind, GameState, MatchState, Package,
+    PackageDelivered, PackagePickedUp, PackageThrown, PlayerIndex, RenderLayers, Score,
+    TimeDilationConfig,
+};
+
+/// Slowest the game is ever allowed to run, whatever the config asks for.
+pub const MIN_TIME_SCALE: f32 = 0.25;
+/// Thickness of the heartbeat bars as a fraction of the screen's shorter side.
+const VIGNETTE_THICKNESS: f32 = 0.08;
+
+/// A burst of slow motion and how many real seconds it has left.
+struct SlowMotion {
+    scale: f32,
+    remaining_seconds: f32,
+}
+
+/// How fast the game should be running. Applied to virtual time, which the fixed schedule and
+/// the physics step both follow, so everything in the world slows together while anything on
+/// real time carries on as normal.
+#[derive(Resource, Default)]
+pub struct TimeScale {
+    slow_motion: Option<SlowMotion>,
+    /// The last stretch of a timed round is playing out.
+    in_final_seconds: bool,
+    /// Packages thrown at full charge that nobody has picked up since.
+    full_charge_throws: HashSet<Entity>,
+    /// Points each player's match point is judged on as of the last check.
+    reported_points: EnumMap<PlayerIndex, i64>,
+}
+
+impl TimeScale {
+    pub fn current(&self, time_dilation: &TimeDilationConfig) -> f32 {
+        let final_seconds_scale = if self.in_final_seconds {
+            time_dilation.final_seconds_scale
+        } else {
+            1.
+        };
+        let slow_motion_scale = self
+            .slow_motion
+            .as_ref()
+            .map_or(1., |slow_motion| slow_motion.scale);
+        final_seconds_scale
+            .min(slow_motion_scale)
+            .clamp(MIN_TIME_SCALE, 1.)
+    }
+
+    pub fn in_final_seconds(&self) -> bool {
+        self.in_final_seconds
+    }
+
+    /// A burst that overlaps one already running keeps the slower of the two for the longer.
+    fn slow_down(&mut self, scale: f32, seconds: f32) {
+        if scale >= 1. || seconds <= 0. {
+            return;
+        }
+        match self.slow_motion.as_mut() {
+            Some(slow_motion) => {
+                slow_motion.scale = slow_motion.scale.min(scale);
+                slow_motion.remaining_seconds = slow_motion.remaining_seconds.max(seconds);
+            }
+            None => {
+                self.slow_motion = Some(SlowMotion {
+                    scale,
+                    remaining_seconds: seconds,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct HeartbeatVignette;
+
+pub fn setup_heartbeat_vignette(mut commands: Commands, app_config: Res<AppConfig>) {
+    let resolution = app_config.base_resolution.as_vec2();
+    let thickness = resolution.min_element() * VIGNETTE_THICKNESS;
+    let bars = [
+        (
+            Vec2::new(0., (resolution.y - thickness) / 2.),
+            Vec2::new(resolution.x, thickness),
+        ),
+        (
+            Vec2::new(0., -(resolution.y - thickness) / 2.),
+            Vec2::new(resolution.x, thickness),
+        ),
+        (
+            Vec2::new(-(resolution.x - thickness) / 2., 0.),
+            Vec2::new(thickness, resolution.y - thickness * 2.),
+        ),
+        (
+            Vec2::new((resolution.x - thickness) / 2., 0.),
+            Vec2::new(thickness, resolution.y - thickness * 2.),
+        ),
+    ];
+    commands
+        .spawn((
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HeartbeatVignette,
+        ))
+        .with_children(|builder| {
+            for (position, size) in bars {
+                builder.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(0.6, 0., 0., 0.),
+                            custom_size: Some(size),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(position.extend(0.)),
+                        ..default()
+                    },
+                    RenderLayers::Single(EntityLayer::ScreenFlash),
+                ));
+            }
+        });
+}
+
+/// Starts slow motion for match points and full charge throws that score, and notes when a timed
+/// round reaches its final seconds. Bursts count down in real time.
+#[allow(clippy::too_many_arguments)]
+pub fn trigger_slow_motion(
+    real_time: Res<Time<Real>>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    match_state: Res<MatchState>,
+    attract_mode: Res<AttractMode>,
+    mut time_scale: ResMut<TimeScale>,
+    mut thrown_events: EventReader<PackageThrown>,
+    mut picked_up_events: EventReader<PackagePickedUp>,
+    mut delivered_events: EventReader<PackageDelivered>,
+    package_query: Query<(), With<Package>>,
+) {
+    let time_dilation = &game_config.feedback_config.time_dilation;
+    let points = EnumMap::from_fn(|player_index| match game_config.game_mode {
+        GameModeKind::Team => Score::team_total(
+            game_state
+                .player_scores
+                .values()
+                .map(|player_score| &player_score.score),
+        ),
+        GameModeKind::Versus => game_state.player_scores[player_index].score.points(),
+    });
+    let reported_points = std::mem::replace(&mut time_scale.reported_points, points);
+
+    if !app_config.time_effects || attract_mode.active {
+        time_scale.slow_motion = None;
+        time_scale.in_final_seconds = false;
+        time_scale.full_charge_throws.clear();
+        thrown_events.clear();
+        picked_up_events.clear();
+        delivered_events.clear();
+        return;
+    }
+
+    if let Some(slow_motion) = time_scale.slow_motion.as_mut() {
+        slow_motion.remaining_seconds -= real_time.delta_seconds();
+        if slow_motion.remaining_seconds <= 0. {
+            time_scale.slow_motion = None;
+        }
+    }
+
+    for event in thrown_events.read() {
+        if event.charge >= time_dilation.full_charge_throw_min_charge {
+            time_scale.full_charge_throws.insert(event.package);
+        }
+    }
+    for event in picked_up_events.read() {
+        time_scale.full_charge_throws.remove(&event.package);
+    }
+
+    let mut delivered = false;
+    for event in delivered_events.read() {
+        delivered = true;
+        if time_scale.full_charge_throws.remove(&event.package) {
+            debug!("{:?} scored a full charge throw", event.player);
+            time_scale.slow_down(
+                time_dilation.full_charge_throw_scale,
+                time_dilation.full_charge_throw_seconds,
+            );
+        }
+    }
+    // packages that went some other way than a delivery are forgotten once they're gone
+    time_scale
+        .full_charge_throws
+        .retain(|package_entity| package_query.contains(*package_entity));
+
+    if let Some(match_point_score) = time_dilation.match_point_score {
+        let match_point = match_point_score.round() as i64;
+        let crossed = points.iter().any(|(player_index, points)| {
+            reported_points[player_index] < match_point && *points >= match_point
+        });
+        if delivered && crossed {
+            info!("Match point reached at {}", match_point);
+            time_scale.slow_down(
+                time_dilation.match_point_scale,
+                time_dilation.match_point_seconds,
+            );
+        }
+    }
+
+    time_scale.in_final_seconds = time_dilation.final_seconds > 0.
+        && match_state
+            .round_seconds_left()
+            .is_some_and(|seconds_left| seconds_left <= time_dilation.final_seconds);
+}
+
+pub fn apply_time_scale(
+    time_scale: Res<TimeScale>,
+    game_config: Res<GameConfig>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    let speed = time_scale.current(&game_config.feedback_config.time_dilation);
+    if virtual_time.relative_speed() != speed {
+        virtual_time.set_relative_speed(speed);
+    }
+}
+
+/// Pulses red bars around the screen edges, two beats at a time, through the final seconds.
+pub fn update_heartbeat_vignette(
+    real_time: Res<Time<Real>>,
+    time_scale: Res<TimeScale>,
+    game_config: Res<GameConfig>,
+    mut vignette_query: Query<(&mut Visibility, &Children), With<HeartbeatVignette>>,
+    mut bar_query: Query<&mut Sprite>,
+) {
+    let time_dilation = &game_config.feedback_config.time_dilation;
+    for (mut visibility, children) in &mut vignette_query {
+        let wanted = if time_scale.in_final_seconds() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        if wanted == Visibility::Hidden {
+            continue;
+        }
+
+        let phase =
+            (real_time.elapsed_seconds() * time_dilation.heartbeat_beats_per_second).fract();
+        let beat = |at: f32, width: f32| (1. - (phase - at).abs() / width).max(0.);
+        let pulse = beat(0.1, 0.1).max(beat(0.35, 0.1) * 0.6);
+        for child in children {
+            if let Ok(mut bar_sprite) = bar_query.get_mut(*child) {
+                bar_sprite
+                    .color
+                    .set_a(time_dilation.heartbeat_alpha * pulse);
+            }
+        }
+    }
+}
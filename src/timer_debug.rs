@@ -0,0 +1,236 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    sprite::Anchor,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    worst_recent_time, AppConfig, Conveyor, ConveyorLabelTag, EntityLayer, GameState, Package,
+    PackagePool, RenderLayers, Supervisor, TIMED_SYSTEMS,
+};
+
+const ROW_FONT_SIZE: f32 = 14.;
+const ROW_MARGIN: f32 = 8.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerDebugSource {
+    PackageWave,
+    PackagePool,
+    /// Loose packages Rapier has put to sleep against those it's still stepping.
+    PackageSleep,
+    Supervisor(Entity),
+    Conveyor(Entity),
+    FrameTime,
+    /// Timed systems ranked by their worst recent wall time, so the culprit of a hitch floats
+    /// to the top.
+    SystemTime(usize),
+}
+
+#[derive(Component)]
+pub struct TimerDebugRow {
+    pub source: TimerDebugSource,
+}
+
+/// On screen listing of every gameplay timer and how long the heavy systems take, only present
+/// when `AppConfig::debug_overlays` is set.
+#[derive(Resource, Default)]
+pub struct TimerDebugOverlay {
+    pub visible: bool,
+    sources: Vec<TimerDebugSource>,
+}
+
+impl TimerDebugOverlay {
+    pub fn is_visible(overlay: Res<TimerDebugOverlay>) -> bool {
+        overlay.visible
+    }
+}
+
+fn describe_timer(timer: &Timer) -> String {
+    format!(
+        "{:.1}s{}",
+        timer.remaining_secs(),
+        if timer.paused() { " (paused)" } else { "" }
+    )
+}
+
+pub fn toggle_timer_debug_overlay(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    app_config: Res<AppConfig>,
+    mut overlay: ResMut<TimerDebugOverlay>,
+    row_query: Query<Entity, With<TimerDebugRow>>,
+) {
+    if !keyboard_input.just_pressed(app_config.debug_overlay_toggle_key) {
+        return;
+    }
+
+    overlay.visible = !overlay.visible;
+    if !overlay.visible {
+        for row_entity in &row_query {
+            commands.entity(row_entity).despawn();
+        }
+        overlay.sources.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_timer_debug_overlay(
+    mut commands: Commands,
+    app_config: Res<AppConfig>,
+    game_state: Res<GameState>,
+    diagnostics: Res<DiagnosticsStore>,
+    package_pool: Res<PackagePool>,
+    rapier_context: Res<RapierContext>,
+    package_body_query: Query<&RapierRigidBodyHandle, With<Package>>,
+    mut overlay: ResMut<TimerDebugOverlay>,
+    conveyor_query: Query<(Entity, &Conveyor, &ConveyorLabelTag)>,
+    supervisor_query: Query<(Entity, &Supervisor)>,
+    mut row_query: Query<(Entity, &TimerDebugRow, &mut Text)>,
+) {
+    // conveyors and supervisors can come and go with the layout, so the rows follow whatever
+    // exists rather than assuming a fixed set
+    let mut sources = vec![
+        TimerDebugSource::PackageWave,
+        TimerDebugSource::PackagePool,
+        TimerDebugSource::PackageSleep,
+    ];
+    sources.extend(
+        supervisor_query
+            .iter()
+            .map(|(supervisor_entity, _)| TimerDebugSource::Supervisor(supervisor_entity)),
+    );
+    sources.extend(
+        conveyor_query
+            .iter()
+            .map(|(conveyor_entity, _, _)| TimerDebugSource::Conveyor(conveyor_entity)),
+    );
+    sources.push(TimerDebugSource::FrameTime);
+    sources.extend((0..TIMED_SYSTEMS.len()).map(TimerDebugSource::SystemTime));
+
+    if sources != overlay.sources {
+        for (row_entity, _, _) in &row_query {
+            commands.entity(row_entity).despawn();
+        }
+        let top_left = Vec2::new(
+            -(app_config.base_resolution.x as f32 / 2.) + ROW_MARGIN,
+            (app_config.base_resolution.y as f32 / 2.) - ROW_MARGIN,
+        );
+        for (index, source) in sources.iter().enumerate() {
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: ROW_FONT_SIZE,
+                            color: Color::YELLOW,
+                            ..default()
+                        },
+                    ),
+                    text_anchor: Anchor::TopLeft,
+                    transform: Transform::from_translation(
+                        (top_left - Vec2::new(0., index as f32 * ROW_FONT_SIZE * 1.2)).extend(0.),
+                    ),
+                    ..default()
+                },
+                TimerDebugRow { source: *source },
+                RenderLayers::Single(EntityLayer::Debugging),
+            ));
+        }
+        overlay.sources = sources;
+        // new rows are filled in next frame once they exist
+        return;
+    }
+
+    let mut ranked_systems = TIMED_SYSTEMS
+        .iter()
+        .filter_map(|path| diagnostics.get(path))
+        .map(|diagnostic| (diagnostic, worst_recent_time(diagnostic)))
+        .collect::<Vec<_>>();
+    ranked_systems.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    for (_, row, mut text) in &mut row_query {
+        let row_text = match row.source {
+            TimerDebugSource::PackageWave => {
+                let wave_timer = &game_state.package_wave_timer;
+                format!(
+                    "wave: {:.1}s{}",
+                    wave_timer.remaining().as_secs_f32(),
+                    wave_timer
+                        .hold_reason()
+                        .map(|reason| format!(" (held: {:?})", reason))
+                        .unwrap_or_default()
+                )
+            }
+            TimerDebugSource::PackagePool => format!(
+                "package pool: {}/{} parked",
+                package_pool.parked(),
+                package_pool.capacity()
+            ),
+            TimerDebugSource::PackageSleep => {
+                let (asleep, awake) = package_body_query
+                    .iter()
+                    .filter_map(|body_handle| rapier_context.bodies.get(body_handle.0))
+                    .fold((0, 0), |(asleep, awake), body| {
+                        if body.is_sleeping() {
+                            (asleep + 1, awake)
+                        } else {
+                            (asleep, awake + 1)
+                        }
+                    });
+                format!("loose packages: {} asleep, {} awake", asleep, awake)
+            }
+            TimerDebugSource::Supervisor(supervisor_entity) => {
+                let Ok((_, supervisor)) = supervisor_query.get(supervisor_entity) else {
+                    continue;
+                };
+                format!(
+                    "supervisor {:?}: monitoring {}, distracted {}",
+                    supervisor_entity,
+                    describe_timer(&supervisor.monitoring_timer),
+                    describe_timer(&supervisor.distracted_timer)
+                )
+            }
+            TimerDebugSource::Conveyor(conveyor_entity) => {
+                let Ok((_, conveyor_info, conveyor_tag)) = conveyor_query.get(conveyor_entity)
+                else {
+                    continue;
+                };
+                format!(
+                    "{:?} {:?}: {:?}, active {}, idle {}, packages {}",
+                    conveyor_tag,
+                    conveyor_entity,
+                    conveyor_info.state,
+                    describe_timer(conveyor_info.active_timer()),
+                    describe_timer(conveyor_info.idle_timer()),
+                    conveyor_info.package_count
+                )
+            }
+            TimerDebugSource::FrameTime => {
+                let frame_time = diagnostics
+                    .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+                    .and_then(|diagnostic| diagnostic.smoothed())
+                    .unwrap_or_default();
+                let fps = diagnostics
+                    .get(&FrameTimeDiagnosticsPlugin::FPS)
+                    .and_then(|diagnostic| diagnostic.smoothed())
+                    .unwrap_or_default();
+                format!("frame: {:.2}ms ({:.0} fps)", frame_time, fps)
+            }
+            TimerDebugSource::SystemTime(rank) => {
+                let Some((diagnostic, worst)) = ranked_systems.get(rank) else {
+                    continue;
+                };
+                format!(
+                    "{}: {:.2}ms (worst {:.2}ms)",
+                    diagnostic.path().as_str(),
+                    diagnostic.value().unwrap_or_default(),
+                    worst
+                )
+            }
+        };
+        if text.sections[0].value != row_text {
+            text.sections[0].value = row_text;
+        }
+    }
+}
@@ -0,0 +1,601 @@
+use bevy::prelude::*;
+use bevy_rapier2d::{dynamics::RigidBody, geometry::Collider, geometry::Sensor};
+
+use crate::{
+    aimed_throw_direction, calculate_attach_point_on_conveyor, throw_distance, AimLockGlow,
+    AnimationData, AppConfig, BeltRegistry, Conveyor, ConveyorLabelTag, CursorAim, EntityLayer,
+    GameConfig, GameState, KeyAction, Package, Player, PlayerControls, PlayerIndex, RenderLayers,
+    Supervisor, PACKAGE_DENSITY, PACKAGE_LINEAR_DAMPING,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObstacleKind {
+    Wall,
+    Conveyor(ConveyorLabelTag),
+    Supervisor,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArenaObstacle {
+    pub entity: Entity,
+    pub bounds: Rect,
+    pub kind: ObstacleKind,
+}
+
+/// Axis aligned bounds of every fixed collider in the arena, cached so previews never have to
+/// query the physics world.
+#[derive(Resource, Default)]
+pub struct ArenaGeometry {
+    pub obstacles: Vec<ArenaObstacle>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PredictedImpact {
+    pub entity: Entity,
+    pub kind: ObstacleKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThrowPrediction {
+    pub start: Vec2,
+    pub unobstructed_end: Vec2,
+    pub end: Vec2,
+    pub impact: Option<PredictedImpact>,
+}
+
+#[derive(Component)]
+pub struct TrajectoryDot {
+    pub player_index: PlayerIndex,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct TrajectoryMarker {
+    pub player_index: PlayerIndex,
+}
+
+fn cuboid_bounds(transform: &Transform, collider: &Collider) -> Option<Rect> {
+    let half_extents = collider.as_cuboid()?.half_extents() * transform.scale.truncate();
+    let (axis, angle) = transform.rotation.to_axis_angle();
+    let angle = angle * axis.z.signum();
+    let (sin, cos) = (angle.sin().abs(), angle.cos().abs());
+    let rotated_half_extents = Vec2::new(
+        cos * half_extents.x + sin * half_extents.y,
+        sin * half_extents.x + cos * half_extents.y,
+    );
+    Some(Rect::from_center_half_size(
+        transform.translation.truncate(),
+        rotated_half_extents,
+    ))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn cache_arena_geometry(
+    mut arena_geometry: ResMut<ArenaGeometry>,
+    // walls and conveyors are top level entities, so their local transform is already in world
+    // space on the frame they are added
+    collider_query: Query<
+        (
+            Entity,
+            &RigidBody,
+            &Collider,
+            &Transform,
+            Option<&ConveyorLabelTag>,
+        ),
+        (Added<Collider>, Without<Sensor>),
+    >,
+    mut removed_colliders: RemovedComponents<Collider>,
+) {
+    // the arena is rebuilt when the tutorial ends
+    let removed = removed_colliders.read().collect::<Vec<_>>();
+    if !removed.is_empty() {
+        arena_geometry
+            .obstacles
+            .retain(|obstacle| !removed.contains(&obstacle.entity));
+    }
+
+    for (entity, rigid_body, collider, transform, conveyor_tag) in &collider_query {
+        if *rigid_body != RigidBody::Fixed {
+            continue;
+        }
+
+        if let Some(bounds) = cuboid_bounds(transform, collider) {
+            arena_geometry.obstacles.push(ArenaObstacle {
+                entity,
+                bounds,
+                kind: conveyor_tag.map_or(ObstacleKind::Wall, |tag| ObstacleKind::Conveyor(*tag)),
+            });
+        }
+    }
+}
+
+/// Distance along `delta` (as a fraction of it) at which a segment starting at `start` enters
+/// `bounds`, segments starting inside the bounds are ignored.
+fn segment_entry_fraction(start: Vec2, delta: Vec2, bounds: Rect) -> Option<f32> {
+    let mut entry = 0f32;
+    let mut exit = 1f32;
+    for axis in 0..2 {
+        if delta[axis].abs() <= f32::EPSILON {
+            if start[axis] < bounds.min[axis] || start[axis] > bounds.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let near = (bounds.min[axis] - start[axis]) / delta[axis];
+        let far = (bounds.max[axis] - start[axis]) / delta[axis];
+        entry = entry.max(near.min(far));
+        exit = exit.min(near.max(far));
+        if entry > exit {
+            return None;
+        }
+    }
+
+    (!bounds.contains(start)).then_some(entry)
+}
+
+/// Approximates the damped straight line a thrown package follows, the package's velocity decays
+/// exponentially so the total distance travelled is the initial velocity over the damping.
+pub fn predict_throw(
+    start: Vec2,
+    impulse: Vec2,
+    package_size: f32,
+    pixels_per_meter: f32,
+    obstacles: impl IntoIterator<Item = ArenaObstacle>,
+) -> ThrowPrediction {
+    let mass = PACKAGE_DENSITY * (package_size / pixels_per_meter).powi(2);
+    let travel = impulse / mass / PACKAGE_LINEAR_DAMPING;
+    let unobstructed_end = start + travel;
+
+    // sweeping the package's box is the same as sweeping its centre against obstacles grown by
+    // half its size
+    let package_half_size = Vec2::splat(package_size / 2.);
+    let nearest_impact = obstacles
+        .into_iter()
+        .filter_map(|obstacle| {
+            let grown_bounds = Rect::from_corners(
+                obstacle.bounds.min - package_half_size,
+                obstacle.bounds.max + package_half_size,
+            );
+            segment_entry_fraction(start, travel, grown_bounds).map(|fraction| (fraction, obstacle))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    match nearest_impact {
+        Some((fraction, obstacle)) => ThrowPrediction {
+            start,
+            unobstructed_end,
+            end: start + travel * fraction,
+            impact: Some(PredictedImpact {
+                entity: obstacle.entity,
+                kind: obstacle.kind,
+            }),
+        },
+        None => ThrowPrediction {
+            start,
+            unobstructed_end,
+            end: unobstructed_end,
+            impact: None,
+        },
+    }
+}
+
+/// What an assisted throw is worked out against.
+pub struct AimAssist {
+    pub cone_degrees: f32,
+    pub max_distance: f32,
+    /// Impulse behind a fully charged throw.
+    pub full_charge_impulse: f32,
+    /// Impulse it takes to carry a package one unit of distance.
+    pub impulse_per_distance: f32,
+    /// How far in front of the player a throw starts.
+    pub start_offset: f32,
+    /// Areas an assisted throw must never cross.
+    pub avoid: Vec<Rect>,
+}
+
+impl AimAssist {
+    pub fn new(app_config: &AppConfig, game_config: &GameConfig) -> Self {
+        let package_mass = PACKAGE_DENSITY
+            * (game_config.package_config.size / game_config.pixels_per_meter()).powi(2);
+        let half_resolution = app_config.base_resolution.as_vec2() / 2.;
+        let office_edge =
+            half_resolution.y - (game_config.supervisor_config.office_sprite_size.y as f32 / 2.);
+        Self {
+            cone_degrees: game_config.player_config.aim_assist_cone_degrees,
+            max_distance: game_config.player_config.aim_assist_max_distance,
+            full_charge_impulse: 1000. * game_config.player_config.throw_power,
+            impulse_per_distance: package_mass * PACKAGE_LINEAR_DAMPING,
+            start_offset: game_config.player_config.size / 2.,
+            avoid: vec![Rect::new(
+                -half_resolution.x,
+                office_edge,
+                half_resolution.x,
+                half_resolution.y,
+            )],
+        }
+    }
+
+    /// Mouse throws already go where they're pointed, only digital aim gets help.
+    pub fn applies_to(game_config: &GameConfig, player_controls: &PlayerControls) -> bool {
+        game_config.player_config.aim_assist
+            && !player_controls.triggered_by_mouse(KeyAction::PickupOrThrow)
+    }
+}
+
+/// Bends a throw towards `free_slot` on `belt` when the belt is inside the facing cone and in
+/// reach, stretching or shortening the charge just enough to bring the package down on the belt.
+/// Returns the direction and impulse to throw with, `None` to throw as aimed.
+pub fn assist_throw(
+    player_pos: Vec2,
+    facing: Vec2,
+    charge: f32,
+    belt: Rect,
+    free_slot: Vec2,
+    assist: &AimAssist,
+) -> Option<(Vec2, Vec2)> {
+    let to_slot = free_slot - player_pos;
+    if to_slot.length() > assist.max_distance {
+        return None;
+    }
+    let direction = to_slot.try_normalize()?;
+    let facing = facing.try_normalize()?;
+    let in_cone = |point: Vec2| {
+        (point - player_pos).try_normalize().is_none_or(|to_point| {
+            facing.angle_between(to_point).abs().to_degrees() <= assist.cone_degrees / 2.
+        })
+    };
+    if !in_cone(player_pos.clamp(belt.min, belt.max)) && !in_cone(free_slot) {
+        return None;
+    }
+
+    // where the throw line crosses into and back out of the belt
+    let start = player_pos + direction * assist.start_offset;
+    let reach = assist.max_distance + belt.size().length();
+    let entry = if belt.contains(start) {
+        0.
+    } else {
+        segment_entry_fraction(start, direction * reach, belt)? * reach
+    };
+    let far_end = start + direction * reach;
+    let exit = reach - segment_entry_fraction(far_end, -direction * reach, belt)? * reach;
+
+    let charged_travel = charge * assist.full_charge_impulse / assist.impulse_per_distance;
+    let travel = charged_travel.clamp(entry, exit.max(entry));
+    let crosses_avoided_area = assist.avoid.iter().any(|area| {
+        area.contains(start) || segment_entry_fraction(start, direction * travel, *area).is_some()
+    });
+    if crosses_avoided_area {
+        return None;
+    }
+
+    Some((direction, direction * travel * assist.impulse_per_distance))
+}
+
+/// The nearest of `own_belts` with room that an assisted throw locks on to, with the direction
+/// and impulse to throw with.
+pub fn find_aim_lock<'a>(
+    player_pos: Vec2,
+    facing: Vec2,
+    charge: f32,
+    own_belts: impl IntoIterator<Item = (Entity, &'a Conveyor, &'a GlobalTransform)>,
+    package_size: f32,
+    assist: &AimAssist,
+) -> Option<(Entity, Vec2, Vec2)> {
+    own_belts
+        .into_iter()
+        .filter(|(_, conveyor_info, _)| conveyor_info.has_free_capacity(package_size))
+        .filter_map(|(conveyor_entity, conveyor_info, conveyor_transform)| {
+            let belt = Rect::from_center_size(
+                conveyor_transform.translation().truncate(),
+                conveyor_info.belt_region,
+            );
+            let free_slot = conveyor_transform
+                .transform_point(
+                    calculate_attach_point_on_conveyor(conveyor_info, Vec2::ZERO, package_size)
+                        .extend(0.),
+                )
+                .truncate();
+            assist_throw(player_pos, facing, charge, belt, free_slot, assist).map(
+                |(direction, impulse)| {
+                    (
+                        conveyor_entity,
+                        direction,
+                        impulse,
+                        free_slot.distance_squared(player_pos),
+                    )
+                },
+            )
+        })
+        .min_by(|a, b| a.3.total_cmp(&b.3))
+        .map(|(conveyor_entity, direction, impulse, _)| (conveyor_entity, direction, impulse))
+}
+
+fn own_outgoing_belts<'a>(
+    belt_registry: &'a BeltRegistry,
+    player_index: PlayerIndex,
+    conveyor_query: &'a Query<(&Conveyor, &GlobalTransform)>,
+) -> Vec<(Entity, &'a Conveyor, &'a GlobalTransform)> {
+    belt_registry
+        .outgoing(player_index)
+        .iter()
+        .filter_map(|belt| {
+            conveyor_query
+                .get(*belt)
+                .ok()
+                .map(|(conveyor_info, conveyor_transform)| {
+                    (*belt, conveyor_info, conveyor_transform)
+                })
+        })
+        .collect()
+}
+
+/// Lights up the belt a charging throw has locked on to.
+#[allow(clippy::too_many_arguments)]
+pub fn update_aim_lock_glows(
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    cursor_aim: Res<CursorAim>,
+    belt_registry: Res<BeltRegistry>,
+    player_query: Query<(&Player, &AnimationData, &Transform, Option<&Children>)>,
+    package_query: Query<(), With<Package>>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform)>,
+    mut glow_query: Query<(&Parent, &mut Visibility), With<AimLockGlow>>,
+) {
+    let aim_assist = AimAssist::new(&app_config, &game_config);
+    let mut locked_belts = Vec::new();
+    for (player_info, player_anim_data, player_transform, player_children) in &player_query {
+        let player_controls = &game_state.player_controls[player_info.player_index];
+        let is_charging = player_controls.state[KeyAction::PickupOrThrow].pressed()
+            && player_info.throw_timer.fraction() > 0.;
+        let is_holding = player_children
+            .is_some_and(|children| children.iter().any(|child| package_query.contains(*child)));
+        if !is_charging || !is_holding || !AimAssist::applies_to(&game_config, player_controls) {
+            continue;
+        }
+
+        let player_pos = player_transform.translation.truncate();
+        let direction = aimed_throw_direction(
+            player_pos,
+            player_anim_data.facing_direction,
+            player_controls,
+            &cursor_aim,
+        );
+        if let Some((conveyor_entity, _, _)) = find_aim_lock(
+            player_pos,
+            direction,
+            player_info.throw_timer.fraction(),
+            own_outgoing_belts(&belt_registry, player_info.player_index, &conveyor_query),
+            game_config.package_config.size,
+            &aim_assist,
+        ) {
+            locked_belts.push(conveyor_entity);
+        }
+    }
+
+    for (glow_parent, mut glow_visibility) in &mut glow_query {
+        let visibility = if locked_belts.contains(&glow_parent.get()) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *glow_visibility != visibility {
+            *glow_visibility = visibility;
+        }
+    }
+}
+
+pub fn spawn_trajectory_previews(mut commands: Commands, game_config: Res<GameConfig>) {
+    let preview_config = &game_config.trajectory_preview_config;
+    for (player_index, player_settings) in game_config.player_config.per_player.iter() {
+        for index in 0..preview_config.dot_count {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: player_settings.colour.with_a(0.6),
+                        custom_size: Some(Vec2::splat(preview_config.dot_size)),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::TrajectoryPreview),
+                TrajectoryDot {
+                    player_index,
+                    index,
+                },
+            ));
+        }
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(game_config.package_config.size)),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            RenderLayers::Single(EntityLayer::TrajectoryPreview),
+            TrajectoryMarker { player_index },
+        ));
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_trajectory_previews(
+    game_state: Res<GameState>,
+    game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    cursor_aim: Res<CursorAim>,
+    arena_geometry: Res<ArenaGeometry>,
+    belt_registry: Res<BeltRegistry>,
+    player_query: Query<(&Player, &AnimationData, &Transform, Option<&Children>)>,
+    package_query: Query<(), With<Package>>,
+    conveyor_query: Query<(&Conveyor, &GlobalTransform)>,
+    supervisor_query: Query<(Entity, &Supervisor, &Transform, &Collider)>,
+    mut dot_query: Query<
+        (&TrajectoryDot, &mut Transform, &mut Visibility),
+        (
+            Without<Player>,
+            Without<Supervisor>,
+            Without<TrajectoryMarker>,
+        ),
+    >,
+    mut marker_query: Query<
+        (
+            &TrajectoryMarker,
+            &mut Sprite,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        (Without<Player>, Without<Supervisor>, Without<TrajectoryDot>),
+    >,
+) {
+    let preview_config = &game_config.trajectory_preview_config;
+    let package_size = game_config.package_config.size;
+
+    // a monitoring supervisor only gets in the way if it has something to collide with
+    let supervisor_obstacles = supervisor_query
+        .iter()
+        .filter(|(_, supervisor, _, _)| !supervisor.monitoring_timer.finished())
+        .filter_map(|(entity, _, transform, collider)| {
+            cuboid_bounds(transform, collider).map(|bounds| ArenaObstacle {
+                entity,
+                bounds,
+                kind: ObstacleKind::Supervisor,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let aim_assist = AimAssist::new(&app_config, &game_config);
+    let mut predictions = Vec::new();
+    if preview_config.enabled {
+        for (player_info, player_anim_data, player_transform, player_children) in &player_query {
+            let player_control_state = &game_state.player_controls[player_info.player_index].state;
+            let is_charging = player_control_state[KeyAction::PickupOrThrow].pressed()
+                && player_info.throw_timer.fraction() > 0.;
+            let is_holding = player_children.is_some_and(|children| {
+                children.iter().any(|child| package_query.contains(*child))
+            });
+            if !is_charging || !is_holding {
+                continue;
+            }
+
+            let player_pos = player_transform.translation.truncate();
+            let player_controls = &game_state.player_controls[player_info.player_index];
+            let mut direction = aimed_throw_direction(
+                player_pos,
+                player_anim_data.facing_direction,
+                player_controls,
+                &cursor_aim,
+            );
+            let mut impulse = direction * throw_distance(player_info, &game_config);
+            if AimAssist::applies_to(&game_config, player_controls) {
+                if let Some((_, assisted_direction, assisted_impulse)) = find_aim_lock(
+                    player_pos,
+                    direction,
+                    player_info.throw_timer.fraction(),
+                    own_outgoing_belts(&belt_registry, player_info.player_index, &conveyor_query),
+                    package_size,
+                    &aim_assist,
+                ) {
+                    direction = assisted_direction;
+                    impulse = assisted_impulse;
+                }
+            }
+            let start = player_pos + direction * (game_config.player_config.size / 2.);
+            let prediction = predict_throw(
+                start,
+                impulse,
+                package_size,
+                game_config.pixels_per_meter(),
+                arena_geometry
+                    .obstacles
+                    .iter()
+                    .chain(supervisor_obstacles.iter())
+                    .copied(),
+            );
+            predictions.push((player_info.player_index, prediction));
+        }
+    }
+
+    for (dot, mut dot_transform, mut dot_visibility) in &mut dot_query {
+        let Some((_, prediction)) = predictions
+            .iter()
+            .find(|(player_index, _)| *player_index == dot.player_index)
+        else {
+            *dot_visibility = Visibility::Hidden;
+            continue;
+        };
+
+        // dots are spaced along the unobstructed path so the trail visibly stops short at an
+        // impact
+        let full_path = prediction.unobstructed_end - prediction.start;
+        let dot_offset =
+            full_path * ((dot.index + 1) as f32 / (preview_config.dot_count + 1) as f32);
+        if dot_offset.length_squared() > (prediction.end - prediction.start).length_squared() {
+            *dot_visibility = Visibility::Hidden;
+            continue;
+        }
+
+        dot_transform.translation = (prediction.start + dot_offset).extend(0.);
+        *dot_visibility = Visibility::Inherited;
+    }
+
+    for (marker, mut marker_sprite, mut marker_transform, mut marker_visibility) in
+        &mut marker_query
+    {
+        let Some((_, prediction)) = predictions
+            .iter()
+            .find(|(player_index, _)| *player_index == marker.player_index)
+        else {
+            *marker_visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let mut marker_pos = prediction.end;
+        let mut snapped_to_slot = false;
+        marker_sprite.color = match prediction.impact.map(|impact| impact.kind) {
+            None => Color::WHITE,
+            Some(ObstacleKind::Wall) | Some(ObstacleKind::Conveyor(ConveyorLabelTag::Incoming)) => {
+                Color::GRAY
+            }
+            Some(ObstacleKind::Conveyor(ConveyorLabelTag::Outgoing(belt_owner))) => {
+                *game_config.player_config.per_player[belt_owner].colour
+            }
+            Some(ObstacleKind::Supervisor) => Color::RED,
+        };
+
+        // outgoing belts capture whatever lands on them, so show the slot the package will take
+        if let Some(PredictedImpact {
+            entity,
+            kind: ObstacleKind::Conveyor(ConveyorLabelTag::Outgoing(_)),
+        }) = prediction.impact
+        {
+            if let Ok((conveyor_info, conveyor_transform)) = conveyor_query.get(entity) {
+                let slot =
+                    calculate_attach_point_on_conveyor(conveyor_info, Vec2::ZERO, package_size);
+                marker_pos = conveyor_transform
+                    .transform_point(slot.extend(0.))
+                    .truncate();
+                snapped_to_slot = true;
+            }
+        }
+
+        // the damped motion approximation drifts over long throws, so widen the marker into a
+        // fuzzy zone rather than pretending to be precise
+        let travelled = prediction.start.distance(prediction.end);
+        let fuzziness = if snapped_to_slot {
+            0.
+        } else {
+            ((travelled - preview_config.fuzzy_distance) / preview_config.fuzzy_distance.max(1.))
+                .clamp(0., 1.)
+        };
+        marker_sprite.color = marker_sprite.color.with_a(0.8 - 0.5 * fuzziness);
+        marker_transform.scale = Vec3::splat(0.5 + fuzziness);
+        marker_transform.translation = marker_pos.extend(0.);
+        *marker_visibility = Visibility::Inherited;
+    }
+}
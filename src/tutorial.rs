@@ -0,0 +1,452 @@
+use bevy::{prelude::*, sprite::Anchor, text::Text2dBounds};
+
+use crate::{
+    key_label, load_conveyor, player_start_position, release_package, spawn_supervisors, AppConfig,
+    ArenaLayout, ControlBindings, Conveyor, ConveyorLabelTag, EntityLayer, GameConfig, GameState,
+    KeyAction, MatchState, Package, PackageDelivered, PackagePickedUp, PackagePool, PlannedWave,
+    Player, PlayerIndex, RenderLayers, ScoreChanged, SessionStarted, SpawnContext, StringKey,
+    StringTable, WallTag,
+};
+
+const ARROW_SIZE: f32 = 28.;
+const ARROW_BOB_SPEED: f32 = 5.;
+const ARROW_BOB_HEIGHT: f32 = 6.;
+
+/// What a new player is asked to do, in the order they're asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialStep {
+    WalkToIncomingBelt,
+    PickUpPackage,
+    ChargeThrow,
+    DeliverPackage,
+}
+
+pub const TUTORIAL_STEPS: [TutorialStep; 4] = [
+    TutorialStep::WalkToIncomingBelt,
+    TutorialStep::PickUpPackage,
+    TutorialStep::ChargeThrow,
+    TutorialStep::DeliverPackage,
+];
+
+impl TutorialStep {
+    fn instruction(&self, string_table: &StringTable, pickup_label: &str) -> String {
+        match self {
+            TutorialStep::WalkToIncomingBelt => {
+                string_table.tr(StringKey::TutorialWalkToBelt).to_string()
+            }
+            TutorialStep::PickUpPackage => format!(
+                "{}[{}]{}",
+                string_table.tr(StringKey::TutorialPickUpBefore),
+                pickup_label,
+                string_table.tr(StringKey::TutorialPickUpAfter)
+            ),
+            TutorialStep::ChargeThrow => format!(
+                "{}[{}]{}",
+                string_table.tr(StringKey::TutorialChargeBefore),
+                pickup_label,
+                string_table.tr(StringKey::TutorialChargeAfter)
+            ),
+            TutorialStep::DeliverPackage => string_table.tr(StringKey::TutorialDeliver).to_string(),
+        }
+    }
+}
+
+/// Only present while the tutorial is running, the normal game starts once it's removed.
+#[derive(Resource)]
+pub struct Tutorial {
+    /// Who the steps are checked against, the tutorial's belts are on their side.
+    pub player: PlayerIndex,
+    /// Index into `TUTORIAL_STEPS`, one past the end once every step is done.
+    step: usize,
+    /// Runs between a step being done and the next one being shown, and after the last step
+    /// until the normal game starts.
+    pause: Option<Timer>,
+}
+
+impl Tutorial {
+    pub fn new(player: PlayerIndex) -> Self {
+        Self {
+            player,
+            step: 0,
+            pause: None,
+        }
+    }
+
+    /// The step being checked, `None` while paused between steps or once they're all done.
+    pub fn current_step(&self) -> Option<TutorialStep> {
+        if self.pause.is_some() {
+            return None;
+        }
+        TUTORIAL_STEPS.get(self.step).copied()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= TUTORIAL_STEPS.len() && self.pause.is_none()
+    }
+}
+
+pub fn in_tutorial(tutorial: Option<Res<Tutorial>>) -> bool {
+    tutorial.is_some()
+}
+
+pub fn not_in_tutorial(tutorial: Option<Res<Tutorial>>) -> bool {
+    tutorial.is_none()
+}
+
+/// Everything on screen that only the tutorial uses, cleared away when it ends.
+#[derive(Component)]
+pub struct TutorialUi;
+
+#[derive(Component)]
+pub struct TutorialInstruction;
+
+/// Bobs above whatever the current step is about, pointing down at it.
+#[derive(Component)]
+pub struct TutorialArrow;
+
+pub fn setup_tutorial(
+    mut commands: Commands,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    string_table: Res<StringTable>,
+    tutorial: Res<Tutorial>,
+) {
+    info!("Starting the tutorial for {:?}", tutorial.player);
+
+    let resolution = app_config.base_resolution.as_vec2();
+    // where the supervisor's office would be, there's no supervisor to cover it
+    let instruction_y =
+        resolution.y / 2. - game_config.supervisor_config.office_sprite_size.y as f32 / 2.;
+    let text_z = EntityLayer::Overlay.z();
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            text_anchor: Anchor::Center,
+            text_2d_bounds: Text2dBounds {
+                size: Vec2::new(resolution.x * 0.8, resolution.y),
+            },
+            transform: Transform::from_translation(Vec3::new(0., instruction_y, text_z)),
+            ..default()
+        },
+        TutorialInstruction,
+        TutorialUi,
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                format!(
+                    "[{}]{}",
+                    key_label(app_config.tutorial.exit_key),
+                    string_table.tr(StringKey::TutorialExitHint)
+                ),
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::WHITE.with_a(0.7),
+                    ..default()
+                },
+            ),
+            text_anchor: Anchor::BottomCenter,
+            transform: Transform::from_translation(Vec3::new(0., -resolution.y / 2. + 8., text_z)),
+            ..default()
+        },
+        TutorialUi,
+    ));
+
+    let arrow_colour = *game_config.player_config.per_player[tutorial.player].colour;
+    commands
+        .spawn((
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TutorialArrow,
+            TutorialUi,
+        ))
+        .with_children(|builder| {
+            // a diamond half hidden behind the end of the shaft reads as the arrow's head
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: arrow_colour,
+                        custom_size: Some(Vec2::new(ARROW_SIZE * 0.3, ARROW_SIZE * 0.6)),
+                        anchor: Anchor::BottomCenter,
+                        ..default()
+                    },
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::Overlay),
+            ));
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: arrow_colour,
+                        custom_size: Some(Vec2::splat(ARROW_SIZE * 0.5)),
+                        ..default()
+                    },
+                    transform: Transform::from_rotation(Quat::from_rotation_z(
+                        std::f32::consts::FRAC_PI_4,
+                    )),
+                    ..default()
+                },
+                RenderLayers::Single(EntityLayer::Overlay),
+            ));
+        });
+}
+
+/// Keeps a package on the incoming belt whenever there isn't one anywhere else, standing in for
+/// the waves the tutorial doesn't have.
+pub fn feed_tutorial_belt(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_config: Res<GameConfig>,
+    tutorial: Res<Tutorial>,
+    mut conveyor_query: Query<(Entity, &mut Conveyor, &ConveyorLabelTag)>,
+    package_query: Query<(), With<Package>>,
+    mut package_pool: ResMut<PackagePool>,
+) {
+    if tutorial.step >= TUTORIAL_STEPS.len() || !package_query.is_empty() {
+        return;
+    }
+    let Some((conveyor_entity, mut conveyor_info, _)) = conveyor_query
+        .iter_mut()
+        .find(|(_, _, tag)| matches!(**tag, ConveyorLabelTag::Incoming))
+    else {
+        return;
+    };
+    load_conveyor(
+        &mut commands,
+        conveyor_entity,
+        &mut conveyor_info,
+        1,
+        None,
+        &mut package_pool,
+        &asset_server,
+        &game_config,
+    );
+}
+
+/// Sent when the tutorial player does what the step asked.
+#[derive(Event)]
+pub struct TutorialStepCompleted {
+    pub step: TutorialStep,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn check_tutorial_steps(
+    time: Res<Time>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    arena_layout: Res<ArenaLayout>,
+    mut tutorial: ResMut<Tutorial>,
+    player_query: Query<(&Player, &Transform)>,
+    mut pickup_events: EventReader<PackagePickedUp>,
+    mut delivered_events: EventReader<PackageDelivered>,
+    mut completed_events: EventWriter<TutorialStepCompleted>,
+) {
+    let player_index = tutorial.player;
+    // read every frame so nothing from an earlier step counts towards a later one
+    let picked_up = pickup_events
+        .read()
+        .any(|picked_up| picked_up.player == player_index);
+    let delivered = delivered_events
+        .read()
+        .any(|delivered| delivered.player == player_index);
+
+    if let Some(pause) = tutorial.pause.as_mut() {
+        pause.tick(time.delta());
+        if pause.finished() {
+            tutorial.pause = None;
+        }
+        return;
+    }
+    let Some(step) = tutorial.current_step() else {
+        return;
+    };
+
+    let Some((player_info, player_transform)) = player_query
+        .iter()
+        .find(|(player_info, _)| player_info.player_index == player_index)
+    else {
+        return;
+    };
+    let done = match step {
+        TutorialStep::WalkToIncomingBelt => {
+            let position = player_transform.translation.truncate();
+            let reach = game_config.player_config.size;
+            arena_layout
+                .conveyors
+                .iter()
+                .filter(|conveyor| matches!(conveyor.tag, ConveyorLabelTag::Incoming))
+                .any(|conveyor| {
+                    let bounds = conveyor.bounds(arena_layout.conveyor_width);
+                    position.clamp(bounds.min, bounds.max).distance(position) <= reach
+                })
+        }
+        TutorialStep::PickUpPackage => picked_up,
+        TutorialStep::ChargeThrow => {
+            player_info.throw_timer.fraction() >= app_config.tutorial.charge_fraction
+        }
+        TutorialStep::DeliverPackage => delivered,
+    };
+    if !done {
+        return;
+    }
+
+    info!("Tutorial step {:?} done", step);
+    completed_events.send(TutorialStepCompleted { step });
+    tutorial.step += 1;
+    let pause_seconds = if tutorial.step < TUTORIAL_STEPS.len() {
+        app_config.tutorial.step_pause_seconds
+    } else {
+        app_config.tutorial.finished_seconds
+    };
+    tutorial.pause = Some(Timer::from_seconds(pause_seconds, TimerMode::Once));
+}
+
+// the glyph is looked up every frame so a pad plugged in mid tutorial is shown straight away
+#[allow(clippy::too_many_arguments)]
+pub fn update_tutorial_guidance(
+    time: Res<Time>,
+    tutorial: Res<Tutorial>,
+    control_bindings: ControlBindings,
+    string_table: Res<StringTable>,
+    player_query: Query<(&Player, &GlobalTransform)>,
+    conveyor_query: Query<(&ConveyorLabelTag, &GlobalTransform), With<Conveyor>>,
+    package_query: Query<&GlobalTransform, With<Package>>,
+    mut instruction_query: Query<&mut Text, With<TutorialInstruction>>,
+    mut arrow_query: Query<(&mut Transform, &mut Visibility), With<TutorialArrow>>,
+) {
+    let player_position = player_query
+        .iter()
+        .find(|(player_info, _)| player_info.player_index == tutorial.player)
+        .map(|(_, player_transform)| player_transform.translation().truncate());
+    let belt_position = |wanted: ConveyorLabelTag| {
+        conveyor_query
+            .iter()
+            .find(|(tag, _)| **tag == wanted)
+            .map(|(_, conveyor_transform)| conveyor_transform.translation().truncate())
+    };
+
+    let step = tutorial.current_step();
+    let instruction = match step {
+        Some(step) => {
+            let pickup_glyph =
+                control_bindings.binding_display(KeyAction::PickupOrThrow, tutorial.player);
+            step.instruction(&string_table, &pickup_glyph.label())
+        }
+        None if tutorial.step >= TUTORIAL_STEPS.len() => {
+            string_table.tr(StringKey::TutorialFinished).to_string()
+        }
+        None => string_table.tr(StringKey::TutorialStepDone).to_string(),
+    };
+    for mut text in &mut instruction_query {
+        if let Some(section) = text.sections.first_mut() {
+            if section.value != instruction {
+                section.value.clone_from(&instruction);
+            }
+        }
+    }
+
+    let target = match step {
+        Some(TutorialStep::WalkToIncomingBelt) => belt_position(ConveyorLabelTag::Incoming),
+        Some(TutorialStep::PickUpPackage) => player_position.and_then(|player_position| {
+            package_query
+                .iter()
+                .map(|package_transform| package_transform.translation().truncate())
+                .min_by(|a, b| {
+                    a.distance_squared(player_position)
+                        .total_cmp(&b.distance_squared(player_position))
+                })
+        }),
+        Some(TutorialStep::ChargeThrow) => player_position,
+        Some(TutorialStep::DeliverPackage) => {
+            belt_position(ConveyorLabelTag::Outgoing(tutorial.player))
+        }
+        None => None,
+    };
+    for (mut arrow_transform, mut arrow_visibility) in &mut arrow_query {
+        let visibility = if target.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *arrow_visibility != visibility {
+            *arrow_visibility = visibility;
+        }
+        if let Some(target) = target {
+            let bob = (time.elapsed_seconds() * ARROW_BOB_SPEED).sin() * ARROW_BOB_HEIGHT;
+            arrow_transform.translation = (target + Vec2::new(0., ARROW_SIZE + bob)).extend(0.);
+        }
+    }
+}
+
+/// Tears down the tutorial's layout and starts a normal game, when the exit key is pressed or
+/// once the closing message has been up long enough.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn exit_tutorial(
+    mut commands: Commands,
+    mut ctx: SpawnContext,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    app_config: Res<AppConfig>,
+    tutorial: Res<Tutorial>,
+    match_state: Res<MatchState>,
+    mut game_state: ResMut<GameState>,
+    mut planned_wave: ResMut<PlannedWave>,
+    package_query: Query<Entity, With<Package>>,
+    arena_query: Query<Entity, Or<(With<Conveyor>, With<WallTag>, With<TutorialUi>)>>,
+    mut player_query: Query<(&Player, &mut Transform)>,
+    mut session_events: EventWriter<SessionStarted>,
+    mut score_events: EventWriter<ScoreChanged>,
+) {
+    let skipped = keyboard_input.just_pressed(app_config.tutorial.exit_key);
+    if !skipped && !tutorial.is_finished() {
+        return;
+    }
+    if skipped {
+        info!("Tutorial skipped, starting a normal game");
+    } else {
+        info!("Tutorial finished, starting a normal game");
+    }
+
+    // packages first, so the ones on a belt are taken out of its children before it goes
+    for package_entity in &package_query {
+        release_package(&mut commands, package_entity);
+    }
+    for entity in &arena_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    let arena_layout = ArenaLayout::new(&app_config, &ctx.game_config, &match_state.sides);
+    arena_layout.spawn(
+        &mut commands,
+        &mut ctx,
+        app_config.colour_palette.blinker_colours(),
+    );
+    commands.insert_resource(arena_layout);
+    spawn_supervisors(&mut commands, &mut ctx);
+
+    for (player_info, mut player_transform) in &mut player_query {
+        player_transform.translation = player_start_position(
+            match_state.sides[player_info.player_index],
+            &app_config,
+            &ctx.game_config,
+        );
+    }
+    game_state.player_scores = default();
+    for (player_index, _) in game_state.player_scores.iter() {
+        score_events.send(ScoreChanged {
+            player: player_index,
+        });
+    }
+    planned_wave.counts = None;
+    game_state.package_wave_timer.begin_countdown();
+    session_events.send(SessionStarted);
+    commands.remove_resource::<Tutorial>();
+}
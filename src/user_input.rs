@@ -1,7 +1,12 @@
-use crate::{GameConfig, GameState, KeyAction, KeyBind};
+use crate::{
+    AppConfig, AxisDirection, CalibrationConfig, GameConfig, GameState, KeyAction, KeyBind,
+    MainCamera, PadAxisFallback, PadCalibration, PadFallbackApplied, Player,
+};
 use bevy::{
     input::gamepad::{GamepadConnection, GamepadEvent},
     prelude::*,
+    utils::HashSet,
+    window::{PrimaryWindow, WindowFocused},
 };
 use enum_map::{enum_map, EnumMap};
 
@@ -11,10 +16,118 @@ pub struct ButtonState {
     pub state_changed_this_frame: bool,
 }
 
+/// One frame's reading of every control bound to an action.
+#[derive(Default, Clone, Copy)]
+pub struct BindSample {
+    pub held: bool,
+    /// Went down this frame, which may be true even when `held` isn't if it was only tapped.
+    pub just_pressed: bool,
+    pub by_mouse: bool,
+}
+
+/// Everything seen of an action since the last fixed tick. Input is sampled every frame but
+/// consumed once a tick, so a tap shorter than a tick still lands and a frame that runs several
+/// ticks doesn't hand the same edge to each of them.
+#[derive(Default, Clone, Copy)]
+pub struct InputAccumulator {
+    /// Held as of the latest sample.
+    pub held: bool,
+    pub pressed_since_tick: bool,
+    pub released_since_tick: bool,
+    pub by_mouse_since_tick: bool,
+}
+
+impl InputAccumulator {
+    pub fn record(&mut self, sample: BindSample) {
+        if !self.held && (sample.held || sample.just_pressed) {
+            self.pressed_since_tick = true;
+        }
+        if (self.held || sample.just_pressed) && !sample.held {
+            self.released_since_tick = true;
+        }
+        self.held = sample.held;
+        self.by_mouse_since_tick |= sample.by_mouse;
+    }
+
+    /// Settles this tick's state from the last one. A press and release between two ticks comes
+    /// out as a press now and the release on the following tick.
+    pub fn consume(&mut self, previous: ButtonState) -> ButtonState {
+        let pressed = if !previous.pressed && self.pressed_since_tick {
+            true
+        } else if previous.pressed && self.released_since_tick {
+            false
+        } else {
+            self.held
+        };
+        self.pressed_since_tick = false;
+        self.released_since_tick = false;
+        self.by_mouse_since_tick = false;
+        ButtonState {
+            pressed,
+            state_changed_this_frame: pressed != previous.pressed,
+        }
+    }
+}
+
+/// What a connected pad has shown it can do, used to swap in fallback buttons for axes it lacks.
+#[derive(Default)]
+pub struct PadCapabilities {
+    pub seen_axes: HashSet<GamepadAxisType>,
+    pub seen_buttons: HashSet<GamepadButtonType>,
+    pub announced_substitutions: HashSet<GamepadAxisType>,
+}
+
 #[derive(Default)]
 pub struct PlayerControls {
     pub pad: Option<Gamepad>,
+    pub pad_capabilities: PadCapabilities,
     pub state: EnumMap<KeyAction, ButtonState>,
+    pub input_accumulators: EnumMap<KeyAction, InputAccumulator>,
+    pub seconds_since_input: f32,
+    /// Actions whose button was down when the window lost focus, they read as released until the
+    /// button comes back up so only a fresh press counts.
+    pub held_since_focus_loss: EnumMap<KeyAction, bool>,
+    /// Whether each action's latest press came from a mouse button, kept through the release.
+    pub pressed_by_mouse: EnumMap<KeyAction, bool>,
+    /// Gone long enough without input to be treated as away from the game.
+    pub afk: bool,
+}
+
+impl PlayerControls {
+    pub fn triggered_by_mouse(&self, key_action: KeyAction) -> bool {
+        self.pressed_by_mouse[key_action]
+    }
+}
+
+/// Where the mouse points in the world, None while it's outside the window or over the office
+/// where nothing can be aimed at.
+#[derive(Resource, Default)]
+pub struct CursorAim {
+    pub world_position: Option<Vec2>,
+}
+
+impl PadCapabilities {
+    // an axis that rests centred never reports, so only treat it as missing once the pad has
+    // used the fallback button instead
+    pub fn fallback_for(
+        &self,
+        axis: GamepadAxisType,
+        direction: AxisDirection,
+        fallbacks: &[PadAxisFallback],
+    ) -> Option<GamepadButtonType> {
+        if self.seen_axes.contains(&axis) {
+            return None;
+        }
+
+        fallbacks
+            .iter()
+            .find(|fallback| {
+                fallback.axis == axis
+                    && fallback.direction == direction
+                    && self.seen_buttons.contains(&fallback.button)
+            })
+            .map(|fallback| fallback.button)
+    }
 }
 
 impl ButtonState {
@@ -33,78 +146,169 @@ impl ButtonState {
 
 pub fn gamepad_connected(
     mut game_state: ResMut<GameState>,
+    game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
     mut gamepad_event: EventReader<GamepadEvent>,
 ) {
+    const AXIS_SEEN_THRESHOLD: f32 = 0.2;
+
     for event in gamepad_event.read() {
         match event {
             GamepadEvent::Connection(connection_event) => match connection_event.connection {
-                GamepadConnection::Connected(_) => {
-                    if let Some((_, player_control)) = game_state
+                GamepadConnection::Connected(ref gamepad_info) => {
+                    if app_config
+                        .calibration
+                        .for_pad(Some(&gamepad_info.name), connection_event.gamepad.id)
+                        .is_none()
+                    {
+                        info!(
+                            "pad {} ({}) hasn't been calibrated, using the default deadzone",
+                            gamepad_info.name, connection_event.gamepad.id
+                        );
+                    }
+                    if game_config.player_config.is_pad_shared() {
+                        // one pad drives both players, so it's only taken while neither has one
+                        if game_state
+                            .player_controls
+                            .values()
+                            .all(|player_control| player_control.pad.is_none())
+                        {
+                            for (_, player_control) in game_state.player_controls.iter_mut() {
+                                player_control.pad = Some(connection_event.gamepad);
+                                player_control.pad_capabilities = PadCapabilities::default();
+                            }
+                        }
+                    } else if let Some((_, player_control)) = game_state
                         .player_controls
                         .iter_mut()
                         .find(|(_, player_control)| player_control.pad.is_none())
                     {
                         player_control.pad = Some(connection_event.gamepad);
+                        player_control.pad_capabilities = PadCapabilities::default();
                     }
                 }
                 GamepadConnection::Disconnected => {
-                    if let Some((_, player_control)) =
+                    // a shared pad suspends everyone using it
+                    for (_, player_control) in
                         game_state
                             .player_controls
                             .iter_mut()
-                            .find(|(_, player_control)| {
+                            .filter(|(_, player_control)| {
                                 player_control
                                     .pad
-                                    .map_or(false, |p| p.id == connection_event.gamepad.id)
+                                    .is_some_and(|p| p.id == connection_event.gamepad.id)
                             })
                     {
                         player_control.pad = None;
+                        player_control.pad_capabilities = PadCapabilities::default();
                     }
                 }
             },
-            _ => {}
+            GamepadEvent::Axis(axis_event) => {
+                if axis_event.value.abs() < AXIS_SEEN_THRESHOLD {
+                    continue;
+                }
+
+                for (player_index, player_control) in game_state.player_controls.iter_mut() {
+                    if player_control.pad != Some(axis_event.gamepad) {
+                        continue;
+                    }
+
+                    let capabilities = &mut player_control.pad_capabilities;
+                    if capabilities.seen_axes.insert(axis_event.axis_type)
+                        && capabilities
+                            .announced_substitutions
+                            .remove(&axis_event.axis_type)
+                    {
+                        info!(
+                            "{:?} pad reported {:?}, no longer using a fallback button for it",
+                            player_index, axis_event.axis_type
+                        );
+                    }
+                }
+            }
+            GamepadEvent::Button(button_event) => {
+                for (_, player_control) in game_state.player_controls.iter_mut() {
+                    if player_control.pad == Some(button_event.gamepad) {
+                        player_control
+                            .pad_capabilities
+                            .seen_buttons
+                            .insert(button_event.button_type);
+                    }
+                }
+            }
         }
     }
 }
 
-pub fn update_controller_mappings(
+/// Samples every bound control once a frame, straight after bevy refreshes its input, and folds
+/// what it sees into each action's accumulator for the next fixed tick to consume.
+#[allow(clippy::too_many_arguments)]
+pub fn sample_controller_input(
     mut game_state: ResMut<GameState>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
     gamepad_buttons: Res<ButtonInput<GamepadButton>>,
     gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
     game_config: Res<GameConfig>,
+    app_config: Res<AppConfig>,
+    mut fallback_events: EventWriter<PadFallbackApplied>,
 ) {
     const GAMEPAD_AXIS_THRESHOLD: f32 = 0.5;
 
     for (player_index, player_control) in game_state.player_controls.iter_mut() {
-        fn write_button_state(
+        #[allow(clippy::too_many_arguments)]
+        fn sample_bind(
             keybind: &KeyBind,
-            button_state: &mut ButtonState,
+            bind_sample: &mut BindSample,
             keyboard_input: &Res<ButtonInput<KeyCode>>,
+            mouse_input: &Res<ButtonInput<MouseButton>>,
             gamepad_buttons: &Res<ButtonInput<GamepadButton>>,
             gamepad_axes: &Res<Axis<GamepadAxis>>,
             pad: Option<Gamepad>,
+            axis_fallback: Option<GamepadButtonType>,
+            calibration: &CalibrationConfig,
+            pad_calibration: Option<&PadCalibration>,
         ) {
             match keybind {
                 crate::KeyBind::Key(key_code) => {
-                    button_state.pressed |= keyboard_input.pressed(*key_code);
+                    bind_sample.held |= keyboard_input.pressed(*key_code);
+                    bind_sample.just_pressed |= keyboard_input.just_pressed(*key_code);
+                }
+                crate::KeyBind::MouseButton(mouse_button) => {
+                    let by_mouse = mouse_input.pressed(*mouse_button)
+                        || mouse_input.just_pressed(*mouse_button);
+                    bind_sample.held |= mouse_input.pressed(*mouse_button);
+                    bind_sample.just_pressed |= mouse_input.just_pressed(*mouse_button);
+                    bind_sample.by_mouse |= by_mouse;
                 }
                 crate::KeyBind::ControllerButton(pad_button) => {
                     if let Some(pad) = pad {
-                        button_state.pressed |= gamepad_buttons.pressed(GamepadButton {
+                        let pad_button = GamepadButton {
                             gamepad: pad,
                             button_type: *pad_button,
-                        });
+                        };
+                        bind_sample.held |= gamepad_buttons.pressed(pad_button);
+                        bind_sample.just_pressed |= gamepad_buttons.just_pressed(pad_button);
                     }
                 }
                 crate::KeyBind::ControllerAxis((pad_axis, axis_direction)) => {
-                    if let Some(pad) = pad {
-                        button_state.pressed |= gamepad_axes
+                    if let (Some(pad), Some(fallback_button)) = (pad, axis_fallback) {
+                        let pad_button = GamepadButton {
+                            gamepad: pad,
+                            button_type: fallback_button,
+                        };
+                        bind_sample.held |= gamepad_buttons.pressed(pad_button);
+                        bind_sample.just_pressed |= gamepad_buttons.just_pressed(pad_button);
+                    } else if let Some(pad) = pad {
+                        bind_sample.held |= gamepad_axes
                             .get(GamepadAxis {
                                 gamepad: pad,
                                 axis_type: *pad_axis,
                             })
-                            .map_or(false, |v| match axis_direction {
+                            .map(|v| calibration.apply(pad_calibration, *pad_axis, v))
+                            .is_some_and(|v| match axis_direction {
                                 crate::AxisDirection::Positive => v > GAMEPAD_AXIS_THRESHOLD,
                                 crate::AxisDirection::Negative => v < -GAMEPAD_AXIS_THRESHOLD,
                             });
@@ -113,61 +317,545 @@ pub fn update_controller_mappings(
             }
         }
 
-        let prev_control_state = player_control.state.clone();
-        let mut new_control_state = enum_map! {
-            KeyAction::MoveUp => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-            KeyAction::MoveDown => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-            KeyAction::MoveLeft => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-            KeyAction::MoveRight => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-            KeyAction::Sprint => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-            KeyAction::PickupOrThrow => ButtonState {
-                pressed: false,
-                state_changed_this_frame: false,
-            },
-        };
-
         let key_mapping = game_config.get_key_map(player_index);
         let pad = player_control.pad;
+        let pad_calibration =
+            pad.and_then(|pad| app_config.calibration.for_pad(gamepads.name(pad), pad.id));
+
+        let mut resolve_axis_fallback = |keybind: &KeyBind, key_action: &KeyAction| {
+            let KeyBind::ControllerAxis((pad_axis, axis_direction)) = keybind else {
+                return None;
+            };
+            let fallback_button = player_control.pad_capabilities.fallback_for(
+                *pad_axis,
+                *axis_direction,
+                &game_config.pad_axis_fallbacks,
+            )?;
+            if player_control
+                .pad_capabilities
+                .announced_substitutions
+                .insert(*pad_axis)
+            {
+                info!(
+                    "{:?} pad has not reported {:?}, using {:?} for {:?} instead",
+                    player_index, pad_axis, fallback_button, key_action
+                );
+                fallback_events.send(PadFallbackApplied {
+                    player: player_index,
+                    action: key_action.clone(),
+                    axis: *pad_axis,
+                    direction: *axis_direction,
+                    button: fallback_button,
+                });
+            }
+            Some(fallback_button)
+        };
 
         for (key_action, key_bind) in key_mapping {
-            let new_button_state = &mut new_control_state[key_action.clone()];
-            write_button_state(
-                &key_bind.priamry,
-                new_button_state,
-                &keyboard_input,
-                &gamepad_buttons,
-                &gamepad_axes,
-                pad,
-            );
+            let mut bind_sample = BindSample::default();
+            for bind in [&key_bind.priamry, &key_bind.secondary] {
+                sample_bind(
+                    bind,
+                    &mut bind_sample,
+                    &keyboard_input,
+                    &mouse_input,
+                    &gamepad_buttons,
+                    &gamepad_axes,
+                    pad,
+                    resolve_axis_fallback(bind, &key_action),
+                    &app_config.calibration,
+                    pad_calibration,
+                );
+            }
+            player_control.input_accumulators[key_action].record(bind_sample);
+        }
+    }
+}
 
-            write_button_state(
-                &key_bind.secondary,
-                new_button_state,
-                &keyboard_input,
-                &gamepad_buttons,
-                &gamepad_axes,
-                pad,
-            );
+/// Turns the input gathered since the last tick into this tick's control state, edges are
+/// worked out here and nowhere else so each one is seen by exactly one tick.
+pub fn update_controller_mappings(mut game_state: ResMut<GameState>, time: Res<Time>) {
+    for (_, player_control) in game_state.player_controls.iter_mut() {
+        let prev_control_state = player_control.state;
+        let mut new_control_state = EnumMap::<KeyAction, ButtonState>::default();
+
+        for (key_action, accumulator) in player_control.input_accumulators.iter_mut() {
+            let by_mouse = accumulator.by_mouse_since_tick;
+            let mut new_button_state = accumulator.consume(prev_control_state[key_action.clone()]);
+
+            let held_since_focus_loss =
+                &mut player_control.held_since_focus_loss[key_action.clone()];
+            if *held_since_focus_loss {
+                *held_since_focus_loss = accumulator.held;
+                new_button_state.pressed = false;
+            }
 
             new_button_state.state_changed_this_frame =
-                new_button_state.pressed != prev_control_state[key_action].pressed;
+                new_button_state.pressed != prev_control_state[key_action.clone()].pressed;
+
+            if new_button_state.just_pressed() {
+                player_control.pressed_by_mouse[key_action.clone()] = by_mouse;
+            }
+            new_control_state[key_action] = new_button_state;
+        }
+
+        // any press or release counts as input, holding a key down does not
+        if new_control_state
+            .values()
+            .any(|button_state| button_state.state_changed_this_frame)
+        {
+            player_control.seconds_since_input = 0.;
+        } else {
+            player_control.seconds_since_input += time.delta_seconds();
         }
 
         player_control.state = new_control_state;
     }
 }
+
+/// Input that changes while the window is unfocused never reaches us, so everything is released on
+/// focus loss without flagging a change, a charged throw is dropped rather than fired, and nothing
+/// counts again until it's pressed afresh.
+pub fn handle_window_focus(
+    mut focus_events: EventReader<WindowFocused>,
+    mut game_state: ResMut<GameState>,
+    mut player_query: Query<&mut Player>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    app_config: Res<AppConfig>,
+    mut paused_by_focus_loss: Local<bool>,
+) {
+    let Some(focus_event) = focus_events.read().last() else {
+        return;
+    };
+
+    if focus_event.focused {
+        if *paused_by_focus_loss {
+            *paused_by_focus_loss = false;
+            virtual_time.unpause();
+            info!("Window focused, resuming");
+        }
+        return;
+    }
+
+    for (_, player_control) in game_state.player_controls.iter_mut() {
+        player_control.state = default();
+        player_control.input_accumulators = default();
+        player_control.held_since_focus_loss = enum_map! { _ => true };
+    }
+    for mut player_info in &mut player_query {
+        player_info.throw_timer.reset();
+    }
+
+    if app_config.pause_on_focus_loss && !virtual_time.is_paused() {
+        *paused_by_focus_loss = true;
+        virtual_time.pause();
+        info!("Window lost focus, pausing");
+    }
+}
+
+pub fn update_cursor_aim(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    app_config: Res<AppConfig>,
+    game_config: Res<GameConfig>,
+    mut cursor_aim: ResMut<CursorAim>,
+) {
+    let office_edge = (app_config.base_resolution.y as f32 / 2.)
+        - (game_config.supervisor_config.office_sprite_size.y as f32 / 2.);
+    let world_position = window_query
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .and_then(|cursor_position| {
+            // with split screen the cursor belongs to whichever half it's over
+            camera_query.iter().find_map(|(camera, camera_transform)| {
+                let viewport_rect = camera.logical_viewport_rect()?;
+                viewport_rect.contains(cursor_position).then(|| {
+                    camera
+                        .viewport_to_world_2d(camera_transform, cursor_position - viewport_rect.min)
+                })?
+            })
+        })
+        .filter(|world_position| world_position.y < office_edge);
+
+    if cursor_aim.world_position != world_position {
+        cursor_aim.world_position = world_position;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        input::{
+            gamepad::{
+                GamepadAxisChangedEvent, GamepadAxisType, GamepadButtonChangedEvent,
+                GamepadButtonType, GamepadConnectionEvent, GamepadInfo,
+            },
+            InputPlugin, InputSystem,
+        },
+        time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+    use crate::{player_charge_throw, KeyBindConfig, MoveAxis, Package, PadSide, PlayerIndex};
+
+    const TICK_SECONDS: f64 = 1. / 16.;
+
+    #[derive(Resource, Default)]
+    struct Presses(u32);
+
+    fn count_presses(game_state: Res<GameState>, mut presses: ResMut<Presses>) {
+        if game_state.player_controls[PlayerIndex::Player1].state[KeyAction::PickupOrThrow]
+            .just_pressed()
+        {
+            presses.0 += 1;
+        }
+    }
+
+    /// Consumes input on fixed ticks the way the game does, with each frame's length set by hand.
+    fn tick_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(bevy::time::TimePlugin)
+            .insert_resource(Time::<Fixed>::from_seconds(TICK_SECONDS))
+            .insert_resource(GameState::from_config(&GameConfig::default()))
+            .init_resource::<Presses>()
+            .add_systems(
+                FixedUpdate,
+                (update_controller_mappings, count_presses).chain(),
+            );
+        // the first update only starts the clock
+        app.update();
+        app
+    }
+
+    /// Samples pickup as `held` for one frame lasting `ticks` fixed ticks, which needn't be whole.
+    fn run_frame(app: &mut App, held: bool, ticks: f64) {
+        let accumulator = &mut app.world.resource_mut::<GameState>().player_controls
+            [PlayerIndex::Player1]
+            .input_accumulators[KeyAction::PickupOrThrow];
+        let just_pressed = held && !accumulator.held;
+        accumulator.record(BindSample {
+            held,
+            just_pressed,
+            by_mouse: false,
+        });
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            TICK_SECONDS * ticks,
+        )));
+        app.update();
+    }
+
+    fn presses(app: &App) -> u32 {
+        app.world.resource::<Presses>().0
+    }
+
+    #[test]
+    fn tap_between_ticks_lands_once() {
+        let mut app = tick_app();
+        run_frame(&mut app, true, 0.5);
+        run_frame(&mut app, false, 0.5);
+        for _ in 0..4 {
+            run_frame(&mut app, false, 0.5);
+        }
+        assert_eq!(presses(&app), 1);
+    }
+
+    #[test]
+    fn press_is_seen_once_however_many_ticks_a_frame_runs() {
+        let mut app = tick_app();
+        run_frame(&mut app, true, 2.);
+        assert_eq!(presses(&app), 1);
+        run_frame(&mut app, true, 0.5);
+        run_frame(&mut app, true, 2.);
+        run_frame(&mut app, false, 0.5);
+        run_frame(&mut app, false, 2.);
+        assert_eq!(presses(&app), 1);
+
+        // down and up again within frames that run no tick at all
+        run_frame(&mut app, true, 0.25);
+        run_frame(&mut app, false, 0.25);
+        run_frame(&mut app, false, 2.);
+        assert_eq!(presses(&app), 2);
+    }
+
+    /// Holds pickup with a package in hand for `ticks` whole ticks from the moment it was picked
+    /// up, then lets go. Returns the throw's charge and whether the release gets past the pickup
+    /// cooldown to throw it.
+    fn release_after_hold(ticks: u32) -> (f32, bool) {
+        let mut app = tick_app();
+        app.add_systems(
+            FixedUpdate,
+            player_charge_throw.after(update_controller_mappings),
+        );
+        let player_entity = app
+            .world
+            .spawn(Player {
+                pickup_cooldown_timer: Timer::from_seconds(
+                    GameConfig::default().player_config.pickup_cooldown_seconds,
+                    TimerMode::Once,
+                ),
+                throw_timer: Timer::from_seconds(1., TimerMode::Once),
+                player_index: PlayerIndex::Player1,
+                last_pressed_axis: MoveAxis::Horizontal,
+                move_velocity: Vec2::ZERO,
+            })
+            .with_children(|builder| {
+                builder.spawn(Package);
+            })
+            .id();
+
+        for _ in 0..ticks {
+            run_frame(&mut app, true, 1.);
+        }
+        run_frame(&mut app, false, 1.);
+        let released = app.world.resource::<GameState>().player_controls[PlayerIndex::Player1]
+            .state[KeyAction::PickupOrThrow]
+            .just_released();
+        assert!(released);
+        let player_info = app.world.get::<Player>(player_entity).unwrap();
+        (
+            player_info.throw_timer.fraction(),
+            player_info.pickup_cooldown_timer.finished(),
+        )
+    }
+
+    #[test]
+    fn throw_charge_matches_how_long_pickup_was_held() {
+        // inside the pickup cooldown the release is ignored and the package stays in hand
+        let (_, throws) = release_after_hold(3);
+        assert!(!throws);
+
+        // just past it, with the charge built during the cooldown kept
+        let (charge, throws) = release_after_hold(6);
+        assert!(throws);
+        assert_eq!(charge, (6. * TICK_SECONDS) as f32);
+
+        let (charge, throws) = release_after_hold(24);
+        assert!(throws);
+        assert_eq!(charge, 1.);
+    }
+
+    /// Everything on one half of the pad, the stick and d-pad on the left, the other stick and
+    /// the face buttons on the right.
+    fn shared_pad_key_map(side: PadSide) -> EnumMap<KeyAction, KeyBindConfig> {
+        use GamepadButtonType::*;
+        let (stick_x, stick_y, [up, down, left, right], [sprint, pickup, drop, interact], key) =
+            match side {
+                PadSide::Left => (
+                    GamepadAxisType::LeftStickX,
+                    GamepadAxisType::LeftStickY,
+                    [DPadUp, DPadDown, DPadLeft, DPadRight],
+                    [LeftTrigger, LeftTrigger2, LeftThumb, Select],
+                    KeyCode::KeyQ,
+                ),
+                PadSide::Right => (
+                    GamepadAxisType::RightStickX,
+                    GamepadAxisType::RightStickY,
+                    [North, South, West, East],
+                    [RightTrigger, RightTrigger2, RightThumb, Start],
+                    KeyCode::KeyP,
+                ),
+            };
+        let axis = |axis_type, direction| KeyBind::ControllerAxis((axis_type, direction));
+        let bind = |priamry, secondary| KeyBindConfig { priamry, secondary };
+        let button = |button_type| bind(KeyBind::ControllerButton(button_type), KeyBind::Key(key));
+        enum_map! {
+            KeyAction::MoveUp => bind(axis(stick_y, AxisDirection::Positive), KeyBind::ControllerButton(up)),
+            KeyAction::MoveDown => bind(axis(stick_y, AxisDirection::Negative), KeyBind::ControllerButton(down)),
+            KeyAction::MoveLeft => bind(axis(stick_x, AxisDirection::Negative), KeyBind::ControllerButton(left)),
+            KeyAction::MoveRight => bind(axis(stick_x, AxisDirection::Positive), KeyBind::ControllerButton(right)),
+            KeyAction::Sprint => button(sprint),
+            KeyAction::PickupOrThrow => button(pickup),
+            KeyAction::Drop => button(drop),
+            KeyAction::Interact => button(interact),
+            KeyAction::Emote => bind(KeyBind::Key(key), KeyBind::Key(key)),
+        }
+    }
+
+    fn shared_pad_config(player_one: Option<PadSide>, player_two: Option<PadSide>) -> GameConfig {
+        let mut game_config = GameConfig::default();
+        for (player_index, pad_share) in [
+            (PlayerIndex::Player1, player_one),
+            (PlayerIndex::Player2, player_two),
+        ] {
+            let per_player = &mut game_config.player_config.per_player[player_index];
+            per_player.pad_share = pad_share;
+            per_player.key_map = shared_pad_key_map(pad_share.unwrap_or(PadSide::Left));
+        }
+        game_config
+    }
+
+    #[test]
+    fn shared_pads_need_complementary_sides_and_controls() {
+        let shared = shared_pad_config(Some(PadSide::Left), Some(PadSide::Right));
+        assert!(shared.player_config.validate().is_ok());
+        assert!(shared.player_config.is_pad_shared());
+
+        for (player_one, player_two) in [
+            (Some(PadSide::Left), Some(PadSide::Left)),
+            (Some(PadSide::Right), Some(PadSide::Right)),
+            (Some(PadSide::Left), None),
+            (None, Some(PadSide::Right)),
+        ] {
+            let game_config = shared_pad_config(player_one, player_two);
+            assert!(
+                game_config.player_config.validate().is_err(),
+                "{:?} and {:?}",
+                player_one,
+                player_two
+            );
+        }
+
+        // the right side borrowing one button from the left
+        let mut overlapping = shared_pad_config(Some(PadSide::Left), Some(PadSide::Right));
+        overlapping.player_config.per_player[PlayerIndex::Player2].key_map[KeyAction::Drop]
+            .secondary = KeyBind::ControllerButton(GamepadButtonType::LeftThumb);
+        assert!(overlapping.player_config.validate().is_err());
+        // or the left stick, whichever way it's pushed
+        let mut overlapping = shared_pad_config(Some(PadSide::Left), Some(PadSide::Right));
+        overlapping.player_config.per_player[PlayerIndex::Player2].key_map[KeyAction::Sprint]
+            .secondary =
+            KeyBind::ControllerAxis((GamepadAxisType::LeftStickY, AxisDirection::Negative));
+        assert!(overlapping.player_config.validate().is_err());
+    }
+
+    const SHARED_PAD: Gamepad = Gamepad { id: 0 };
+
+    /// Samples a real pad's events each frame and runs one fixed tick per frame.
+    fn shared_pad_app() -> App {
+        let game_config = shared_pad_config(Some(PadSide::Left), Some(PadSide::Right));
+        let mut app = App::new();
+        app.add_plugins((bevy::time::TimePlugin, InputPlugin))
+            .insert_resource(Time::<Fixed>::from_seconds(TICK_SECONDS))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                TICK_SECONDS,
+            )))
+            .insert_resource(GameState::from_config(&game_config))
+            .insert_resource(game_config)
+            .insert_resource(AppConfig::default())
+            .add_event::<PadFallbackApplied>()
+            .add_systems(PreUpdate, sample_controller_input.after(InputSystem))
+            .add_systems(
+                FixedUpdate,
+                (gamepad_connected, update_controller_mappings).chain(),
+            );
+        app.update();
+        app
+    }
+
+    fn pad_event(app: &mut App, event: GamepadEvent) {
+        app.world.send_event(event);
+        app.update();
+    }
+
+    fn pressed(app: &App, player_index: PlayerIndex, key_action: KeyAction) -> bool {
+        app.world.resource::<GameState>().player_controls[player_index].state[key_action].pressed()
+    }
+
+    fn pads(app: &App) -> [Option<Gamepad>; 2] {
+        let player_controls = &app.world.resource::<GameState>().player_controls;
+        [
+            player_controls[PlayerIndex::Player1].pad,
+            player_controls[PlayerIndex::Player2].pad,
+        ]
+    }
+
+    #[test]
+    fn one_pad_drives_both_players_until_it_disconnects() {
+        let mut app = shared_pad_app();
+        pad_event(
+            &mut app,
+            GamepadEvent::Connection(GamepadConnectionEvent::new(
+                SHARED_PAD,
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Shared Pad".to_string(),
+                }),
+            )),
+        );
+        assert_eq!(pads(&app), [Some(SHARED_PAD); 2]);
+
+        // a second pad doesn't take either player's place
+        pad_event(
+            &mut app,
+            GamepadEvent::Connection(GamepadConnectionEvent::new(
+                Gamepad::new(1),
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Another Pad".to_string(),
+                }),
+            )),
+        );
+        assert_eq!(pads(&app), [Some(SHARED_PAD); 2]);
+
+        pad_event(
+            &mut app,
+            GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                SHARED_PAD,
+                GamepadButtonType::LeftTrigger2,
+                1.,
+            )),
+        );
+        assert!(pressed(
+            &app,
+            PlayerIndex::Player1,
+            KeyAction::PickupOrThrow
+        ));
+        assert!(!pressed(
+            &app,
+            PlayerIndex::Player2,
+            KeyAction::PickupOrThrow
+        ));
+
+        pad_event(
+            &mut app,
+            GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                SHARED_PAD,
+                GamepadButtonType::South,
+                1.,
+            )),
+        );
+        pad_event(
+            &mut app,
+            GamepadEvent::Axis(GamepadAxisChangedEvent::new(
+                SHARED_PAD,
+                GamepadAxisType::RightStickX,
+                1.,
+            )),
+        );
+        assert!(pressed(
+            &app,
+            PlayerIndex::Player1,
+            KeyAction::PickupOrThrow
+        ));
+        assert!(pressed(&app, PlayerIndex::Player2, KeyAction::MoveDown));
+        assert!(pressed(&app, PlayerIndex::Player2, KeyAction::MoveRight));
+        assert!(!pressed(&app, PlayerIndex::Player1, KeyAction::MoveDown));
+        assert!(!pressed(&app, PlayerIndex::Player1, KeyAction::MoveRight));
+
+        // losing the pad suspends both of them, nothing it was holding stays down
+        pad_event(
+            &mut app,
+            GamepadEvent::Connection(GamepadConnectionEvent::new(
+                SHARED_PAD,
+                GamepadConnection::Disconnected,
+            )),
+        );
+        app.update();
+        assert_eq!(pads(&app), [None; 2]);
+        for player_index in [PlayerIndex::Player1, PlayerIndex::Player2] {
+            for key_action in [
+                KeyAction::PickupOrThrow,
+                KeyAction::MoveDown,
+                KeyAction::MoveRight,
+            ] {
+                assert!(
+                    !pressed(&app, player_index, key_action.clone()),
+                    "{:?} still has {:?}",
+                    player_index,
+                    key_action
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    random::*, release_package, GameConfig, Package, StringKey, WaveModifier, WaveModifierConfig,
+    PACKAGE_DENSITY,
+};
+
+/// Slowest a modifier can make the wave countdown or the supervisor's watch run, so a zero in
+/// the config can't stop either for good.
+const MIN_TICK_SCALE: f32 = 0.05;
+
+/// The modifier announced for the next wave and the one in force for the wave being played.
+#[derive(Resource, Default)]
+pub struct WaveModifiers {
+    /// Rolled with the wave plan, the inner `None` is a plain wave. Kept if the plan is redone
+    /// so what's announced doesn't change mid countdown.
+    next: Option<Option<WaveModifier>>,
+    active: Option<WaveModifier>,
+    /// Counts every wave spawned, so a modifier only ends with its own wave's packages.
+    wave: u32,
+}
+
+/// Marks a package from a wave that had a modifier.
+#[derive(Component, Clone, Copy)]
+pub struct WaveCargo {
+    pub modifier: WaveModifier,
+    pub wave: u32,
+}
+
+/// How fast a loose fragile package was moving as of the last step.
+#[derive(Component, Default)]
+pub struct Fragile {
+    last_speed: f32,
+}
+
+impl WaveModifier {
+    pub fn name(&self) -> StringKey {
+        match self {
+            WaveModifier::HeavyFreight => StringKey::WaveModifierHeavyFreight,
+            WaveModifier::FragileGoods => StringKey::WaveModifierFragileGoods,
+            WaveModifier::RushOrder => StringKey::WaveModifierRushOrder,
+            WaveModifier::Audit => StringKey::WaveModifierAudit,
+        }
+    }
+
+    /// What a package of this wave is worth on delivery, as a multiple of its usual value.
+    pub fn value_scale(modifier: Option<WaveModifier>, config: &WaveModifierConfig) -> f32 {
+        match modifier {
+            Some(WaveModifier::FragileGoods) => config.fragile_value_scale,
+            _ => 1.,
+        }
+    }
+}
+
+fn roll_wave_modifier(config: &WaveModifierConfig, rng: &mut Rand) -> Option<WaveModifier> {
+    let plain_weight = config.plain_wave_weight.max(0.);
+    let total_weight = plain_weight
+        + config
+            .modifiers
+            .iter()
+            .map(|weighted| weighted.weight.max(0.))
+            .sum::<f32>();
+    if total_weight <= 0. {
+        return None;
+    }
+    let mut pick = rng.gen_range(0.0..total_weight) - plain_weight;
+    if pick < 0. {
+        return None;
+    }
+    config
+        .modifiers
+        .iter()
+        .find(|weighted| {
+            pick -= weighted.weight.max(0.);
+            pick < 0.
+        })
+        .map(|weighted| weighted.modifier)
+}
+
+impl WaveModifiers {
+    /// The modifier shown for the next wave, rolled the first time it's asked for.
+    pub fn plan_next(&mut self, config: &WaveModifierConfig, rng: &mut Rand) {
+        if config.enabled && self.next.is_none() {
+            self.next = Some(roll_wave_modifier(config, rng));
+        }
+    }
+
+    pub fn next(&self) -> Option<WaveModifier> {
+        self.next.flatten()
+    }
+
+    pub fn active(&self) -> Option<WaveModifier> {
+        self.active
+    }
+
+    /// The announced modifier takes over as the new wave spawns, returns what its packages
+    /// should be tagged with.
+    pub fn start_wave(&mut self) -> Option<WaveCargo> {
+        self.wave = self.wave.wrapping_add(1);
+        self.active = self.next.take().flatten();
+        if let Some(modifier) = self.active {
+            info!("Wave {} is a {:?} wave", self.wave, modifier);
+        }
+        self.active.map(|modifier| WaveCargo {
+            modifier,
+            wave: self.wave,
+        })
+    }
+
+    pub fn belt_speed_scale(&self, config: &WaveModifierConfig) -> f32 {
+        match self.active {
+            Some(WaveModifier::RushOrder) => config.rush_belt_speed_scale.max(0.),
+            _ => 1.,
+        }
+    }
+
+    /// Multiplies how long the countdown to the next wave takes.
+    pub fn countdown_scale(&self, config: &WaveModifierConfig) -> f32 {
+        match self.active {
+            Some(WaveModifier::RushOrder) => config.rush_countdown_scale.max(MIN_TICK_SCALE),
+            _ => 1.,
+        }
+    }
+
+    /// Multiplies how long the supervisor watches for each time.
+    pub fn monitoring_scale(&self, config: &WaveModifierConfig) -> f32 {
+        match self.active {
+            Some(WaveModifier::Audit) => config.audit_monitoring_scale.max(MIN_TICK_SCALE),
+            _ => 1.,
+        }
+    }
+}
+
+/// Ends the modifier once every package of its wave has been delivered, confiscated, jammed or
+/// broken, putting everything it changed back as it was.
+pub fn resolve_wave_modifier(
+    mut wave_modifiers: ResMut<WaveModifiers>,
+    cargo_query: Query<&WaveCargo>,
+) {
+    let Some(modifier) = wave_modifiers.active else {
+        return;
+    };
+    let wave = wave_modifiers.wave;
+    if cargo_query.iter().all(|cargo| cargo.wave != wave) {
+        info!("Every package from the {:?} wave is resolved", modifier);
+        wave_modifiers.active = None;
+    }
+}
+
+/// Heavy freight is denser whenever its physics are switched back on, packages start out
+/// without physics on the belt and get the usual density each time they're let go.
+pub fn weigh_wave_cargo(
+    game_config: Res<GameConfig>,
+    mut cargo_query: Query<
+        (&WaveCargo, &mut ColliderMassProperties),
+        Changed<ColliderMassProperties>,
+    >,
+) {
+    let density = PACKAGE_DENSITY
+        * game_config
+            .wave_modifier_config
+            .heavy_density_scale
+            .max(0.01);
+    for (cargo, mut mass_props) in &mut cargo_query {
+        if cargo.modifier != WaveModifier::HeavyFreight {
+            continue;
+        }
+        if !matches!(*mass_props, ColliderMassProperties::Density(current) if current == density) {
+            *mass_props = ColliderMassProperties::Density(density);
+        }
+    }
+}
+
+/// A loose fragile package that loses too much speed in one step has hit something hard and
+/// breaks, taking its value with it. Being picked up or landing on a belt doesn't count.
+#[allow(clippy::type_complexity)]
+pub fn break_fragile_packages(
+    mut commands: Commands,
+    game_config: Res<GameConfig>,
+    mut package_query: Query<
+        (
+            Entity,
+            &WaveCargo,
+            Option<&Velocity>,
+            Option<&mut Fragile>,
+            Has<Parent>,
+        ),
+        With<Package>,
+    >,
+) {
+    let break_speed = game_config.wave_modifier_config.fragile_break_speed;
+    for (package_entity, cargo, velocity, fragile, has_parent) in &mut package_query {
+        if cargo.modifier != WaveModifier::FragileGoods {
+            continue;
+        }
+        let Some(mut fragile) = fragile else {
+            commands.entity(package_entity).insert(Fragile::default());
+            continue;
+        };
+        // held or on a belt there's nothing to hit, it starts from rest when let go
+        let speed = match velocity {
+            Some(velocity) if !has_parent => velocity.linvel.length(),
+            _ => {
+                fragile.last_speed = 0.;
+                continue;
+            }
+        };
+        if fragile.last_speed - speed > break_speed {
+            info!("A fragile package broke at {:.0} speed", fragile.last_speed);
+            release_package(&mut commands, package_entity);
+            continue;
+        }
+        fragile.last_speed = speed;
+    }
+}
@@ -0,0 +1,61 @@
+//! The game ticking before anything is spawned, then with the arena and players spawned part way
+//! through. Run with `--features test-support`.
+
+use bevy::prelude::*;
+use play_nice::*;
+
+const EMPTY_TICKS: u32 = 10;
+/// Long enough for a few waves to come in once there are belts to take them.
+const TICKS_AFTER_SPAWN: u32 = 3_000;
+
+#[derive(Resource, Default)]
+struct WaveCount(usize);
+
+fn count_waves(mut wave_events: EventReader<WaveSpawned>, mut wave_count: ResMut<WaveCount>) {
+    wave_count.0 += wave_events.read().count();
+}
+
+fn entity_count<F: bevy::ecs::query::QueryFilter>(world: &mut World) -> usize {
+    world.query_filtered::<(), F>().iter(world).count()
+}
+
+#[test]
+fn systems_run_before_and_after_everything_is_spawned() {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    let mut app = headless_app(config);
+    app.init_resource::<WaveCount>()
+        .add_systems(FixedUpdate, count_waves.after(spawn_package_wave));
+
+    // held back so the first ticks run with nothing in the world
+    let startup = app
+        .world
+        .resource_mut::<Schedules>()
+        .remove(Startup)
+        .expect("the game spawns its world at startup");
+    for _ in 0..EMPTY_TICKS {
+        app.update();
+    }
+    assert_eq!(entity_count::<With<Player>>(&mut app.world), 0);
+    assert_eq!(entity_count::<With<Conveyor>>(&mut app.world), 0);
+    assert_eq!(app.world.resource::<WaveCount>().0, 0);
+
+    app.world.resource_mut::<Schedules>().insert(startup);
+    app.world.run_schedule(Startup);
+    assert_eq!(entity_count::<With<Player>>(&mut app.world), 2);
+    assert!(entity_count::<With<Conveyor>>(&mut app.world) > 0);
+
+    for tick in 1..=TICKS_AFTER_SPAWN {
+        app.update();
+        let mismatches = conveyor_package_mismatches(&mut app.world);
+        assert!(
+            mismatches.is_empty(),
+            "tick {}: belts with (conveyor, package_count, parented) of {:?}",
+            tick,
+            mismatches
+        );
+    }
+    assert!(app.world.resource::<WaveCount>().0 > 0, "no waves came in");
+    assert!(entity_count::<With<Package>>(&mut app.world) > 0);
+}
@@ -0,0 +1,123 @@
+//! A throw being charged when the window loses focus, the button let go while it's away. Run with
+//! `--features test-support`.
+
+use bevy::{prelude::*, window::WindowFocused};
+use play_nice::*;
+
+/// Tries at grabbing the front package off the incoming belt before giving up, the first wave
+/// takes a while to arrive.
+const PICKUP_ATTEMPTS: u32 = 60;
+/// Long enough for the pickup cooldown to run out between tries.
+const PICKUP_WAIT_TICKS: u32 = 64;
+const CHARGE_TICKS: u32 = 30;
+
+#[derive(Resource, Default)]
+struct Throws(usize);
+
+fn count_throws(mut thrown_events: EventReader<PackageThrown>, mut throws: ResMut<Throws>) {
+    throws.0 += thrown_events.read().count();
+}
+
+/// Player1's throw charge and whether they're holding anything.
+fn player_one(world: &mut World) -> (f32, bool) {
+    let mut player_query = world.query::<(&Player, Option<&Children>)>();
+    let (throw_charge, children) = player_query
+        .iter(world)
+        .find(|(player_info, _)| player_info.player_index == PlayerIndex::Player1)
+        .map(|(player_info, player_children)| {
+            (
+                player_info.throw_timer.fraction(),
+                player_children.map(|children| children.to_vec()),
+            )
+        })
+        .unwrap();
+    let mut package_query = world.query_filtered::<(), With<Package>>();
+    let is_holding = children
+        .unwrap_or_default()
+        .into_iter()
+        .any(|child| package_query.get(world, child).is_ok());
+    (throw_charge, is_holding)
+}
+
+fn run_ticks(app: &mut App, ticks: u32) {
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+#[test]
+fn focus_lost_mid_charge_throws_nothing() {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    // kept running so the release lands while the window is still unfocused
+    config.app.pause_on_focus_loss = false;
+    let start = player_start_position(PlayAreaAligment::Left, &config.app, &config.game);
+    let tick_seconds = Time::<Fixed>::default().timestep().as_secs_f32();
+    let inward_distance = start.x.abs() - config.game.conveyor_config.size.x / 2.;
+    let inward_ticks =
+        (inward_distance / (config.game.player_config.move_speed * tick_seconds)).round() as u32;
+
+    let mut app = headless_app(config);
+    // the window's part is played by hand, it isn't in the gameplay plugin
+    app.add_event::<WindowFocused>()
+        .init_resource::<Throws>()
+        .add_systems(Update, handle_window_focus)
+        .add_systems(FixedUpdate, count_throws);
+    // into the corner and across to stand against the end of the incoming belt, as in full_round
+    app.world
+        .resource_mut::<InputScript>()
+        .hold(PlayerIndex::Player1, 200, &[KeyAction::MoveDown])
+        .hold(PlayerIndex::Player1, inward_ticks, &[KeyAction::MoveRight])
+        .hold(PlayerIndex::Player1, 60, &[KeyAction::MoveUp]);
+
+    // startup
+    app.update();
+    run_ticks(&mut app, 260 + inward_ticks);
+    let mut attempts = 0;
+    while !player_one(&mut app.world).1 {
+        assert!(attempts < PICKUP_ATTEMPTS, "never picked up a package");
+        attempts += 1;
+        app.world
+            .resource_mut::<InputScript>()
+            .tap(PlayerIndex::Player1, KeyAction::PickupOrThrow)
+            .wait(PlayerIndex::Player1, PICKUP_WAIT_TICKS);
+        run_ticks(&mut app, PICKUP_WAIT_TICKS + 2);
+    }
+    assert_eq!(app.world.resource::<Throws>().0, 0);
+
+    app.world.resource_mut::<InputScript>().hold(
+        PlayerIndex::Player1,
+        CHARGE_TICKS * 2,
+        &[KeyAction::PickupOrThrow],
+    );
+    run_ticks(&mut app, CHARGE_TICKS);
+    assert!(player_one(&mut app.world).0 > 0., "the throw never charged");
+
+    app.world.send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: false,
+    });
+    // still held for a while, then let go with the focus still gone
+    run_ticks(&mut app, CHARGE_TICKS + PICKUP_WAIT_TICKS);
+    let (throw_charge, is_holding) = player_one(&mut app.world);
+    assert_eq!(app.world.resource::<Throws>().0, 0, "the release threw");
+    assert!(is_holding);
+    assert_eq!(throw_charge, 0.);
+
+    // a fresh press once it's back still throws
+    app.world.send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: true,
+    });
+    app.world
+        .resource_mut::<InputScript>()
+        .hold(
+            PlayerIndex::Player1,
+            CHARGE_TICKS * 2,
+            &[KeyAction::PickupOrThrow],
+        )
+        .wait(PlayerIndex::Player1, 2);
+    run_ticks(&mut app, CHARGE_TICKS * 2 + 2);
+    assert_eq!(app.world.resource::<Throws>().0, 1);
+}
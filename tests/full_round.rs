@@ -0,0 +1,144 @@
+//! A match played headless from start to finish, both players walking packages from the incoming
+//! belts to their outgoing belt on a fixed script. Run with `--features test-support`.
+
+use bevy::prelude::*;
+use enum_map::EnumMap;
+use play_nice::*;
+
+const TICKS: u32 = 10_000;
+/// Well under what the script manages when everything works, a broken loop delivers nothing.
+const MIN_DELIVERIES: usize = 10;
+/// Waves recycle packages through the pool, so the world shouldn't keep growing.
+const MAX_ENTITY_GROWTH: u32 = 2;
+
+/// Every delivery seen, as (count, total value) per player.
+#[derive(Resource, Default)]
+struct DeliveryLog(EnumMap<PlayerIndex, (usize, Score)>);
+
+fn log_deliveries(
+    mut delivered_events: EventReader<PackageDelivered>,
+    mut delivery_log: ResMut<DeliveryLog>,
+) {
+    for event in delivered_events.read() {
+        let (count, value) = &mut delivery_log.0[event.player];
+        *count += 1;
+        *value += event.value;
+    }
+}
+
+/// Nobody idles out and only deliveries score, so the scores can be checked against them. There's
+/// no supervisor either, they're spawned by the game along with their office.
+fn scripted_config() -> Config {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    config.game.score_config.streaks.clear();
+    config.game.score_config.jam_penalty_per_package = 0.;
+    config
+}
+
+/// Down into the corner by their outgoing belt, then over and over: along the bottom wall to
+/// under the incoming belt, up against its end to grab the front package, and back the same way
+/// to put it on the outgoing belt. Each leg runs into a wall or belt, so it ends in the same place
+/// whatever the leg before it left over.
+fn script_deliveries(
+    script: &mut InputScript,
+    player_index: PlayerIndex,
+    inward: KeyAction,
+    outward: KeyAction,
+    inward_ticks: u32,
+) {
+    script.hold(player_index, 200, &[KeyAction::MoveDown]);
+    for _ in 0..TICKS / (inward_ticks * 2) {
+        script
+            .hold(player_index, inward_ticks, &[inward.clone()])
+            .hold(player_index, 60, &[KeyAction::MoveUp])
+            .tap(player_index, KeyAction::PickupOrThrow)
+            .hold(player_index, 60, &[KeyAction::MoveDown])
+            .hold(player_index, inward_ticks + 40, &[outward.clone()])
+            .tap(player_index, KeyAction::PickupOrThrow)
+            .wait(player_index, 5);
+    }
+}
+
+#[test]
+fn scripted_match_delivers_and_keeps_belts_consistent() {
+    let config = scripted_config();
+    // from the start beside the outgoing belt to the middle of the incoming belt
+    let start = player_start_position(PlayAreaAligment::Left, &config.app, &config.game);
+    let tick_seconds = Time::<Fixed>::default().timestep().as_secs_f32();
+    let inward_distance = start.x.abs() - config.game.conveyor_config.size.x / 2.;
+    let inward_ticks =
+        (inward_distance / (config.game.player_config.move_speed * tick_seconds)).round() as u32;
+
+    let mut app = headless_app(config);
+    app.init_resource::<DeliveryLog>().add_systems(
+        FixedUpdate,
+        log_deliveries.after(check_for_delivered_packages),
+    );
+    {
+        let mut script = app.world.resource_mut::<InputScript>();
+        script_deliveries(
+            &mut script,
+            PlayerIndex::Player1,
+            KeyAction::MoveRight,
+            KeyAction::MoveLeft,
+            inward_ticks,
+        );
+        script_deliveries(
+            &mut script,
+            PlayerIndex::Player2,
+            KeyAction::MoveLeft,
+            KeyAction::MoveRight,
+            inward_ticks,
+        );
+    }
+
+    // startup
+    app.update();
+    let starting_entities = app.world.entities().len();
+    for tick in 1..=TICKS {
+        app.update();
+        let mismatches = conveyor_package_mismatches(&mut app.world);
+        assert!(
+            mismatches.is_empty(),
+            "tick {}: belts with (package_count, parented) of {:?}",
+            tick,
+            mismatches
+                .iter()
+                .map(|(_, count, parented)| (count, parented))
+                .collect::<Vec<_>>()
+        );
+        let entities = app.world.entities().len();
+        assert!(
+            entities <= starting_entities * MAX_ENTITY_GROWTH,
+            "tick {}: {} entities, started with {}",
+            tick,
+            entities,
+            starting_entities
+        );
+    }
+
+    let delivery_log = &app.world.resource::<DeliveryLog>().0;
+    let game_state = app.world.resource::<GameState>();
+    let delivered = delivery_log.values().map(|(count, _)| count).sum::<usize>();
+    assert!(
+        delivered >= MIN_DELIVERIES,
+        "only {} packages delivered",
+        delivered
+    );
+    for (player_index, (count, value)) in delivery_log.iter() {
+        let player_score = &game_state.player_scores[player_index];
+        assert!(
+            player_score.score > Score::ZERO,
+            "{:?} scored nothing",
+            player_index
+        );
+        assert_eq!(player_score.score, *value, "{:?}", player_index);
+        assert_eq!(
+            player_score.deliveries as usize, *count,
+            "{:?}",
+            player_index
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Waves played into an arena nobody works, so every wave jams and is cleared back into the pool.
+//! Run with `--features test-support`.
+
+use bevy::prelude::*;
+use play_nice::*;
+
+const TICKS: u32 = 20_000;
+const MIN_CLEARED_JAMS: usize = 5;
+
+#[derive(Resource, Default)]
+struct WaveCount(usize);
+
+fn count_waves(mut wave_events: EventReader<WaveSpawned>, mut wave_count: ResMut<WaveCount>) {
+    wave_count.0 += wave_events.read().count();
+}
+
+/// Packages in play and parked in the pool.
+fn package_entities(world: &mut World) -> (usize, usize) {
+    let live = world
+        .query_filtered::<(), With<Package>>()
+        .iter(world)
+        .count();
+    let parked = world
+        .query_filtered::<(), With<PooledPackage>>()
+        .iter(world)
+        .count();
+    (live, parked)
+}
+
+fn unattended_config() -> Config {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    config
+}
+
+#[test]
+fn unattended_waves_keep_reusing_the_pool() {
+    let mut app = headless_app(unattended_config());
+    app.init_resource::<WaveCount>()
+        .add_systems(FixedUpdate, count_waves.after(spawn_package_wave));
+
+    // startup
+    app.update();
+    let starting_entities = app.world.entities().len();
+    let pool_capacity = app.world.resource::<PackagePool>().capacity();
+    assert!(pool_capacity > 0);
+
+    let mut cleared_jams = 0;
+    for tick in 1..=TICKS {
+        app.update();
+        let (live, parked) = package_entities(&mut app.world);
+        assert_eq!(
+            parked,
+            app.world.resource::<PackagePool>().parked(),
+            "tick {}",
+            tick
+        );
+        // a wave bigger than the pool spawns the rest fresh, and the extras go once released
+        assert!(
+            live + parked <= pool_capacity.max(live),
+            "tick {}: {} packages in play and {} parked with room for {}",
+            tick,
+            live,
+            parked,
+            pool_capacity
+        );
+
+        let cleared = app
+            .world
+            .resource::<Events<ConveyorCleared>>()
+            .iter_current_update_events()
+            .filter(|cleared| cleared.packages_lost > 0)
+            .count();
+        if cleared > 0 {
+            cleared_jams += cleared;
+            assert!(parked > 0, "tick {}: jammed packages weren't parked", tick);
+        }
+        let entities = app.world.entities().len();
+        assert!(
+            entities <= starting_entities * 2,
+            "tick {}: {} entities, started with {}",
+            tick,
+            entities,
+            starting_entities
+        );
+    }
+
+    assert!(
+        cleared_jams >= MIN_CLEARED_JAMS,
+        "only {} jams cleared in {} waves",
+        cleared_jams,
+        app.world.resource::<WaveCount>().0
+    );
+}
@@ -0,0 +1,97 @@
+//! A hundred short rounds played headless, both players sprinting about and emoting, checking
+//! nothing is left behind from one round to the next. Run with `--features test-support`.
+
+use bevy::prelude::*;
+use play_nice::*;
+
+const ROUNDS: usize = 100;
+const ROUND_SECONDS: f32 = 3.;
+/// Rounds played before the counts are expected to have settled, the first waves and afterimages
+/// fill the pools.
+const WARM_UP_ROUNDS: usize = 10;
+
+/// Entity and atlas layout counts at the end of each tick that started a round.
+#[derive(Resource, Default)]
+struct RoundCounts(Vec<(u32, usize)>);
+
+fn short_rounds_config() -> Config {
+    let mut config = Config::default();
+    config.game.attract_mode_config.enabled = false;
+    config.game.afk_config.enabled = false;
+    config.game.match_config.round_seconds = Some(ROUND_SECONDS);
+    config.game.match_config.rounds_per_match = ROUNDS * 2;
+    config
+}
+
+/// Up and down the player's half at a sprint, with an emote at each end.
+fn script_laps(script: &mut InputScript, player_index: PlayerIndex, ticks: u32) {
+    for _ in 0..ticks / 100 {
+        script
+            .hold(player_index, 48, &[KeyAction::Sprint, KeyAction::MoveUp])
+            .tap(player_index, KeyAction::Emote)
+            .hold(player_index, 48, &[KeyAction::Sprint, KeyAction::MoveDown])
+            .tap(player_index, KeyAction::Emote);
+    }
+}
+
+#[test]
+fn hundred_rounds_leave_nothing_behind() {
+    let mut app = headless_app(short_rounds_config());
+    // drawn by the game rather than played, but they're what the rounds have to clear up
+    app.add_systems(Update, (spawn_sprint_trails, fade_sprint_trails))
+        .init_resource::<RoundCounts>();
+    let tick_seconds = Time::<Fixed>::default().timestep().as_secs_f32();
+    let ticks = ((ROUNDS + 1) as f32 * ROUND_SECONDS / tick_seconds) as u32;
+    {
+        let mut script = app.world.resource_mut::<InputScript>();
+        script_laps(&mut script, PlayerIndex::Player1, ticks);
+        script_laps(&mut script, PlayerIndex::Player2, ticks);
+    }
+
+    // startup
+    app.update();
+    for _ in 0..ticks {
+        app.update();
+        if app
+            .world
+            .resource::<Events<SessionStarted>>()
+            .iter_current_update_events()
+            .next()
+            .is_some()
+        {
+            let entities = app.world.entities().len();
+            let atlas_layouts = app.world.resource::<Assets<TextureAtlasLayout>>().len();
+            app.world
+                .resource_mut::<RoundCounts>()
+                .0
+                .push((entities, atlas_layouts));
+        }
+        if app.world.resource::<RoundCounts>().0.len() == ROUNDS {
+            break;
+        }
+    }
+
+    let round_counts = &app.world.resource::<RoundCounts>().0;
+    assert_eq!(round_counts.len(), ROUNDS);
+    let (settled_entities, settled_atlas_layouts) = round_counts[..WARM_UP_ROUNDS]
+        .iter()
+        .fold((0, 0), |(entities, layouts), counts| {
+            (entities.max(counts.0), layouts.max(counts.1))
+        });
+    for (round, (entities, atlas_layouts)) in round_counts.iter().enumerate() {
+        assert!(
+            *entities <= settled_entities,
+            "round {}: {} entities, no more than {} before",
+            round + 1,
+            entities,
+            settled_entities
+        );
+        assert!(
+            *atlas_layouts <= settled_atlas_layouts,
+            "round {}: {} atlas layouts, no more than {} before",
+            round + 1,
+            atlas_layouts,
+            settled_atlas_layouts
+        );
+    }
+}